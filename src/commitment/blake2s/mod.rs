@@ -31,3 +31,80 @@ impl CommitmentScheme for Commitment {
         Ok(result)
     }
 }
+
+/// The Blake2s personalization of [`VariableLengthCommitment`], which keeps
+/// its outputs apart from plain Blake2s hashes of the same bytes.
+pub const VARIABLE_LENGTH_PERSONALIZATION: [u8; 8] = *b"ArkVLCom";
+
+/// Commits to a byte string of any length as `blake2s(r || m)`, with
+/// [`VARIABLE_LENGTH_PERSONALIZATION`] as the Blake2s personalization. The
+/// randomness has a fixed length, so it delimits the message.
+pub struct VariableLengthCommitment;
+
+impl CommitmentScheme for VariableLengthCommitment {
+    type Parameters = ();
+    type Randomness = [u8; 32];
+    type Output = [u8; 32];
+
+    fn setup<R: Rng>(_: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn commit(
+        _: &Self::Parameters,
+        input: &[u8],
+        r: &Self::Randomness,
+    ) -> Result<Self::Output, Error> {
+        let mut h = b2s::with_params(&[], &[], &VARIABLE_LENGTH_PERSONALIZATION);
+        h.update(r.as_ref());
+        h.update(input);
+        let mut result = [0u8; 32];
+        result.copy_from_slice(&h.finalize());
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Vec;
+
+    #[test]
+    fn variable_length_commitment_test() {
+        let rng = &mut ark_std::test_rng();
+        VariableLengthCommitment::setup(rng).unwrap();
+        let parameters = ();
+        let mut r = [0u8; 32];
+        rng.fill(&mut r);
+        let mut other_r = [0u8; 32];
+        rng.fill(&mut other_r);
+        let kib = (0..1024).map(|i| i as u8).collect::<Vec<_>>();
+
+        for message in [&[][..], &b"note"[..], &kib[..]].iter() {
+            let commitment = VariableLengthCommitment::commit(&parameters, message, &r).unwrap();
+            assert_eq!(
+                commitment,
+                VariableLengthCommitment::commit(&parameters, message, &r).unwrap()
+            );
+            assert_ne!(
+                commitment,
+                VariableLengthCommitment::commit(&parameters, message, &other_r).unwrap()
+            );
+        }
+
+        let empty = VariableLengthCommitment::commit(&parameters, &[], &r).unwrap();
+        assert_ne!(
+            empty,
+            VariableLengthCommitment::commit(&parameters, &[0], &r).unwrap()
+        );
+        assert_ne!(
+            VariableLengthCommitment::commit(&parameters, &kib, &r).unwrap(),
+            VariableLengthCommitment::commit(&parameters, &kib[..1023], &r).unwrap()
+        );
+
+        // The personalization separates the commitment from a plain hash.
+        let mut h = b2s::new();
+        h.update(r.as_ref());
+        assert_ne!(&empty[..], &h.finalize()[..]);
+    }
+}
@@ -1,6 +1,7 @@
-use crate::commitment::CommitmentScheme;
-use ark_ff::Field;
-use ark_r1cs_std::prelude::*;
+use crate::commitment::{CommitmentScheme, ToLeaf};
+use crate::Vec;
+use ark_ff::{Field, PrimeField};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
 use ark_relations::r1cs::SynthesisError;
 use core::fmt::Debug;
 
@@ -20,4 +21,40 @@ pub trait CommitmentGadget<C: CommitmentScheme, ConstraintF: Field> {
         input: &[UInt8<ConstraintF>],
         r: &Self::RandomnessVar,
     ) -> Result<Self::OutputVar, SynthesisError>;
+
+    /// Enforces that `commitment` opens to `input` with randomness `r`, by
+    /// recomputing the commitment.
+    #[tracing::instrument(target = "r1cs", skip(parameters, input, r, commitment))]
+    fn verify_opening(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<ConstraintF>],
+        r: &Self::RandomnessVar,
+        commitment: &Self::OutputVar,
+    ) -> Result<(), SynthesisError> {
+        Self::commit(parameters, input, r)?.enforce_equal(commitment)
+    }
+
+    /// Enforces that `commitment` opens to `input` with randomness `r` if
+    /// `should_enforce` is true, and enforces nothing otherwise.
+    #[tracing::instrument(
+        target = "r1cs",
+        skip(should_enforce, parameters, input, r, commitment)
+    )]
+    fn conditionally_verify_opening(
+        should_enforce: &Boolean<ConstraintF>,
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<ConstraintF>],
+        r: &Self::RandomnessVar,
+        commitment: &Self::OutputVar,
+    ) -> Result<(), SynthesisError> {
+        Self::commit(parameters, input, r)?.conditional_enforce_equal(commitment, should_enforce)
+    }
+}
+
+/// The in-circuit counterpart of [`ToLeaf`]: the leaf of an output variable
+/// equals the native leaf of its value.
+pub trait ToLeafGadget<C: ToLeaf<ConstraintF>, ConstraintF: PrimeField>:
+    CommitmentGadget<C, ConstraintF>
+{
+    fn to_leaf(output: &Self::OutputVar) -> Result<Vec<FpVar<ConstraintF>>, SynthesisError>;
 }
@@ -1,5 +1,5 @@
-use ark_ff::UniformRand;
-use ark_std::rand::Rng;
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_std::rand::{Rng, SeedableRng};
 use ark_std::{fmt::Debug, hash::Hash};
 
 use ark_ff::bytes::ToBytes;
@@ -7,13 +7,18 @@ use ark_ff::bytes::ToBytes;
 pub mod blake2s;
 pub mod injective_map;
 pub mod pedersen;
+pub mod poseidon;
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
 #[cfg(feature = "r1cs")]
 pub use constraints::*;
 
-use crate::Error;
+use crate::{prf::ChaChaRng, Error, Vec};
+use blake2::{Blake2s, Digest};
+
+/// The Blake2s personalization of [`randomness_from_seed`].
+const RANDOMNESS_PERSONALIZATION: [u8; 8] = *b"ArkComRS";
 
 pub trait CommitmentScheme {
     type Output: ToBytes + Clone + Default + Eq + Hash + Debug;
@@ -22,9 +27,52 @@ pub trait CommitmentScheme {
 
     fn setup<R: Rng>(r: &mut R) -> Result<Self::Parameters, Error>;
 
+    /// Runs [`setup`](Self::setup) with a [`ChaChaRng`] seeded by `seed`, so
+    /// that the same seed always gives the same parameters.
+    fn setup_from_seed(seed: [u8; 32]) -> Result<Self::Parameters, Error> {
+        Self::setup(&mut ChaChaRng::from_seed(seed))
+    }
+
     fn commit(
         parameters: &Self::Parameters,
         input: &[u8],
         r: &Self::Randomness,
     ) -> Result<Self::Output, Error>;
+
+    /// Commits to every input with its randomness, preserving their order.
+    /// Schemes override this to share work between the commitments.
+    fn batch_commit(
+        parameters: &Self::Parameters,
+        inputs: &[(&[u8], Self::Randomness)],
+    ) -> Result<Vec<Self::Output>, Error> {
+        inputs
+            .iter()
+            .map(|(input, r)| Self::commit(parameters, input, r))
+            .collect()
+    }
+}
+
+/// Converts the outputs of a commitment scheme to the field elements of a
+/// Merkle leaf, and back. The conversion is injective, and
+/// `from_leaf(to_leaf(output))` returns `output`.
+pub trait ToLeaf<F: Field>: CommitmentScheme {
+    fn to_leaf(output: &Self::Output) -> Vec<F>;
+
+    /// Rejects leaves that are not the conversion of a valid output.
+    fn from_leaf(leaf: &[F]) -> Result<Self::Output, Error>;
+}
+
+/// Derives commitment randomness deterministically from `seed`, e.g. to
+/// recover the randomness of a note from a wallet seed. The two 32-byte
+/// Blake2s digests of `seed || 0` and `seed || 1` are reduced modulo the
+/// field order as one little-endian integer, so the bias is negligible.
+pub(crate) fn randomness_from_seed<F: PrimeField>(seed: &[u8]) -> F {
+    let mut bytes = Vec::with_capacity(64);
+    for counter in 0..2u8 {
+        let mut h = Blake2s::with_params(&[], &[], &RANDOMNESS_PERSONALIZATION);
+        h.update(seed);
+        h.update([counter]);
+        bytes.extend_from_slice(&h.finalize());
+    }
+    F::from_le_bytes_mod_order(&bytes)
 }
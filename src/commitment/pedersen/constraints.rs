@@ -1,16 +1,22 @@
 use crate::{
-    commitment::pedersen::{Commitment, Parameters, Randomness},
+    commitment::{
+        pedersen::{Commitment, Parameters, Randomness},
+        ToLeafGadget,
+    },
     crh::pedersen::Window,
     Vec,
 };
-use ark_ec::ProjectiveCurve;
+use ark_ec::{
+    models::TEModelParameters, twisted_edwards_extended::GroupProjective as TEProjective,
+    ProjectiveCurve,
+};
 use ark_ff::{
     fields::{Field, PrimeField},
     to_bytes, Zero,
 };
 use ark_relations::r1cs::{Namespace, SynthesisError};
 
-use ark_r1cs_std::prelude::*;
+use ark_r1cs_std::{fields::fp::FpVar, groups::curves::twisted_edwards::AffineVar, prelude::*};
 use core::{borrow::Borrow, marker::PhantomData};
 
 type ConstraintF<C> = <<C as ProjectiveCurve>::BaseField as Field>::BasePrimeField;
@@ -96,6 +102,54 @@ where
 
         Ok(result)
     }
+
+    /// Rejects a message longer than the windows with
+    /// `SynthesisError::Unsatisfiable`, where `commit` panics.
+    #[tracing::instrument(target = "r1cs", skip(parameters, input, r, commitment))]
+    fn verify_opening(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<ConstraintF<C>>],
+        r: &Self::RandomnessVar,
+        commitment: &Self::OutputVar,
+    ) -> Result<(), SynthesisError> {
+        Self::check_input_len(input)?;
+        Self::commit(parameters, input, r)?.enforce_equal(commitment)
+    }
+
+    /// As `verify_opening`, and synthesizes nothing
+    /// if `should_enforce` is the constant `false`.
+    #[tracing::instrument(
+        target = "r1cs",
+        skip(should_enforce, parameters, input, r, commitment)
+    )]
+    fn conditionally_verify_opening(
+        should_enforce: &Boolean<ConstraintF<C>>,
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<ConstraintF<C>>],
+        r: &Self::RandomnessVar,
+        commitment: &Self::OutputVar,
+    ) -> Result<(), SynthesisError> {
+        Self::check_input_len(input)?;
+        if let Boolean::Constant(false) = should_enforce {
+            return Ok(());
+        }
+        Self::commit(parameters, input, r)?.conditional_enforce_equal(commitment, should_enforce)
+    }
+}
+
+impl<C, GG, W> CommGadget<C, GG, W>
+where
+    C: ProjectiveCurve,
+    GG: CurveVar<C, ConstraintF<C>>,
+    W: Window,
+    for<'a> &'a GG: GroupOpsBounds<'a, C, GG>,
+{
+    fn check_input_len(input: &[UInt8<ConstraintF<C>>]) -> Result<(), SynthesisError> {
+        if input.len() * 8 > W::WINDOW_SIZE * W::NUM_WINDOWS {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        Ok(())
+    }
 }
 
 impl<C, GG> AllocVar<Parameters<C>, ConstraintF<C>> for ParametersVar<C, GG>
@@ -136,6 +190,20 @@ where
     }
 }
 
+impl<P, W> ToLeafGadget<Commitment<TEProjective<P>, W>, P::BaseField>
+    for CommGadget<TEProjective<P>, AffineVar<P, FpVar<P::BaseField>>, W>
+where
+    P: TEModelParameters,
+    P::BaseField: PrimeField,
+    W: Window,
+{
+    fn to_leaf(
+        output: &AffineVar<P, FpVar<P::BaseField>>,
+    ) -> Result<Vec<FpVar<P::BaseField>>, SynthesisError> {
+        Ok(vec![output.x.clone(), output.y.clone()])
+    }
+}
+
 #[cfg(test)]
 mod test {
     use ark_ed_on_bls12_381::{constraints::EdwardsVar, EdwardsProjective as JubJub, Fq, Fr};
@@ -144,10 +212,11 @@ mod test {
     use crate::{
         commitment::{
             pedersen::{constraints::CommGadget, Commitment, Randomness},
-            CommitmentGadget, CommitmentScheme,
+            CommitmentGadget, CommitmentScheme, ToLeaf, ToLeafGadget,
         },
         crh::pedersen,
     };
+    use ark_ff::PrimeField;
     use ark_r1cs_std::prelude::*;
     use ark_relations::r1cs::ConstraintSystem;
 
@@ -200,4 +269,177 @@ mod test {
         assert_eq!(primitive_result, result_var.value().unwrap());
         assert!(cs.is_satisfied().unwrap());
     }
+
+    #[test]
+    fn verify_opening_test() {
+        #[derive(Clone, PartialEq, Eq, Hash)]
+        pub(super) struct Window;
+
+        impl pedersen::Window for Window {
+            const WINDOW_SIZE: usize = 4;
+            const NUM_WINDOWS: usize = 8;
+        }
+
+        type TestCOMM = Commitment<JubJub, Window>;
+        type TestCOMMGadget = CommGadget<JubJub, EdwardsVar, Window>;
+
+        let rng = &mut test_rng();
+        let input = [1u8; 4];
+        let randomness = Randomness(Fr::rand(rng));
+        let parameters = TestCOMM::setup(rng).unwrap();
+        let commitment = TestCOMM::commit(&parameters, &input, &randomness).unwrap();
+
+        // Returns whether opening `commitment` to `input` with `r` is
+        // accepted, checked only if `should_enforce`.
+        let open = |r: &Randomness<JubJub>, should_enforce: bool| {
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let input_var =
+                UInt8::new_witness_vec(ark_relations::ns!(cs, "input"), &input).unwrap();
+            let randomness_var =
+                <TestCOMMGadget as CommitmentGadget<TestCOMM, Fq>>::RandomnessVar::new_witness(
+                    ark_relations::ns!(cs, "gadget_randomness"),
+                    || Ok(r),
+                )
+                .unwrap();
+            let parameters_var =
+                <TestCOMMGadget as CommitmentGadget<TestCOMM, Fq>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "gadget_parameters"),
+                    &parameters,
+                )
+                .unwrap();
+            let commitment_var =
+                <TestCOMMGadget as CommitmentGadget<TestCOMM, Fq>>::OutputVar::new_input(
+                    ark_relations::ns!(cs, "commitment"),
+                    || Ok(commitment),
+                )
+                .unwrap();
+            if should_enforce {
+                TestCOMMGadget::verify_opening(
+                    &parameters_var,
+                    &input_var,
+                    &randomness_var,
+                    &commitment_var,
+                )
+                .unwrap();
+            } else {
+                let should_enforce =
+                    Boolean::new_witness(ark_relations::ns!(cs, "should_enforce"), || Ok(false))
+                        .unwrap();
+                TestCOMMGadget::conditionally_verify_opening(
+                    &should_enforce,
+                    &parameters_var,
+                    &input_var,
+                    &randomness_var,
+                    &commitment_var,
+                )
+                .unwrap();
+            }
+            cs.is_satisfied().unwrap()
+        };
+
+        let tampered = Randomness(randomness.0 + Fr::from(1u64));
+        assert!(open(&randomness, true));
+        assert!(!open(&tampered, true));
+        assert!(open(&tampered, false));
+
+        // A constant false condition synthesizes nothing, and a message
+        // longer than the windows is an error rather than a panic.
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let input_var = UInt8::new_witness_vec(ark_relations::ns!(cs, "input"), &[1u8; 5]).unwrap();
+        let randomness_var =
+            <TestCOMMGadget as CommitmentGadget<TestCOMM, Fq>>::RandomnessVar::new_witness(
+                ark_relations::ns!(cs, "gadget_randomness"),
+                || Ok(&randomness),
+            )
+            .unwrap();
+        let parameters_var =
+            <TestCOMMGadget as CommitmentGadget<TestCOMM, Fq>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "gadget_parameters"),
+                &parameters,
+            )
+            .unwrap();
+        let commitment_var =
+            <TestCOMMGadget as CommitmentGadget<TestCOMM, Fq>>::OutputVar::new_input(
+                ark_relations::ns!(cs, "commitment"),
+                || Ok(commitment),
+            )
+            .unwrap();
+        let constraints = cs.num_constraints();
+        TestCOMMGadget::conditionally_verify_opening(
+            &Boolean::constant(false),
+            &parameters_var,
+            &input_var[..4],
+            &randomness_var,
+            &commitment_var,
+        )
+        .unwrap();
+        assert_eq!(cs.num_constraints(), constraints);
+        assert!(TestCOMMGadget::verify_opening(
+            &parameters_var,
+            &input_var,
+            &randomness_var,
+            &commitment_var,
+        )
+        .is_err());
+        assert!(TestCOMMGadget::conditionally_verify_opening(
+            &Boolean::constant(false),
+            &parameters_var,
+            &input_var,
+            &randomness_var,
+            &commitment_var,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn to_leaf_gadget_test() {
+        #[derive(Clone, PartialEq, Eq, Hash)]
+        pub(super) struct Window;
+
+        impl pedersen::Window for Window {
+            const WINDOW_SIZE: usize = 4;
+            const NUM_WINDOWS: usize = 8;
+        }
+
+        type TestCOMM = Commitment<JubJub, Window>;
+        type TestCOMMGadget = CommGadget<JubJub, EdwardsVar, Window>;
+
+        let rng = &mut test_rng();
+        let parameters = TestCOMM::setup(rng).unwrap();
+        // y is negative if it is larger than -y as an integer.
+        let is_negative = |y: Fq| y.into_repr() > (-y).into_repr();
+        let mut seen_negative = (false, false);
+        for i in 0..16u8 {
+            let input = [i; 4];
+            let randomness = Randomness(Fr::rand(rng));
+            let commitment = TestCOMM::commit(&parameters, &input, &randomness).unwrap();
+            if is_negative(commitment.y) {
+                seen_negative.0 = true;
+            } else {
+                seen_negative.1 = true;
+            }
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let input_var =
+                UInt8::new_witness_vec(ark_relations::ns!(cs, "input"), &input).unwrap();
+            let randomness_var =
+                <TestCOMMGadget as CommitmentGadget<TestCOMM, Fq>>::RandomnessVar::new_witness(
+                    ark_relations::ns!(cs, "gadget_randomness"),
+                    || Ok(&randomness),
+                )
+                .unwrap();
+            let parameters_var =
+                <TestCOMMGadget as CommitmentGadget<TestCOMM, Fq>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "gadget_parameters"),
+                    &parameters,
+                )
+                .unwrap();
+            let result_var =
+                TestCOMMGadget::commit(&parameters_var, &input_var, &randomness_var).unwrap();
+            let leaf_var = TestCOMMGadget::to_leaf(&result_var).unwrap();
+            assert_eq!(leaf_var.value().unwrap(), TestCOMM::to_leaf(&commitment));
+            assert!(cs.is_satisfied().unwrap());
+        }
+        assert_eq!(seen_negative, (true, true));
+    }
 }
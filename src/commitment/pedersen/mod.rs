@@ -1,15 +1,23 @@
-use crate::{Error, Vec};
-use ark_ec::ProjectiveCurve;
+use crate::{CryptoError, Error, Vec};
+use ark_ec::{
+    models::TEModelParameters,
+    twisted_edwards_extended::{GroupAffine as TEAffine, GroupProjective as TEProjective},
+    ProjectiveCurve,
+};
 use ark_ff::{bytes::ToBytes, BitIteratorLE, Field, FpParameters, PrimeField, ToConstraintField};
-use ark_std::io::{Result as IoResult, Write};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::io::{Read, Result as IoResult, Write};
 use ark_std::marker::PhantomData;
 use ark_std::rand::Rng;
 use ark_std::UniformRand;
 
-use super::CommitmentScheme;
+use super::{randomness_from_seed, CommitmentScheme, ToLeaf};
 
-pub use crate::crh::pedersen::Window;
-use crate::crh::{pedersen, FixedLengthCRH};
+use crate::crh::pedersen;
+pub use crate::crh::pedersen::{required_windows, Window};
+use ark_std::cfg_iter;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
@@ -20,6 +28,20 @@ pub struct Parameters<C: ProjectiveCurve> {
     pub generators: Vec<Vec<C>>,
 }
 
+impl<C: ProjectiveCurve> Parameters<C> {
+    /// Checks that the generators, including the randomness generator, are
+    /// distinct non-identity elements of the prime order subgroup, without
+    /// which the commitment is not binding.
+    pub fn validate(&self) -> Result<(), CryptoError> {
+        pedersen::validate_generators(
+            self.generators
+                .iter()
+                .flatten()
+                .chain(&self.randomness_generator),
+        )
+    }
+}
+
 pub struct Commitment<C: ProjectiveCurve, W: Window> {
     group: PhantomData<C>,
     window: PhantomData<W>,
@@ -42,6 +64,29 @@ impl<C: ProjectiveCurve> ToBytes for Randomness<C> {
     }
 }
 
+impl<C: ProjectiveCurve> Randomness<C> {
+    /// Derives the randomness deterministically from `seed`.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        Randomness(randomness_from_seed(seed))
+    }
+}
+
+impl<C: ProjectiveCurve> CanonicalSerialize for Randomness<C> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.0.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.0.serialized_size()
+    }
+}
+
+impl<C: ProjectiveCurve> CanonicalDeserialize for Randomness<C> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(Randomness(C::ScalarField::deserialize(reader)?))
+    }
+}
+
 impl<C: ProjectiveCurve, W: Window> CommitmentScheme for Commitment<C, W> {
     type Parameters = Parameters<C>;
     type Randomness = Randomness<C>;
@@ -72,9 +117,74 @@ impl<C: ProjectiveCurve, W: Window> CommitmentScheme for Commitment<C, W> {
     ) -> Result<Self::Output, Error> {
         let commit_time = start_timer!(|| "PedersenCOMM::Commit");
         // If the input is too long, return an error.
-        if input.len() > W::WINDOW_SIZE * W::NUM_WINDOWS {
-            panic!("incorrect input length: {:?}", input.len());
+        pedersen::check_input_size::<W>(input.len() * 8)?;
+        assert_eq!(parameters.generators.len(), W::NUM_WINDOWS);
+
+        // Invoke Pedersen CRH here, to prevent code duplication.
+        let crh_parameters = pedersen::Parameters {
+            generators: parameters.generators.clone(),
+        };
+        let result = Self::commit_unchecked(parameters, &crh_parameters, input, randomness);
+        end_timer!(commit_time);
+
+        Ok(result)
+    }
+
+    fn batch_commit(
+        parameters: &Self::Parameters,
+        inputs: &[(&[u8], Self::Randomness)],
+    ) -> Result<Vec<Self::Output>, Error> {
+        let commit_time = start_timer!(|| format!("PedersenCOMM::BatchCommit: {}", inputs.len()));
+        for (input, _) in inputs {
+            pedersen::check_input_size::<W>(input.len() * 8)?;
+        }
+        assert_eq!(parameters.generators.len(), W::NUM_WINDOWS);
+
+        // The CRH parameters are shared by all the commitments.
+        let crh_parameters = pedersen::Parameters {
+            generators: parameters.generators.clone(),
+        };
+        let result = cfg_iter!(inputs)
+            .map(|(input, randomness)| {
+                Self::commit_unchecked(parameters, &crh_parameters, input, randomness)
+            })
+            .collect();
+        end_timer!(commit_time);
+
+        Ok(result)
+    }
+}
+
+impl<C: ProjectiveCurve, W: Window> Commitment<C, W> {
+    /// Derives the parameters from `personalization` instead of sampling
+    /// them, so that they can be reproduced independently. The generators are
+    /// those of [`pedersen::CRH::setup_deterministic`], and the base of the
+    /// randomness generator is [`pedersen::CRH::hash_to_generator`] of
+    /// `(personalization, W::NUM_WINDOWS)`.
+    pub fn setup_deterministic(personalization: &[u8]) -> Result<Parameters<C>, Error> {
+        let num_powers = <C::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+        let mut randomness_generator = Vec::with_capacity(num_powers);
+        let mut base =
+            pedersen::CRH::<C, W>::hash_to_generator(personalization, W::NUM_WINDOWS as u32)?;
+        for _ in 0..num_powers {
+            randomness_generator.push(base);
+            base.double_in_place();
         }
+        let generators = pedersen::CRH::<C, W>::setup_deterministic(personalization)?.generators;
+
+        Ok(Parameters {
+            randomness_generator,
+            generators,
+        })
+    }
+
+    /// Commits to an input whose length has already been checked.
+    fn commit_unchecked(
+        parameters: &Parameters<C>,
+        crh_parameters: &pedersen::Parameters<C>,
+        input: &[u8],
+        randomness: &Randomness<C>,
+    ) -> C::Affine {
         // Pad the input to the necessary length.
         let mut padded_input = Vec::with_capacity(input.len());
         let mut input = input;
@@ -84,14 +194,8 @@ impl<C: ProjectiveCurve, W: Window> CommitmentScheme for Commitment<C, W> {
             padded_input.resize(padded_length, 0u8);
             input = padded_input.as_slice();
         }
-        assert_eq!(parameters.generators.len(), W::NUM_WINDOWS);
 
-        // Invoke Pedersen CRH here, to prevent code duplication.
-
-        let crh_parameters = pedersen::Parameters {
-            generators: parameters.generators.clone(),
-        };
-        let mut result: C = pedersen::CRH::<C, W>::evaluate(&crh_parameters, &input)?.into();
+        let mut result = pedersen::CRH::<C, W>::evaluate_padded(crh_parameters, input);
         let randomize_time = start_timer!(|| "Randomize");
 
         // Compute h^r.
@@ -104,9 +208,31 @@ impl<C: ProjectiveCurve, W: Window> CommitmentScheme for Commitment<C, W> {
             }
         }
         end_timer!(randomize_time);
-        end_timer!(commit_time);
 
-        Ok(result.into())
+        result.into()
+    }
+}
+
+/// The leaf of a commitment on a twisted Edwards curve is `[x, y]`.
+impl<P, W> ToLeaf<P::BaseField> for Commitment<TEProjective<P>, W>
+where
+    P: TEModelParameters,
+    P::BaseField: PrimeField,
+    W: Window,
+{
+    fn to_leaf(output: &TEAffine<P>) -> Vec<P::BaseField> {
+        vec![output.x, output.y]
+    }
+
+    fn from_leaf(leaf: &[P::BaseField]) -> Result<TEAffine<P>, Error> {
+        if leaf.len() != 2 {
+            return Err(CryptoError::IncorrectInputLength(leaf.len()).into());
+        }
+        let point = TEAffine::<P>::new(leaf[0], leaf[1]);
+        if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(CryptoError::NotPrimeOrder.into());
+        }
+        Ok(point)
     }
 }
 
@@ -118,3 +244,198 @@ impl<ConstraintF: Field, C: ProjectiveCurve + ToConstraintField<ConstraintF>>
         Some(Vec::new())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CryptoError;
+    use ark_ec::AffineCurve;
+    use ark_ed_on_bls12_381::{EdwardsParameters, EdwardsProjective as JubJub, Fq};
+    use ark_ff::{One, Zero};
+
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct Window;
+
+    impl pedersen::Window for Window {
+        const WINDOW_SIZE: usize = 4;
+        const NUM_WINDOWS: usize = 8;
+    }
+
+    type TestCOMM = Commitment<JubJub, Window>;
+
+    #[test]
+    fn required_windows_test() {
+        assert_eq!(required_windows(0, 4), 0);
+        assert_eq!(required_windows(32, 4), 8);
+        assert_eq!(required_windows(33, 4), 9);
+        assert_eq!(required_windows(256, 128), 2);
+    }
+
+    #[test]
+    fn batch_commit_test() {
+        let rng = &mut ark_std::test_rng();
+        let parameters = TestCOMM::setup(rng).unwrap();
+        let inputs = (0..5u8)
+            .map(|i| (vec![i; i as usize], Randomness(UniformRand::rand(rng))))
+            .collect::<Vec<_>>();
+        let inputs = inputs
+            .iter()
+            .map(|(input, r)| (input.as_slice(), r.clone()))
+            .collect::<Vec<_>>();
+
+        let sequential = inputs
+            .iter()
+            .map(|(input, r)| TestCOMM::commit(&parameters, input, r).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            TestCOMM::batch_commit(&parameters, &inputs).unwrap(),
+            sequential
+        );
+        assert!(TestCOMM::batch_commit(&parameters, &[]).unwrap().is_empty());
+        assert!(TestCOMM::batch_commit(
+            &parameters,
+            &[
+                (&[1u8; 4], inputs[0].1.clone()),
+                (&[1u8; 5], inputs[0].1.clone())
+            ]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn randomness_test() {
+        let rng = &mut ark_std::test_rng();
+        let randomness = Randomness::<JubJub>::rand(rng);
+        let mut bytes = Vec::new();
+        randomness.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), randomness.serialized_size());
+        assert_eq!(
+            Randomness::<JubJub>::deserialize(&bytes[..]).unwrap(),
+            randomness
+        );
+
+        let seeded = Randomness::<JubJub>::from_seed(b"wallet seed");
+        assert_eq!(seeded, Randomness::<JubJub>::from_seed(b"wallet seed"));
+        assert_ne!(seeded, Randomness::<JubJub>::from_seed(b"wallet seed 2"));
+        assert_ne!(seeded, Randomness::<JubJub>::default());
+    }
+
+    #[test]
+    fn deterministic_setup_test() {
+        let parameters = TestCOMM::setup_deterministic(b"ark-crypto-primitives").unwrap();
+        let again = TestCOMM::setup_deterministic(b"ark-crypto-primitives").unwrap();
+        let other = TestCOMM::setup_deterministic(b"ark-crypto-primitives 2").unwrap();
+        assert_eq!(parameters.generators, again.generators);
+        assert_eq!(parameters.randomness_generator, again.randomness_generator);
+        assert_ne!(parameters.generators[0][0], other.generators[0][0]);
+
+        let order = <<JubJub as ProjectiveCurve>::ScalarField as PrimeField>::Params::MODULUS;
+        let bases = parameters
+            .generators
+            .iter()
+            .chain(Some(&parameters.randomness_generator))
+            .map(|powers| powers[0])
+            .collect::<Vec<_>>();
+        for (i, base) in bases.iter().enumerate() {
+            assert!(!base.is_zero());
+            assert!(base.into_affine().mul(order).is_zero());
+            assert!(bases[..i].iter().all(|other| other != base));
+        }
+
+        // Pin the coordinates (x then y, little-endian) of the first
+        // generator, so that the procedure cannot change unnoticed.
+        let generator_hex = ark_ff::to_bytes![parameters.generators[0][0].into_affine()]
+            .unwrap()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        assert_eq!(
+            generator_hex,
+            "db28696143c2e0e899272910fe40684f670023e7bce0eee05bac733f4fbd2442\
+             0b89b542b6e45a0877fa4849b9d9e42cb5466d9114107a03eb2f573b6970f04a"
+        );
+    }
+
+    #[test]
+    fn setup_from_seed_test() {
+        let parameters = TestCOMM::setup_from_seed([1u8; 32]).unwrap();
+        let again = TestCOMM::setup_from_seed([1u8; 32]).unwrap();
+        let other = TestCOMM::setup_from_seed([2u8; 32]).unwrap();
+        assert_eq!(parameters.generators, again.generators);
+        assert_eq!(parameters.randomness_generator, again.randomness_generator);
+        assert_ne!(parameters.generators[0][0], other.generators[0][0]);
+    }
+
+    #[test]
+    fn to_leaf_test() {
+        let rng = &mut ark_std::test_rng();
+        let parameters = TestCOMM::setup(rng).unwrap();
+        for i in 0..8u8 {
+            let commitment =
+                TestCOMM::commit(&parameters, &[i; 4], &Randomness::rand(rng)).unwrap();
+            let leaf = TestCOMM::to_leaf(&commitment);
+            assert_eq!(leaf, vec![commitment.x, commitment.y]);
+            assert_eq!(TestCOMM::from_leaf(&leaf).unwrap(), commitment);
+            // The point with the other y is off the prime order subgroup.
+            assert!(TestCOMM::from_leaf(&[commitment.x, -commitment.y]).is_err());
+            assert!(TestCOMM::from_leaf(&leaf[..1]).is_err());
+        }
+        assert!(TestCOMM::from_leaf(&[Fq::from(1u64), Fq::from(1u64)]).is_err());
+    }
+
+    #[test]
+    fn validate_parameters_test() {
+        let rng = &mut ark_std::test_rng();
+        let parameters = TestCOMM::setup(rng).unwrap();
+        assert!(parameters.validate().is_ok());
+        assert!(TestCOMM::setup_deterministic(b"ark-crypto-primitives")
+            .unwrap()
+            .validate()
+            .is_ok());
+        let crh_parameters = pedersen::Parameters {
+            generators: parameters.generators.clone(),
+        };
+        assert!(crh_parameters.validate().is_ok());
+
+        // (0, -1) has order 2.
+        let small_order = TEAffine::<EdwardsParameters>::new(Fq::zero(), -Fq::one());
+        assert!(small_order.is_on_curve());
+        let cases = vec![
+            (JubJub::zero(), CryptoError::NotPrimeOrder),
+            (small_order.into_projective(), CryptoError::NotPrimeOrder),
+            (
+                parameters.generators[0][0] + small_order.into_projective(),
+                CryptoError::NotPrimeOrder,
+            ),
+            (parameters.generators[0][0], CryptoError::DuplicateGenerator),
+        ];
+        for (generator, error) in cases {
+            let mut bad = parameters.clone();
+            bad.generators[1][0] = generator;
+            assert_eq!(
+                format!("{}", bad.validate().unwrap_err()),
+                format!("{}", error)
+            );
+            let mut bad = parameters.clone();
+            bad.randomness_generator[3] = generator;
+            assert!(bad.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn undersized_window_test() {
+        let rng = &mut ark_std::test_rng();
+        let parameters = TestCOMM::setup(rng).unwrap();
+        let randomness = Randomness(UniformRand::rand(rng));
+
+        // The windows cover 32 bits, i.e. 4 bytes.
+        assert!(TestCOMM::commit(&parameters, &[1u8; 4], &randomness).is_ok());
+        let err = TestCOMM::commit(&parameters, &[1u8; 5], &randomness).unwrap_err();
+        match err {
+            Error::Crypto(CryptoError::InputTooLong { bits, max_bits }) => {
+                assert_eq!((bits, max_bits), (40, 32));
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+    }
+}
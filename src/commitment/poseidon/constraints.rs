@@ -0,0 +1,422 @@
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use crate::{
+    commitment::{
+        poseidon::{
+            Commitment, Randomness, VectorCommitment, COMMITMENT_DOMAIN_TAG,
+            FIELD_COMMITMENT_DOMAIN_TAG, VECTOR_COMMITMENT_DOMAIN_TAG,
+        },
+        CommitmentGadget, ToLeafGadget,
+    },
+    crh::poseidon::{
+        constraints::{to_field_var_elements_packed, CRHGadget, PoseidonParametersVar},
+        domain_element, Rounds,
+    },
+    Vec,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_std::marker::PhantomData;
+use core::borrow::Borrow;
+
+#[derive(Clone, Debug)]
+pub struct RandomnessVar<F: PrimeField>(pub FpVar<F>);
+
+pub struct CommGadget<F: PrimeField, P: Rounds> {
+    field: PhantomData<F>,
+    rounds: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: Rounds> CommitmentGadget<Commitment<F, P>, F> for CommGadget<F, P> {
+    type OutputVar = FpVar<F>;
+    type ParametersVar = PoseidonParametersVar<F>;
+    type RandomnessVar = RandomnessVar<F>;
+
+    /// Packs the message bytes into field elements through their bits, as
+    /// the native scheme does, so the commitment is bound to the message
+    /// variables. The length of the message is fixed by the circuit, so the
+    /// domain element is a constant.
+    #[tracing::instrument(target = "r1cs", skip(parameters, input, r))]
+    fn commit(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+        r: &Self::RandomnessVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        if P::WIDTH < 3 || input.len() > Commitment::<F, P>::MAX_INPUT_LEN {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let domain = domain_element(COMMITMENT_DOMAIN_TAG, input.len());
+        let message = to_field_var_elements_packed(input)?;
+        Self::commit_elements(parameters, domain, message, r)
+    }
+
+    /// The commitment is a single element, so the opening costs the
+    /// permutation and one equality constraint.
+    #[tracing::instrument(target = "r1cs", skip(parameters, input, r, commitment))]
+    fn verify_opening(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+        r: &Self::RandomnessVar,
+        commitment: &Self::OutputVar,
+    ) -> Result<(), SynthesisError> {
+        Self::commit(parameters, input, r)?.enforce_equal(commitment)
+    }
+
+    /// As `verify_opening`, and synthesizes no
+    /// permutation if `should_enforce` is the constant `false`.
+    #[tracing::instrument(
+        target = "r1cs",
+        skip(should_enforce, parameters, input, r, commitment)
+    )]
+    fn conditionally_verify_opening(
+        should_enforce: &Boolean<F>,
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+        r: &Self::RandomnessVar,
+        commitment: &Self::OutputVar,
+    ) -> Result<(), SynthesisError> {
+        if let Boolean::Constant(false) = should_enforce {
+            if P::WIDTH < 3 || input.len() > Commitment::<F, P>::MAX_INPUT_LEN {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+            return Ok(());
+        }
+        Self::commit(parameters, input, r)?.conditional_enforce_equal(commitment, should_enforce)
+    }
+}
+
+impl<F: PrimeField, P: Rounds> CommGadget<F, P> {
+    /// The counterpart of the native
+    /// [`commit_field`](Commitment::commit_field). The elements enter the
+    /// permutation directly, with no bit decomposition.
+    #[tracing::instrument(target = "r1cs", skip(parameters, input, r))]
+    pub fn commit_field(
+        parameters: &PoseidonParametersVar<F>,
+        input: &[FpVar<F>],
+        r: &RandomnessVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        if P::WIDTH < 3 || input.len() > Commitment::<F, P>::MAX_FIELD_INPUT_LEN {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let domain = domain_element(FIELD_COMMITMENT_DOMAIN_TAG, input.len());
+        Self::commit_elements(parameters, domain, input.to_vec(), r)
+    }
+
+    /// Permutes `[domain, m_1, ..., m_k, r]`, with the message zero-padded
+    /// to `P::WIDTH - 2` elements.
+    fn commit_elements(
+        parameters: &PoseidonParametersVar<F>,
+        domain: F,
+        message: Vec<FpVar<F>>,
+        r: &RandomnessVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let mut elements = Vec::with_capacity(P::WIDTH);
+        elements.push(FpVar::constant(domain));
+        elements.extend(message);
+        elements.resize(P::WIDTH - 1, FpVar::zero());
+        elements.push(r.0.clone());
+        CRHGadget::<F, P>::evaluate_elements(parameters, elements)
+    }
+}
+
+/// The in-circuit [`VectorCommitment`].
+pub struct VectorCommitmentGadget<F: PrimeField, P: Rounds> {
+    field: PhantomData<F>,
+    rounds: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: Rounds> VectorCommitmentGadget<F, P> {
+    /// Commits to `values`. The number of values is fixed by the circuit, so
+    /// the domain element is a constant.
+    #[tracing::instrument(target = "r1cs", skip(parameters, values, r))]
+    pub fn commit(
+        parameters: &PoseidonParametersVar<F>,
+        values: &[FpVar<F>],
+        r: &RandomnessVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        if VectorCommitment::<F, P>::RATE == 0 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut elements = values.to_vec();
+        elements.push(r.0.clone());
+        CRHGadget::<F, P>::sponge(
+            parameters,
+            FpVar::constant(domain_element(VECTOR_COMMITMENT_DOMAIN_TAG, values.len())),
+            &elements,
+        )
+    }
+}
+
+impl<F: PrimeField> AllocVar<Randomness<F>, F> for RandomnessVar<F> {
+    fn new_variable<T: Borrow<Randomness<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        FpVar::new_variable(cs, || f().map(|r| r.borrow().0), mode).map(Self)
+    }
+}
+
+impl<F: PrimeField, P: Rounds> ToLeafGadget<Commitment<F, P>, F> for CommGadget<F, P> {
+    fn to_leaf(output: &FpVar<F>) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(vec![output.clone()])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitment::{
+        poseidon::{
+            constraints::{CommGadget, RandomnessVar, VectorCommitmentGadget},
+            Commitment, Randomness, VectorCommitment,
+        },
+        CommitmentGadget, CommitmentScheme, ToLeaf, ToLeafGadget,
+    };
+    use crate::crh::poseidon::{
+        constraints::PoseidonParametersVar,
+        sbox::PoseidonSbox,
+        test_data::{get_mds_5, get_rounds_5},
+        PoseidonParameters, Rounds,
+    };
+    use ark_ed_on_bn254::Fq;
+    use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+
+    #[derive(Default, Clone)]
+    struct PoseidonRounds5;
+
+    impl Rounds for PoseidonRounds5 {
+        const WIDTH: usize = 5;
+        const PARTIAL_ROUNDS: usize = 60;
+        const FULL_ROUNDS: usize = 8;
+        const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+    }
+
+    type TestCOMM = Commitment<Fq, PoseidonRounds5>;
+    type TestCOMMGadget = CommGadget<Fq, PoseidonRounds5>;
+
+    #[test]
+    fn poseidon_commitment_gadget_test() {
+        let rng = &mut ark_std::test_rng();
+        let parameters = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let input = [7u8; 40];
+        let randomness = Randomness(Fq::rand(rng));
+        let primitive_result = TestCOMM::commit(&parameters, &input, &randomness).unwrap();
+
+        // Returns whether opening the public `primitive_result` to `input`
+        // with the witnessed randomness `r` is accepted.
+        let open = |r: Randomness<Fq>| {
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let parameters_var = PoseidonParametersVar::new_constant(
+                ark_relations::ns!(cs, "gadget_parameters"),
+                &parameters,
+            )
+            .unwrap();
+            let input_var =
+                UInt8::new_witness_vec(ark_relations::ns!(cs, "gadget_input"), &input).unwrap();
+            let randomness_var =
+                RandomnessVar::new_witness(ark_relations::ns!(cs, "gadget_randomness"), || Ok(r))
+                    .unwrap();
+            let commitment_var = FpVar::new_input(ark_relations::ns!(cs, "commitment"), || {
+                Ok(primitive_result)
+            })
+            .unwrap();
+
+            let result_var =
+                TestCOMMGadget::commit(&parameters_var, &input_var, &randomness_var).unwrap();
+            if r == randomness {
+                assert_eq!(primitive_result, result_var.value().unwrap());
+                assert_eq!(
+                    TestCOMMGadget::to_leaf(&result_var)
+                        .unwrap()
+                        .value()
+                        .unwrap(),
+                    TestCOMM::to_leaf(&primitive_result)
+                );
+            }
+            result_var.enforce_equal(&commitment_var).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        assert!(open(randomness));
+        assert!(!open(Randomness(randomness.0 + Fq::from(1u64))));
+    }
+
+    #[test]
+    fn poseidon_verify_opening_test() {
+        let rng = &mut ark_std::test_rng();
+        let parameters = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let input = [7u8; 40];
+        let randomness = Randomness(Fq::rand(rng));
+        let commitment = TestCOMM::commit(&parameters, &input, &randomness).unwrap();
+
+        // Returns whether opening `commitment` to `input` with `r` is
+        // accepted, checked only if `should_enforce`.
+        let open = |r: Randomness<Fq>, should_enforce: bool| {
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let parameters_var = PoseidonParametersVar::new_constant(
+                ark_relations::ns!(cs, "gadget_parameters"),
+                &parameters,
+            )
+            .unwrap();
+            let input_var =
+                UInt8::new_witness_vec(ark_relations::ns!(cs, "gadget_input"), &input).unwrap();
+            let randomness_var =
+                RandomnessVar::new_witness(ark_relations::ns!(cs, "gadget_randomness"), || Ok(r))
+                    .unwrap();
+            let commitment_var =
+                FpVar::new_input(ark_relations::ns!(cs, "commitment"), || Ok(commitment)).unwrap();
+            let should_enforce =
+                Boolean::new_witness(ark_relations::ns!(cs, "should_enforce"), || {
+                    Ok(should_enforce)
+                })
+                .unwrap();
+            TestCOMMGadget::conditionally_verify_opening(
+                &should_enforce,
+                &parameters_var,
+                &input_var,
+                &randomness_var,
+                &commitment_var,
+            )
+            .unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        let tampered = Randomness(randomness.0 + Fq::from(1u64));
+        assert!(open(randomness, true));
+        assert!(!open(tampered, true));
+        assert!(open(tampered, false));
+
+        // A constant false condition synthesizes no permutation, but an
+        // overlong message is still an error.
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let parameters_var = PoseidonParametersVar::new_constant(
+            ark_relations::ns!(cs, "gadget_parameters"),
+            &parameters,
+        )
+        .unwrap();
+        let input_var = UInt8::new_witness_vec(
+            ark_relations::ns!(cs, "gadget_input"),
+            &[7u8; TestCOMM::MAX_INPUT_LEN + 1],
+        )
+        .unwrap();
+        let randomness_var =
+            RandomnessVar::new_witness(ark_relations::ns!(cs, "gadget_randomness"), || {
+                Ok(randomness)
+            })
+            .unwrap();
+        let commitment_var =
+            FpVar::new_input(ark_relations::ns!(cs, "commitment"), || Ok(commitment)).unwrap();
+        let constraints = cs.num_constraints();
+        TestCOMMGadget::conditionally_verify_opening(
+            &Boolean::constant(false),
+            &parameters_var,
+            &input_var[..input.len()],
+            &randomness_var,
+            &commitment_var,
+        )
+        .unwrap();
+        assert_eq!(cs.num_constraints(), constraints);
+        assert!(TestCOMMGadget::conditionally_verify_opening(
+            &Boolean::constant(false),
+            &parameters_var,
+            &input_var,
+            &randomness_var,
+            &commitment_var,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn poseidon_field_commitment_gadget_test() {
+        let rng = &mut ark_std::test_rng();
+        let parameters = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let input = [Fq::rand(rng), Fq::rand(rng)];
+        let randomness = Randomness(Fq::rand(rng));
+        let commitment = TestCOMM::commit_field(&parameters, &input, &randomness).unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let parameters_var = PoseidonParametersVar::new_constant(
+            ark_relations::ns!(cs, "gadget_parameters"),
+            &parameters,
+        )
+        .unwrap();
+        let constraints = cs.num_constraints();
+        let input_var =
+            Vec::<FpVar<Fq>>::new_witness(ark_relations::ns!(cs, "gadget_input"), || {
+                Ok(&input[..])
+            })
+            .unwrap();
+        let randomness_var =
+            RandomnessVar::new_witness(ark_relations::ns!(cs, "gadget_randomness"), || {
+                Ok(randomness)
+            })
+            .unwrap();
+        let result_var =
+            TestCOMMGadget::commit_field(&parameters_var, &input_var, &randomness_var).unwrap();
+        assert_eq!(result_var.value().unwrap(), commitment);
+        assert!(cs.is_satisfied().unwrap());
+
+        // The elements are not decomposed, unlike the bytes of a message
+        // that packs into as many elements, each of whose bits is
+        // constrained.
+        let field_cost = cs.num_constraints() - constraints;
+        let bytes_cs = ConstraintSystem::<Fq>::new_ref();
+        let bytes_parameters_var = PoseidonParametersVar::new_constant(
+            ark_relations::ns!(bytes_cs, "gadget_parameters"),
+            &parameters,
+        )
+        .unwrap();
+        let constraints = bytes_cs.num_constraints();
+        let bytes_var =
+            UInt8::new_witness_vec(ark_relations::ns!(bytes_cs, "gadget_input"), &[7u8; 40])
+                .unwrap();
+        let bytes_randomness_var =
+            RandomnessVar::new_witness(ark_relations::ns!(bytes_cs, "gadget_randomness"), || {
+                Ok(randomness)
+            })
+            .unwrap();
+        let _ = TestCOMMGadget::commit(&bytes_parameters_var, &bytes_var, &bytes_randomness_var)
+            .unwrap();
+        assert!(field_cost < bytes_cs.num_constraints() - constraints);
+
+        let too_long = vec![input_var[0].clone(); TestCOMM::MAX_FIELD_INPUT_LEN + 1];
+        assert!(TestCOMMGadget::commit_field(&parameters_var, &too_long, &randomness_var).is_err());
+    }
+
+    #[test]
+    fn vector_commitment_gadget_test() {
+        type VectorCOMM = VectorCommitment<Fq, PoseidonRounds5>;
+        type VectorCOMMGadget = VectorCommitmentGadget<Fq, PoseidonRounds5>;
+
+        let rng = &mut ark_std::test_rng();
+        let parameters = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let rate = VectorCOMM::RATE;
+        for &len in [1, rate, 3 * rate].iter() {
+            let values = (0..len).map(|_| Fq::rand(rng)).collect::<Vec<_>>();
+            let randomness = Randomness(Fq::rand(rng));
+            let commitment = VectorCOMM::commit(&parameters, &values, &randomness).unwrap();
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let parameters_var = PoseidonParametersVar::new_constant(
+                ark_relations::ns!(cs, "gadget_parameters"),
+                &parameters,
+            )
+            .unwrap();
+            let values_var =
+                Vec::<FpVar<Fq>>::new_witness(ark_relations::ns!(cs, "values"), || Ok(&values[..]))
+                    .unwrap();
+            let randomness_var =
+                RandomnessVar::new_witness(ark_relations::ns!(cs, "gadget_randomness"), || {
+                    Ok(randomness)
+                })
+                .unwrap();
+            let result_var =
+                VectorCOMMGadget::commit(&parameters_var, &values_var, &randomness_var).unwrap();
+            assert_eq!(result_var.value().unwrap(), commitment);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
@@ -0,0 +1,490 @@
+use crate::crh::poseidon::{
+    circom, domain_element, to_field_elements_packed, PoseidonParameters, Rounds, CRH,
+};
+use crate::{CryptoError, Error, Vec};
+use ark_ff::{bytes::ToBytes, FpParameters, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::cfg_iter;
+use ark_std::io::{Read, Result as IoResult, Write};
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+use ark_std::UniformRand;
+
+use super::{randomness_from_seed, CommitmentScheme, ToLeaf};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// Distinguishes commitments from other uses of the same Poseidon
+/// parameters.
+pub const COMMITMENT_DOMAIN_TAG: u8 = 2;
+
+/// Distinguishes commitments to field elements, see
+/// [`Commitment::commit_field`].
+pub const FIELD_COMMITMENT_DOMAIN_TAG: u8 = 7;
+
+/// Commits to a message `m` with randomness `r` as a single Poseidon
+/// permutation over `[domain, m_1, ..., m_k, r]`, where:
+///
+/// * `domain` packs [`COMMITMENT_DOMAIN_TAG`] in its lowest byte and the
+///   length of `m` in bytes, as a little-endian `u64`, in the next eight;
+/// * `m_1, ..., m_k` are the [packed](to_field_elements_packed) elements
+///   of `m`, zero-padded to `k = P::WIDTH - 2` elements.
+///
+/// The message therefore holds at most `(P::WIDTH - 2) * CAPACITY` bits,
+/// see [`Commitment::MAX_INPUT_LEN`], and its length keeps zero-padded
+/// messages apart. Messages of field elements are committed to by
+/// [`Commitment::commit_field`] without going through bytes.
+pub struct Commitment<F: PrimeField, P: Rounds> {
+    field: PhantomData<F>,
+    rounds: PhantomData<P>,
+}
+
+#[derive(Derivative)]
+#[derivative(Clone, Copy, PartialEq, Debug, Eq, Default)]
+pub struct Randomness<F: PrimeField>(pub F);
+
+impl<F: PrimeField> UniformRand for Randomness<F> {
+    #[inline]
+    fn rand<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Randomness(UniformRand::rand(rng))
+    }
+}
+
+impl<F: PrimeField> ToBytes for Randomness<F> {
+    fn write<W: Write>(&self, writer: W) -> IoResult<()> {
+        self.0.write(writer)
+    }
+}
+
+impl<F: PrimeField> Randomness<F> {
+    /// Derives the randomness deterministically from `seed`.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        Randomness(randomness_from_seed(seed))
+    }
+}
+
+impl<F: PrimeField> CanonicalSerialize for Randomness<F> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.0.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.0.serialized_size()
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for Randomness<F> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(Randomness(F::deserialize(reader)?))
+    }
+}
+
+impl<F: PrimeField, P: Rounds> Commitment<F, P> {
+    /// The maximum length of a message in bytes, the whole bytes that pack
+    /// into `P::WIDTH - 2` elements.
+    pub const MAX_INPUT_LEN: usize = P::WIDTH.saturating_sub(2) * F::Params::CAPACITY as usize / 8;
+
+    /// The maximum number of elements of a message of
+    /// [`commit_field`](Self::commit_field).
+    pub const MAX_FIELD_INPUT_LEN: usize = P::WIDTH.saturating_sub(2);
+
+    fn check_input_len(input: &[u8]) -> Result<(), Error> {
+        if P::WIDTH < 3 || input.len() > Self::MAX_INPUT_LEN {
+            return Err(CryptoError::IncorrectInputLength(input.len()).into());
+        }
+        Ok(())
+    }
+
+    /// The input of the permutation for a message of a valid length.
+    fn elements(input: &[u8], r: &Randomness<F>) -> Vec<F> {
+        let domain = domain_element(COMMITMENT_DOMAIN_TAG, input.len());
+        Self::permutation_input(domain, to_field_elements_packed::<F>(input), r)
+    }
+
+    /// `[domain, m_1, ..., m_k, r]`, with the message zero-padded to
+    /// `P::WIDTH - 2` elements.
+    fn permutation_input(domain: F, message: Vec<F>, r: &Randomness<F>) -> Vec<F> {
+        let mut elements = Vec::with_capacity(P::WIDTH);
+        elements.push(domain);
+        elements.extend(message);
+        elements.resize(P::WIDTH - 1, F::zero());
+        elements.push(r.0);
+        elements
+    }
+
+    /// Commits to a message of field elements, which enter the permutation
+    /// as they are rather than packed into bytes and back. The input is
+    /// `[domain, m_1, ..., m_k, r]` as for bytes, but `domain` packs
+    /// [`FIELD_COMMITMENT_DOMAIN_TAG`] and the number of elements, so these
+    /// commitments never collide with those of byte messages. The message
+    /// holds at most [`MAX_FIELD_INPUT_LEN`](Self::MAX_FIELD_INPUT_LEN)
+    /// elements.
+    pub fn commit_field(
+        parameters: &PoseidonParameters<F>,
+        input: &[F],
+        r: &Randomness<F>,
+    ) -> Result<F, Error> {
+        if P::WIDTH < 3 || input.len() > Self::MAX_FIELD_INPUT_LEN {
+            return Err(CryptoError::IncorrectInputLength(input.len()).into());
+        }
+        let domain = domain_element(FIELD_COMMITMENT_DOMAIN_TAG, input.len());
+        let elements = Self::permutation_input(domain, input.to_vec(), r);
+        Ok(CRH::<F, P>::evaluate_elements(parameters, elements))
+    }
+}
+
+impl<F: PrimeField, P: Rounds> CommitmentScheme for Commitment<F, P> {
+    type Parameters = PoseidonParameters<F>;
+    type Randomness = Randomness<F>;
+    type Output = F;
+
+    /// circomlib's constants for `P`, see
+    /// [`parameters_for_rounds`](circom::parameters_for_rounds). Other
+    /// constants are built with [`PoseidonParameters::new`] instead.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(circom::parameters_for_rounds::<F, P>()?)
+    }
+
+    fn commit(
+        parameters: &Self::Parameters,
+        input: &[u8],
+        r: &Self::Randomness,
+    ) -> Result<Self::Output, Error> {
+        let commit_time = start_timer!(|| "PoseidonCOMM::Commit");
+        Self::check_input_len(input)?;
+        let result = CRH::<F, P>::evaluate_elements(parameters, Self::elements(input, r));
+        end_timer!(commit_time);
+
+        Ok(result)
+    }
+
+    fn batch_commit(
+        parameters: &Self::Parameters,
+        inputs: &[(&[u8], Self::Randomness)],
+    ) -> Result<Vec<Self::Output>, Error> {
+        let commit_time = start_timer!(|| format!("PoseidonCOMM::BatchCommit: {}", inputs.len()));
+        for (input, _) in inputs {
+            Self::check_input_len(input)?;
+        }
+        let result = cfg_iter!(inputs)
+            .map(|(input, r)| CRH::<F, P>::evaluate_elements(parameters, Self::elements(input, r)))
+            .collect();
+        end_timer!(commit_time);
+
+        Ok(result)
+    }
+}
+
+/// Distinguishes vector commitments from other uses of the same Poseidon
+/// parameters.
+pub const VECTOR_COMMITMENT_DOMAIN_TAG: u8 = 3;
+
+/// Commits to an ordered list of field elements with a single field
+/// element. The state starts as `[domain, 0, ..., 0]`, where `domain` packs
+/// [`VECTOR_COMMITMENT_DOMAIN_TAG`] in its lowest byte and the number of
+/// values, as a little-endian `u64`, in the next eight. The values followed
+/// by `r` are then absorbed `P::WIDTH - 1` at a time: each chunk is added to
+/// the last `P::WIDTH - 1` elements of the state, which is then permuted.
+/// The commitment is the second element of the final state.
+///
+/// There is no succinct opening of a single position: a circuit opens a
+/// position by committing again to the whole vector, given as witnesses,
+/// and reading the position from it.
+pub struct VectorCommitment<F: PrimeField, P: Rounds> {
+    field: PhantomData<F>,
+    rounds: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: Rounds> VectorCommitment<F, P> {
+    /// The number of elements absorbed by each permutation.
+    pub const RATE: usize = P::WIDTH.saturating_sub(1);
+
+    pub fn commit(
+        parameters: &PoseidonParameters<F>,
+        values: &[F],
+        r: &Randomness<F>,
+    ) -> Result<F, Error> {
+        if Self::RATE == 0 {
+            return Err(CryptoError::IncorrectInputLength(values.len()).into());
+        }
+        let mut elements = values.to_vec();
+        elements.push(r.0);
+        CRH::<F, P>::sponge(
+            parameters,
+            domain_element(VECTOR_COMMITMENT_DOMAIN_TAG, values.len()),
+            &elements,
+        )
+    }
+}
+
+/// The leaf of a commitment is the commitment itself.
+impl<F: PrimeField, P: Rounds> ToLeaf<F> for Commitment<F, P> {
+    fn to_leaf(output: &F) -> Vec<F> {
+        vec![*output]
+    }
+
+    fn from_leaf(leaf: &[F]) -> Result<F, Error> {
+        match leaf {
+            [output] => Ok(*output),
+            _ => Err(CryptoError::IncorrectInputLength(leaf.len()).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::poseidon::{
+        sbox::PoseidonSbox,
+        test_data::{get_mds_3, get_mds_5, get_rounds_3, get_rounds_5},
+    };
+    use crate::crh::FixedLengthCRH;
+    use ark_ed_on_bn254::Fq;
+    use ark_ff::to_bytes;
+
+    #[derive(Default, Clone)]
+    struct PoseidonRounds3;
+    #[derive(Default, Clone)]
+    struct PoseidonRounds5;
+
+    impl Rounds for PoseidonRounds3 {
+        const WIDTH: usize = 3;
+        const PARTIAL_ROUNDS: usize = 57;
+        const FULL_ROUNDS: usize = 8;
+        const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+    }
+
+    impl Rounds for PoseidonRounds5 {
+        const WIDTH: usize = 5;
+        const PARTIAL_ROUNDS: usize = 60;
+        const FULL_ROUNDS: usize = 8;
+        const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+    }
+
+    type PoseidonCOMM3 = Commitment<Fq, PoseidonRounds3>;
+    type PoseidonCOMM5 = Commitment<Fq, PoseidonRounds5>;
+
+    #[test]
+    fn setup_test() {
+        let rng = &mut ark_std::test_rng();
+        for params in [
+            PoseidonCOMM5::setup(rng),
+            PoseidonCOMM5::setup_from_seed([1u8; 32]),
+        ] {
+            if cfg!(not(feature = "circom-constants")) {
+                assert!(params.is_err());
+                continue;
+            }
+            let params = params.unwrap();
+            assert_eq!(params.round_keys, get_rounds_5::<Fq>());
+            let r = Randomness::rand(rng);
+            let commitment = PoseidonCOMM5::commit(&params, b"message", &r).unwrap();
+            assert_ne!(
+                commitment,
+                PoseidonCOMM5::commit(&params, b"other message", &r).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn poseidon_commitment_test() {
+        let rng = &mut ark_std::test_rng();
+        let params = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let r = Randomness::rand(rng);
+        let other_r = Randomness::rand(rng);
+
+        let commitment = PoseidonCOMM5::commit(&params, b"message", &r).unwrap();
+        assert_eq!(
+            commitment,
+            PoseidonCOMM5::commit(&params, b"message", &r).unwrap()
+        );
+        // Binding: a different message, including one that only differs by
+        // zero padding, gives a different commitment.
+        assert_ne!(
+            commitment,
+            PoseidonCOMM5::commit(&params, b"massage", &r).unwrap()
+        );
+        assert_ne!(
+            commitment,
+            PoseidonCOMM5::commit(&params, b"message\0", &r).unwrap()
+        );
+        // Hiding: the same message with different randomness gives a
+        // different commitment.
+        assert_ne!(
+            commitment,
+            PoseidonCOMM5::commit(&params, b"message", &other_r).unwrap()
+        );
+
+        // Three elements of 253 bits hold 94 whole bytes.
+        assert_eq!(PoseidonCOMM5::MAX_INPUT_LEN, 94);
+        let longest = vec![7u8; PoseidonCOMM5::MAX_INPUT_LEN];
+        assert!(PoseidonCOMM5::commit(&params, &longest, &r).is_ok());
+        assert!(PoseidonCOMM5::commit(&params, &[longest, vec![7]].concat(), &r).is_err());
+    }
+
+    #[test]
+    fn poseidon_field_commitment_test() {
+        let rng = &mut ark_std::test_rng();
+        let params = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let r = Randomness::rand(rng);
+        let message = [Fq::from(7u64), Fq::from(8u64)];
+
+        let commitment = PoseidonCOMM5::commit_field(&params, &message, &r).unwrap();
+        // Binding, including against zero padding, and hiding.
+        assert_ne!(
+            commitment,
+            PoseidonCOMM5::commit_field(&params, &[message[1], message[0]], &r).unwrap()
+        );
+        assert_ne!(
+            commitment,
+            PoseidonCOMM5::commit_field(&params, &[message[0], message[1], Fq::from(0u64)], &r)
+                .unwrap()
+        );
+        assert_ne!(
+            commitment,
+            PoseidonCOMM5::commit_field(&params, &message, &Randomness::rand(rng)).unwrap()
+        );
+        // Distinct from the commitment to the bytes that pack into the same
+        // elements.
+        let bytes = [7u8];
+        assert_eq!(to_field_elements_packed::<Fq>(&bytes), vec![message[0]]);
+        assert_ne!(
+            PoseidonCOMM5::commit_field(&params, &message[..1], &r).unwrap(),
+            PoseidonCOMM5::commit(&params, &bytes, &r).unwrap()
+        );
+
+        assert_eq!(PoseidonCOMM5::MAX_FIELD_INPUT_LEN, 3);
+        assert!(PoseidonCOMM5::commit_field(&params, &[Fq::from(1u64); 3], &r).is_ok());
+        assert!(PoseidonCOMM5::commit_field(&params, &[Fq::from(1u64); 4], &r).is_err());
+    }
+
+    #[test]
+    fn poseidon_batch_commit_test() {
+        let rng = &mut ark_std::test_rng();
+        let params = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let inputs = (0..10u8)
+            .map(|i| (vec![i; i as usize * 6], Randomness::rand(rng)))
+            .collect::<Vec<_>>();
+        let inputs = inputs
+            .iter()
+            .map(|(input, r)| (input.as_slice(), *r))
+            .collect::<Vec<_>>();
+
+        let sequential = inputs
+            .iter()
+            .map(|(input, r)| PoseidonCOMM5::commit(&params, input, r).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            PoseidonCOMM5::batch_commit(&params, &inputs).unwrap(),
+            sequential
+        );
+        assert!(PoseidonCOMM5::batch_commit(&params, &[])
+            .unwrap()
+            .is_empty());
+
+        let too_long = vec![0u8; PoseidonCOMM5::MAX_INPUT_LEN + 1];
+        assert!(
+            PoseidonCOMM5::batch_commit(&params, &[(&too_long, Randomness::rand(rng))]).is_err()
+        );
+    }
+
+    #[test]
+    fn poseidon_randomness_test() {
+        let rng = &mut ark_std::test_rng();
+        let randomness = Randomness::<Fq>::rand(rng);
+        let mut bytes = Vec::new();
+        randomness.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), randomness.serialized_size());
+        assert_eq!(
+            Randomness::<Fq>::deserialize(&bytes[..]).unwrap(),
+            randomness
+        );
+
+        let seeded = Randomness::<Fq>::from_seed(b"wallet seed");
+        assert_eq!(seeded, Randomness::<Fq>::from_seed(b"wallet seed"));
+        assert_ne!(seeded, Randomness::<Fq>::from_seed(b"wallet seed 2"));
+        assert_ne!(seeded, Randomness::<Fq>::default());
+    }
+
+    #[test]
+    fn poseidon_to_leaf_test() {
+        let rng = &mut ark_std::test_rng();
+        let params = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let commitment = PoseidonCOMM5::commit(&params, b"note", &Randomness::rand(rng)).unwrap();
+        let leaf = PoseidonCOMM5::to_leaf(&commitment);
+        assert_eq!(leaf, vec![commitment]);
+        assert_eq!(PoseidonCOMM5::from_leaf(&leaf).unwrap(), commitment);
+        assert!(PoseidonCOMM5::from_leaf(&[]).is_err());
+        assert!(PoseidonCOMM5::from_leaf(&[commitment, commitment]).is_err());
+    }
+
+    #[test]
+    fn vector_commitment_test() {
+        type VectorCOMM5 = VectorCommitment<Fq, PoseidonRounds5>;
+
+        let rng = &mut ark_std::test_rng();
+        let params = PoseidonParameters::<Fq>::new(get_rounds_5(), get_mds_5());
+        let r = Randomness::rand(rng);
+        let values = (0..9u64).map(Fq::from).collect::<Vec<_>>();
+        let commitment = VectorCOMM5::commit(&params, &values, &r).unwrap();
+        assert_eq!(
+            commitment,
+            VectorCOMM5::commit(&params, &values, &r).unwrap()
+        );
+
+        // Changing any element changes the commitment.
+        for i in 0..values.len() {
+            let mut changed = values.clone();
+            changed[i] += Fq::from(1u64);
+            assert_ne!(
+                commitment,
+                VectorCOMM5::commit(&params, &changed, &r).unwrap()
+            );
+        }
+        // So does the order, appending a zero, and the randomness.
+        let mut swapped = values.clone();
+        swapped.swap(0, 1);
+        assert_ne!(
+            commitment,
+            VectorCOMM5::commit(&params, &swapped, &r).unwrap()
+        );
+        let extended = [&values[..], &[Fq::from(0u64)]].concat();
+        assert_ne!(
+            commitment,
+            VectorCOMM5::commit(&params, &extended, &r).unwrap()
+        );
+        assert_ne!(
+            commitment,
+            VectorCOMM5::commit(&params, &values, &Randomness::rand(rng)).unwrap()
+        );
+    }
+
+    #[test]
+    fn poseidon_commitment_bn254_test() {
+        let params = PoseidonParameters::<Fq>::new(get_rounds_3(), get_mds_3());
+        let commitment =
+            PoseidonCOMM3::commit(&params, &[1u8; 31], &Randomness(Fq::from(42u64))).unwrap();
+        // Pin the output, so that the encoding cannot change unnoticed.
+        let commitment_hex = to_bytes![commitment]
+            .unwrap()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        assert_eq!(
+            commitment_hex,
+            "1a4d62e3a88ec67b601702066510f7c7c4e5377f76ef1a7c9074188f8187002c"
+        );
+
+        // The same as hashing the packed elements with the Poseidon CRH.
+        let domain = Fq::from(2u64 + (31u64 << 8));
+        let message = Fq::from_le_bytes_mod_order(&[1u8; 31]);
+        let input = to_bytes![domain, message, Fq::from(42u64)].unwrap();
+        assert_eq!(
+            commitment,
+            CRH::<Fq, PoseidonRounds3>::evaluate(&params, &input).unwrap()
+        );
+    }
+}
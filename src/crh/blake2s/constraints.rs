@@ -0,0 +1,116 @@
+use super::{InputSize, CRH};
+use crate::prf::blake2s::constraints::{evaluate_blake2s, OutputVar};
+use crate::{utils::constraints as utils, FixedLengthCRHGadget, Vec};
+use ark_ff::PrimeField;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_std::marker::PhantomData;
+use core::borrow::Borrow;
+
+/// The empty parameters of the Blake2s CRH.
+#[derive(Clone, Default)]
+pub struct ParametersVar;
+
+impl<F: PrimeField> AllocVar<(), F> for ParametersVar {
+    fn new_variable<T: Borrow<()>>(
+        _cs: impl Into<Namespace<F>>,
+        _f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        Ok(ParametersVar)
+    }
+}
+
+/// Runs the Blake2s gadget of the PRF over the input padded exactly like the
+/// native CRH.
+pub struct CRHGadget<I: InputSize> {
+    input_size: PhantomData<I>,
+}
+
+impl<I: InputSize, F: PrimeField> FixedLengthCRHGadget<CRH<I>, F> for CRHGadget<I> {
+    type OutputVar = OutputVar<F>;
+    type ParametersVar = ParametersVar;
+
+    fn evaluate(
+        _parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        if input.len() > I::BYTES {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut padded = input.to_vec();
+        padded.resize(I::BYTES, UInt8::constant(0u8));
+
+        let bits = utils::bytes_to_bits_le(&padded)?;
+        let mut output = Vec::with_capacity(32);
+        for word in evaluate_blake2s(&bits)? {
+            output.extend(word.to_bytes()?);
+        }
+        Ok(OutputVar(output))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::blake2s::test::{to_hex, Empty, ThreeBytes, ABC_DIGEST, EMPTY_DIGEST};
+    use crate::crh::FixedLengthCRH;
+    use ark_ed_on_bls12_381::Fq;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::Rng;
+
+    #[derive(Clone)]
+    struct SixtyFourBytes;
+    impl InputSize for SixtyFourBytes {
+        const BYTES: usize = 64;
+    }
+
+    fn gadget_output<I: InputSize>(input: &[u8]) -> [u8; 32] {
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = ParametersVar::new_constant(cs.clone(), ()).unwrap();
+        let input_var = UInt8::new_witness_vec(cs.clone(), input).unwrap();
+        let output_var = CRHGadget::<I>::evaluate(&params_var, &input_var).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        output_var.value().unwrap()
+    }
+
+    #[test]
+    fn rfc_vectors_test() {
+        assert_eq!(to_hex(&gadget_output::<Empty>(&[])), EMPTY_DIGEST);
+        assert_eq!(to_hex(&gadget_output::<ThreeBytes>(b"abc")), ABC_DIGEST);
+    }
+
+    #[test]
+    fn native_equality_test() {
+        let mut rng = ark_std::test_rng();
+        for len in [0, 1, 31, 63, 64].iter() {
+            let input = (0..*len).map(|_| rng.gen()).collect::<Vec<u8>>();
+            assert_eq!(
+                gadget_output::<SixtyFourBytes>(&input),
+                CRH::<SixtyFourBytes>::evaluate(&(), &input).unwrap()
+            );
+        }
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let input_var = UInt8::new_witness_vec(cs.clone(), &[0u8; 65]).unwrap();
+        assert!(matches!(
+            CRHGadget::<SixtyFourBytes>::evaluate(&ParametersVar, &input_var),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn conditional_select_test() {
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let left = CRH::<ThreeBytes>::evaluate(&(), b"abc").unwrap();
+        let right = CRH::<ThreeBytes>::evaluate(&(), b"xyz").unwrap();
+        let left_var = OutputVar::new_witness(cs.clone(), || Ok(left)).unwrap();
+        let right_var = OutputVar::new_witness(cs.clone(), || Ok(right)).unwrap();
+        for (bit, expected) in [(true, left), (false, right)].iter() {
+            let cond = Boolean::new_witness(cs.clone(), || Ok(*bit)).unwrap();
+            let selected = OutputVar::conditionally_select(&cond, &left_var, &right_var).unwrap();
+            assert_eq!(selected.value().unwrap(), *expected);
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
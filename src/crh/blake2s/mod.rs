@@ -0,0 +1,121 @@
+//! Blake2s as a [`FixedLengthCRH`], for data that is mostly hashed outside
+//! of circuits. Inputs shorter than [`InputSize::BYTES`] are padded with
+//! zeros, natively and in the gadget alike, so inputs that differ only by
+//! trailing zeros collide, as for the Pedersen CRH.
+
+use crate::crh::FixedLengthCRH;
+use crate::{CryptoError, Error, Vec};
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+use blake2::Blake2s;
+use digest::Digest;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The input length of a [`CRH`].
+pub trait InputSize: Clone {
+    /// The number of bytes hashed, after padding.
+    const BYTES: usize;
+}
+
+/// Blake2s-256 of the input padded to `I::BYTES` bytes, with an unkeyed,
+/// default parameter block. The parameters are empty.
+pub struct CRH<I: InputSize> {
+    input_size: PhantomData<I>,
+}
+
+impl<I: InputSize> CRH<I> {
+    pub(crate) fn pad(input: &[u8]) -> Result<Vec<u8>, Error> {
+        if input.len() > I::BYTES {
+            return Err(CryptoError::InputTooLong {
+                bits: input.len() * 8,
+                max_bits: I::BYTES * 8,
+            }
+            .into());
+        }
+        let mut padded = input.to_vec();
+        padded.resize(I::BYTES, 0u8);
+        Ok(padded)
+    }
+}
+
+impl<I: InputSize> FixedLengthCRH for CRH<I> {
+    const INPUT_SIZE_BITS: usize = I::BYTES * 8;
+    type Output = [u8; 32];
+    type Parameters = ();
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn evaluate(_parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
+        let eval_time = start_timer!(|| "Blake2sCRH::Eval");
+
+        let padded = match Self::pad(input) {
+            Ok(padded) => padded,
+            Err(e) => {
+                end_timer!(eval_time);
+                return Err(e);
+            }
+        };
+        let mut result = [0u8; 32];
+        result.copy_from_slice(&Blake2s::digest(&padded));
+
+        end_timer!(eval_time);
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone)]
+    pub(super) struct Empty;
+    impl InputSize for Empty {
+        const BYTES: usize = 0;
+    }
+
+    #[derive(Clone)]
+    pub(super) struct ThreeBytes;
+    impl InputSize for ThreeBytes {
+        const BYTES: usize = 3;
+    }
+
+    /// Blake2s-256 of the empty string and of "abc", the latter from
+    /// appendix B of RFC 7693.
+    pub(super) const EMPTY_DIGEST: &str =
+        "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9";
+    pub(super) const ABC_DIGEST: &str =
+        "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982";
+
+    pub(super) fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn rfc_vectors_test() {
+        let output = CRH::<Empty>::evaluate(&(), &[]).unwrap();
+        assert_eq!(to_hex(&output), EMPTY_DIGEST);
+        let output = CRH::<ThreeBytes>::evaluate(&(), b"abc").unwrap();
+        assert_eq!(to_hex(&output), ABC_DIGEST);
+    }
+
+    #[test]
+    fn padding_test() {
+        // Short inputs are padded with zeros.
+        let padded = CRH::<ThreeBytes>::evaluate(&(), b"ab").unwrap();
+        assert_eq!(padded, CRH::<ThreeBytes>::evaluate(&(), b"ab\0").unwrap());
+        assert_ne!(padded, CRH::<Empty>::evaluate(&(), &[]).unwrap());
+
+        assert!(matches!(
+            CRH::<ThreeBytes>::evaluate(&(), b"abcd"),
+            Err(Error::Crypto(CryptoError::InputTooLong {
+                bits: 32,
+                max_bits: 24
+            }))
+        ));
+    }
+}
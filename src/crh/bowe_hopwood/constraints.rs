@@ -37,35 +37,29 @@ where
     _base_field: PhantomData<F>,
 }
 
-impl<P, F, W> FixedLengthCRHGadget<CRH<P, W>, ConstraintF<P>> for CRHGadget<P, F>
+impl<P, F> CRHGadget<P, F>
 where
     for<'a> &'a F: FieldOpsBounds<'a, P::BaseField, F>,
     F: FieldVar<P::BaseField, ConstraintF<P>>,
     F: TwoBitLookupGadget<ConstraintF<P>, TableConstant = P::BaseField>
         + ThreeBitCondNegLookupGadget<ConstraintF<P>, TableConstant = P::BaseField>,
     P: TEModelParameters,
-    W: Window,
 {
-    type OutputVar = AffineVar<P, F>;
-    type ParametersVar = ParametersVar<P, W>;
-
+    /// The counterpart of the native `evaluate_bits`.
     #[tracing::instrument(target = "r1cs", skip(parameters, input))]
-    fn evaluate(
-        parameters: &Self::ParametersVar,
-        input: &[UInt8<ConstraintF<P>>],
-    ) -> Result<Self::OutputVar, SynthesisError> {
-        // Pad the input if it is not the current length.
-        let mut input_in_bits: Vec<Boolean<_>> = input
-            .iter()
-            .flat_map(|byte| byte.to_bits_le().unwrap())
-            .collect();
-        if (input_in_bits.len()) % CHUNK_SIZE != 0 {
-            let current_length = input_in_bits.len();
-            for _ in 0..(CHUNK_SIZE - current_length % CHUNK_SIZE) {
-                input_in_bits.push(Boolean::constant(false));
-            }
+    pub fn evaluate_bits<W: Window>(
+        parameters: &ParametersVar<P, W>,
+        input: &[Boolean<ConstraintF<P>>],
+    ) -> Result<AffineVar<P, F>, SynthesisError> {
+        if input.len() > W::WINDOW_SIZE * W::NUM_WINDOWS * CHUNK_SIZE {
+            return Err(SynthesisError::Unsatisfiable);
         }
-        assert!(input_in_bits.len() % CHUNK_SIZE == 0);
+        // Pad the input if it is not the current length.
+        let mut input_in_bits = input.to_vec();
+        input_in_bits.resize(
+            input.len().div_ceil(CHUNK_SIZE) * CHUNK_SIZE,
+            Boolean::constant(false),
+        );
         assert_eq!(parameters.params.generators.len(), W::NUM_WINDOWS);
         for generators in parameters.params.generators.iter() {
             assert_eq!(generators.len(), W::WINDOW_SIZE);
@@ -85,6 +79,31 @@ where
     }
 }
 
+impl<P, F, W> FixedLengthCRHGadget<CRH<P, W>, ConstraintF<P>> for CRHGadget<P, F>
+where
+    for<'a> &'a F: FieldOpsBounds<'a, P::BaseField, F>,
+    F: FieldVar<P::BaseField, ConstraintF<P>>,
+    F: TwoBitLookupGadget<ConstraintF<P>, TableConstant = P::BaseField>
+        + ThreeBitCondNegLookupGadget<ConstraintF<P>, TableConstant = P::BaseField>,
+    P: TEModelParameters,
+    W: Window,
+{
+    type OutputVar = AffineVar<P, F>;
+    type ParametersVar = ParametersVar<P, W>;
+
+    #[tracing::instrument(target = "r1cs", skip(parameters, input))]
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<ConstraintF<P>>],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let input_in_bits: Vec<Boolean<_>> = input
+            .iter()
+            .flat_map(|byte| byte.to_bits_le().unwrap())
+            .collect();
+        Self::evaluate_bits(parameters, &input_in_bits)
+    }
+}
+
 impl<P, W> AllocVar<Parameters<P>, ConstraintF<P>> for ParametersVar<P, W>
 where
     P: TEModelParameters,
@@ -12,12 +12,13 @@ use crate::crh::FixedLengthCRH;
 use ark_ec::{
     twisted_edwards_extended::GroupProjective as TEProjective, ProjectiveCurve, TEModelParameters,
 };
-use ark_ff::{biginteger::BigInteger, fields::PrimeField};
+use ark_ff::{biginteger::BigInteger, fields::PrimeField, Field, ToConstraintField};
 use ark_std::cfg_chunks;
 use ark_std::UniformRand;
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
+pub mod sapling;
 
 pub const CHUNK_SIZE: usize = 3;
 
@@ -48,52 +49,16 @@ impl<P: TEModelParameters, W: pedersen::Window> CRH<P, W> {
         }
         generators
     }
-}
-
-impl<P: TEModelParameters, W: pedersen::Window> FixedLengthCRH for CRH<P, W> {
-    const INPUT_SIZE_BITS: usize = pedersen::CRH::<TEProjective<P>, W>::INPUT_SIZE_BITS;
-    type Output = TEProjective<P>;
-    type Parameters = Parameters<P>;
-
-    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
-        fn calculate_num_chunks_in_segment<F: PrimeField>() -> usize {
-            let upper_limit = F::modulus_minus_one_div_two();
-            let mut c = 0;
-            let mut range = F::BigInt::from(2_u64);
-            while range < upper_limit {
-                range.muln(4);
-                c += 1;
-            }
 
-            c
-        }
-
-        let maximum_num_chunks_in_segment = calculate_num_chunks_in_segment::<P::ScalarField>();
-        if W::WINDOW_SIZE > maximum_num_chunks_in_segment {
+    /// Hashes a sequence of bits, which need not fill whole bytes. The bits
+    /// are padded with zeros to a multiple of [`CHUNK_SIZE`]; segments past
+    /// the end of the input are not used.
+    ///
+    /// Panics if there are more bits than the windows hold.
+    pub fn evaluate_bits(parameters: &Parameters<P>, input: &[bool]) -> TEProjective<P> {
+        if input.len() > W::WINDOW_SIZE * W::NUM_WINDOWS * CHUNK_SIZE {
             panic!(
-                "Bowe-Hopwood-PedersenCRH hash must have a window size resulting in scalars < (p-1)/2, \
-                 maximum segment size is {}",
-                maximum_num_chunks_in_segment
-            );
-        }
-
-        let time = start_timer!(|| format!(
-            "Bowe-Hopwood-PedersenCRH::Setup: {} segments of {} 3-bit chunks; {{0,1}}^{{{}}} -> P",
-            W::NUM_WINDOWS,
-            W::WINDOW_SIZE,
-            W::WINDOW_SIZE * W::NUM_WINDOWS * CHUNK_SIZE
-        ));
-        let generators = Self::create_generators(rng);
-        end_timer!(time);
-        Ok(Self::Parameters { generators })
-    }
-
-    fn evaluate(parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
-        let eval_time = start_timer!(|| "BoweHopwoodPedersenCRH::Eval");
-
-        if (input.len() * 8) > W::WINDOW_SIZE * W::NUM_WINDOWS * CHUNK_SIZE {
-            panic!(
-                "incorrect input length {:?} for window params {:?}x{:?}x{}",
+                "incorrect input length of {:?} bits for window params {:?}x{:?}x{}",
                 input.len(),
                 W::WINDOW_SIZE,
                 W::NUM_WINDOWS,
@@ -102,9 +67,8 @@ impl<P: TEModelParameters, W: pedersen::Window> FixedLengthCRH for CRH<P, W> {
         }
 
         let mut padded_input = Vec::with_capacity(input.len());
-        let input = pedersen::bytes_to_bits(input);
         // Pad the input if it is not the current length.
-        padded_input.extend_from_slice(&input);
+        padded_input.extend_from_slice(input);
         if input.len() % CHUNK_SIZE != 0 {
             let remaining = CHUNK_SIZE - input.len() % CHUNK_SIZE;
             padded_input.extend_from_slice(&vec![false; remaining]);
@@ -131,7 +95,7 @@ impl<P: TEModelParameters, W: pedersen::Window> FixedLengthCRH for CRH<P, W> {
         // for all i. Described in section 5.4.1.7 in the Zcash protocol
         // specification.
 
-        let result = cfg_chunks!(padded_input, W::WINDOW_SIZE * CHUNK_SIZE)
+        cfg_chunks!(padded_input, W::WINDOW_SIZE * CHUNK_SIZE)
             .zip(&parameters.generators)
             .map(|(segment_bits, segment_generators)| {
                 cfg_chunks!(segment_bits, CHUNK_SIZE)
@@ -151,7 +115,62 @@ impl<P: TEModelParameters, W: pedersen::Window> FixedLengthCRH for CRH<P, W> {
                     })
                     .sum::<TEProjective<P>>()
             })
-            .sum::<TEProjective<P>>();
+            .sum::<TEProjective<P>>()
+    }
+}
+
+impl<P: TEModelParameters, W: pedersen::Window> FixedLengthCRH for CRH<P, W> {
+    const INPUT_SIZE_BITS: usize = pedersen::CRH::<TEProjective<P>, W>::INPUT_SIZE_BITS;
+    type Output = TEProjective<P>;
+    type Parameters = Parameters<P>;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        fn calculate_num_chunks_in_segment<F: PrimeField>() -> usize {
+            let upper_limit = F::modulus_minus_one_div_two();
+            let mut c = 0;
+            let mut range = F::BigInt::from(2_u64);
+            while range < upper_limit {
+                range.muln(4);
+                c += 1;
+            }
+
+            c
+        }
+
+        let maximum_num_chunks_in_segment = calculate_num_chunks_in_segment::<P::ScalarField>();
+        if W::WINDOW_SIZE > maximum_num_chunks_in_segment {
+            panic!(
+                "Bowe-Hopwood-PedersenCRH hash must have a window size resulting in scalars < (p-1)/2, \
+                 maximum segment size is {}",
+                maximum_num_chunks_in_segment
+            );
+        }
+
+        let time = start_timer!(|| format!(
+            "Bowe-Hopwood-PedersenCRH::Setup: {} segments of {} 3-bit chunks; {{0,1}}^{{{}}} -> P",
+            W::NUM_WINDOWS,
+            W::WINDOW_SIZE,
+            W::WINDOW_SIZE * W::NUM_WINDOWS * CHUNK_SIZE
+        ));
+        let generators = Self::create_generators(rng);
+        end_timer!(time);
+        Ok(Self::Parameters { generators })
+    }
+
+    fn evaluate(parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
+        let eval_time = start_timer!(|| "BoweHopwoodPedersenCRH::Eval");
+
+        if (input.len() * 8) > W::WINDOW_SIZE * W::NUM_WINDOWS * CHUNK_SIZE {
+            panic!(
+                "incorrect input length {:?} for window params {:?}x{:?}x{}",
+                input.len(),
+                W::WINDOW_SIZE,
+                W::NUM_WINDOWS,
+                CHUNK_SIZE,
+            );
+        }
+
+        let result = Self::evaluate_bits(parameters, &pedersen::bytes_to_bits(input));
 
         end_timer!(eval_time);
 
@@ -169,6 +188,15 @@ impl<P: TEModelParameters> Debug for Parameters<P> {
     }
 }
 
+/// As for Pedersen, the generators are constants of the circuit and
+/// contribute no public inputs.
+impl<ConstraintF: Field, P: TEModelParameters> ToConstraintField<ConstraintF> for Parameters<P> {
+    #[inline]
+    fn to_field_elements(&self) -> Option<Vec<ConstraintF>> {
+        Some(Vec::new())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
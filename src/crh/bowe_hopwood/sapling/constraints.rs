@@ -0,0 +1,97 @@
+use super::{Personalization, SaplingWindow};
+use crate::crh::bowe_hopwood::constraints::{CRHGadget, ParametersVar};
+use crate::Vec;
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    fields::fp::FpVar, groups::curves::twisted_edwards::AffineVar, prelude::*, ToBitsGadget,
+};
+use ark_relations::r1cs::SynthesisError;
+
+/// The counterpart of the native `pedersen_hash`. The personalization is a
+/// constant of the circuit.
+pub fn pedersen_hash<P>(
+    parameters: &ParametersVar<P, SaplingWindow>,
+    personalization: Personalization,
+    bits: &[Boolean<P::BaseField>],
+) -> Result<AffineVar<P, FpVar<P::BaseField>>, SynthesisError>
+where
+    P: TEModelParameters,
+    P::BaseField: PrimeField,
+{
+    let mut input = personalization
+        .bits()
+        .into_iter()
+        .map(Boolean::constant)
+        .collect::<Vec<_>>();
+    input.extend_from_slice(bits);
+    CRHGadget::evaluate_bits(parameters, &input)
+}
+
+/// The counterpart of the native `merkle_hash`. The nodes are decomposed
+/// into their canonical bits.
+pub fn merkle_hash<P>(
+    parameters: &ParametersVar<P, SaplingWindow>,
+    depth: usize,
+    left: &FpVar<P::BaseField>,
+    right: &FpVar<P::BaseField>,
+) -> Result<FpVar<P::BaseField>, SynthesisError>
+where
+    P: TEModelParameters,
+    P::BaseField: PrimeField,
+{
+    let mut bits = left.to_bits_le()?;
+    bits.extend(right.to_bits_le()?);
+    Ok(pedersen_hash(parameters, Personalization::MerkleTree(depth), &bits)?.x)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::bowe_hopwood::sapling::{self, test::fq_from_le_hex, test::EMPTY_ROOTS};
+    use ark_ec::ProjectiveCurve;
+    use ark_ed_on_bls12_381::{EdwardsParameters, Fq};
+    use ark_ff::One;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::Rng;
+    use ark_std::UniformRand;
+
+    #[test]
+    fn empty_roots_test() {
+        let parameters = sapling::parameters::<EdwardsParameters>();
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let parameters_var = ParametersVar::new_constant(cs.clone(), &parameters).unwrap();
+        let mut root = FpVar::new_witness(cs.clone(), || Ok(Fq::one())).unwrap();
+        for (depth, expected) in EMPTY_ROOTS.iter().enumerate() {
+            root = merkle_hash(&parameters_var, depth, &root, &root).unwrap();
+            assert_eq!(root.value().unwrap(), fq_from_le_hex(expected));
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn native_equality_test() {
+        let rng = &mut ark_std::test_rng();
+        let parameters = sapling::parameters::<EdwardsParameters>();
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let parameters_var = ParametersVar::new_constant(cs.clone(), &parameters).unwrap();
+
+        // A length that fills neither a chunk nor a segment.
+        let bits = (0..400).map(|_| rng.gen()).collect::<Vec<bool>>();
+        let bits_var = Vec::<Boolean<Fq>>::new_witness(cs.clone(), || Ok(bits.clone())).unwrap();
+        let hash_var =
+            pedersen_hash(&parameters_var, Personalization::NoteCommitment, &bits_var).unwrap();
+        let hash = sapling::pedersen_hash(&parameters, Personalization::NoteCommitment, &bits);
+        assert_eq!(hash_var.value().unwrap(), hash.into_affine());
+
+        let (left, right) = (Fq::rand(rng), Fq::rand(rng));
+        let left_var = FpVar::new_witness(cs.clone(), || Ok(left)).unwrap();
+        let right_var = FpVar::new_witness(cs.clone(), || Ok(right)).unwrap();
+        let hash_var = merkle_hash(&parameters_var, 31, &left_var, &right_var).unwrap();
+        assert_eq!(
+            hash_var.value().unwrap(),
+            sapling::merkle_hash(&parameters, 31, &left, &right)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,238 @@
+//! The Pedersen hash of Zcash Sapling (section 5.4.1.7 of the protocol
+//! specification): the Bowe-Hopwood CRH over 63 chunks of 3 bits per
+//! segment, with the segment generators derived by `FindGroupHash` from
+//! the `"Zcash_PH"` personalization, and a personalization prefixed to the
+//! input bits.
+//!
+//! Everything is generic over the twisted Edwards curve, but the outputs
+//! only match Zcash's on Jubjub, `ark_ed_on_bls12_381`.
+
+use super::{Parameters, CRH};
+use crate::crh::pedersen::Window;
+use crate::Vec;
+use ark_ec::{
+    twisted_edwards_extended::{GroupAffine as TEAffine, GroupProjective as TEProjective},
+    AffineCurve, ProjectiveCurve, TEModelParameters,
+};
+use ark_ff::{BigInteger, Field, FromBytes, One, PrimeField, SquareRootField, Zero};
+use blake2::Blake2s;
+use digest::Digest;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The Blake2s personalization from which the generators are derived.
+pub const GENERATORS_PERSONALIZATION: [u8; 8] = *b"Zcash_PH";
+
+/// The first block hashed by `GroupHash`, the hex digits of a Blake2s digest
+/// of no particular structure, as ASCII.
+pub const GH_FIRST_BLOCK: &[u8; 64] =
+    b"096b36a5804bfacef1691e173c366a47ff5ba84a44f26ddd7e8d9f79d5b42df0";
+
+/// Sapling's segments of 63 chunks, with enough segments for its note
+/// commitments and Merkle tree nodes.
+#[derive(Clone)]
+pub struct SaplingWindow;
+
+impl Window for SaplingWindow {
+    const WINDOW_SIZE: usize = 63;
+    const NUM_WINDOWS: usize = 6;
+}
+
+/// The bits prefixed to the input of the hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Personalization {
+    /// Six ones.
+    NoteCommitment,
+    /// The depth of the hashed nodes, from the leaves, as six
+    /// little-endian bits.
+    MerkleTree(usize),
+}
+
+impl Personalization {
+    pub fn bits(&self) -> Vec<bool> {
+        match self {
+            Personalization::NoteCommitment => vec![true; 6],
+            Personalization::MerkleTree(depth) => (0..6).map(|i| (depth >> i) & 1 == 1).collect(),
+        }
+    }
+}
+
+/// `GroupHash`: the Blake2s digest of [`GH_FIRST_BLOCK`] and `tag`, read as
+/// a compressed point and multiplied by the cofactor. `None` if the digest
+/// is not a point or the product is the identity.
+pub fn group_hash<P: TEModelParameters>(
+    tag: &[u8],
+    personalization: &[u8; 8],
+) -> Option<TEProjective<P>>
+where
+    P::BaseField: PrimeField,
+{
+    let mut h = Blake2s::with_params(&[], &[], personalization);
+    h.update(GH_FIRST_BLOCK.as_ref());
+    h.update(tag);
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&h.finalize());
+
+    let point = decompress::<P>(&bytes)?.mul_by_cofactor_to_projective();
+    if point.is_zero() {
+        None
+    } else {
+        Some(point)
+    }
+}
+
+/// `FindGroupHash`: [`group_hash`] of `m` followed by the first byte for
+/// which it succeeds. `None` if none does.
+pub fn find_group_hash<P: TEModelParameters>(
+    m: &[u8],
+    personalization: &[u8; 8],
+) -> Option<TEProjective<P>>
+where
+    P::BaseField: PrimeField,
+{
+    let mut tag = m.to_vec();
+    tag.push(0);
+    for i in 0..=255u8 {
+        *tag.last_mut().unwrap() = i;
+        if let Some(point) = group_hash::<P>(&tag, personalization) {
+            return Some(point);
+        }
+    }
+    None
+}
+
+/// Reads the encoding of a point as the little-endian `y` coordinate, with
+/// the parity of `x` in the top bit. `None` for non-canonical encodings and
+/// coordinates that are not on the curve.
+fn decompress<P: TEModelParameters>(bytes: &[u8; 32]) -> Option<TEAffine<P>>
+where
+    P::BaseField: PrimeField,
+{
+    let mut bytes = *bytes;
+    let sign = bytes[31] >> 7 == 1;
+    bytes[31] &= 0x7f;
+    let repr = <P::BaseField as PrimeField>::BigInt::read(&bytes[..]).ok()?;
+    let y = P::BaseField::from_repr(repr)?;
+
+    // a x^2 + y^2 = 1 + d x^2 y^2
+    let y2 = y.square();
+    let x2 = (y2 - P::BaseField::one()) * (P::COEFF_D * y2 - P::COEFF_A).inverse()?;
+    let mut x = x2.sqrt()?;
+    if x.is_zero() && sign {
+        return None;
+    }
+    if x.into_repr().is_odd() != sign {
+        x = -x;
+    }
+    Some(TEAffine::new(x, y))
+}
+
+/// The generators of [`SaplingWindow`]: the `i`-th segment starts at
+/// `FindGroupHash("Zcash_PH", i)`, with `i` as four little-endian bytes.
+pub fn parameters<P: TEModelParameters>() -> Parameters<P>
+where
+    P::BaseField: PrimeField,
+{
+    let generators = (0..SaplingWindow::NUM_WINDOWS as u32)
+        .map(|i| {
+            let mut base = find_group_hash::<P>(&i.to_le_bytes(), &GENERATORS_PERSONALIZATION)
+                .expect("a generator for every segment");
+            let mut generators_for_segment = Vec::with_capacity(SaplingWindow::WINDOW_SIZE);
+            for _ in 0..SaplingWindow::WINDOW_SIZE {
+                generators_for_segment.push(base);
+                for _ in 0..4 {
+                    base.double_in_place();
+                }
+            }
+            generators_for_segment
+        })
+        .collect();
+    Parameters { generators }
+}
+
+/// `PedersenHashToPoint` of the personalization bits followed by `bits`.
+pub fn pedersen_hash<P: TEModelParameters>(
+    parameters: &Parameters<P>,
+    personalization: Personalization,
+    bits: &[bool],
+) -> TEProjective<P> {
+    let mut input = personalization.bits();
+    input.extend_from_slice(bits);
+    CRH::<P, SaplingWindow>::evaluate_bits(parameters, &input)
+}
+
+/// `MerkleCRH`: the `x` coordinate of the hash of two nodes at `depth`
+/// above the leaves, each as the little-endian bits of its canonical
+/// representation.
+pub fn merkle_hash<P: TEModelParameters>(
+    parameters: &Parameters<P>,
+    depth: usize,
+    left: &P::BaseField,
+    right: &P::BaseField,
+) -> P::BaseField
+where
+    P::BaseField: PrimeField,
+{
+    let num_bits = <P::BaseField as PrimeField>::size_in_bits();
+    let mut bits = left.into_repr().to_bits_le();
+    bits.truncate(num_bits);
+    let mut right_bits = right.into_repr().to_bits_le();
+    right_bits.truncate(num_bits);
+    bits.extend(right_bits);
+    pedersen_hash(parameters, Personalization::MerkleTree(depth), &bits)
+        .into_affine()
+        .x
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ed_on_bls12_381::{EdwardsParameters, Fq};
+
+    pub(super) fn fq_from_le_hex(hex: &str) -> Fq {
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect::<Vec<_>>();
+        Fq::from_repr(<Fq as PrimeField>::BigInt::read(&bytes[..]).unwrap()).unwrap()
+    }
+
+    /// The roots of empty Sapling note commitment trees of depth 1 to 3, as
+    /// little-endian bytes: zcashd's `merkle_roots_empty_sapling.json`. The
+    /// root of depth `i + 1` is `MerkleCRH(i, r_i, r_i)`, starting from the
+    /// uncommitted leaf 1.
+    pub(super) const EMPTY_ROOTS: [&str; 3] = [
+        "817de36ab2d57feb077634bca77819c8e0bd298c04f6fed0e6a83cc1356ca155",
+        "ffe9fc03f18b176c998806439ff0bb8ad193afdb27b2ccbc88856916dd804e34",
+        "d8283386ef2ef07ebdbb4383c12a739a953a4d6e0d6fb1139a4036d693bfbb6c",
+    ];
+
+    #[test]
+    fn empty_roots_test() {
+        let parameters = parameters::<EdwardsParameters>();
+        let mut root = Fq::one();
+        for (depth, expected) in EMPTY_ROOTS.iter().enumerate() {
+            root = merkle_hash(&parameters, depth, &root, &root);
+            assert_eq!(root, fq_from_le_hex(expected));
+        }
+    }
+
+    #[test]
+    fn personalization_test() {
+        assert_eq!(
+            Personalization::MerkleTree(5).bits(),
+            vec![true, false, true, false, false, false]
+        );
+        assert_eq!(Personalization::NoteCommitment.bits(), vec![true; 6]);
+
+        // The generators are in the prime-order subgroup.
+        let parameters = parameters::<EdwardsParameters>();
+        for generators in parameters.generators.iter() {
+            let generator = generators[0].into_affine();
+            assert!(generator.is_in_correct_subgroup_assuming_on_curve());
+            assert!(generator.is_on_curve());
+        }
+        assert!(find_group_hash::<EdwardsParameters>(&[], b"Zcash_PH").is_some());
+    }
+}
@@ -0,0 +1,185 @@
+use super::GMiMCParameters;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::{alloc::AllocVar, fields::FieldVar, prelude::*};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+use core::borrow::Borrow;
+
+#[derive(Clone)]
+pub struct GMiMCParametersVar<F: PrimeField> {
+    /// The round constants, one per round.
+    pub round_keys: Vec<FpVar<F>>,
+    /// The size of the permutation, in field elements.
+    pub width: usize,
+    /// The exponent of the S-box.
+    pub exponent: u64,
+}
+
+pub struct CRHGadget<F: PrimeField> {
+    field: PhantomData<F>,
+}
+
+impl<F: PrimeField> CRHGadget<F> {
+    /// The counterpart of the native `permute`. Each round costs the
+    /// constraints of one exponentiation and one more, whatever the width.
+    pub fn permute(
+        parameters: &GMiMCParametersVar<F>,
+        input: Vec<FpVar<F>>,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        if input.len() != parameters.width || parameters.width < 2 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut state = input;
+        for c in &parameters.round_keys {
+            // `x_0 + c` is allocated so that `x_0` starts over as a short
+            // linear combination. Otherwise every element would carry all
+            // the S-boxes added to it since the first round.
+            let mut input = &state[0] + c;
+            if !input.is_constant() {
+                let allocated = FpVar::new_witness(input.cs(), || input.value())?;
+                allocated.enforce_equal(&input)?;
+                input = allocated;
+            }
+            let sbox = input.pow_by_constant([parameters.exponent])?;
+            state[0] = input - c;
+            for x in state[1..].iter_mut() {
+                *x += &sbox;
+            }
+            state.rotate_left(1);
+        }
+        Ok(state)
+    }
+
+    /// The counterpart of the native `hash`. The number of values is fixed
+    /// by the circuit, so the first element of the state is a constant.
+    pub fn hash(
+        parameters: &GMiMCParametersVar<F>,
+        values: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        if parameters.width < 2 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut state = vec![FpVar::zero(); parameters.width];
+        state[0] = FpVar::constant(F::from(values.len() as u64));
+        if values.is_empty() {
+            return Ok(Self::permute(parameters, state)?[1].clone());
+        }
+        for chunk in values.chunks(parameters.width - 1) {
+            for (s, value) in state[1..].iter_mut().zip(chunk) {
+                *s += value;
+            }
+            state = Self::permute(parameters, state)?;
+        }
+        Ok(state[1].clone())
+    }
+}
+
+impl<F: PrimeField> AllocVar<GMiMCParameters<F>, F> for GMiMCParametersVar<F> {
+    #[tracing::instrument(target = "r1cs", skip(_cs, f))]
+    fn new_variable<T: Borrow<GMiMCParameters<F>>>(
+        _cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let params = f()?.borrow().clone();
+        Ok(Self {
+            round_keys: params.round_keys.into_iter().map(FpVar::Constant).collect(),
+            width: params.width,
+            exponent: params.exponent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::gmimc::test_data::{get_rounds, EXPONENT};
+    use crate::crh::gmimc::CRH;
+    use ark_ed_on_bn254::Fq;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+
+    #[test]
+    fn native_equality_test() {
+        let rng = &mut ark_std::test_rng();
+        for width in [2, 3, 17].iter() {
+            let params = GMiMCParameters::<Fq>::new(get_rounds(), *width, EXPONENT);
+            let values = (0..40).map(|_| Fq::rand(rng)).collect::<Vec<_>>();
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let params_var = GMiMCParametersVar::new_constant(cs.clone(), &params).unwrap();
+            let values_var =
+                Vec::<FpVar<Fq>>::new_witness(cs.clone(), || Ok(values.clone())).unwrap();
+            let hash_var = CRHGadget::hash(&params_var, &values_var).unwrap();
+            assert_eq!(
+                hash_var.value().unwrap(),
+                CRH::hash(&params, &values).unwrap()
+            );
+
+            let state = &values_var[..*width];
+            let before = cs.num_constraints();
+            let permuted = CRHGadget::permute(&params_var, state.to_vec()).unwrap();
+            assert_eq!(
+                permuted.value().unwrap(),
+                CRH::permute(&params, values[..*width].to_vec()).unwrap()
+            );
+            assert_eq!(cs.num_constraints() - before, 226 * (3 + 1));
+            assert!(cs.is_satisfied().unwrap());
+
+            assert!(matches!(
+                CRHGadget::permute(&params_var, values_var[..*width + 1].to_vec()),
+                Err(SynthesisError::Unsatisfiable)
+            ));
+        }
+    }
+
+    #[test]
+    fn poseidon_cost_test() {
+        use crate::crh::poseidon::constraints::{
+            CRHGadget as PoseidonGadget, PoseidonParametersVar,
+        };
+        use crate::crh::poseidon::sbox::PoseidonSbox;
+        use crate::crh::poseidon::test_data::{get_mds_5, get_rounds_5};
+        use crate::crh::poseidon::{PoseidonParameters, Rounds};
+
+        #[derive(Default, Clone)]
+        struct PoseidonRounds5;
+
+        impl Rounds for PoseidonRounds5 {
+            const WIDTH: usize = 5;
+            const PARTIAL_ROUNDS: usize = 60;
+            const FULL_ROUNDS: usize = 8;
+            const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+        }
+
+        let permutation_cost = |width: usize| {
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let state = (0..width as u64)
+                .map(|i| FpVar::new_witness(cs.clone(), || Ok(Fq::from(i))).unwrap())
+                .collect::<Vec<_>>();
+            if width == 5 {
+                let params = PoseidonParameters::new(get_rounds_5(), get_mds_5());
+                let params_var = PoseidonParametersVar::new_constant(cs.clone(), &params).unwrap();
+                PoseidonGadget::<Fq, PoseidonRounds5>::permute(&params_var, state).unwrap();
+            } else {
+                let params = GMiMCParameters::<Fq>::new(get_rounds(), width, EXPONENT);
+                let params_var = GMiMCParametersVar::new_constant(cs.clone(), &params).unwrap();
+                CRHGadget::permute(&params_var, state).unwrap();
+            }
+            cs.num_constraints()
+        };
+
+        // Both absorb `width - 1` elements per permutation.
+        let poseidon = permutation_cost(5) as f64 / 4.0;
+        let gmimc = permutation_cost(17) as f64 / 16.0;
+        println!(
+            "constraints per absorbed element: Poseidon (width 5) {:.1}, GMiMC-erf (width 17, {} rounds) {:.1}",
+            poseidon,
+            get_rounds::<Fq>().len(),
+            gmimc
+        );
+        assert!(gmimc < poseidon);
+    }
+}
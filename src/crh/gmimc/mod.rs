@@ -0,0 +1,182 @@
+//! GMiMC-erf, the generalized MiMC with an expanding round function: each
+//! round computes a single S-box `(x_0 + c_i)^d`, adds it to every other
+//! element of the state and rotates the state left by one. A round costs
+//! one S-box whatever the width, which makes wide states cheap per element.
+//!
+//! The width is chosen at run time, by the parameters, rather than by a
+//! type as for Poseidon.
+
+use crate::{CryptoError, Vec};
+use ark_ff::fields::PrimeField;
+use ark_ff::ToConstraintField;
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+
+#[cfg(test)]
+pub mod test_data;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The GMiMC-erf permutation.
+#[derive(Default, Clone, Debug)]
+pub struct GMiMCParameters<F> {
+    /// The round constants, one per round.
+    pub round_keys: Vec<F>,
+    /// The size of the permutation, in field elements; at least two.
+    pub width: usize,
+    /// The exponent of the S-box, which must be coprime to the order of the
+    /// multiplicative group of `F`.
+    pub exponent: u64,
+}
+
+impl<F: PrimeField> GMiMCParameters<F> {
+    pub fn new(round_keys: Vec<F>, width: usize, exponent: u64) -> Self {
+        Self {
+            round_keys,
+            width,
+            exponent,
+        }
+    }
+
+    /// Random round constants for `rounds` rounds. The number of rounds
+    /// needed for a given security level depends on the width and the
+    /// exponent, and is left to the caller.
+    pub fn generate<R: Rng>(rng: &mut R, width: usize, rounds: usize, exponent: u64) -> Self {
+        let round_keys = (0..rounds).map(|_| F::rand(rng)).collect();
+        Self::new(round_keys, width, exponent)
+    }
+
+    /// The number of elements absorbed by each permutation of
+    /// [`CRH::hash`].
+    pub fn rate(&self) -> usize {
+        self.width - 1
+    }
+}
+
+/// The parameters are constants of the circuit (see `GMiMCParametersVar`),
+/// so they contribute no public inputs.
+impl<F: PrimeField> ToConstraintField<F> for GMiMCParameters<F> {
+    #[inline]
+    fn to_field_elements(&self) -> Option<Vec<F>> {
+        Some(Vec::new())
+    }
+}
+
+pub struct CRH<F: PrimeField> {
+    field: PhantomData<F>,
+}
+
+impl<F: PrimeField> CRH<F> {
+    /// Permutes a state of `parameters.width` elements.
+    pub fn permute(parameters: &GMiMCParameters<F>, input: Vec<F>) -> Result<Vec<F>, CryptoError> {
+        if input.len() != parameters.width || parameters.width < 2 {
+            return Err(CryptoError::InvalidLength {
+                expected: parameters.width,
+                got: input.len(),
+            });
+        }
+        let mut state = input;
+        for c in &parameters.round_keys {
+            let sbox = (state[0] + c).pow([parameters.exponent]);
+            for x in state[1..].iter_mut() {
+                *x += sbox;
+            }
+            state.rotate_left(1);
+        }
+        Ok(state)
+    }
+
+    /// Hashes any number of field elements with a capacity of one element:
+    /// the state starts as `[n, 0, ..., 0]` for `n` values, the values are
+    /// added to its last `width - 1` elements in chunks, permuting after
+    /// each chunk, and the output is the second element of the state. No
+    /// values are absorbed as a single empty chunk.
+    ///
+    /// The count in the first element keeps inputs that differ by trailing
+    /// zeros apart.
+    pub fn hash(parameters: &GMiMCParameters<F>, values: &[F]) -> Result<F, CryptoError> {
+        if parameters.width < 2 {
+            return Err(CryptoError::InvalidLength {
+                expected: 2,
+                got: parameters.width,
+            });
+        }
+        let mut state = vec![F::zero(); parameters.width];
+        state[0] = F::from(values.len() as u64);
+        if values.is_empty() {
+            return Ok(Self::permute(parameters, state)?[1]);
+        }
+        for chunk in values.chunks(parameters.rate()) {
+            for (s, value) in state[1..].iter_mut().zip(chunk) {
+                *s += value;
+            }
+            state = Self::permute(parameters, state)?;
+        }
+        Ok(state[1])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::hex::{fe_from_hex, fes_from_hex};
+    use ark_ed_on_bn254::Fq;
+    use ark_ff::Zero;
+
+    use test_data::{get_rounds, EXPONENT};
+
+    fn parameters(width: usize) -> GMiMCParameters<Fq> {
+        GMiMCParameters::new(get_rounds(), width, EXPONENT)
+    }
+
+    #[test]
+    fn regression_snapshot_test() {
+        let state = (0..3u64).map(Fq::from).collect();
+        assert_eq!(
+            CRH::permute(&parameters(3), state).unwrap(),
+            fes_from_hex::<Fq>(&test_data::PERMUTATION_3).unwrap()
+        );
+        let state = (0..17u64).map(Fq::from).collect();
+        assert_eq!(
+            CRH::permute(&parameters(17), state).unwrap(),
+            fes_from_hex::<Fq>(&test_data::PERMUTATION_17).unwrap()
+        );
+
+        let values = [Fq::from(1u64), Fq::from(2u64)];
+        assert_eq!(
+            CRH::hash(&parameters(3), &values).unwrap(),
+            fe_from_hex::<Fq>(test_data::HASH_3).unwrap()
+        );
+        let values = (1..=40u64).map(Fq::from).collect::<Vec<_>>();
+        assert_eq!(
+            CRH::hash(&parameters(17), &values).unwrap(),
+            fe_from_hex::<Fq>(test_data::HASH_17).unwrap()
+        );
+    }
+
+    #[test]
+    fn width_test() {
+        let params = parameters(5);
+        let values = [Fq::from(1u64), Fq::from(2u64)];
+        assert_ne!(
+            CRH::hash(&params, &values).unwrap(),
+            CRH::hash(&params, &[values[0], values[1], Fq::zero()]).unwrap()
+        );
+        assert_ne!(CRH::hash(&params, &[]).unwrap(), Fq::zero());
+        assert!(matches!(
+            CRH::permute(&params, vec![Fq::zero(); 4]),
+            Err(CryptoError::InvalidLength {
+                expected: 5,
+                got: 4
+            })
+        ));
+        assert!(CRH::hash(&parameters(1), &values).is_err());
+
+        let rng = &mut ark_std::test_rng();
+        let params = GMiMCParameters::<Fq>::generate(rng, 8, 100, EXPONENT);
+        assert_eq!(params.round_keys.len(), 100);
+        assert_eq!(params.rate(), 7);
+        assert!(CRH::hash(&params, &values).is_ok());
+    }
+}
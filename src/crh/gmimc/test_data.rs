@@ -0,0 +1,278 @@
+//! Round constants for GMiMC-erf over the BN254 scalar field, with the
+//! exponent 5 and 226 rounds. Round constant `i` is the little-endian
+//! integer of bytes `33 * i` to `33 * (i + 1)` of `SHAKE256("GMiMC_erf")`,
+//! reduced modulo the field.
+//!
+//! The outputs are a regression snapshot, recorded from this
+//! implementation. They are not vectors of the GMiMC reference
+//! implementation, so they catch changes to the permutation but do not show
+//! that it agrees with the reference.
+
+use crate::utils::hex::fes_from_hex;
+use ark_ff::fields::PrimeField;
+
+pub const EXPONENT: u64 = 5;
+
+pub const ROUND_CONSTS: [&str; 226] = [
+    "0x0c8a92c99cf69fe0ad723203275852ce6685d5a48386f0e6b5d6d0c55a68eb0f",
+    "0x0fc7d4b61b844d61aaeb726b91ece5345ae82c60b978e56af05f99b513cf0ab1",
+    "0x2ed740f5080b16ac1c94d55796012302bf7f327c44cfc487d3308fd6e638eb26",
+    "0x2ed0e734424f06a2d6d443a0e7e06fb3e195c297909bd89c3ea7ea1327af9bd4",
+    "0x09dbc014c2646c020c88e03dc30ab86ea3207dc5cb632c10816050ce73882b5b",
+    "0x0f24c2c8b5084c144492f51fa49262485bc3db8929d64213e2d829d058476741",
+    "0x11c307976e395700395236d002b96eeefb8a4ef42aa69268391b683e9f659faa",
+    "0x1a5ae57f59b7dfc5a8008dde1c381e9377d68c26716cb6c0a6729a6ab160721e",
+    "0x0e37a1c76c8426b69e2d73b1aa37cf5b2dcedf6596d4e3bdabda2e2587a4bc61",
+    "0x054ce6d3c3bedfaa342f09989b7ba42e7078a7188764f91e43813f23c27e50a6",
+    "0x1a2c2e2a8a323f544627a51e0bd619caf71fe187f1b251a67ff8d9815417a5bc",
+    "0x259b7ae13d29b055fa85bde0d811f1e7211b9a33abc8153dd35cc954d7d3a48f",
+    "0x2eee5289d15b136b7effc1e060ce6fae1aea3b8a24d240e73bf99e3f2d07a57b",
+    "0x1e1f6ac58f2d29e40749686cced5809aaf198e884ea077fb29e5c3e4a9d616ed",
+    "0x09d19fa0cf60d022d895d9ed1c3be5fd39921d857a92d874b3a4456d94f95a0b",
+    "0x01fe5c2ce534bbda5f9f256d6f3e6169f1e39de6f1b9669eaeb6842375c6bcf5",
+    "0x066b47d14f70054327a964346ca471965e199c7127b91eb03a95aebdc31bba1e",
+    "0x2c1e177ff8ffe54fa40398e6bfb6f47d356b6b088a1cbeaf7f0a908170189f4d",
+    "0x23332ce0ccd0c77930e1537bcca8a418cb1c6578da1331b564b5491d55387ab3",
+    "0x0e34acba0d32a1a440f7dac51c4b7a982d5eeaffd4ffa88d5ebfc459e2510e00",
+    "0x2295e55e476cf2d350d753caafef960cdad3e82ab5805812d105a614554d3250",
+    "0x137c4d7531c1dfa3d59e35d8822fcce99385d73ac14c742f69cf06d476f68679",
+    "0x01991dde17c1826d794c52fa398b9a93993b72fcde16eb4c2db742d120f2eb4c",
+    "0x03fe01ec74ac8196f41bd0f962e978d759e541fe224bac2e72fbdcd80d29ebe6",
+    "0x11fea47594cdf4eef76bc2bd61ccb5d92e06d102f48c83829667c128106ac491",
+    "0x03510ba65ca9daeab6a568ec73985138a150a03e4917437eb8a617978ce88336",
+    "0x015be7bc0a46a3a62315981014768d521b5e95f366b4416821b94d328910289c",
+    "0x28776eb48a42d2bf2f4c274dfdecdddadd843eb1fdd347b41daee0dcb86d1eba",
+    "0x167d80922fa13c7f992a17f6fcd1cb864a32ee0e0297bd5dfc0a8b3b66937361",
+    "0x06173cde054e0cd8fd8250e38c628e2489da6226b8fb70f05d51bcf98ecbb238",
+    "0x2704fa4ce740dae0752731d72af5ce49ab3ffd4bd00bf36b401713e5f4d12966",
+    "0x2f85836ca1d441852c921408e92e0d6dbe76cc361d5deb95c4211df20ee69858",
+    "0x2564b14f5226c9b3b504d32d4ebf332d7576d37ad34245731549179341e1cc45",
+    "0x0fe38fcb36ed369d4a4799f2359a5ea8b4eb377434c8db934d17b558a1958936",
+    "0x2cb662641400f302df5a003324948fd648967807889d3e9d8dd51d7fbc4d2a54",
+    "0x1f7b7cdbebdeb668aaf99cf50015db510e53c34be6f7cfdfc8fbabc24ef635dc",
+    "0x0d6ac9245c151acba62f8591912b0baada6818697ba989d81d9ae5ba174a3c5e",
+    "0x29b95626053ac2a4ce47aa8b2e8b7d6480ffc0684aba88c8da419dcd37700dad",
+    "0x116f0bfa0ead3f0d5cbff884ea8c3b18b90641ab9fd8a1ff767bd8a79552dfd2",
+    "0x284d3739fd3ff1c85ccb3be1a03c55b23885891b46942809453369c48b285b50",
+    "0x033211e084acf45cf1a377b3d899c939753a4718c1a32cb0e644f0e533be3bdb",
+    "0x156f23729d7c3bddae0522343e9f4cca7fa20851ce277546468625dc0186ca7e",
+    "0x140c34954ce18edd9f27e63e97f8662a67b8ad5a1cff9b3e00b2a33e6e588b80",
+    "0x257543ac1d7d3ef7cd94ba22f794c0de68bc760b7fdbe245a8ef4e3cba23a60e",
+    "0x282f0ead07da79cd8af090adac82f14bb38116f5b456c4a1b4521bd3dbfa6fca",
+    "0x1aff1c1bf8069f329923969538b8de94d97b84ed423cd04bce1a70c93ff93e2a",
+    "0x131e5bb87e7bae4bf2cae43a5053432c7fb5dc943a191eee9274f21541e537b7",
+    "0x1045d788fb02d695cb348fc2f7ea5dbd1be55d34aa4a0b312f458fdb25ceb84a",
+    "0x1853772586e2d65797c05ebaabeb83095edb3ae448e9073bd525c5254289bc2c",
+    "0x1a40811d4eca39378ef49e2838512e51e4b122e30680b77abc3ceacce32858c0",
+    "0x1f7a48436dee53f5d67a23b40406d40c80a2d865df57640c9e92cff1c9392d71",
+    "0x04cefc24cf7eb55bdd05a13df65b96111935fa94948c00c8dadab100184d800e",
+    "0x146d5d6afd19e5afdfe00fbc261ec0014abd8d2df7fac21024206570361f53b2",
+    "0x159e65b4ab408cd7553961730d4daad518a62567c7f654fb2798a867d508e3b9",
+    "0x0fa8e5032245c0453f0440e74a21958a43a6346c355ff1ab34c3321746486081",
+    "0x28272069322dd55d5cfc7d09799d8acda96f3e3cfe17b21ff069d2e9c49d1d3d",
+    "0x03210ac2442b440db410128da179f005af503a9e5f10f0ea54d5c6ee13d14a7f",
+    "0x1a107bd2c108027b5629aee8403215f6df5b0c9545b65f05fec4ec8f7904a295",
+    "0x228c4b43ebf471d5fd7102ea0d55663e16cae8d970f0c1c7a6855d59f9b6d07d",
+    "0x24924ad5fa16c2ecaa4591b6e90743288671396ad0a8cbf27c0a4e87c66bae0d",
+    "0x20c0860409f68c308b2103d31b5a6ce9280b9e76ee8dcea808914fb0f3311330",
+    "0x1bdf3a1d12530fa74cf5043213cb69d564a22e1b11e7c97afbd7b65350bf845e",
+    "0x16f7a77821305f7a75540134f6d4e1c70aa880008e907c471e783fdb25ed168b",
+    "0x1ff5b39f54c77bd38fa0343846d4af64c6496a5b3e18dc105afeef33fcf4f98f",
+    "0x04e951f86be739b4609536b1b8ccc430758bb485016715f4644b9bb6ec89b6ce",
+    "0x2162578e6ad04e2fabeac99983fde99a703e70c98f641904419bb9488c01e2f3",
+    "0x0c3bb84352a1e4bd2ba515310fa2469de0e673acda490f524fcb38593b031dc5",
+    "0x017b30c93e4487126cfc49e2ce5217ea7b00cd3d9fdb9c2b8c949e8ed2df3b5e",
+    "0x1e45de5192771e1340af998e921288537f577b41a7579133389bfe0e07903314",
+    "0x2b8e99d9daa819c697c86616405645e3c0370723c521c46bf990d775abc9262a",
+    "0x03fc258ac743eadf122191177790b589d165462a60005ce0c208dae329e67b0a",
+    "0x038e1ed763d58210189521abde799e560d648d25acc1bf8a212fbaa8cc5b90ad",
+    "0x2d8f433ccf9adde383c6dc04929da4fb7990a60f8f6a9c03f58619eafc3f8a87",
+    "0x22a8d48c564e09308f0fc59723920d337f634fff0587e96e230d36b29b6b35a1",
+    "0x0c45ad662f7354a17c4f3d576700f9889d7e8246cec0bd8c08a84194c54387f0",
+    "0x25c37ad30393b10a10623d39659f8df5670664dee04029981499634b30670e47",
+    "0x191f315b2bd655d0e3f8b3dac015aacf25d12dc40ecf7ca5309da1f59f59a095",
+    "0x2551dbf1d49e45596dec0a6b3df1d3fa4da96b99dadebd545c73db0e0417ed35",
+    "0x03abdc2a8fb6f489769819e3ac7b7561608e65d4e72214124f687c8edddb17f0",
+    "0x1d75ad42d19c617732f633d28c2ef48e2f298711f055b4f3843cd96d4b83fed6",
+    "0x0756cb092554142d5fc58b2f7bc7465b5d03220778d4948ae0174af96e7c2c85",
+    "0x2eef0ce619b780b3bdcd4e2dca7c5a82df2d88b5c46b7584eb834aa9c873f865",
+    "0x29205fe81c129fba6ccd2e307e0fd3dddff611f928beca03d6749797feb0c097",
+    "0x19fa3bb00b8587f0f0a10878cd8806b0e3bd78e96195ad60d7a84431b6c15e5a",
+    "0x2c795eedd365a9096049a27ba49534af08ed72ffff066d5ef487026881491cab",
+    "0x0705696c7e514f18bfedd964ba423b1238d400cca97f947b317fe3c5f527983a",
+    "0x1f85776e30dd483fe6d12d8c115dd96a47565932daaec6e77dbb3026425ccda5",
+    "0x2487c42d0f5cfe9a97050a4fa7b4486e9f016523cd74b6e4bd78e2fe6be8a3d2",
+    "0x03f9a579b3f6acae13c9b4f94cae6910159c6e2d5e01c7a41e1a60586edfb4a7",
+    "0x1b3f1434f68babaf3c68569ba467e5903d4fdce52b476f8263a24704c426971a",
+    "0x1092edf1e63454589245293722ce0c3b61319b18a58afc095bac436710bf7fed",
+    "0x21b6c0d213a507db9339ba37329cf85dd976e0a0a1bdf2de242cb7e692cc13a8",
+    "0x0a07c46ad1f0460526d797a5a2cb2f325526d7fb3f8bd3a498616b369aec2c48",
+    "0x0a76f27008a78068116ed9e9337532e3518e11a7aec1b3742cbdb71571a100cd",
+    "0x02c74c2a6019745d935af678f36f725ad51004e5f23fa47481adcf95bfe2530f",
+    "0x0927ab10787cef1bc474c712536bebc27be67d78d68e1b596393376d441da6d0",
+    "0x2a9b03c0d0a310738860c4eb16609209176a6cfcd7bfc5a7c90a06271926d715",
+    "0x2692631dbf517d7a3d76cf76d4091fa37580f875992a0e7c997c5cca12eeb3d6",
+    "0x24d932f144625ac09576fec3d10dacf64260bf244647f84e8bc25bb93fb1f37b",
+    "0x22106bc61484f5889f8f824435d32b648ade07df0b6699953aac14b62f518ea3",
+    "0x1c47160e0ada6787d77a115472c07a29c4a983eb9d2d011bf4b3f3cea83d448b",
+    "0x19060b74360f281f4d644837c289a0d499a7eb88a603f83c09729a6f964c7055",
+    "0x073f3880242eb3aa5f5af07a6beb7ee2df50b264d322505420fd054b1e1bbe89",
+    "0x22c09a591621274d49e969035392390ae4a8849a39593143e841777b60da17bf",
+    "0x038092375bf624983c69dc2f35d115685eee86c5d7c9250ebcdffd1f69222c60",
+    "0x08b62ca8af3b13657c16fa031cba10766e4dc9d07fb86d64063159c9cedc67c7",
+    "0x15f2844e5db785d5ed5cc821bb11dc0b0009f86fcd445f281df88f1e4ffed6a6",
+    "0x1357ab040ba2475f9760131bf13e7293cdedb17cc5ddb3506a7c4d352ed4cbc6",
+    "0x05a77232909b1523027d7e78779236fe52e46558c96cc960837db0426f2593df",
+    "0x13cc73173251002cb50c19db8ab423f604bffae287f7b205350d0d0593817b41",
+    "0x154ba491d57fe56390b83a481ed09fbd49e552680fd5aef852e6c0607d7950ae",
+    "0x09be0b2099fe474fbdf1b5935d4fd87ef652d0a583ce7474d8743afac9d5cf50",
+    "0x28d6ecef1f8d9385d8bae831f401503fb81d1d2455ac0643bad80fa1b66f93fc",
+    "0x2be813a9c88db38e78d9711a8f85bb93da5696863cff61f09e4e887b8f8d6fd8",
+    "0x267f4dc2344dfcdd084ed99a043f676e108bcbac078b05ccbc96042db132995f",
+    "0x100dc99a1c4cb15a904faff1baaacf8095d2b4fded141214bdf149ab38ee45ae",
+    "0x099930d26a88ae930eaef5a8d90ba4eb8d6664c0267970324f90669d76b07cc0",
+    "0x16c4039320cc794f59cc2adcf4a240a4e606ee69054c0cba4be070754b06a28d",
+    "0x15d83d152941cb1b2b3f002d18e919fc6d4ebbdd7e9aa7a77648bf5fd23da81b",
+    "0x1f46951583f80016c2e03d2635695dd0189d7dce25e598432636ce16cacc5341",
+    "0x2d17d0937f9132ab52863e528fc69638ef91c0c751a4d9e68586e454a95dc2d8",
+    "0x1e56f1cb6965809078c859d659a0f4028cb703326f4eaf6de7fa9c32834c4ce2",
+    "0x1effe83b663ba5abfcdfc038d233f9aef69d009b6a76022c184841d167067263",
+    "0x0b48632cdec64e89e6369eb051d7f0633eb6c06f551928f1ad2c4c55cb265f3f",
+    "0x0ece4698151ea84f599bc5b6b62e24f79790f1e1766835875708db7c5d704475",
+    "0x100dfdd951f5f310eec2122820db38ee8a4b881c16cc32afa8e824fd574fc903",
+    "0x14d17a6f56f245e3ab3bc74dc03e2034668f15f885b2c3df5ed7008159f85598",
+    "0x0ba1f09cd94cdcc95efba2954b1b5885303d6cdbb0d3396913f75d6269429570",
+    "0x1dcf0df52c126ce7922b25f4ae7ca887c6efde75bc1b856eba02617caf86a126",
+    "0x293009fbbada44f57f81fd2cf7ea95369c95e0ed8e27d75d00cba10939bf8218",
+    "0x1bef41ea4833ac0cb7cd525a9e89754b19e6c208daceb19c23b0843456588864",
+    "0x03c8683b3cf453dabf24c3df3a16da77004143c6a30ca352b6af5e6bcbf7ec7d",
+    "0x04fd027c94090f3b4b53c1edf8f780fc1c6425fc0d33dc31de542ffc8035f6c6",
+    "0x2795c2f969f7678c61fe9890bc4ce19f33b00d36b250475b308d8c05607443ee",
+    "0x2e09658399a0f4b9a380e9d0194476cc31442d78d178fa46798e38acacd129e3",
+    "0x0b33ef995044c9b45db6b46ac76fd53a796bff2e2ef57ebf25220a46df84cf3e",
+    "0x1083ef4cb7810cfed0a93e1a4f6c7d551ad57f611fe2d6738f73610d8cb0df3e",
+    "0x280d48cebc9f01a6685a0dbeac4261b91335a1f21c2f391851181c638f658862",
+    "0x24c4c5be3dc65897445c8dac00e723c246a5588d2f4e6d0727fb64522ec8dc8c",
+    "0x2b2797716778fa5805180f60af12ba4e141c7cde924411cc86a0e38653499c03",
+    "0x17abc121139a06320fb5111602711d2910df34e598f8b5701958ab5c8836bd17",
+    "0x013a854b963cb201cb7ad37d372ff2d00580c4ea9976fa09ea491fd11b66934d",
+    "0x1b230c332d576088c9ef2fd379476a1dcf4c86c934c073835074c984852fb32f",
+    "0x0e37dba3b403c365e3c97c4eaab4644abee481506468b5771a394c85a3c24355",
+    "0x04360fbfe85b5d95f50aa2e22094e9f798e13b3b762251c1e34de2eb589fc328",
+    "0x0315f73ec01ae7f23d79b59243d9bdb2bdba1ed4319961549ae9cd60211610d3",
+    "0x003ac6c4855ffd0136154b9499c346a90f95fd00e693655e4e6b215cfa2c9897",
+    "0x0b3dd9f5219b441c1aac6c292f3aa324debd93b6e6de59866c90889518396a6a",
+    "0x07958bb12688565037c89241f4f875d1bd72a6b6ebf32424b75d3d7e6daa1c95",
+    "0x2a9495abe933635b51fc3866f1ec552866c5afbcb438804902119191945b226b",
+    "0x1bbf350edcd63a64a26dc73d49f9a0ba709dabd7b5d02b5c972b9ca0900eccff",
+    "0x0e0b537f6c936138dd603dae164919ffb4388abee8385fef9f02a6464d575ad6",
+    "0x10ac10637323ebac9b820b0983092704893cebe88bfe24bd676372c350b8d25f",
+    "0x106851565d69e4657c14a09eff1e2b3724e8b9ffffa1d38c49f414a9820d3193",
+    "0x018bb90cd9fa093407e7e5adfd8fbcd7378cf50d1749f9645d761b4dfdaa4e15",
+    "0x0f0f503707fbc83a475432f25d6fcbcc231136ea12d688828bde5289a7fed5ea",
+    "0x1648fe605c1a526004822e709087862cbcbb9d7d613cdcd1cffc2b3cb6256762",
+    "0x18dc927edb909a8d8b6bc689a35d662bfcd6851ea9cac7ef9c0d27d62202d3cb",
+    "0x10280892ae8571cbe53b29159f0603e6073a8c598cff0bece69125ce88857470",
+    "0x1c3c975a8475b33570df51cb15dee3fa5d32e8884cf67e44695b1120d249f151",
+    "0x0acf0c96564c389f3818f472f01a2425cfcbe742d9de88540b5b189897973a64",
+    "0x072619a3b554f8ac43646c5106c7932a6c4b7c2d7a3bb1c4a044fa3608d307a1",
+    "0x2bdfb275d2d8d4414c0a77d2ea0ace8457db00d1af5d74faf46cd0fbf3a19f52",
+    "0x26fdd272edd027fe1480e843bfce9ed8c963d0fa3d3f39a340df1a8734174760",
+    "0x2fa757827541ae5307ebc348419d6809725e6f23fb534f60c10d5992c59250a0",
+    "0x235ae939e06bcaeca60e7aa5e5add9a483cf51a9bf2e2b957249947c6264ea98",
+    "0x178349dbf03b0b0a96db6640d744d09fda05227fde258bb0bbd512d9d6fd6439",
+    "0x2e4661d6ffe5436a2d0c0ec7cadde47a9c2ffc0e2db5a0a5f4ccda2e6d50d2cd",
+    "0x186fc0b5e15aa5d0811b3d7d61abe681ee7f0dab207d7eba645c9c894005782d",
+    "0x23b0ce90d4840d825cd14b18e31089672fc8e81c3dc10f91de455d0aa789041a",
+    "0x0681dab820d7580cf7280b444208c430162ac9a67de10d0cf4c0096d93c31e89",
+    "0x177439448f37a93e9be012b629190408ca6b0f255a556ce132ac1b74f1582d7d",
+    "0x0f1412bd3468935073c720721a5b7bd689c681bb1380f693f7d4df2fc431dfc7",
+    "0x122f70bcecd98c55c53db4b1d14fd35aeaacc49e925c75626fa2cf42b92e1a59",
+    "0x107270a8bfc2b8ce8badc90b63970b3b39b71965e5047c75b021d9bf2baa6495",
+    "0x1c4278cd82708773f6d2df148b99f45ab1ba86c44f392519a07c5266e4b41d57",
+    "0x1dff1900ac989a1105aec81302103be49224fc314b3cfaf66fadcffd041cbc9e",
+    "0x100cbeaf39add924a797f37a63ffa46422dbf0ade3b4fb5ed05d6fbb9cf529b4",
+    "0x13b92451d09590ed3323d3e97915ed99677edd5f43d091202c57420ae7378351",
+    "0x0c0acca1dfb7997657634104b7f7cfe11a325d57672738c5dac7a4fb9c1dfc2f",
+    "0x2babaf8b5245cffff41769c71f07a0cb61bbb1f6a187203074d96e1f13ce3edd",
+    "0x294299c91766c47744489cb2b9ca23c09a62dfc59aa9fb5e503ec83d25884a1a",
+    "0x1b0b56111f693ecc51da8044f5d810a94d3922a9d8b6e75899edd844af4d1bb8",
+    "0x25f0c8e6883c948b255e4d905bd1b0c5a009811ebcb0b840a7585ec3b1829a0c",
+    "0x1bda9b5791853f9abd3927e1c37f6b3f76773eb29c36bffc1ee38c6e5757d8cc",
+    "0x20e62f243072cc05c88568eef7e9af86aba4331586dd05ab2cc1506a20ae1a78",
+    "0x1113d1c0c227c72348fc18e94cc2bc1f983a4df74b9dc4586cbbbe3e8374d206",
+    "0x0019f309c0853679ba6cd5d8747dde7e5beb6c667e7ad66b0bb93a69782cf90f",
+    "0x2902d80830622e0e4d6e69a20e4af6957a2f25efdde5bcb435de7829d0210b0d",
+    "0x25394ebcab5927b1609f338c4e4fc5b62d139b7d2d8f7b48467f09caaa3ae965",
+    "0x0f435ccac6e313b1ab9a3e51a300e8c09d5ef643f884d743b6a47eb5d7855b22",
+    "0x0bc9c2bc312d93ff5533c8cf0c5d9d6200ec28eec95233242c7bb2a7e14b1338",
+    "0x0bea2f4406bd24e721a8012410025ab7eedf8dbd02a52b267a7354561651ebdd",
+    "0x17dfab18db9929d14e20115cc9689c29f9bad44a801e222492c8a44d77cb574d",
+    "0x25d56c1793b270f196501b00e39c808bb17bd6270e1a9911335d675b58f5ed95",
+    "0x280270659f6ea034ed681e4fed892f4276109679d962101601501f46c22eeef3",
+    "0x1bc8bc6904e3e04a8bcb3a9c71425f9bdd129e03a8e37d8dfd4f7b84414fe7ed",
+    "0x0a09b5b300f4eb25bae77d464df45f8754e592353f9477f3d356a4fca7e40e05",
+    "0x1ea1c28d67eed2a8834df501a94e33a1201cbe1f622ba6de73f80ffc76028ec6",
+    "0x11d39b3951b77d8e0b83c945fb7cedbea2a51cec883821c4fdd308a159e17220",
+    "0x10e36b0b25ed755779b2b5c8bb6217379265ad92500b3cedf346cd66ed389a32",
+    "0x07f6eb5d18a70eeb77e51c70dfbecb68e0f46a4ac1a9bf05380737385ac11404",
+    "0x26b5362c45c0e3d06e7fd75a45b8becce71e2871663f28203645df2763af117e",
+    "0x1c35cda3c2c994c4ecee7af08ce78f94f26800f582afbbf0210aa470ad55a2e5",
+    "0x0255adc1642033137c3d105ea2c2b76c473ff57e8df3cc80a70ed0c7b9d3c9bc",
+    "0x22f9b477b046378a6337deeac0f1d98dac6c66c1696ebdd3be9e3529cb7caa1c",
+    "0x25e3ba63ddeb8532ebe48288e743d4de151820eba29a93b5f57dd0858a2dd0f6",
+    "0x1760d261959d6db79c1f07a488510a68d4d104d25dab35fb2a297a5ee2713c91",
+    "0x03b2b0de9caf1b187cae60f431ff5c63a495d20eb7aa7962a42ab64a7a0de0ec",
+    "0x276b722aed00f950f619299afcd40153072ee8cff2b8dce7b9d4d1de5745dedd",
+    "0x28c1844e523a18acefe7486cfb6c7a4d7cb7da49bebf2604f4a3aa1dfa991baa",
+    "0x0b0bc6f9bc3da8b9b167160a233b1e6a3a99079b0057e633342d9a9fe4413f8d",
+    "0x04362041c3a79486dfc6e20f21835d83b2ce7195590c01b44c9d6e22684a0f9f",
+    "0x100776ceda5eac73c396cc26463303842671d28b15eedbe01f5f397db12795d4",
+    "0x15baddd5b1e532dbfe6b8e5113d313ba6539552f7484660bf8d963586bc668a2",
+    "0x2321bd94ccbee2fa1b9afbbf9aaf7dfd0236352f425575e5b43a65299d947790",
+    "0x120cd837885c7f9f87b12938c172676000d1b41377d18241241e6f5fe7b1cf72",
+    "0x0d33c05064f6233d71898ee20cefb8b338bb4df1d4861b7f73375a726beeef09",
+    "0x14d8dd817a8dc1eef33be750e3b6aa5f8ec2cc5f84324b556f161c4fc0b1c56d",
+    "0x131da3ddcdc88af1ca7d8791fbc917e73c0675f2ee6d15166a98a3cee8f3b926",
+    "0x03ac39f30c963b204d8bf60d0412b191d88f2db69b0fc72ee52a317cd46eaf9c",
+    "0x000adb75ab611f2bb2308c0a96aaebebddc0f59a5a5d63835fdf8d22e2b5ff86",
+    "0x22f0d94d8316d67d4151cf7136976e2cb513585652ee50dd888e7cd124640f61",
+    "0x1ffd59dca7547bcb5d95e88225aac82177db8c73745fff2a3ce74ba540b82424",
+    "0x0bb2f3327c904b5ba470dd143f9d20c9d8045c00f214e56ce985a60ab46259e6",
+    "0x1d77ed71c547723c571cf42e5ce2086cf4c0911206ed7b44791f948ea21a9be3",
+];
+
+/// The permutation of `[0, 1, 2]`.
+pub const PERMUTATION_3: [&str; 3] = [
+    "0x28b34c96a8c1ffc063cbd3a7da99ecba5db4d4d5b688a37f57f8e6090968740a",
+    "0x15200171b21f5e6c1bac169591707f3b68084dbf110646fd7c5099c63c60693e",
+    "0x089c7eca2c0ed8363bd27f074588a0d3f3f8b017629d2b8311e280819057f48d",
+];
+/// The permutation of `[0, 1, ..., 16]`.
+pub const PERMUTATION_17: [&str; 17] = [
+    "0x2bc10584067f69e24c7bf80f7eb29982ffb5ad8da52f9eafe85da424827fae61",
+    "0x11ff2c51139fc156b7e91c311034468007e73a88353224c2284e01b396a4eafa",
+    "0x2e7b8be9be72dedf59f8b8c0d55239f1ef6f38cbc5b2c1a6a3c2ff95b74866ac",
+    "0x05a643747d3e8016cabf28d6d599f93e77f60e3990d7a5f4282738113de95de5",
+    "0x19377720a001e3cbf1b6a11ae0239127ef1ad1c918aad21a15e55982630e73aa",
+    "0x082a5ff2908a5489e46a466574bdbd254b302d8f1b38bf09209cf4548a798cef",
+    "0x2cb8033a1ee6b51c965e13404366e9bb56451291a3ff03e1d94ea8169c63ced3",
+    "0x05ebc949707a83a61aad297249af6807c44b1e3a292c6c63e37cec6758df83a6",
+    "0x2556114eb73d76b101015f3d5524fcff4711f79fcf08af1d563041550ffdf392",
+    "0x10dc32a9069d7f3fd994e2b82503d492ffb9aa286d26b7902aa4192ce95b6ef0",
+    "0x04a60a6512818319afb7a9041265f00340fa182bc3a88c714e57503e48d11e8c",
+    "0x015f6c4fffa84a202410adf65f3fa0b738ac4ec5e2144e776ed1def484107517",
+    "0x27341b9742710b6f6ec3f9385065d4cf6c9cd207d7878da3387e852626cb6659",
+    "0x1a44348ddfc61d5a90e0cf5e372de995ae10cf2158573c851b14a4a815ed60dc",
+    "0x0a882c3cbacccefb674c5ae671cda01f366dadc0f66cec21908a4f89637e3717",
+    "0x121673e832b1d38725acbf6339aae858075c6bc4ce85bb4c7a59385a1cd7de35",
+    "0x03837783df26aa5e5068fd26c113439ba3c42f8da3472c3131970f298e042bff",
+];
+/// The hash of `[1, 2]` with a width of 3.
+pub const HASH_3: &str = "0x2fb5e01d18025fca82d2270a1ca21a8530904e5e78fa13a0efa41753ae9bb805";
+/// The hash of `[1, 2, ..., 40]` with a width of 17.
+pub const HASH_17: &str = "0x08dc6d13ab16361f39e2082507f32cc95f1cb3c3d7f971660fcd0a9e2ba67f3d";
+
+pub fn get_rounds<F: PrimeField>() -> Vec<F> {
+    fes_from_hex(&ROUND_CONSTS).unwrap()
+}
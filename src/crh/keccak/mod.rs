@@ -0,0 +1,212 @@
+//! Keccak-256, as used by Ethereum, for roots and commitments that are
+//! posted on chain. It is the original Keccak submission with a 1088-bit
+//! rate and the `0x01` padding byte, which differs from the standardized
+//! SHA3-256 only by that byte.
+//!
+//! Only the native hash is provided: there is no r1cs gadget, so the CRH
+//! cannot be the hash of a Merkle tree that is proven in a circuit.
+
+use crate::crh::{blake2s::InputSize, FixedLengthCRH};
+use crate::{CryptoError, Error, Vec};
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+
+/// The number of bytes absorbed per permutation by Keccak-256.
+pub const RATE: usize = 136;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// The rotation offsets of the rho step, indexed by `x + 5 * y`.
+const ROTATIONS: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// The Keccak-f[1600] permutation, over the lanes of the state indexed by
+/// `x + 5 * y`.
+pub fn keccak_f(state: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS.iter() {
+        // theta
+        let mut c = [0u64; 5];
+        for (x, c) in c.iter_mut().enumerate() {
+            *c = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut b = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                let lane = state[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y]);
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = lane;
+            }
+        }
+
+        // chi
+        for x in 0..5 {
+            for y in 0..5 {
+                state[x + 5 * y] =
+                    b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+
+        // iota
+        state[0] ^= rc;
+    }
+}
+
+/// Keccak-256 of a message of any length.
+pub fn keccak256(message: &[u8]) -> [u8; 32] {
+    let mut padded = message.to_vec();
+    padded.push(0x01);
+    padded.resize(padded.len().div_ceil(RATE) * RATE, 0u8);
+    *padded.last_mut().unwrap() |= 0x80;
+
+    let mut state = [0u64; 25];
+    for block in padded.chunks(RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(bytes);
+            *lane ^= u64::from_le_bytes(word);
+        }
+        keccak_f(&mut state);
+    }
+
+    let mut result = [0u8; 32];
+    for (bytes, lane) in result.chunks_mut(8).zip(state.iter()) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    result
+}
+
+/// Keccak-256 of the input padded with zeros to `I::BYTES` bytes, like the
+/// Blake2s CRH. The parameters are empty; [`keccak256`] hashes inputs of
+/// any length.
+pub struct CRH<I: InputSize> {
+    input_size: PhantomData<I>,
+}
+
+impl<I: InputSize> FixedLengthCRH for CRH<I> {
+    const INPUT_SIZE_BITS: usize = I::BYTES * 8;
+    type Output = [u8; 32];
+    type Parameters = ();
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn evaluate(_parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
+        let eval_time = start_timer!(|| "KeccakCRH::Eval");
+
+        if input.len() > I::BYTES {
+            end_timer!(eval_time);
+            return Err(CryptoError::InputTooLong {
+                bits: input.len() * 8,
+                max_bits: I::BYTES * 8,
+            }
+            .into());
+        }
+        let mut padded: Vec<u8> = input.to_vec();
+        padded.resize(I::BYTES, 0u8);
+        let result = keccak256(&padded);
+
+        end_timer!(eval_time);
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone)]
+    struct SixtyFourBytes;
+    impl InputSize for SixtyFourBytes {
+        const BYTES: usize = 64;
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn ethereum_vectors_test() {
+        assert_eq!(
+            to_hex(&keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            to_hex(&keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[test]
+    fn keccak_vectors_test() {
+        // `Len = 8` of ShortMsgKAT_256 from the Keccak team.
+        assert_eq!(
+            to_hex(&keccak256(&[0xcc])),
+            "eead6dbfc7340a56caedc044696a168870549a6a7f6f56961e84a54bd9970b8a"
+        );
+        // Messages that fill the last block and that need a block of padding.
+        let message = (0..200u8).collect::<Vec<_>>();
+        assert_eq!(
+            to_hex(&keccak256(&message[..RATE - 1])),
+            "cbdfd9dee5faad3818d6b06f95a219fd290b0e1706f6a82e5a595b9ce9faca62"
+        );
+        assert_eq!(
+            to_hex(&keccak256(&message[..RATE])),
+            "7ce759f1ab7f9ce437719970c26b0a66ff11fe3e38e17df89cf5d29c7d7f807e"
+        );
+        assert_eq!(
+            to_hex(&keccak256(&message)),
+            "bfb0aa97863e797943cf7c33bb7e880bb4543f3d2703c0923c6901c2af57b890"
+        );
+    }
+
+    #[test]
+    fn padding_test() {
+        let output = CRH::<SixtyFourBytes>::evaluate(&(), b"abc").unwrap();
+        let mut padded = b"abc".to_vec();
+        padded.resize(64, 0);
+        assert_eq!(output, keccak256(&padded));
+
+        assert!(matches!(
+            CRH::<SixtyFourBytes>::evaluate(&(), &[0u8; 65]),
+            Err(Error::Crypto(CryptoError::InputTooLong {
+                bits: 520,
+                max_bits: 512
+            }))
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+use super::{MiMCParameters, BYTES_PER_CHUNK, CRH};
+use crate::crh::poseidon::constraints::to_field_var_elements;
+use crate::FixedLengthCRHGadget;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::{alloc::AllocVar, fields::FieldVar, prelude::*};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+use core::borrow::Borrow;
+
+#[derive(Clone)]
+pub struct MiMCParametersVar<F: PrimeField> {
+    /// The round constants, one per round.
+    pub round_keys: Vec<FpVar<F>>,
+    /// The number of rounds.
+    pub rounds: usize,
+    /// The exponent of the round function.
+    pub exponent: u64,
+    /// The key added to every round.
+    pub key: FpVar<F>,
+}
+
+pub struct CRHGadget<F: PrimeField> {
+    field: PhantomData<F>,
+}
+
+impl<F: PrimeField> CRHGadget<F> {
+    /// The counterpart of the native `feistel`. Each round costs the
+    /// constraints of one exponentiation, three for circomlib's exponent.
+    pub fn feistel(
+        parameters: &MiMCParametersVar<F>,
+        x_l: FpVar<F>,
+        x_r: FpVar<F>,
+    ) -> Result<(FpVar<F>, FpVar<F>), SynthesisError> {
+        if parameters.round_keys.len() < parameters.rounds {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let (mut x_l, mut x_r) = (x_l, x_r);
+        let round_keys = &parameters.round_keys[..parameters.rounds];
+        for (i, c) in round_keys.iter().enumerate() {
+            let t = (&x_l + &parameters.key + c).pow_by_constant([parameters.exponent])?;
+            if i + 1 < parameters.rounds {
+                let x_l_next = x_r + t;
+                x_r = x_l;
+                x_l = x_l_next;
+            } else {
+                x_r += t;
+            }
+        }
+        Ok((x_l, x_r))
+    }
+
+    /// The counterpart of the native `sponge`.
+    pub fn sponge(
+        parameters: &MiMCParametersVar<F>,
+        values: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let (mut x_l, mut x_r) = (FpVar::zero(), FpVar::zero());
+        for value in values {
+            let state = Self::feistel(parameters, x_l + value, x_r)?;
+            x_l = state.0;
+            x_r = state.1;
+        }
+        Ok(x_l)
+    }
+
+    /// The counterpart of the native `hash_two`.
+    pub fn hash_two(
+        parameters: &MiMCParametersVar<F>,
+        left: FpVar<F>,
+        right: FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        Self::sponge(parameters, &[left, right])
+    }
+}
+
+impl<F: PrimeField> FixedLengthCRHGadget<CRH<F>, F> for CRHGadget<F> {
+    type OutputVar = FpVar<F>;
+    type ParametersVar = MiMCParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        if input.len() != BYTES_PER_CHUNK * 2 || parameters.round_keys.len() != parameters.rounds {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let elements = to_field_var_elements(input)?;
+
+        Self::sponge(parameters, &elements)
+    }
+}
+
+impl<F: PrimeField> AllocVar<MiMCParameters<F>, F> for MiMCParametersVar<F> {
+    #[tracing::instrument(target = "r1cs", skip(_cs, f))]
+    fn new_variable<T: Borrow<MiMCParameters<F>>>(
+        _cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let params = f()?.borrow().clone();
+        Ok(Self {
+            round_keys: params.round_keys.into_iter().map(FpVar::Constant).collect(),
+            rounds: params.rounds,
+            exponent: params.exponent,
+            key: FpVar::Constant(params.key),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::FixedLengthCRH;
+    use ark_ed_on_bn254::Fq;
+    use ark_ff::to_bytes;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+
+    type MiMCCRH = CRH<Fq>;
+    type MiMCCRHGadget = CRHGadget<Fq>;
+
+    #[test]
+    fn native_equality_test() {
+        let rng = &mut ark_std::test_rng();
+        let params = MiMCParameters::<Fq>::circomlib().unwrap();
+        let (left, right) = (Fq::rand(rng), Fq::rand(rng));
+        let input = to_bytes![left, right].unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = MiMCParametersVar::new_constant(cs.clone(), &params).unwrap();
+        let input_var = UInt8::new_witness_vec(cs.clone(), &input).unwrap();
+        let output_var = MiMCCRHGadget::evaluate(&params_var, &input_var).unwrap();
+        assert_eq!(
+            output_var.value().unwrap(),
+            MiMCCRH::evaluate(&params, &input).unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+
+        let left_var = FpVar::new_witness(cs.clone(), || Ok(left)).unwrap();
+        let right_var = FpVar::new_witness(cs.clone(), || Ok(right)).unwrap();
+        let before = cs.num_constraints();
+        let hash_var = MiMCCRHGadget::hash_two(&params_var, left_var, right_var).unwrap();
+        hash_var.enforce_equal(&output_var).unwrap();
+        // Two permutations of 220 rounds of three constraints each, and the
+        // equality.
+        assert_eq!(cs.num_constraints() - before, 2 * 220 * 3 + 1);
+
+        let left_var = FpVar::new_witness(cs.clone(), || Ok(left)).unwrap();
+        let right_var = FpVar::new_witness(cs.clone(), || Ok(right)).unwrap();
+        let (x_l, x_r) = MiMCCRHGadget::feistel(&params_var, left_var, right_var).unwrap();
+        assert_eq!(
+            (x_l.value().unwrap(), x_r.value().unwrap()),
+            MiMCCRH::feistel(&params, left, right)
+        );
+        assert!(cs.is_satisfied().unwrap());
+
+        for len in [0, 63, 65].iter() {
+            let input_var = UInt8::new_witness_vec(cs.clone(), &vec![0u8; *len]).unwrap();
+            assert!(matches!(
+                MiMCCRHGadget::evaluate(&params_var, &input_var),
+                Err(SynthesisError::Unsatisfiable)
+            ));
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! The MiMC hash in the Feistel mode of circomlib's `MiMCSponge`, so that
+//! hashes computed here agree with circuits and contracts built on
+//! circomlib or iden3 over BN254.
+
+use crate::crh::poseidon::{to_field_elements, BYTES_PER_CHUNK};
+use crate::crh::FixedLengthCRH;
+use crate::{utils::hex, CryptoError, Error, Vec};
+use ark_ff::fields::PrimeField;
+use ark_ff::ToConstraintField;
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+
+pub mod constants;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The number of rounds of circomlib's `MiMCSponge`.
+pub const CIRCOMLIB_ROUNDS: usize = 220;
+/// The exponent of circomlib's `MiMCSponge`.
+pub const CIRCOMLIB_EXPONENT: u64 = 5;
+
+/// The largest element of the BN254 scalar field, the only field for which
+/// [`MiMCParameters::circomlib`] has constants, and circomlib's Poseidon too.
+pub(crate) const BN254_SCALAR_MINUS_ONE: &str =
+    "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000";
+
+/// The MiMC-Feistel permutation.
+#[derive(Default, Clone, Debug)]
+pub struct MiMCParameters<F> {
+    /// The round constants, one per round.
+    pub round_keys: Vec<F>,
+    /// The number of rounds.
+    pub rounds: usize,
+    /// The exponent of the round function, which must be coprime to the
+    /// order of the multiplicative group of `F` for the round to be a
+    /// permutation.
+    pub exponent: u64,
+    /// The key added to every round, `k` in circomlib.
+    pub key: F,
+}
+
+impl<F: PrimeField> MiMCParameters<F> {
+    pub fn new(round_keys: Vec<F>, rounds: usize, exponent: u64, key: F) -> Self {
+        Self {
+            round_keys,
+            rounds,
+            exponent,
+            key,
+        }
+    }
+
+    /// The parameters of circomlib's `MiMCSponge(nInputs, 220, nOutputs)`
+    /// with a zero key. Fails unless `F` is the BN254 scalar field.
+    pub fn circomlib() -> Result<Self, CryptoError> {
+        if hex::fe_to_hex(&-F::one()) != BN254_SCALAR_MINUS_ONE {
+            return Err(CryptoError::UnsupportedOperation);
+        }
+        let round_keys = hex::fes_from_hex(&constants::CIRCOMLIB_SPONGE_220)?;
+        Ok(Self::new(
+            round_keys,
+            CIRCOMLIB_ROUNDS,
+            CIRCOMLIB_EXPONENT,
+            F::zero(),
+        ))
+    }
+
+    /// Random round constants for [`CIRCOMLIB_ROUNDS`] rounds of
+    /// [`CIRCOMLIB_EXPONENT`], with a zero key.
+    pub fn generate<R: Rng>(rng: &mut R) -> Self {
+        let round_keys = (0..CIRCOMLIB_ROUNDS).map(|_| F::rand(rng)).collect();
+        Self::new(round_keys, CIRCOMLIB_ROUNDS, CIRCOMLIB_EXPONENT, F::zero())
+    }
+
+    pub(crate) fn check(&self) -> Result<(), CryptoError> {
+        if self.round_keys.len() != self.rounds {
+            return Err(CryptoError::InvalidLength {
+                expected: self.rounds,
+                got: self.round_keys.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The parameters are constants of the circuit (see `MiMCParametersVar`), so
+/// they contribute no public inputs.
+impl<F: PrimeField> ToConstraintField<F> for MiMCParameters<F> {
+    #[inline]
+    fn to_field_elements(&self) -> Option<Vec<F>> {
+        Some(Vec::new())
+    }
+}
+
+/// MiMC as a two-to-one hash: `evaluate` reads its input as two elements,
+/// as `to_field_elements` does for Poseidon, and absorbs them with
+/// [`CRH::sponge`].
+pub struct CRH<F: PrimeField> {
+    field: PhantomData<F>,
+}
+
+impl<F: PrimeField> CRH<F> {
+    /// The Feistel permutation of `(x_l, x_r)`, circomlib's `MiMCFeistel`:
+    /// each round sets `(x_l, x_r)` to `(x_r + t^e, x_l)` with
+    /// `t = x_l + key + c_i`, except the last, which only updates `x_r`.
+    ///
+    /// Panics if `parameters` has fewer round keys than rounds.
+    pub fn feistel(parameters: &MiMCParameters<F>, x_l: F, x_r: F) -> (F, F) {
+        let (mut x_l, mut x_r) = (x_l, x_r);
+        let round_keys = &parameters.round_keys[..parameters.rounds];
+        for (i, c) in round_keys.iter().enumerate() {
+            let t = (x_l + parameters.key + c).pow([parameters.exponent]);
+            if i + 1 < parameters.rounds {
+                let x_l_next = x_r + t;
+                x_r = x_l;
+                x_l = x_l_next;
+            } else {
+                x_r += t;
+            }
+        }
+        (x_l, x_r)
+    }
+
+    /// Hashes any number of field elements, circomlib's `MiMCSponge` with one
+    /// output: starting from `(0, 0)`, each value is added to the left half
+    /// of the state before a [`feistel`](Self::feistel) permutation, and the
+    /// output is the final left half.
+    pub fn sponge(parameters: &MiMCParameters<F>, values: &[F]) -> F {
+        let (mut x_l, mut x_r) = (F::zero(), F::zero());
+        for value in values {
+            let state = Self::feistel(parameters, x_l + value, x_r);
+            x_l = state.0;
+            x_r = state.1;
+        }
+        x_l
+    }
+
+    /// Hashes two elements, as the Merkle trees built on circomlib do.
+    pub fn hash_two(parameters: &MiMCParameters<F>, left: F, right: F) -> F {
+        Self::sponge(parameters, &[left, right])
+    }
+}
+
+impl<F: PrimeField> FixedLengthCRH for CRH<F> {
+    const INPUT_SIZE_BITS: usize = BYTES_PER_CHUNK * 8 * 2;
+    type Output = F;
+    type Parameters = MiMCParameters<F>;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(Self::Parameters::generate(rng))
+    }
+
+    fn evaluate(parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
+        let eval_time = start_timer!(|| "MiMCCRH::Eval");
+
+        if (input.len() * 8) != Self::INPUT_SIZE_BITS {
+            end_timer!(eval_time);
+            return Err(CryptoError::IncorrectInputLength(input.len()).into());
+        }
+        if let Err(e) = parameters.check() {
+            end_timer!(eval_time);
+            return Err(e.into());
+        }
+
+        let result = Self::sponge(parameters, &to_field_elements(input));
+
+        end_timer!(eval_time);
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ed_on_bn254::Fq;
+    use ark_ff::{to_bytes, Zero};
+
+    type MiMCCRH = CRH<Fq>;
+
+    fn fe(value: &str) -> Fq {
+        hex::fe_from_hex(value).unwrap()
+    }
+
+    #[test]
+    fn circomlib_vectors_test() {
+        let params = MiMCParameters::<Fq>::circomlib().unwrap();
+        assert_eq!(params.round_keys[0], Fq::zero());
+        assert_eq!(params.round_keys[CIRCOMLIB_ROUNDS - 1], Fq::zero());
+
+        // `mimcsponge.multiHash([1, 2])` of circomlib.
+        assert_eq!(
+            MiMCCRH::hash_two(&params, Fq::from(1u64), Fq::from(2u64)),
+            fe("0x2bcea035a1251603f1ceaf73cd4ae89427c47075bb8e3a944039ff1e3d6d2a6f")
+        );
+        let input = to_bytes![Fq::from(1u64), Fq::from(2u64)].unwrap();
+        assert_eq!(
+            MiMCCRH::evaluate(&params, &input).unwrap(),
+            MiMCCRH::hash_two(&params, Fq::from(1u64), Fq::from(2u64))
+        );
+
+        // `mimcsponge.hash(1, 2, 3)`, the bare permutation with key 3.
+        let keyed = MiMCParameters {
+            key: Fq::from(3u64),
+            ..params.clone()
+        };
+        assert_eq!(
+            MiMCCRH::feistel(&keyed, Fq::from(1u64), Fq::from(2u64)),
+            (
+                fe("0x28c6f78ee3ed6b336280d3e522b03efc49eeb5a2a3af1075ccf6f64e5d867e53"),
+                fe("0x05d9ff7555e18007f7809e5977e6dd41ff93ca7c8d11249d51eaeb0b4f727d37")
+            )
+        );
+
+        // The empty subtrees of Tornado Cash's MiMC Merkle tree, whose
+        // zero leaf is `keccak256("tornado")` reduced into the field.
+        let zeros = [
+            "0x2fe54c60d3acabf3343a35b6eba15db4821b340f76e741e2249685ed4899af6c",
+            "0x256a6135777eee2fd26f54b8b7037a25439d5235caee224154186d2b8a52e31d",
+            "0x1151949895e82ab19924de92c40a3d6f7bcb60d92b00504b8199613683f0c200",
+        ];
+        for level in zeros.windows(2) {
+            let child = fe(level[0]);
+            assert_eq!(MiMCCRH::hash_two(&params, child, child), fe(level[1]));
+        }
+    }
+
+    #[test]
+    fn parameters_test() {
+        assert!(matches!(
+            MiMCParameters::<ark_ed_on_bls12_381::Fq>::circomlib(),
+            Err(CryptoError::UnsupportedOperation)
+        ));
+
+        let rng = &mut ark_std::test_rng();
+        let mut params = MiMCCRH::setup(rng).unwrap();
+        assert_eq!(params.round_keys.len(), CIRCOMLIB_ROUNDS);
+        let input = [7u8; 64];
+        assert!(MiMCCRH::evaluate(&params, &input).is_ok());
+        for len in [0, 32, 63, 65, 96].iter() {
+            assert!(matches!(
+                MiMCCRH::evaluate(&params, &vec![0u8; *len]),
+                Err(Error::Crypto(CryptoError::IncorrectInputLength(l))) if l == *len
+            ));
+        }
+
+        params.round_keys.pop();
+        assert!(matches!(
+            MiMCCRH::evaluate(&params, &input),
+            Err(Error::Crypto(CryptoError::InvalidLength {
+                expected: CIRCOMLIB_ROUNDS,
+                ..
+            }))
+        ));
+    }
+}
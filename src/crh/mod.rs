@@ -1,13 +1,19 @@
 use ark_ff::bytes::ToBytes;
 use ark_std::hash::Hash;
-use ark_std::rand::Rng;
+use ark_std::rand::{Rng, SeedableRng};
 
+pub mod blake2s;
 pub mod bowe_hopwood;
+pub mod gmimc;
 pub mod injective_map;
+pub mod keccak;
+pub mod mimc;
 pub mod pedersen;
 pub mod poseidon;
+pub mod rescue;
+pub mod sha256;
 
-use crate::Error;
+use crate::{prf::ChaChaRng, Error};
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
@@ -16,10 +22,22 @@ pub use constraints::*;
 
 pub trait FixedLengthCRH {
     const INPUT_SIZE_BITS: usize;
+    /// The number of leading input bytes that `evaluate` reads into the
+    /// capacity of its sponge. The tagged domain separation of
+    /// `merkle_tree` writes its tag there. CRHs that are not sponges keep
+    /// the default of a single byte, which then only holds the tag.
+    const CAPACITY_BYTES: usize = 1;
 
     type Output: ToBytes + Clone + Eq + core::fmt::Debug + Hash + Default;
     type Parameters: Clone + Default;
 
     fn setup<R: Rng>(r: &mut R) -> Result<Self::Parameters, Error>;
+
+    /// Runs [`setup`](Self::setup) with a [`ChaChaRng`] seeded by `seed`, so
+    /// that the same seed always gives the same parameters.
+    fn setup_from_seed(seed: [u8; 32]) -> Result<Self::Parameters, Error> {
+        Self::setup(&mut ChaChaRng::from_seed(seed))
+    }
+
     fn evaluate(parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error>;
 }
@@ -147,4 +147,47 @@ mod test {
         assert_eq!(primitive_result, result_var.value().unwrap());
         assert!(cs.is_satisfied().unwrap());
     }
+
+    #[test]
+    fn setup_from_seed_test() {
+        let parameters = TestCRH::setup_from_seed([1u8; 32]).unwrap();
+        let again = TestCRH::setup_from_seed([1u8; 32]).unwrap();
+        let other = TestCRH::setup_from_seed([2u8; 32]).unwrap();
+        assert_eq!(parameters.generators, again.generators);
+        assert_ne!(parameters.generators[0][0], other.generators[0][0]);
+        let input = [7u8; 128];
+        assert_eq!(
+            TestCRH::evaluate(&parameters, &input).unwrap(),
+            TestCRH::evaluate(&again, &input).unwrap()
+        );
+    }
+
+    /// A Pedersen output allocated as a public input takes the slots of its
+    /// native `ToConstraintField` elements, `[x, y]`.
+    #[test]
+    fn public_input_order_test() {
+        use ark_ff::ToConstraintField;
+        use ark_r1cs_std::ToConstraintFieldGadget;
+
+        let rng = &mut test_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let (input, input_var) = generate_input(cs.clone(), rng);
+        let parameters = TestCRH::setup(rng).unwrap();
+        assert_eq!(parameters.to_field_elements(), Some(Vec::<Fr>::new()));
+        let output = TestCRH::evaluate(&parameters, &input).unwrap();
+
+        let parameters_var = CRHParametersVar::new_constant(cs.clone(), &parameters).unwrap();
+        let output_var = TestCRHGadget::evaluate(&parameters_var, &input_var).unwrap();
+        let public_var = EdwardsVar::new_input(cs.clone(), || Ok(output)).unwrap();
+        output_var.enforce_equal(&public_var).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        let native = output.to_field_elements().unwrap();
+        assert_eq!(native, vec![output.x, output.y]);
+        assert_eq!(cs.borrow().unwrap().instance_assignment[1..], native[..]);
+        assert_eq!(
+            output_var.to_constraint_field().unwrap().value().unwrap(),
+            native
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{Error, Vec};
+use crate::{CryptoError, Error, Vec};
 use ark_std::rand::Rng;
 use ark_std::{
     fmt::{Debug, Formatter, Result as FmtResult},
@@ -8,23 +8,84 @@ use ark_std::{
 use rayon::prelude::*;
 
 use crate::crh::FixedLengthCRH;
-use ark_ec::ProjectiveCurve;
-use ark_ff::{Field, ToConstraintField};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{to_bytes, Field, FpParameters, PrimeField, ToConstraintField};
+use ark_serialize::CanonicalSerialize;
 use ark_std::cfg_chunks;
+use ark_std::collections::BTreeSet;
+use blake2::{Blake2s, Digest};
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
 
+/// The Blake2s personalization of [`CRH::hash_to_generator`].
+pub const GENERATOR_PERSONALIZATION: [u8; 8] = *b"ArkPedGn";
+
 pub trait Window: Clone {
     const WINDOW_SIZE: usize;
     const NUM_WINDOWS: usize;
 }
 
+/// Returns the number of windows of `window_size` bits that are needed to
+/// cover an input of `input_bits` bits, i.e. the smallest valid
+/// `NUM_WINDOWS` for a [`Window`] with that `WINDOW_SIZE`. `window_size` has
+/// to be non-zero.
+pub fn required_windows(input_bits: usize, window_size: usize) -> usize {
+    let windows = input_bits / window_size;
+    if windows * window_size < input_bits {
+        windows + 1
+    } else {
+        windows
+    }
+}
+
+/// Checks that the windows of `W` cover an input of `input_bits` bits.
+pub(crate) fn check_input_size<W: Window>(input_bits: usize) -> Result<(), Error> {
+    let max_bits = W::WINDOW_SIZE * W::NUM_WINDOWS;
+    if input_bits > max_bits {
+        return Err(CryptoError::InputTooLong {
+            bits: input_bits,
+            max_bits,
+        }
+        .into());
+    }
+    Ok(())
+}
+
 #[derive(Clone, Default)]
 pub struct Parameters<C: ProjectiveCurve> {
     pub generators: Vec<Vec<C>>,
 }
 
+impl<C: ProjectiveCurve> Parameters<C> {
+    /// Checks that the generators are distinct non-identity elements of the
+    /// prime order subgroup, without which the hash is not collision
+    /// resistant.
+    pub fn validate(&self) -> Result<(), CryptoError> {
+        validate_generators(self.generators.iter().flatten())
+    }
+}
+
+/// Checks that `generators` are distinct non-identity elements of the prime
+/// order subgroup.
+pub(crate) fn validate_generators<'a, C: ProjectiveCurve>(
+    generators: impl IntoIterator<Item = &'a C>,
+) -> Result<(), CryptoError> {
+    let order = <C::ScalarField as PrimeField>::Params::MODULUS;
+    let mut seen = BTreeSet::new();
+    for generator in generators {
+        if generator.is_zero() || !generator.mul(order).is_zero() {
+            return Err(CryptoError::NotPrimeOrder);
+        }
+        let encoding =
+            to_bytes![generator.into_affine()].map_err(|_| CryptoError::NotPrimeOrder)?;
+        if !seen.insert(encoding) {
+            return Err(CryptoError::DuplicateGenerator);
+        }
+    }
+    Ok(())
+}
+
 pub struct CRH<C: ProjectiveCurve, W: Window> {
     group: PhantomData<C>,
     window: PhantomData<W>,
@@ -40,8 +101,63 @@ impl<C: ProjectiveCurve, W: Window> CRH<C, W> {
     }
 
     pub fn generator_powers<R: Rng>(num_powers: usize, rng: &mut R) -> Vec<C> {
+        Self::powers(C::rand(rng), num_powers)
+    }
+
+    /// Derives the parameters from `personalization` instead of sampling
+    /// them, so that they can be reproduced independently. The base of
+    /// window `i` is [`CRH::hash_to_generator`] of `(personalization, i)`.
+    pub fn setup_deterministic(personalization: &[u8]) -> Result<Parameters<C>, Error> {
+        let generators = (0..W::NUM_WINDOWS)
+            .map(|i| {
+                let base = Self::hash_to_generator(personalization, i as u32)?;
+                Ok(Self::powers(base, W::WINDOW_SIZE))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Parameters { generators })
+    }
+
+    /// Hashes `(personalization, index)` to an element of the prime order
+    /// subgroup, by try-and-increment over a counter `c` from 0 to 255:
+    ///
+    /// 1. Let `B_j` be the Blake2s digest, with the Blake2s personalization
+    ///    [`GENERATOR_PERSONALIZATION`], of
+    ///    `LE64(len(personalization)) || personalization || LE32(index) || c || j`,
+    ///    where `c` and `j` are single bytes. Take the first `n` bytes of
+    ///    `B_0 || B_1 || ...`, where `n` is the size of a compressed point.
+    /// 2. Decode them with `AffineCurve::from_random_bytes`, i.e. as the
+    ///    little-endian coordinate and flags of a compressed point.
+    /// 3. Multiply the point by the cofactor, and return it unless it is the
+    ///    identity or outside the prime order subgroup.
+    pub fn hash_to_generator(personalization: &[u8], index: u32) -> Result<C, Error> {
+        let len = C::Affine::prime_subgroup_generator().serialized_size();
+        for counter in 0..=u8::MAX {
+            let mut bytes = Vec::with_capacity(len + 32);
+            let mut block = 0u8;
+            while bytes.len() < len {
+                let mut h = Blake2s::with_params(&[], &[], &GENERATOR_PERSONALIZATION);
+                h.update((personalization.len() as u64).to_le_bytes());
+                h.update(personalization);
+                h.update(index.to_le_bytes());
+                h.update([counter, block]);
+                bytes.extend_from_slice(&h.finalize());
+                block += 1;
+            }
+            bytes.truncate(len);
+
+            if let Some(point) = C::Affine::from_random_bytes(&bytes) {
+                let generator = point.mul_by_cofactor_to_projective();
+                let order = <C::ScalarField as PrimeField>::Params::MODULUS;
+                if !generator.is_zero() && generator.into_affine().mul(order).is_zero() {
+                    return Ok(generator);
+                }
+            }
+        }
+        Err(CryptoError::HashToCurveFailed.into())
+    }
+
+    fn powers(mut base: C, num_powers: usize) -> Vec<C> {
         let mut cur_gen_powers = Vec::with_capacity(num_powers);
-        let mut base = C::rand(rng);
         for _ in 0..num_powers {
             cur_gen_powers.push(base);
             base.double_in_place();
@@ -70,14 +186,7 @@ impl<C: ProjectiveCurve, W: Window> FixedLengthCRH for CRH<C, W> {
     fn evaluate(parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
         let eval_time = start_timer!(|| "PedersenCRH::Eval");
 
-        if (input.len() * 8) > W::WINDOW_SIZE * W::NUM_WINDOWS {
-            panic!(
-                "incorrect input length {:?} for window params {:?}✕{:?}",
-                input.len(),
-                W::WINDOW_SIZE,
-                W::NUM_WINDOWS
-            );
-        }
+        check_input_size::<W>(input.len() * 8)?;
 
         let mut padded_input = Vec::with_capacity(input.len());
         let mut input = input;
@@ -99,9 +208,20 @@ impl<C: ProjectiveCurve, W: Window> FixedLengthCRH for CRH<C, W> {
             W::NUM_WINDOWS
         );
 
-        // Compute sum of h_i^{m_i} for all i.
+        let result = Self::evaluate_padded(parameters, input);
+
+        end_timer!(eval_time);
+
+        Ok(result.into())
+    }
+}
+
+impl<C: ProjectiveCurve, W: Window> CRH<C, W> {
+    /// Computes the sum of h_i^{m_i} for all i, for an input that is already
+    /// padded to the size of the windows.
+    pub(crate) fn evaluate_padded(parameters: &Parameters<C>, input: &[u8]) -> C {
         let bits = bytes_to_bits(input);
-        let result = cfg_chunks!(bits, W::WINDOW_SIZE)
+        cfg_chunks!(bits, W::WINDOW_SIZE)
             .zip(&parameters.generators)
             .map(|(bits, generator_powers)| {
                 let mut encoded = C::zero();
@@ -112,11 +232,7 @@ impl<C: ProjectiveCurve, W: Window> FixedLengthCRH for CRH<C, W> {
                 }
                 encoded
             })
-            .sum::<C>();
-
-        end_timer!(eval_time);
-
-        Ok(result.into())
+            .sum::<C>()
     }
 }
 
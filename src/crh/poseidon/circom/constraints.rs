@@ -0,0 +1,88 @@
+use crate::crh::poseidon::constraints::{CRHGadget, PoseidonParametersVar};
+use crate::crh::poseidon::Rounds;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::SynthesisError;
+
+/// The counterpart of the native `hash`.
+pub fn hash<F: PrimeField, P: Rounds>(
+    parameters: &PoseidonParametersVar<F>,
+    inputs: &[FpVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    let round_keys = (P::FULL_ROUNDS + P::PARTIAL_ROUNDS) * P::WIDTH;
+    if inputs.len() + 1 != P::WIDTH
+        || parameters.round_keys.len() != round_keys
+        || parameters.mds_matrix.len() != P::WIDTH
+    {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let mut state = vec![FpVar::zero()];
+    state.extend_from_slice(inputs);
+    Ok(CRHGadget::<F, P>::permute(parameters, state)?.remove(0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::poseidon::circom::test::{inputs, VECTORS};
+    use crate::crh::poseidon::circom::{CircomRounds3, CircomRounds5};
+    use crate::crh::poseidon::test_data::{get_mds_3, get_mds_5, get_rounds_3, get_rounds_5};
+    use crate::crh::poseidon::PoseidonParameters;
+    use crate::utils::hex::fe_from_hex;
+    use ark_ed_on_bn254::Fq;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn gadget_hash<P: Rounds>(params: &PoseidonParameters<Fq>, values: &[u64]) -> Fq {
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = PoseidonParametersVar::new_constant(cs.clone(), params).unwrap();
+        let inputs_var = Vec::<FpVar<Fq>>::new_witness(cs.clone(), || Ok(inputs(values))).unwrap();
+        let output = hash::<Fq, P>(&params_var, &inputs_var).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        output.value().unwrap()
+    }
+
+    #[test]
+    fn circomlibjs_vectors_test() {
+        let params = PoseidonParameters::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let (values, expected) = VECTORS[1];
+        assert_eq!(
+            gadget_hash::<CircomRounds3>(&params, values),
+            fe_from_hex(expected).unwrap()
+        );
+
+        let params = PoseidonParameters::new(get_rounds_5::<Fq>(), get_mds_5::<Fq>());
+        let (values, expected) = VECTORS[4];
+        assert_eq!(
+            gadget_hash::<CircomRounds5>(&params, values),
+            fe_from_hex(expected).unwrap()
+        );
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = PoseidonParametersVar::new_constant(cs.clone(), &params).unwrap();
+        let inputs_var = Vec::<FpVar<Fq>>::new_witness(cs, || Ok(inputs(&[1, 2]))).unwrap();
+        assert!(matches!(
+            hash::<Fq, CircomRounds5>(&params_var, &inputs_var),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[cfg(feature = "circom-constants")]
+    #[test]
+    fn embedded_constants_test() {
+        use crate::crh::poseidon::circom::{
+            circom_parameters, CircomRounds2, CircomRounds4, CircomRounds6,
+        };
+
+        for (values, expected) in VECTORS.iter() {
+            let params = circom_parameters::<Fq>(values.len() + 1).unwrap();
+            let output = match values.len() {
+                1 => gadget_hash::<CircomRounds2>(&params, values),
+                2 => gadget_hash::<CircomRounds3>(&params, values),
+                3 => gadget_hash::<CircomRounds4>(&params, values),
+                4 => gadget_hash::<CircomRounds5>(&params, values),
+                _ => gadget_hash::<CircomRounds6>(&params, values),
+            };
+            assert_eq!(output, fe_from_hex(expected).unwrap());
+        }
+    }
+}
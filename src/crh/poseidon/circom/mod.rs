@@ -0,0 +1,327 @@
+//! Compatibility with circomlib's Poseidon over the BN254 scalar field.
+//! circomlib hashes `n` inputs with a permutation of width `n + 1`, whose
+//! state starts as `[0, inputs...]`, and outputs the first element of the
+//! permuted state. The permutation is that of [`CRH`], so only the
+//! constants and the round counts are needed; [`from_circom_constants`]
+//! converts the former from circomlib's format.
+//!
+//! The constants themselves, for widths 2 to 6, are embedded with the
+//! `circom-constants` feature.
+
+use super::{sbox::PoseidonSbox, PoseidonParameters, Rounds, CRH};
+use crate::utils::hex;
+use crate::{CryptoError, Vec};
+use ark_ff::PrimeField;
+
+#[cfg(feature = "circom-constants")]
+pub mod constants;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+macro_rules! circom_rounds {
+    ($name:ident, $width:expr, $partial_rounds:expr) => {
+        #[doc = concat!("The rounds of circomlib's Poseidon of width ", $width, ".")]
+        #[derive(Default, Clone)]
+        pub struct $name;
+
+        impl Rounds for $name {
+            const WIDTH: usize = $width;
+            const FULL_ROUNDS: usize = 8;
+            const PARTIAL_ROUNDS: usize = $partial_rounds;
+            const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+        }
+    };
+}
+
+circom_rounds!(CircomRounds2, 2, 56);
+circom_rounds!(CircomRounds3, 3, 57);
+circom_rounds!(CircomRounds4, 4, 56);
+circom_rounds!(CircomRounds5, 5, 60);
+circom_rounds!(CircomRounds6, 6, 60);
+
+/// Converts circomlib's constants, `C` and `M` of one width in its
+/// `poseidon_constants.json`, into parameters: `c` are the round constants,
+/// round by round, and `m` the rows of the MDS matrix, which multiplies the
+/// state as a column.
+pub fn from_circom_constants<F: PrimeField>(
+    c: &[&str],
+    m: &[&[&str]],
+) -> Result<PoseidonParameters<F>, CryptoError> {
+    let width = m.len();
+    if let Some(row) = m.iter().find(|row| row.len() != width) {
+        return Err(CryptoError::InvalidLength {
+            expected: width,
+            got: row.len(),
+        });
+    }
+    let rounds = c.len().checked_div(width).unwrap_or(0);
+    if rounds == 0 || rounds * width != c.len() {
+        return Err(CryptoError::IncorrectInputLength(c.len()));
+    }
+    let round_keys = hex::fes_from_hex(c)?;
+    let mds_matrix = m
+        .iter()
+        .map(|row| hex::fes_from_hex(row))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(PoseidonParameters::new(round_keys, mds_matrix))
+}
+
+/// The embedded parameters of circomlib's Poseidon of `width`, from 2 to 6.
+/// Fails for other widths, and unless `F` is the BN254 scalar field.
+#[cfg(feature = "circom-constants")]
+pub fn circom_parameters<F: PrimeField>(
+    width: usize,
+) -> Result<PoseidonParameters<F>, CryptoError> {
+    use crate::crh::mimc::BN254_SCALAR_MINUS_ONE;
+
+    if hex::fe_to_hex(&-F::one()) != BN254_SCALAR_MINUS_ONE {
+        return Err(CryptoError::UnsupportedOperation);
+    }
+    match width {
+        2 => from_circom_constants(&constants::C_2, &rows(&constants::M_2)),
+        3 => from_circom_constants(&constants::C_3, &rows(&constants::M_3)),
+        4 => from_circom_constants(&constants::C_4, &rows(&constants::M_4)),
+        5 => from_circom_constants(&constants::C_5, &rows(&constants::M_5)),
+        6 => from_circom_constants(&constants::C_6, &rows(&constants::M_6)),
+        _ => Err(CryptoError::UnsupportedOperation),
+    }
+}
+
+/// The embedded parameters of circomlib's Poseidon for the rounds `P`,
+/// which schemes over Poseidon return from `setup`. Fails with
+/// [`CryptoError::InvalidParameters`] unless `P` has the width, round
+/// counts and S-box of one of circomlib's permutations, and with
+/// [`CryptoError::UnsupportedOperation`] unless `F` is the BN254 scalar
+/// field, and always without the `circom-constants` feature: other
+/// parameters cannot be generated and have to be passed in.
+pub fn parameters_for_rounds<F: PrimeField, P: Rounds>(
+) -> Result<PoseidonParameters<F>, CryptoError> {
+    #[cfg(feature = "circom-constants")]
+    {
+        if !(2..=6).contains(&P::WIDTH)
+            || P::FULL_ROUNDS != 8
+            || P::SBOX != PoseidonSbox::Exponentiation(5)
+        {
+            return Err(CryptoError::InvalidParameters);
+        }
+        let parameters = circom_parameters(P::WIDTH)?;
+        check::<F, P>(&parameters)?;
+        Ok(parameters)
+    }
+    #[cfg(not(feature = "circom-constants"))]
+    Err(CryptoError::UnsupportedOperation)
+}
+
+/// The rows of an embedded matrix, as [`from_circom_constants`] takes them.
+#[cfg(any(test, feature = "circom-constants"))]
+fn rows<'a, const W: usize>(m: &'a [[&'static str; W]]) -> Vec<&'a [&'static str]> {
+    m.iter().map(|row| &row[..]).collect()
+}
+
+/// Checks that `parameters` have the constants of `P`, and that the S-box
+/// of `P` is a permutation of `F`.
+pub(crate) fn check<F: PrimeField, P: Rounds>(
+    parameters: &PoseidonParameters<F>,
+) -> Result<(), CryptoError> {
+    let round_keys = (P::FULL_ROUNDS + P::PARTIAL_ROUNDS) * P::WIDTH;
+    if parameters.round_keys.len() != round_keys
+        || parameters.mds_matrix.len() != P::WIDTH
+        || parameters
+            .mds_matrix
+            .iter()
+            .any(|row| row.len() != P::WIDTH)
+    {
+        return Err(CryptoError::InvalidParameters);
+    }
+    P::SBOX.check::<F>()
+}
+
+/// circomlib's `Poseidon(P::WIDTH - 1)` of `inputs`.
+pub fn hash<F: PrimeField, P: Rounds>(
+    parameters: &PoseidonParameters<F>,
+    inputs: &[F],
+) -> Result<F, CryptoError> {
+    check::<F, P>(parameters)?;
+    if inputs.len() + 1 != P::WIDTH {
+        return Err(CryptoError::InvalidLength {
+            expected: P::WIDTH - 1,
+            got: inputs.len(),
+        });
+    }
+    let mut state = vec![F::zero()];
+    state.extend_from_slice(inputs);
+    Ok(CRH::<F, P>::permute(parameters, state)[0])
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::crh::poseidon::test_data::{
+        get_mds_3, get_mds_5, get_rounds_3, get_rounds_5, x5_254_3, x5_254_5,
+    };
+    use ark_ed_on_bn254::Fq;
+
+    /// Inputs and outputs of circomlibjs's `poseidon`, whose width is one
+    /// more than the number of inputs.
+    pub(crate) const VECTORS: [(&[u64], &str); 6] = [
+        (
+            &[1],
+            "0x29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133",
+        ),
+        (
+            &[1, 2],
+            "0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+        ),
+        (
+            &[3, 4],
+            "0x20a3af0435914ccd84b806164531b0cd36e37d4efb93efab76913a93e1f30996",
+        ),
+        (
+            &[1, 2, 3],
+            "0x0e7732d89e6939c0ff03d5e58dab6302f3230e269dc5b968f725df34ab36d732",
+        ),
+        (
+            &[1, 2, 3, 4],
+            "0x299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465",
+        ),
+        (
+            &[1, 2, 0, 0, 0],
+            "0x024058dd1e168f34bac462b6fffe58fd69982807e9884c1c6148182319cee427",
+        ),
+    ];
+
+    pub(crate) fn inputs(values: &[u64]) -> Vec<Fq> {
+        values.iter().map(|v| Fq::from(*v)).collect()
+    }
+
+    #[test]
+    fn converter_test() {
+        let params =
+            from_circom_constants::<Fq>(&x5_254_3::ROUND_CONSTS, &rows(&x5_254_3::MDS_ENTRIES))
+                .unwrap();
+        assert_eq!(params.round_keys, get_rounds_3::<Fq>());
+        assert_eq!(params.mds_matrix, get_mds_3::<Fq>());
+        let expected = hex::fe_from_hex(VECTORS[1].1).unwrap();
+        assert_eq!(
+            hash::<Fq, CircomRounds3>(&params, &inputs(VECTORS[1].0)).unwrap(),
+            expected
+        );
+
+        let params =
+            from_circom_constants::<Fq>(&x5_254_5::ROUND_CONSTS, &rows(&x5_254_5::MDS_ENTRIES))
+                .unwrap();
+        assert_eq!(params.round_keys, get_rounds_5::<Fq>());
+        assert_eq!(params.mds_matrix, get_mds_5::<Fq>());
+        let expected = hex::fe_from_hex(VECTORS[4].1).unwrap();
+        assert_eq!(
+            hash::<Fq, CircomRounds5>(&params, &inputs(VECTORS[4].0)).unwrap(),
+            expected
+        );
+
+        // Malformed constants.
+        let m = rows(&x5_254_3::MDS_ENTRIES);
+        assert!(matches!(
+            from_circom_constants::<Fq>(&x5_254_3::ROUND_CONSTS[1..], &m),
+            Err(CryptoError::IncorrectInputLength(194))
+        ));
+        assert!(matches!(
+            from_circom_constants::<Fq>(&x5_254_3::ROUND_CONSTS, &m[1..]),
+            Err(CryptoError::InvalidLength {
+                expected: 2,
+                got: 3
+            })
+        ));
+        assert!(matches!(
+            from_circom_constants::<Fq>(&["0xzz", "0x1", "0x2"], &m),
+            Err(CryptoError::InvalidHex)
+        ));
+
+        // Parameters or inputs of the wrong width.
+        assert!(matches!(
+            hash::<Fq, CircomRounds5>(&params, &inputs(&[1, 2, 3])),
+            Err(CryptoError::InvalidLength {
+                expected: 4,
+                got: 3
+            })
+        ));
+        assert!(matches!(
+            hash::<Fq, CircomRounds3>(&params, &inputs(&[1, 2])),
+            Err(CryptoError::InvalidParameters)
+        ));
+    }
+
+    #[cfg(feature = "circom-constants")]
+    #[test]
+    fn circomlibjs_vectors_test() {
+        use ark_ed_on_bls12_381::Fq as OtherFq;
+
+        for (values, expected) in VECTORS.iter() {
+            let params = circom_parameters::<Fq>(values.len() + 1).unwrap();
+            let output = match values.len() {
+                1 => hash::<Fq, CircomRounds2>(&params, &inputs(values)),
+                2 => hash::<Fq, CircomRounds3>(&params, &inputs(values)),
+                3 => hash::<Fq, CircomRounds4>(&params, &inputs(values)),
+                4 => hash::<Fq, CircomRounds5>(&params, &inputs(values)),
+                _ => hash::<Fq, CircomRounds6>(&params, &inputs(values)),
+            };
+            assert_eq!(output.unwrap(), hex::fe_from_hex(expected).unwrap());
+        }
+
+        // The embedded constants of widths 3 and 5 are those of the tests.
+        assert_eq!(
+            circom_parameters::<Fq>(3).unwrap().round_keys,
+            get_rounds_3::<Fq>()
+        );
+        assert_eq!(
+            circom_parameters::<Fq>(5).unwrap().mds_matrix,
+            get_mds_5::<Fq>()
+        );
+
+        assert!(matches!(
+            circom_parameters::<Fq>(7),
+            Err(CryptoError::UnsupportedOperation)
+        ));
+        assert!(matches!(
+            circom_parameters::<OtherFq>(3),
+            Err(CryptoError::UnsupportedOperation)
+        ));
+    }
+
+    #[derive(Default, Clone)]
+    struct CubeRounds3;
+
+    impl Rounds for CubeRounds3 {
+        const WIDTH: usize = 3;
+        const PARTIAL_ROUNDS: usize = 57;
+        const FULL_ROUNDS: usize = 8;
+        const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(3);
+    }
+
+    #[test]
+    fn check_test() {
+        let params = PoseidonParameters::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        assert!(check::<Fq, CircomRounds3>(&params).is_ok());
+        assert!(matches!(
+            check::<Fq, CircomRounds5>(&params),
+            Err(CryptoError::InvalidParameters)
+        ));
+
+        // 3 divides `p - 1`, so `x^3` is not a permutation, and `x^4` never is.
+        assert!(matches!(
+            check::<Fq, CubeRounds3>(&params),
+            Err(CryptoError::SboxError)
+        ));
+        assert!(matches!(
+            PoseidonSbox::Exponentiation(4).check::<Fq>(),
+            Err(CryptoError::SboxError)
+        ));
+        assert!(PoseidonSbox::Inverse.check::<Fq>().is_ok());
+
+        let result = parameters_for_rounds::<Fq, CubeRounds3>();
+        #[cfg(feature = "circom-constants")]
+        assert!(matches!(result, Err(CryptoError::InvalidParameters)));
+        #[cfg(not(feature = "circom-constants"))]
+        assert!(matches!(result, Err(CryptoError::UnsupportedOperation)));
+    }
+}
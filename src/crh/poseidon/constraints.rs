@@ -1,8 +1,10 @@
 use super::sbox::constraints::SboxConstraints;
-use super::{PoseidonParameters, Rounds, CRH};
-use crate::FixedLengthCRHGadget;
+use super::{
+    domain_element, to_field_elements, PoseidonParameters, Rounds, BYTES_DOMAIN_TAG,
+    BYTES_PER_CHUNK, CRH,
+};
+use crate::{utils::constraints as utils, FixedLengthCRHGadget};
 use ark_ff::PrimeField;
-use ark_r1cs_std::fields::fp::AllocatedFp;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::uint8::UInt8;
 use ark_r1cs_std::{alloc::AllocVar, fields::FieldVar, prelude::*};
@@ -25,7 +27,7 @@ pub struct CRHGadget<F: PrimeField, P: Rounds> {
 }
 
 impl<F: PrimeField, P: Rounds> CRHGadget<F, P> {
-    fn permute(
+    pub(crate) fn permute(
         parameters: &PoseidonParametersVar<F>,
         input: Vec<FpVar<F>>,
     ) -> Result<Vec<FpVar<F>>, SynthesisError> {
@@ -91,7 +93,43 @@ impl<F: PrimeField, P: Rounds> CRHGadget<F, P> {
     }
 }
 
-// https://github.com/arkworks-rs/r1cs-std/blob/master/src/bits/uint8.rs#L343
+/// The in-circuit twin of [`to_field_elements`](super::to_field_elements):
+/// each chunk is the weighted sum of its bits, which reduces it modulo the
+/// characteristic of `F` exactly like the native function. This costs no
+/// constraints beyond those of the bit decomposition of `bytes`, and
+/// constant bytes are folded natively into constants.
+pub fn to_field_var_elements<F: PrimeField>(
+    bytes: &[UInt8<F>],
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    if bytes.is_constant() {
+        let elements = to_field_elements(&bytes.value()?);
+        return Ok(elements.into_iter().map(FpVar::constant).collect());
+    }
+    to_field_var_elements_from_bits(&utils::bytes_to_bits_le(bytes)?)
+}
+
+/// Like [`to_field_var_elements`], for callers that already hold the
+/// little-endian bits of the bytes, such as those of an `FpVar`. The bits
+/// are read in chunks of `8 * BYTES_PER_CHUNK`, the last one possibly
+/// shorter.
+pub fn to_field_var_elements_from_bits<F: PrimeField>(
+    bits: &[Boolean<F>],
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    Ok(bits
+        .chunks(8 * BYTES_PER_CHUNK)
+        .map(utils::le_bits_to_fp)
+        .collect())
+}
+
+/// The in-circuit twin of
+/// [`to_field_elements_packed`](super::to_field_elements_packed). Each
+/// element has fewer bits than the modulus, so no range check is needed.
+pub fn to_field_var_elements_packed<F: PrimeField>(
+    bytes: &[UInt8<F>],
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    Ok(utils::bits_to_fp_chunks(&utils::bytes_to_bits_le(bytes)?))
+}
+
 impl<F: PrimeField, P: Rounds> FixedLengthCRHGadget<CRH<F, P>, F> for CRHGadget<F, P> {
     type OutputVar = FpVar<F>;
     type ParametersVar = PoseidonParametersVar<F>;
@@ -100,27 +138,61 @@ impl<F: PrimeField, P: Rounds> FixedLengthCRHGadget<CRH<F, P>, F> for CRHGadget<
         parameters: &Self::ParametersVar,
         input: &[UInt8<F>],
     ) -> Result<Self::OutputVar, SynthesisError> {
-        assert_eq!(input.len() / 32, P::WIDTH);
-        // Not giving expected results
-        // let f_var_inputs: Vec<FpVar<F>> = input.to_constraint_field()?;
-
-        let f_var_inputs: Vec<FpVar<F>> = input
-            .chunks(32)
-            .map(|x| {
-                let mapped_x: Vec<u8> = x.iter().map(|x| x.value().unwrap()).collect();
-                FpVar::from(
-                    AllocatedFp::new_variable(
-                        input.cs(),
-                        || Ok(F::from_le_bytes_mod_order(&mapped_x)),
-                        AllocationMode::Witness,
-                    )
-                    .unwrap(),
-                )
-            })
-            .collect();
-
-        let result = Self::permute(&parameters, f_var_inputs);
-        result.map(|x| x.get(1).cloned().unwrap())
+        if input.len() != BYTES_PER_CHUNK * P::WIDTH {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let f_var_inputs = to_field_var_elements(input)?;
+
+        Self::evaluate_elements(parameters, f_var_inputs)
+    }
+}
+
+impl<F: PrimeField, P: Rounds> CRHGadget<F, P> {
+    /// Hashes exactly `P::WIDTH` field elements, skipping the byte encoding
+    /// of `evaluate`.
+    pub(crate) fn evaluate_elements(
+        parameters: &PoseidonParametersVar<F>,
+        input: Vec<FpVar<F>>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        if input.len() != P::WIDTH {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let result = Self::permute(parameters, input)?;
+        Ok(result[1].clone())
+    }
+
+    /// The counterpart of the native `hash_bytes`. The message length is
+    /// fixed by the circuit, so the domain element is a constant.
+    pub fn hash_bytes(
+        parameters: &PoseidonParametersVar<F>,
+        input: &[UInt8<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let elements = to_field_var_elements_packed(input)?;
+        let domain = FpVar::constant(domain_element(BYTES_DOMAIN_TAG, input.len()));
+        Self::sponge(parameters, domain, &elements)
+    }
+
+    /// The counterpart of the native `sponge`.
+    pub(crate) fn sponge(
+        parameters: &PoseidonParametersVar<F>,
+        domain: FpVar<F>,
+        values: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        if P::WIDTH < 2 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut state = vec![FpVar::zero(); P::WIDTH];
+        state[0] = domain;
+        if values.is_empty() {
+            return Ok(Self::permute(parameters, state)?[1].clone());
+        }
+        for chunk in values.chunks(P::WIDTH - 1) {
+            for (s, value) in state[1..].iter_mut().zip(chunk) {
+                *s += value;
+            }
+            state = Self::permute(parameters, state)?;
+        }
+        Ok(state[1].clone())
     }
 }
 
@@ -158,11 +230,11 @@ mod test {
     use crate::crh::FixedLengthCRH;
     use ark_ed_on_bn254::Fq;
     use ark_ff::to_bytes;
-    use ark_ff::Zero;
+    use ark_ff::{FpParameters, One, Zero};
     use ark_relations::r1cs::ConstraintSystem;
 
-    use crate::crh::poseidon::test_data::{get_mds_3, get_rounds_3};
-    use crate::crh::poseidon::PoseidonSbox;
+    use crate::crh::poseidon::test_data::{get_mds_3, get_results_3, get_rounds_3};
+    use crate::crh::poseidon::{to_field_elements_packed, PoseidonSbox};
 
     #[derive(Default, Clone)]
     struct PoseidonRounds3;
@@ -177,6 +249,104 @@ mod test {
     type PoseidonCRH3 = CRH<Fq, PoseidonRounds3>;
     type PoseidonCRH3Gadget = CRHGadget<Fq, PoseidonRounds3>;
 
+    #[test]
+    fn to_field_var_elements_test() {
+        let max = to_bytes![-Fq::one()].unwrap();
+        let modulus = to_bytes![<Fq as PrimeField>::Params::MODULUS].unwrap();
+        let cases = [
+            max.clone(),
+            [&max[..], &[1, 2]].concat(),
+            vec![0xFF; BYTES_PER_CHUNK],
+            modulus,
+        ];
+        for bytes in cases.iter() {
+            let native = to_field_elements::<Fq>(bytes);
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let witnesses = UInt8::new_witness_vec(cs.clone(), bytes).unwrap();
+            let elements = to_field_var_elements(&witnesses).unwrap();
+            assert_eq!(elements.value().unwrap(), native);
+            assert!(cs.is_satisfied().unwrap());
+
+            let bits = witnesses.to_bits_le().unwrap();
+            let from_bits = to_field_var_elements_from_bits(&bits).unwrap();
+            assert_eq!(from_bits.value().unwrap(), native);
+
+            // Constant bytes give constants, which emit no constraints even
+            // when combined with variables of `cs`.
+            let constants = UInt8::constant_vec(bytes);
+            let elements = to_field_var_elements::<Fq>(&constants).unwrap();
+            assert!(elements.iter().all(|c| c.is_constant()));
+            assert_eq!(elements.value().unwrap(), native);
+            let before = cs.num_constraints();
+            for (constant, witness) in elements.iter().zip(&from_bits) {
+                let _product = constant * witness;
+            }
+            assert_eq!(cs.num_constraints(), before);
+        }
+
+        // The bits of a field element give back the element.
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let element = FpVar::new_witness(cs.clone(), || Ok(-Fq::one())).unwrap();
+        let bits = element.to_bits_le().unwrap();
+        let before = cs.num_constraints();
+        let elements = to_field_var_elements_from_bits(&bits).unwrap();
+        assert_eq!(cs.num_constraints(), before);
+        elements[0].enforce_equal(&element).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn packed_elements_test() {
+        let rounds = get_rounds_3::<Fq>();
+        let mds = get_mds_3::<Fq>();
+        let params = PoseidonParameters::<Fq>::new(rounds, mds);
+        for len in [0, 1, 32, 100].iter() {
+            let bytes = (0..*len).map(|i| (i * 37 + 255) as u8).collect::<Vec<_>>();
+            let native = to_field_elements_packed::<Fq>(&bytes);
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let params_var = PoseidonParametersVar::new_constant(cs.clone(), &params).unwrap();
+            let bytes_var = UInt8::new_witness_vec(cs.clone(), &bytes).unwrap();
+            let elements = to_field_var_elements_packed(&bytes_var).unwrap();
+            assert_eq!(elements.value().unwrap(), native);
+
+            let hash = PoseidonCRH3Gadget::hash_bytes(&params_var, &bytes_var).unwrap();
+            assert_eq!(
+                hash.value().unwrap(),
+                PoseidonCRH3::hash_bytes(&params, &bytes).unwrap()
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn empty_input_test() {
+        let params = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = PoseidonParametersVar::new_constant(cs.clone(), &params).unwrap();
+        assert!(to_field_var_elements::<Fq>(&[]).unwrap().is_empty());
+        assert!(to_field_var_elements_from_bits::<Fq>(&[])
+            .unwrap()
+            .is_empty());
+        assert!(to_field_var_elements_packed::<Fq>(&[]).unwrap().is_empty());
+
+        for len in [0, 1, 97, 127].iter() {
+            let input = UInt8::new_witness_vec(cs.clone(), &vec![0u8; *len]).unwrap();
+            assert!(matches!(
+                PoseidonCRH3Gadget::evaluate(&params_var, &input),
+                Err(SynthesisError::Unsatisfiable)
+            ));
+        }
+
+        let empty = PoseidonCRH3Gadget::hash_bytes(&params_var, &[]).unwrap();
+        assert_eq!(
+            empty.value().unwrap(),
+            PoseidonCRH3::hash_bytes(&params, &[]).unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_poseidon_native_equality() {
         let rounds = get_rounds_3::<Fq>();
@@ -201,5 +371,76 @@ mod test {
         let res = PoseidonCRH3::evaluate(&params, &inp).unwrap();
         let res_var = PoseidonCRH3Gadget::evaluate(&params_var.unwrap(), &inp_u8).unwrap();
         assert_eq!(res, res_var.value().unwrap());
+        assert_eq!(res, get_results_3::<Fq>()[1]);
+    }
+
+    #[test]
+    fn public_input_order_test() {
+        use ark_ff::ToConstraintField;
+        use ark_r1cs_std::ToConstraintFieldGadget;
+
+        let params = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        assert_eq!(params.to_field_elements(), Some(Vec::new()));
+        let bytes = to_bytes![Fq::zero(), Fq::from(1u64), Fq::from(2u64)].unwrap();
+        let output = PoseidonCRH3::evaluate(&params, &bytes).unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = PoseidonParametersVar::new_constant(cs.clone(), &params).unwrap();
+        let input_var = UInt8::new_witness_vec(cs.clone(), &bytes).unwrap();
+        let output_var = PoseidonCRH3Gadget::evaluate(&params_var, &input_var).unwrap();
+        let public_var = FpVar::new_input(cs.clone(), || Ok(output)).unwrap();
+        output_var.enforce_equal(&public_var).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        let native = output.to_field_elements().unwrap();
+        assert_eq!(cs.borrow().unwrap().instance_assignment[1..], native[..]);
+        assert_eq!(
+            output_var.to_constraint_field().unwrap().value().unwrap(),
+            native
+        );
+    }
+
+    /// Every byte-to-field path of the Poseidon CRH must read the same bytes
+    /// as the same elements; a change to one of them breaks this test.
+    #[test]
+    fn conversion_consistency_test() {
+        let params = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let params_var =
+            PoseidonParametersVar::new_constant(ConstraintSystem::new_ref(), &params).unwrap();
+        let modulus = to_bytes![<Fq as PrimeField>::Params::MODULUS].unwrap();
+        let inputs = [
+            vec![0u8; 96],
+            vec![0xFF; 96],
+            [&modulus[..], &modulus[..], &modulus[..]].concat(),
+            (0..96u8).map(|i| i.wrapping_mul(151)).collect(),
+        ];
+        for bytes in inputs.iter() {
+            let native = to_field_elements::<Fq>(bytes);
+            assert_eq!(
+                PoseidonCRH3::evaluate(&params, bytes).unwrap(),
+                PoseidonCRH3::evaluate_elements(&params, native.clone())
+            );
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let witnesses = UInt8::new_witness_vec(cs.clone(), bytes).unwrap();
+            let constants = UInt8::constant_vec(bytes);
+            let bits = witnesses.to_bits_le().unwrap();
+            for elements in [
+                to_field_var_elements(&witnesses).unwrap(),
+                to_field_var_elements(&constants).unwrap(),
+                to_field_var_elements_from_bits(&bits).unwrap(),
+            ]
+            .iter()
+            {
+                assert_eq!(elements.value().unwrap(), native);
+            }
+
+            let hash = PoseidonCRH3Gadget::evaluate(&params_var, &witnesses).unwrap();
+            assert_eq!(
+                hash.value().unwrap(),
+                PoseidonCRH3::evaluate(&params, bytes).unwrap()
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
     }
 }
@@ -0,0 +1,330 @@
+//! Imports Poseidon parameters from the formats of other tooling: the
+//! bytes of webb's arkworks-gadgets `PoseidonParameters::to_bytes`, and the
+//! output of the reference `generate_parameters_grain.sage` script.
+//!
+//! Both formats carry the width, the round counts and the S-box, which must
+//! be those of the [`Rounds`] the parameters are imported for; the numbers
+//! of round constants and the shape of the MDS matrix are checked against
+//! them too.
+
+use super::{circom, sbox::PoseidonSbox, PoseidonParameters, Rounds};
+use crate::utils::hex::{fe_from_hex, fes_from_hex};
+use crate::{CryptoError, Vec};
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_serialize::SerializationError;
+use ark_std::io::Read;
+
+/// The S-box exponent of `P`, with `-1` for the inverse, as both formats
+/// encode it.
+fn sbox_exponent<P: Rounds>() -> i64 {
+    match P::SBOX {
+        PoseidonSbox::Exponentiation(alpha) => alpha as i64,
+        PoseidonSbox::Inverse => -1,
+    }
+}
+
+/// Checks that the header values of a format are those of `P`.
+fn check_header<P: Rounds>(
+    width: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    alpha: i64,
+) -> Result<(), CryptoError> {
+    for (expected, got) in [
+        (P::WIDTH, width),
+        (P::FULL_ROUNDS, full_rounds),
+        (P::PARTIAL_ROUNDS, partial_rounds),
+    ]
+    .iter()
+    {
+        if expected != got {
+            return Err(CryptoError::InvalidLength {
+                expected: *expected,
+                got: *got,
+            });
+        }
+    }
+    if alpha != sbox_exponent::<P>() {
+        return Err(CryptoError::UnsupportedOperation);
+    }
+    Ok(())
+}
+
+fn read_u32(reader: &mut &[u8]) -> Result<usize, CryptoError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes) as usize)
+}
+
+/// Reads a big-endian `u32` length and checks that it is `expected`, before
+/// anything of that length is read.
+fn read_len(reader: &mut &[u8], expected: usize) -> Result<(), CryptoError> {
+    let got = read_u32(reader)?;
+    if got != expected {
+        return Err(CryptoError::InvalidLength { expected, got });
+    }
+    Ok(())
+}
+
+/// Reads the bytes of arkworks-gadgets' `PoseidonParameters::to_bytes`:
+///
+/// ```text
+/// u32 len(round_keys) || round_keys
+/// u32 len(mds_matrix) || for each row: u32 len(row) || row
+/// u8 full_rounds || u8 partial_rounds || u8 width || i8 sbox
+/// ```
+///
+/// where the lengths are big-endian, each element is its canonical
+/// representative in `F::BigInt::NUM_LIMBS * 8` little-endian bytes, and
+/// the S-box is its exponent, `-1` for the inverse. Truncated input fails
+/// with an I/O error, and trailing bytes with invalid data.
+pub fn from_arkworks_gadgets_bytes<F: PrimeField, P: Rounds>(
+    bytes: &[u8],
+) -> Result<PoseidonParameters<F>, CryptoError> {
+    let reader = &mut &bytes[..];
+    let read_elements = |reader: &mut &[u8], len: usize| -> Result<Vec<F>, CryptoError> {
+        (0..len).map(|_| Ok(F::read(&mut *reader)?)).collect()
+    };
+
+    let num_round_keys = (P::FULL_ROUNDS + P::PARTIAL_ROUNDS) * P::WIDTH;
+    read_len(reader, num_round_keys)?;
+    let round_keys = read_elements(reader, num_round_keys)?;
+    read_len(reader, P::WIDTH)?;
+    let mds_matrix = (0..P::WIDTH)
+        .map(|_| {
+            read_len(reader, P::WIDTH)?;
+            read_elements(reader, P::WIDTH)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut header = [0u8; 4];
+    reader.read_exact(&mut header)?;
+    let [full_rounds, partial_rounds, width, sbox] = header;
+    check_header::<P>(
+        width as usize,
+        full_rounds as usize,
+        partial_rounds as usize,
+        sbox as i8 as i64,
+    )?;
+    if !reader.is_empty() {
+        return Err(SerializationError::InvalidData.into());
+    }
+
+    Ok(PoseidonParameters::new(round_keys, mds_matrix))
+}
+
+/// The error for a section or value missing from the text.
+const MISSING: CryptoError = CryptoError::Serialization(SerializationError::InvalidData);
+
+/// The tokens of `text` that are hexadecimal numbers with a `0x` prefix.
+fn hex_tokens(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|token| token.starts_with("0x"))
+        .collect()
+}
+
+/// The text after the line that starts with `marker`, if any.
+fn after<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    let start = text.find(marker)?;
+    let rest = &text[start..];
+    Some(rest.find('\n').map_or("", |end| &rest[end + 1..]))
+}
+
+/// Parses the output of the reference `generate_parameters_grain.sage`:
+///
+/// ```text
+/// Params: n=255, t=3, alpha=5, M=128, R_F=8, R_P=57
+/// ...
+/// Round constants for GF(p):
+/// ['0x0ee9...', '0x00f1...', ...]
+/// ...
+/// Prime number: 0x3064...
+/// MDS matrix:
+///
+///  [['0x109b...', ...], [...], [...]]
+/// ```
+///
+/// The round constants are the hex numbers of the first list after their
+/// header, and the rows of the MDS matrix the innermost lists after its
+/// header. The prime, if present, must be the modulus of `F`. Other lines
+/// are ignored, and missing sections fail with invalid data.
+pub fn from_sage_text<F: PrimeField, P: Rounds>(
+    text: &str,
+) -> Result<PoseidonParameters<F>, CryptoError> {
+    let params = text
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Params:"))
+        .ok_or(MISSING)?;
+    let value = |key: &str| -> Result<i64, CryptoError> {
+        params
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| k.trim() == key)
+            .and_then(|(_, v)| v.trim().parse().ok())
+            .ok_or(MISSING)
+    };
+    let count = |key: &str| -> Result<usize, CryptoError> {
+        let v = value(key)?;
+        if v < 0 {
+            return Err(MISSING);
+        }
+        Ok(v as usize)
+    };
+    check_header::<P>(count("t")?, count("R_F")?, count("R_P")?, value("alpha")?)?;
+
+    if let Some(line) = text
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Prime number:"))
+    {
+        let modulus = F::Params::MODULUS.to_bytes_be();
+        let modulus = modulus
+            .iter()
+            .skip_while(|b| **b == 0)
+            .map(|b| format!("{:02x}", b))
+            .collect::<crate::String>();
+        let prime = line.trim().trim_start_matches("0x").trim_start_matches('0');
+        if !prime.eq_ignore_ascii_case(modulus.trim_start_matches('0')) {
+            return Err(CryptoError::NotInField);
+        }
+    }
+
+    let constants = after(text, "Round constants for GF(p):")
+        .and_then(|rest| rest.lines().find(|line| line.trim_start().starts_with('[')))
+        .ok_or(MISSING)?;
+    let round_keys = fes_from_hex(&hex_tokens(constants))?;
+
+    let matrix = after(text, "MDS matrix:")
+        .and_then(|rest| rest.lines().find(|line| line.trim_start().starts_with('[')))
+        .ok_or(MISSING)?;
+    let mds_matrix = matrix
+        .split(']')
+        .filter_map(|row| row.rsplit('[').next())
+        .map(hex_tokens)
+        .filter(|row| !row.is_empty())
+        .map(|row| row.into_iter().map(fe_from_hex).collect())
+        .collect::<Result<Vec<Vec<F>>, _>>()?;
+
+    let parameters = PoseidonParameters::new(round_keys, mds_matrix);
+    circom::check::<F, P>(&parameters)?;
+    Ok(parameters)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::poseidon::circom::{CircomRounds3, CircomRounds5};
+    use crate::crh::poseidon::test_data::{get_mds_3, get_results_3, get_rounds_3};
+    use crate::crh::poseidon::CRH;
+    use ark_ed_on_bls12_381::Fq as OtherFq;
+    use ark_ed_on_bn254::Fq;
+
+    // Both fixtures were generated by the crate before the importer existed,
+    // from its x5_254_3 constants: the elements of `BYTES` are their
+    // `ToBytes` encoding, and the lists of `SAGE_TEXT` their hex strings.
+    // That crate had no serializer for parameters, so the framing around
+    // them follows the layouts documented above; neither fixture was
+    // recorded from arkworks-gadgets or the sage script themselves.
+    const BYTES: &[u8] = include_bytes!("../test_data/x5_254_3.bin");
+    const SAGE_TEXT: &str = include_str!("../test_data/poseidonperm_x5_254_3.txt");
+
+    /// The historical digest: the permutation of `[0, 1, 2]`.
+    fn check_digest(parameters: &PoseidonParameters<Fq>) {
+        assert_eq!(parameters.round_keys, get_rounds_3::<Fq>());
+        assert_eq!(parameters.mds_matrix, get_mds_3::<Fq>());
+        let state = (0..3u64).map(Fq::from).collect();
+        assert_eq!(
+            CRH::<Fq, CircomRounds3>::permute(parameters, state),
+            get_results_3::<Fq>()
+        );
+    }
+
+    #[test]
+    fn arkworks_gadgets_test() {
+        check_digest(&from_arkworks_gadgets_bytes::<Fq, CircomRounds3>(BYTES).unwrap());
+
+        // Truncated, with trailing bytes, or for another width.
+        for len in [0, 3, 100, BYTES.len() - 1].iter() {
+            assert!(matches!(
+                from_arkworks_gadgets_bytes::<Fq, CircomRounds3>(&BYTES[..*len]),
+                Err(CryptoError::Serialization(_))
+            ));
+        }
+        let mut bytes = BYTES.to_vec();
+        bytes.push(0);
+        assert!(matches!(
+            from_arkworks_gadgets_bytes::<Fq, CircomRounds3>(&bytes),
+            Err(CryptoError::Serialization(SerializationError::InvalidData))
+        ));
+        assert!(matches!(
+            from_arkworks_gadgets_bytes::<Fq, CircomRounds5>(BYTES),
+            Err(CryptoError::InvalidLength {
+                expected: 340,
+                got: 195
+            })
+        ));
+
+        // Headers that disagree with the data.
+        let header = BYTES.len() - 4;
+        bytes.pop();
+        bytes[header + 2] = 5;
+        assert!(matches!(
+            from_arkworks_gadgets_bytes::<Fq, CircomRounds3>(&bytes),
+            Err(CryptoError::InvalidLength {
+                expected: 3,
+                got: 5
+            })
+        ));
+        bytes[header + 2] = 3;
+        bytes[header + 3] = 0xff;
+        assert!(matches!(
+            from_arkworks_gadgets_bytes::<Fq, CircomRounds3>(&bytes),
+            Err(CryptoError::UnsupportedOperation)
+        ));
+    }
+
+    #[test]
+    fn sage_text_test() {
+        check_digest(&from_sage_text::<Fq, CircomRounds3>(SAGE_TEXT).unwrap());
+
+        assert!(matches!(
+            from_sage_text::<Fq, CircomRounds5>(SAGE_TEXT),
+            Err(CryptoError::InvalidLength {
+                expected: 5,
+                got: 3
+            })
+        ));
+        assert!(matches!(
+            from_sage_text::<OtherFq, CircomRounds3>(SAGE_TEXT),
+            Err(CryptoError::NotInField)
+        ));
+
+        // A header for another width, and truncated constants or matrix.
+        let text = SAGE_TEXT.replace("t=3", "t=5");
+        assert!(matches!(
+            from_sage_text::<Fq, CircomRounds3>(&text),
+            Err(CryptoError::InvalidLength {
+                expected: 3,
+                got: 5
+            })
+        ));
+        let text = SAGE_TEXT.replacen(
+            "['0x0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e', ",
+            "[",
+            1,
+        );
+        assert!(matches!(
+            from_sage_text::<Fq, CircomRounds3>(&text),
+            Err(CryptoError::InvalidParameters)
+        ));
+        let end = SAGE_TEXT.trim_end().len() - "]]".len();
+        let text = format!("{}]]", &SAGE_TEXT[..end - 70]);
+        assert!(matches!(
+            from_sage_text::<Fq, CircomRounds3>(&text),
+            Err(CryptoError::InvalidParameters)
+        ));
+        assert!(matches!(
+            from_sage_text::<Fq, CircomRounds3>(&SAGE_TEXT[..SAGE_TEXT.find("MDS").unwrap()]),
+            Err(CryptoError::Serialization(SerializationError::InvalidData))
+        ));
+    }
+}
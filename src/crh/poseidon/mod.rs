@@ -1,10 +1,13 @@
 use crate::crh::poseidon::sbox::PoseidonSbox;
 use crate::crh::FixedLengthCRH;
-use crate::{Error, Vec};
+use crate::{utils, CryptoError, Error, Vec};
 use ark_ff::fields::PrimeField;
+use ark_ff::{BigInteger, ToConstraintField};
 use ark_std::marker::PhantomData;
 use ark_std::rand::Rng;
 
+pub mod circom;
+pub mod import;
 pub mod sbox;
 
 #[cfg(test)]
@@ -17,6 +20,89 @@ pub mod constraints;
 pub const PADDING_CONST: u64 = 101;
 pub const ZERO_CONST: u64 = 0;
 
+/// The number of input bytes read into each field element.
+pub const BYTES_PER_CHUNK: usize = 32;
+
+/// Splits `bytes` into chunks of [`BYTES_PER_CHUNK`] bytes, the last one
+/// possibly shorter, and reads each chunk as a little-endian integer reduced
+/// modulo the characteristic of `F`. Chunks that are not smaller than the
+/// modulus are thus reduced, not rejected: chunks differing by a multiple of
+/// the modulus map to the same element, and callers that need an injective
+/// encoding must keep their chunks below it.
+///
+/// An empty input gives no elements, and a zero-length chunk never occurs.
+/// Callers that need a fixed number of elements, such as `CRH::evaluate`,
+/// must reject inputs of the wrong length themselves.
+///
+/// The in-circuit twin, `to_field_var_elements`, reduces identically.
+///
+/// To hash arbitrary bytes, prefer [`to_field_elements_packed`] (through
+/// [`CRH::hash_bytes`]), which is injective.
+pub fn to_field_elements<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    bytes
+        .chunks(BYTES_PER_CHUNK)
+        .map(F::from_le_bytes_mod_order)
+        .collect()
+}
+
+/// Distinguishes [`CRH::hash_bytes`] from other uses of the same Poseidon
+/// parameters.
+pub const BYTES_DOMAIN_TAG: u8 = 6;
+
+/// The first element of the state of a Poseidon hash of variable-length
+/// input, shared by all its domains: `tag` in the lowest byte and `len`, the
+/// length of the input, as a little-endian `u64` in the next eight.
+pub fn domain_element<F: PrimeField>(tag: u8, len: usize) -> F {
+    let mut domain = vec![tag];
+    domain.extend_from_slice(&(len as u64).to_le_bytes());
+    F::from_le_bytes_mod_order(&domain)
+}
+
+/// The number of bits packed into each element by
+/// [`to_field_elements_packed`]: the capacity of `F`, `MODULUS_BITS - 1`, so
+/// that every element is below the modulus.
+pub fn packed_bits_per_element<F: PrimeField>() -> usize {
+    utils::fp_chunk_bits::<F>()
+}
+
+/// Packs the little-endian bits of `bytes` into elements of
+/// [`packed_bits_per_element`] bits each, the last one zero-padded. No
+/// element overflows the field, so the packing is injective for inputs of a
+/// given length; [`from_field_elements_packed`] inverts it.
+///
+/// The in-circuit twin is `to_field_var_elements_packed`.
+pub fn to_field_elements_packed<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    utils::bits_to_fp_chunks(&utils::bytes_to_bits_le(bytes))
+}
+
+/// Recovers the `len` bytes packed by [`to_field_elements_packed`]. Fails
+/// if `elements` is not the packing of exactly `len` bytes.
+pub fn from_field_elements_packed<F: PrimeField>(
+    elements: &[F],
+    len: usize,
+) -> Result<Vec<u8>, CryptoError> {
+    let bits_per_element = packed_bits_per_element::<F>();
+    let expected = (len * 8).div_ceil(bits_per_element);
+    if elements.len() != expected {
+        return Err(CryptoError::InvalidLength {
+            expected,
+            got: elements.len(),
+        });
+    }
+    let mut bits = Vec::with_capacity(elements.len() * bits_per_element);
+    for element in elements {
+        let element_bits = element.into_repr().to_bits_le();
+        if element_bits.iter().skip(bits_per_element).any(|bit| *bit) {
+            return Err(CryptoError::InvalidPacking);
+        }
+        bits.extend_from_slice(&element_bits[..bits_per_element]);
+    }
+    if bits.iter().skip(len * 8).any(|bit| *bit) {
+        return Err(CryptoError::InvalidPacking);
+    }
+    Ok(utils::bits_to_bytes_le(&bits[..len * 8]))
+}
+
 pub trait Rounds: Default + Clone {
     /// The size of the permutation, in field elements.
     const WIDTH: usize;
@@ -62,13 +148,22 @@ impl<F: PrimeField> PoseidonParameters<F> {
     }
 }
 
+/// The parameters are constants of the circuit (see `PoseidonParametersVar`),
+/// so they contribute no public inputs.
+impl<F: PrimeField> ToConstraintField<F> for PoseidonParameters<F> {
+    #[inline]
+    fn to_field_elements(&self) -> Option<Vec<F>> {
+        Some(Vec::new())
+    }
+}
+
 pub struct CRH<F: PrimeField, P: Rounds> {
     field: PhantomData<F>,
     rounds: PhantomData<P>,
 }
 
 impl<F: PrimeField, P: Rounds> CRH<F, P> {
-    fn permute(params: &PoseidonParameters<F>, input: Vec<F>) -> Vec<F> {
+    pub(crate) fn permute(params: &PoseidonParameters<F>, input: Vec<F>) -> Vec<F> {
         let width = P::WIDTH;
 
         let partial_rounds = P::PARTIAL_ROUNDS;
@@ -132,7 +227,9 @@ impl<F: PrimeField, P: Rounds> CRH<F, P> {
 }
 
 impl<F: PrimeField, P: Rounds> FixedLengthCRH for CRH<F, P> {
-    const INPUT_SIZE_BITS: usize = 32 * 8 * P::WIDTH;
+    const INPUT_SIZE_BITS: usize = BYTES_PER_CHUNK * 8 * P::WIDTH;
+    /// The first chunk is the first element of the state.
+    const CAPACITY_BYTES: usize = BYTES_PER_CHUNK;
     type Output = F;
     type Parameters = PoseidonParameters<F>;
 
@@ -145,26 +242,71 @@ impl<F: PrimeField, P: Rounds> FixedLengthCRH for CRH<F, P> {
         let eval_time = start_timer!(|| "PoseidonCRH::Eval");
 
         if (input.len() * 8) != Self::INPUT_SIZE_BITS {
-            panic!(
-                "incorrect input length {:?} for width {:?}",
-                input.len() / 32,
-                P::WIDTH,
-            );
+            end_timer!(eval_time);
+            return Err(CryptoError::IncorrectInputLength(input.len()).into());
         }
 
-        // Not giving expected results
-        // let elts: Vec<F> = input.to_field_elements().unwrap_or(Vec::new());
+        let result = Self::evaluate_elements(parameters, to_field_elements(input));
 
-        let f_inputs: Vec<F> = input
-            .chunks(32)
-            .map(|x| F::from_le_bytes_mod_order(x))
-            .collect();
+        end_timer!(eval_time);
 
-        let result = Self::permute(&parameters, f_inputs);
+        Ok(result)
+    }
+}
 
-        end_timer!(eval_time);
+impl<F: PrimeField, P: Rounds> CRH<F, P> {
+    /// Hashes exactly `P::WIDTH` field elements, skipping the byte encoding
+    /// of `evaluate`.
+    pub(crate) fn evaluate_elements(parameters: &PoseidonParameters<F>, input: Vec<F>) -> F {
+        assert_eq!(input.len(), P::WIDTH, "incorrect number of elements");
+        let result = Self::permute(parameters, input);
+        result[1]
+    }
 
-        Ok(result.get(1).cloned().unwrap())
+    /// Hashes a message of any length: its [packed](to_field_elements_packed)
+    /// elements are absorbed by the sponge, with a domain element that packs
+    /// [`BYTES_DOMAIN_TAG`] in its lowest byte and the length of the message
+    /// in bytes, as a little-endian `u64`, in the next eight.
+    ///
+    /// This is the recommended way to hash arbitrary bytes: unlike
+    /// `evaluate`, it neither reduces overflowing chunks nor fixes the input
+    /// length. Fails with [`CryptoError::InvalidParameters`] if `P::WIDTH`
+    /// is below 2, which leaves no room to absorb the message.
+    pub fn hash_bytes(parameters: &PoseidonParameters<F>, input: &[u8]) -> Result<F, Error> {
+        let elements = to_field_elements_packed(input);
+        Self::sponge(
+            parameters,
+            domain_element(BYTES_DOMAIN_TAG, input.len()),
+            &elements,
+        )
+    }
+
+    /// Hashes any number of field elements with a capacity of one element:
+    /// the state starts as `[domain, 0, ..., 0]`, `values` are added to the
+    /// last `P::WIDTH - 1` elements in chunks, permuting after each chunk,
+    /// and the output is the second element of the state. No values are
+    /// absorbed as a single empty chunk, so that the state is still permuted.
+    /// Fails if `P::WIDTH` is below 2.
+    pub(crate) fn sponge(
+        parameters: &PoseidonParameters<F>,
+        domain: F,
+        values: &[F],
+    ) -> Result<F, Error> {
+        if P::WIDTH < 2 {
+            return Err(CryptoError::InvalidParameters.into());
+        }
+        let mut state = vec![F::zero(); P::WIDTH];
+        state[0] = domain;
+        if values.is_empty() {
+            return Ok(Self::permute(parameters, state)[1]);
+        }
+        for chunk in values.chunks(P::WIDTH - 1) {
+            for (s, value) in state[1..].iter_mut().zip(chunk) {
+                *s += value;
+            }
+            state = Self::permute(parameters, state);
+        }
+        Ok(state[1])
     }
 }
 
@@ -173,7 +315,8 @@ mod test {
     use super::*;
     use ark_ed_on_bn254::Fq;
     use ark_ff::to_bytes;
-    use ark_ff::Zero;
+    use ark_ff::{Field, FpParameters, One, Zero};
+    use ark_std::rand::Rng;
 
     use test_data::{
         get_mds_3, get_mds_5, get_results_3, get_results_5, get_rounds_3, get_rounds_5,
@@ -201,6 +344,135 @@ mod test {
     type PoseidonCRH3 = CRH<Fq, PoseidonRounds3>;
     type PoseidonCRH5 = CRH<Fq, PoseidonRounds5>;
 
+    #[test]
+    fn to_field_elements_test() {
+        let max = to_bytes![-Fq::one()].unwrap();
+        assert_eq!(to_field_elements::<Fq>(&max), vec![-Fq::one()]);
+
+        // The last chunk may be short; chunks are little-endian.
+        let mut bytes = max.clone();
+        bytes.extend_from_slice(&[1, 2]);
+        assert_eq!(
+            to_field_elements::<Fq>(&bytes),
+            vec![-Fq::one(), Fq::from(0x0201u64)]
+        );
+
+        // Chunks that overflow the field are reduced.
+        let all_ones = Fq::from(2u64).pow([8 * BYTES_PER_CHUNK as u64]) - Fq::one();
+        assert_eq!(
+            to_field_elements::<Fq>(&[0xFF; BYTES_PER_CHUNK]),
+            vec![all_ones]
+        );
+        let modulus = to_bytes![<Fq as PrimeField>::Params::MODULUS].unwrap();
+        assert_eq!(to_field_elements::<Fq>(&modulus), vec![Fq::zero()]);
+    }
+
+    #[test]
+    fn packed_round_trip_test() {
+        let rng = &mut ark_std::test_rng();
+        for len in [0, 1, 31, 32, 33, 64, 100].iter() {
+            let bytes = (0..*len).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let elements = to_field_elements_packed::<Fq>(&bytes);
+            assert_eq!(
+                from_field_elements_packed(&elements, bytes.len()).unwrap(),
+                bytes
+            );
+            if !elements.is_empty() {
+                assert!(matches!(
+                    from_field_elements_packed(&elements, bytes.len() + 32),
+                    Err(CryptoError::InvalidLength { got, .. }) if got == elements.len()
+                ));
+            }
+        }
+
+        // An element above the capacity, or bits past the end of the
+        // message, are not a packing.
+        assert!(from_field_elements_packed(&[-Fq::one()], 31).is_err());
+        assert!(from_field_elements_packed(&[Fq::from(256u64)], 1).is_err());
+
+        // Over BN254, 1 KiB packs into 33 elements, where chunks of the 31
+        // bytes that never overflow would take 34.
+        let kib = [0xFFu8; 1024];
+        assert_eq!(packed_bits_per_element::<Fq>(), 253);
+        assert_eq!(to_field_elements_packed::<Fq>(&kib).len(), 33);
+        assert_eq!(kib.chunks(31).count(), 34);
+    }
+
+    #[test]
+    fn hash_bytes_test() {
+        let params = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let hash = PoseidonCRH3::hash_bytes(&params, b"message").unwrap();
+        assert_eq!(PoseidonCRH3::hash_bytes(&params, b"message").unwrap(), hash);
+        assert_ne!(PoseidonCRH3::hash_bytes(&params, b"messagf").unwrap(), hash);
+        // Trailing zeros are not padding.
+        assert_ne!(
+            PoseidonCRH3::hash_bytes(&params, b"message\0").unwrap(),
+            hash
+        );
+
+        // Pinned, so that a change to the byte encoding shows up here.
+        let pinned = [
+            (
+                &b"message"[..],
+                "0x0dc936e72272826311157ba37641ab3e967eea6224b7f0cb31521a671b3d9870",
+            ),
+            (
+                &[0xA5; 100][..],
+                "0x1a4c6fbd1caacce59fcc59b8e981492f8effc584dbce85ac72504ca0968c0a52",
+            ),
+        ];
+        for (bytes, expected) in pinned.iter() {
+            assert_eq!(
+                PoseidonCRH3::hash_bytes(&params, bytes).unwrap(),
+                crate::utils::hex::fe_from_hex(expected).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn empty_input_test() {
+        assert!(to_field_elements::<Fq>(&[]).is_empty());
+        assert!(to_field_elements_packed::<Fq>(&[]).is_empty());
+        assert!(from_field_elements_packed::<Fq>(&[], 0).unwrap().is_empty());
+
+        // The fixed-length hash rejects an empty input like any other of the
+        // wrong length.
+        let params = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        for len in [0, 1, 95, 97, 127].iter() {
+            let error = PoseidonCRH3::evaluate(&params, &vec![0u8; *len]).unwrap_err();
+            assert!(matches!(
+                error,
+                Error::Crypto(CryptoError::IncorrectInputLength(l)) if l == *len
+            ));
+        }
+
+        // The empty message is still permuted, rather than hashing to zero.
+        let empty = PoseidonCRH3::hash_bytes(&params, &[]).unwrap();
+        assert_ne!(empty, Fq::zero());
+        assert_ne!(empty, PoseidonCRH3::hash_bytes(&params, &[0]).unwrap());
+    }
+
+    #[test]
+    fn hash_bytes_width_1_test() {
+        #[derive(Default, Clone)]
+        struct PoseidonRounds1;
+
+        impl Rounds for PoseidonRounds1 {
+            const WIDTH: usize = 1;
+            const PARTIAL_ROUNDS: usize = 57;
+            const FULL_ROUNDS: usize = 8;
+            const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+        }
+
+        // A single element leaves no rate to absorb with.
+        let params = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let error = CRH::<Fq, PoseidonRounds1>::hash_bytes(&params, b"message").unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Crypto(CryptoError::InvalidParameters)
+        ));
+    }
+
     #[test]
     fn test_width_3_bn_254() {
         let rounds = get_rounds_3::<Fq>();
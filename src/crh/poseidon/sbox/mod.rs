@@ -1,4 +1,6 @@
-use ark_ff::PrimeField;
+use crate::crh::rescue::{div_rem_small, gcd};
+use crate::CryptoError;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
@@ -11,6 +13,25 @@ pub enum PoseidonSbox {
 }
 
 impl PoseidonSbox {
+    /// Checks that the S-box is a permutation of `F` that
+    /// [`apply_sbox`](Self::apply_sbox) implements: `x^α` for `α` of 3, 5, 7
+    /// or 17 and coprime to `p - 1`, or the inverse.
+    pub fn check<F: PrimeField>(&self) -> Result<(), CryptoError> {
+        match *self {
+            PoseidonSbox::Exponentiation(alpha) if [3, 5, 7, 17].contains(&alpha) => {
+                let mut order = F::Params::MODULUS;
+                order.sub_noborrow(&F::BigInt::from(1));
+                let alpha = alpha as u64;
+                if gcd(alpha, div_rem_small(order.as_ref(), alpha).1) != 1 {
+                    return Err(CryptoError::SboxError);
+                }
+                Ok(())
+            }
+            PoseidonSbox::Exponentiation(_) => Err(CryptoError::SboxError),
+            PoseidonSbox::Inverse => Ok(()),
+        }
+    }
+
     pub fn apply_sbox<F: PrimeField>(&self, elem: F) -> F {
         match self {
             PoseidonSbox::Exponentiation(val) => {
@@ -0,0 +1,240 @@
+use super::{RescueParameters, Rounds, BYTES_PER_CHUNK, CRH};
+use crate::crh::poseidon::constraints::to_field_var_elements;
+use crate::FixedLengthCRHGadget;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::{alloc::AllocVar, fields::FieldVar, prelude::*};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+use core::borrow::Borrow;
+
+#[derive(Default, Clone)]
+pub struct RescueParametersVar<F: PrimeField> {
+    /// The MDS matrix to apply after each S-box layer.
+    pub mds_matrix: Vec<Vec<FpVar<F>>>,
+    /// The round constants, `2 * m` per round.
+    pub round_keys: Vec<FpVar<F>>,
+    /// The exponent of the forward S-box.
+    pub alpha: u64,
+    /// The exponent of the inverse S-box, as little-endian limbs.
+    pub alpha_inv: Vec<u64>,
+}
+
+pub struct CRHGadget<F: PrimeField, P: Rounds> {
+    field: PhantomData<F>,
+    params: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: Rounds> CRHGadget<F, P> {
+    pub fn permute(
+        parameters: &RescueParametersVar<F>,
+        input: Vec<FpVar<F>>,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let width = P::WIDTH;
+        if parameters.round_keys.len() < 2 * width * P::ROUNDS {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut state = input;
+
+        for round_keys in parameters.round_keys.chunks(2 * width).take(P::ROUNDS) {
+            // forward Sbox layer
+            for x in state.iter_mut() {
+                *x = x.pow_by_constant([parameters.alpha])?;
+            }
+            state = Self::apply_linear_layer(&state, &parameters.mds_matrix);
+            for (x, key) in state.iter_mut().zip(&round_keys[..width]) {
+                *x += key;
+            }
+
+            // inverse Sbox layer
+            for x in state.iter_mut() {
+                *x = Self::inverse_sbox(parameters, x)?;
+            }
+            state = Self::apply_linear_layer(&state, &parameters.mds_matrix);
+            for (x, key) in state.iter_mut().zip(&round_keys[width..]) {
+                *x += key;
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// `x^(1/α)`, computed out of the circuit as a witness `y` and
+    /// constrained by `y^α = x`, which has a single solution since `x^α` is
+    /// a permutation. This costs the constraints of the forward S-box and
+    /// one more, where computing the inverse exponent in the circuit would
+    /// take hundreds.
+    fn inverse_sbox(
+        parameters: &RescueParametersVar<F>,
+        x: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        if let FpVar::Constant(x) = x {
+            return Ok(FpVar::Constant(x.pow(&parameters.alpha_inv)));
+        }
+        let y = FpVar::new_witness(x.cs(), || Ok(x.value()?.pow(&parameters.alpha_inv)))?;
+        y.pow_by_constant([parameters.alpha])?.enforce_equal(x)?;
+        Ok(y)
+    }
+
+    fn apply_linear_layer(state: &[FpVar<F>], mds_matrix: &[Vec<FpVar<F>>]) -> Vec<FpVar<F>> {
+        let mut new_state: Vec<FpVar<F>> = Vec::new();
+        for row in mds_matrix {
+            let mut sc = FpVar::<F>::zero();
+            for (mij, s) in row.iter().zip(state) {
+                sc += mij * s;
+            }
+            new_state.push(sc);
+        }
+        new_state
+    }
+
+    /// The counterpart of the native `hash`. The padding depends only on the
+    /// number of elements, which the circuit fixes.
+    pub fn hash(
+        parameters: &RescueParametersVar<F>,
+        input: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let rate = CRH::<F, P>::RATE;
+        let mut padded = input.to_vec();
+        padded.push(FpVar::one());
+        padded.resize(padded.len().div_ceil(rate) * rate, FpVar::zero());
+
+        let mut state = vec![FpVar::zero(); P::WIDTH];
+        for block in padded.chunks(rate) {
+            for (s, value) in state.iter_mut().zip(block) {
+                *s += value;
+            }
+            state = Self::permute(parameters, state)?;
+        }
+        state.truncate(rate);
+        Ok(state)
+    }
+}
+
+impl<F: PrimeField, P: Rounds> FixedLengthCRHGadget<CRH<F, P>, F> for CRHGadget<F, P> {
+    type OutputVar = FpVar<F>;
+    type ParametersVar = RescueParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        if input.len() != BYTES_PER_CHUNK * CRH::<F, P>::RATE {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let elements = to_field_var_elements(input)?;
+
+        Ok(Self::hash(parameters, &elements)?.remove(0))
+    }
+}
+
+impl<F: PrimeField> AllocVar<RescueParameters<F>, F> for RescueParametersVar<F> {
+    #[tracing::instrument(target = "r1cs", skip(_cs, f))]
+    fn new_variable<T: Borrow<RescueParameters<F>>>(
+        _cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let params = f()?.borrow().clone();
+        Ok(Self {
+            mds_matrix: params
+                .mds_matrix
+                .into_iter()
+                .map(|row| row.into_iter().map(FpVar::Constant).collect())
+                .collect(),
+            round_keys: params.round_keys.into_iter().map(FpVar::Constant).collect(),
+            alpha: params.alpha,
+            alpha_inv: params.alpha_inv,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::rescue::test_data::{get_mds, get_rounds, ALPHA, ALPHA_INV};
+    use crate::crh::FixedLengthCRH;
+    use crate::utils::hex::fe_from_hex;
+    use ark_ed_on_bn254::Fq;
+    use ark_ff::{to_bytes, Field};
+    use ark_relations::r1cs::{ConstraintSystem, Variable};
+    use ark_std::UniformRand;
+
+    #[derive(Default, Clone)]
+    struct RescueRounds3;
+
+    impl Rounds for RescueRounds3 {
+        const WIDTH: usize = 3;
+        const CAPACITY: usize = 1;
+        const ROUNDS: usize = 14;
+    }
+
+    type RescueCRH3 = CRH<Fq, RescueRounds3>;
+    type RescueCRH3Gadget = CRHGadget<Fq, RescueRounds3>;
+
+    #[test]
+    fn native_equality_test() {
+        let rng = &mut ark_std::test_rng();
+        let alpha_inv = fe_from_hex::<Fq>(ALPHA_INV).unwrap();
+        let params = RescueParameters::new(
+            get_mds(),
+            get_rounds(),
+            ALPHA,
+            alpha_inv.into_repr().as_ref().to_vec(),
+        );
+        let input = to_bytes![Fq::rand(rng), Fq::rand(rng)].unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = RescueParametersVar::new_constant(cs.clone(), &params).unwrap();
+        let input_var = UInt8::new_witness_vec(cs.clone(), &input).unwrap();
+        let output_var = RescueCRH3Gadget::evaluate(&params_var, &input_var).unwrap();
+        assert_eq!(
+            output_var.value().unwrap(),
+            RescueCRH3::evaluate(&params, &input).unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+
+        // Each round costs three constraints per forward S-box and four per
+        // inverse one.
+        let state = (0..3)
+            .map(|_| FpVar::new_witness(cs.clone(), || Ok(Fq::rand(rng))).unwrap())
+            .collect::<Vec<_>>();
+        let before = cs.num_constraints();
+        let permuted = RescueCRH3Gadget::permute(&params_var, state.clone()).unwrap();
+        assert_eq!(cs.num_constraints() - before, 14 * 3 * (3 + 4));
+        assert_eq!(
+            permuted.value().unwrap(),
+            RescueCRH3::permute(&params, state.value().unwrap())
+        );
+        assert!(cs.is_satisfied().unwrap());
+
+        // Constant inputs give a constant output.
+        let constant = RescueCRH3Gadget::evaluate(&params_var, &UInt8::constant_vec(&input));
+        assert!(constant.unwrap().is_constant());
+    }
+
+    #[test]
+    fn inverse_sbox_test() {
+        let rng = &mut ark_std::test_rng();
+        let params = RescueCRH3::setup(rng).unwrap();
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = RescueParametersVar::new_constant(cs.clone(), &params).unwrap();
+
+        let x = FpVar::new_witness(cs.clone(), || Ok(Fq::rand(rng))).unwrap();
+        let y = RescueCRH3Gadget::inverse_sbox(&params_var, &x).unwrap();
+        assert_eq!(y.value().unwrap().pow([params.alpha]), x.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+
+        // Any other witness for the root violates the constraints.
+        let index = match &y {
+            FpVar::Var(y) => match y.variable {
+                Variable::Witness(index) => index,
+                _ => unreachable!(),
+            },
+            FpVar::Constant(_) => unreachable!(),
+        };
+        cs.borrow_mut().unwrap().witness_assignment[index] += Fq::from(1u64);
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,356 @@
+//! The Rescue-Prime hash: every round applies an S-box layer of `x^α`, then
+//! one of its inverse `x^(1/α)`, each followed by the MDS matrix and round
+//! constants.
+
+use crate::crh::poseidon::{to_field_elements, BYTES_PER_CHUNK};
+use crate::crh::FixedLengthCRH;
+use crate::{CryptoError, Error, Vec};
+use ark_ff::fields::PrimeField;
+use ark_ff::{BigInteger, FpParameters, ToConstraintField};
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+
+#[cfg(test)]
+pub mod test_data;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+pub trait Rounds: Default + Clone {
+    /// The size of the permutation, `m`, in field elements.
+    const WIDTH: usize;
+    /// The number of elements of the state that are never absorbed into or
+    /// squeezed.
+    const CAPACITY: usize;
+    /// The number of rounds, `N`, each of two S-box layers.
+    const ROUNDS: usize;
+}
+
+/// The Rescue-Prime permutation.
+#[derive(Default, Clone)]
+pub struct RescueParameters<F> {
+    /// The MDS matrix to apply after each S-box layer.
+    pub mds_matrix: Vec<Vec<F>>,
+    /// The round constants, `2 * m` per round: the first `m` follow the
+    /// forward S-box layer and the last `m` the inverse one.
+    pub round_keys: Vec<F>,
+    /// The exponent of the forward S-box, coprime to the order of the
+    /// multiplicative group of `F`.
+    pub alpha: u64,
+    /// The exponent of the inverse S-box, the inverse of `alpha` modulo the
+    /// order of the multiplicative group of `F`, as little-endian limbs.
+    pub alpha_inv: Vec<u64>,
+}
+
+impl<F: PrimeField> RescueParameters<F> {
+    pub fn new(
+        mds_matrix: Vec<Vec<F>>,
+        round_keys: Vec<F>,
+        alpha: u64,
+        alpha_inv: Vec<u64>,
+    ) -> Self {
+        Self {
+            mds_matrix,
+            round_keys,
+            alpha,
+            alpha_inv,
+        }
+    }
+
+    /// Random round constants for `rounds` rounds of a permutation of
+    /// `width` elements, with a Cauchy MDS matrix and the exponents of
+    /// [`alphas`](Self::alphas).
+    pub fn generate<R: Rng>(rng: &mut R, width: usize, rounds: usize) -> Self {
+        let (alpha, alpha_inv) = Self::alphas();
+        Self {
+            mds_matrix: Self::create_mds(width),
+            round_keys: (0..2 * width * rounds).map(|_| F::rand(rng)).collect(),
+            alpha,
+            alpha_inv,
+        }
+    }
+
+    /// The Cauchy matrix `1 / (i + j + width)`, which is MDS for any field
+    /// larger than `3 * width`.
+    pub fn create_mds(width: usize) -> Vec<Vec<F>> {
+        (0..width)
+            .map(|i| {
+                (0..width)
+                    .map(|j| F::from((i + j + width) as u64).inverse().unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The smallest `α >= 3` coprime to `p - 1`, and its inverse modulo
+    /// `p - 1`, as the specification chooses them.
+    pub fn alphas() -> (u64, Vec<u64>) {
+        let mut order = F::Params::MODULUS;
+        order.sub_noborrow(&F::BigInt::from(1));
+        let order = order.as_ref();
+
+        let mut alpha = 3;
+        while gcd(alpha, div_rem_small(order, alpha).1) != 1 {
+            alpha += 1;
+        }
+        // `α * α⁻¹ = k * (p - 1) + 1` for some `0 < k < α`.
+        let rem = div_rem_small(order, alpha).1 as u128;
+        let k = (1..alpha)
+            .find(|k| (*k as u128 * rem) % alpha as u128 == alpha as u128 - 1)
+            .unwrap();
+        let mut multiple = Vec::with_capacity(order.len() + 1);
+        let mut carry = 1u128;
+        for limb in order {
+            let acc = *limb as u128 * k as u128 + carry;
+            multiple.push(acc as u64);
+            carry = acc >> 64;
+        }
+        multiple.push(carry as u64);
+        let (mut alpha_inv, _) = div_rem_small(&multiple, alpha);
+        alpha_inv.truncate(order.len());
+        (alpha, alpha_inv)
+    }
+
+    pub(crate) fn check(&self, width: usize, rounds: usize) -> Result<(), CryptoError> {
+        if self.round_keys.len() != 2 * width * rounds {
+            return Err(CryptoError::InvalidLength {
+                expected: 2 * width * rounds,
+                got: self.round_keys.len(),
+            });
+        }
+        if self.mds_matrix.len() != width || self.mds_matrix.iter().any(|r| r.len() != width) {
+            return Err(CryptoError::InvalidLength {
+                expected: width,
+                got: self.mds_matrix.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Divides the little-endian `limbs` by `divisor`.
+pub(crate) fn div_rem_small(limbs: &[u64], divisor: u64) -> (Vec<u64>, u64) {
+    let mut quotient = vec![0u64; limbs.len()];
+    let mut rem = 0u128;
+    for (q, limb) in quotient.iter_mut().zip(limbs).rev() {
+        let acc = (rem << 64) | *limb as u128;
+        *q = (acc / divisor as u128) as u64;
+        rem = acc % divisor as u128;
+    }
+    (quotient, rem as u64)
+}
+
+/// The parameters are constants of the circuit (see `RescueParametersVar`),
+/// so they contribute no public inputs.
+impl<F: PrimeField> ToConstraintField<F> for RescueParameters<F> {
+    #[inline]
+    fn to_field_elements(&self) -> Option<Vec<F>> {
+        Some(Vec::new())
+    }
+}
+
+pub struct CRH<F: PrimeField, P: Rounds> {
+    field: PhantomData<F>,
+    rounds: PhantomData<P>,
+}
+
+impl<F: PrimeField, P: Rounds> CRH<F, P> {
+    /// The number of elements absorbed by each permutation, `m - capacity`.
+    pub const RATE: usize = P::WIDTH - P::CAPACITY;
+
+    pub fn permute(params: &RescueParameters<F>, input: Vec<F>) -> Vec<F> {
+        let width = P::WIDTH;
+        let mut state = input;
+
+        for round_keys in params.round_keys.chunks(2 * width).take(P::ROUNDS) {
+            // forward Sbox layer
+            for x in state.iter_mut() {
+                *x = x.pow([params.alpha]);
+            }
+            state = Self::apply_linear_layer(&state, &params.mds_matrix);
+            for (x, key) in state.iter_mut().zip(&round_keys[..width]) {
+                *x += key;
+            }
+
+            // inverse Sbox layer
+            for x in state.iter_mut() {
+                *x = x.pow(&params.alpha_inv);
+            }
+            state = Self::apply_linear_layer(&state, &params.mds_matrix);
+            for (x, key) in state.iter_mut().zip(&round_keys[width..]) {
+                *x += key;
+            }
+        }
+
+        state
+    }
+
+    fn apply_linear_layer(state: &[F], mds: &[Vec<F>]) -> Vec<F> {
+        mds.iter()
+            .map(|row| row.iter().zip(state).map(|(m, s)| *m * s).sum())
+            .collect()
+    }
+
+    /// The hash of the specification: `input` is padded with a one and then
+    /// zeros to a multiple of [`RATE`](Self::RATE) elements, absorbed into
+    /// the first `RATE` elements of a zero state with a permutation after
+    /// each block, and those elements are the output.
+    pub fn hash(params: &RescueParameters<F>, input: &[F]) -> Vec<F> {
+        let rate = Self::RATE;
+        let mut padded = input.to_vec();
+        padded.push(F::one());
+        padded.resize(padded.len().div_ceil(rate) * rate, F::zero());
+
+        let mut state = vec![F::zero(); P::WIDTH];
+        for block in padded.chunks(rate) {
+            for (s, value) in state.iter_mut().zip(block) {
+                *s += value;
+            }
+            state = Self::permute(params, state);
+        }
+        state.truncate(rate);
+        state
+    }
+}
+
+impl<F: PrimeField, P: Rounds> FixedLengthCRH for CRH<F, P> {
+    const INPUT_SIZE_BITS: usize = BYTES_PER_CHUNK * 8 * (P::WIDTH - P::CAPACITY);
+    type Output = F;
+    type Parameters = RescueParameters<F>;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(Self::Parameters::generate(rng, P::WIDTH, P::ROUNDS))
+    }
+
+    /// Reads `input` as [`RATE`](Self::RATE) elements, as `to_field_elements`
+    /// does for Poseidon, and outputs the first element of their hash.
+    fn evaluate(parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
+        let eval_time = start_timer!(|| "RescueCRH::Eval");
+
+        if (input.len() * 8) != Self::INPUT_SIZE_BITS {
+            end_timer!(eval_time);
+            return Err(CryptoError::IncorrectInputLength(input.len()).into());
+        }
+        if let Err(e) = parameters.check(P::WIDTH, P::ROUNDS) {
+            end_timer!(eval_time);
+            return Err(e.into());
+        }
+
+        let result = Self::hash(parameters, &to_field_elements(input))[0];
+
+        end_timer!(eval_time);
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::hex::{fe_from_hex, fes_from_hex};
+    use ark_ed_on_bn254::Fq;
+    use ark_ff::{to_bytes, Field, UniformRand};
+
+    use test_data::{get_mds, get_rounds};
+
+    #[derive(Default, Clone)]
+    struct RescueRounds3;
+
+    impl Rounds for RescueRounds3 {
+        const WIDTH: usize = 3;
+        const CAPACITY: usize = 1;
+        const ROUNDS: usize = 14;
+    }
+
+    type RescueCRH3 = CRH<Fq, RescueRounds3>;
+
+    fn snapshot_parameters() -> RescueParameters<Fq> {
+        let alpha_inv = fe_from_hex::<Fq>(test_data::ALPHA_INV).unwrap();
+        RescueParameters::new(
+            get_mds(),
+            get_rounds(),
+            test_data::ALPHA,
+            alpha_inv.into_repr().as_ref().to_vec(),
+        )
+    }
+
+    #[test]
+    fn regression_snapshot_test() {
+        let params = snapshot_parameters();
+        let permuted = RescueCRH3::permute(
+            &params,
+            vec![Fq::from(0u64), Fq::from(1u64), Fq::from(2u64)],
+        );
+        assert_eq!(
+            permuted,
+            fes_from_hex::<Fq>(&test_data::PERMUTATION_RESULT).unwrap()
+        );
+
+        let cases: [(&[u64], [&str; 2]); 3] = [
+            (&[], test_data::HASH_EMPTY),
+            (&[1, 2], test_data::HASH_1_2),
+            (&[1, 2, 3], test_data::HASH_1_2_3),
+        ];
+        for (input, expected) in cases.iter() {
+            let input = input.iter().map(|x| Fq::from(*x)).collect::<Vec<_>>();
+            assert_eq!(
+                RescueCRH3::hash(&params, &input),
+                fes_from_hex::<Fq>(expected).unwrap()
+            );
+        }
+
+        let bytes = to_bytes![Fq::from(1u64), Fq::from(2u64)].unwrap();
+        assert_eq!(
+            RescueCRH3::evaluate(&params, &bytes).unwrap(),
+            fe_from_hex::<Fq>(test_data::HASH_1_2[0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn alphas_test() {
+        let (alpha, alpha_inv) = RescueParameters::<Fq>::alphas();
+        assert_eq!(alpha, test_data::ALPHA);
+        assert_eq!(alpha_inv, snapshot_parameters().alpha_inv);
+
+        // Over BLS12-381, 3 divides `p - 1` as well.
+        type BlsFr = ark_ed_on_bls12_381::Fq;
+        let (alpha, alpha_inv) = RescueParameters::<BlsFr>::alphas();
+        assert_eq!(alpha, 5);
+        assert_eq!(alpha_inv.len(), <BlsFr as PrimeField>::BigInt::NUM_LIMBS);
+        let x = BlsFr::rand(&mut ark_std::test_rng());
+        assert_eq!(x.pow([alpha]).pow(&alpha_inv), x);
+    }
+
+    #[test]
+    fn setup_test() {
+        let rng = &mut ark_std::test_rng();
+        let mut params = RescueCRH3::setup(rng).unwrap();
+        assert_eq!(params.round_keys.len(), 2 * 3 * 14);
+        let x = Fq::rand(rng);
+        assert_eq!(x.pow([params.alpha]).pow(&params.alpha_inv), x);
+        assert!(RescueCRH3::evaluate(&params, &[7u8; 64]).is_ok());
+
+        for len in [0, 32, 96].iter() {
+            assert!(matches!(
+                RescueCRH3::evaluate(&params, &vec![0u8; *len]),
+                Err(Error::Crypto(CryptoError::IncorrectInputLength(l))) if l == *len
+            ));
+        }
+        params.round_keys.pop();
+        assert!(matches!(
+            RescueCRH3::evaluate(&params, &[7u8; 64]),
+            Err(Error::Crypto(CryptoError::InvalidLength {
+                expected: 84,
+                ..
+            }))
+        ));
+    }
+}
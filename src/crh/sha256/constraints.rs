@@ -0,0 +1,310 @@
+use super::{padding, INPUT_BYTES, IV, ROUND_CONSTANTS};
+use crate::prf::blake2s::constraints::OutputVar;
+use crate::{FixedLengthCRHGadget, Vec};
+use ark_ff::PrimeField;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use core::borrow::Borrow;
+
+/// The empty parameters of the SHA-256 CRH.
+#[derive(Clone, Default)]
+pub struct ParametersVar;
+
+impl<F: PrimeField> AllocVar<(), F> for ParametersVar {
+    fn new_variable<T: Borrow<()>>(
+        _cs: impl Into<Namespace<F>>,
+        _f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        Ok(ParametersVar)
+    }
+}
+
+pub struct CRHGadget;
+
+impl CRHGadget {
+    /// The counterpart of the native `compress`, over a state of eight words
+    /// and a block of 64 bytes.
+    pub fn compress<F: PrimeField>(
+        state: &[UInt32<F>],
+        block: &[UInt8<F>],
+    ) -> Result<Vec<UInt32<F>>, SynthesisError> {
+        if state.len() != 8 || block.len() != 64 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut w = block
+            .chunks(4)
+            .map(word_from_be_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        for i in 16..64 {
+            let s0 = w[i - 15]
+                .rotr(7)
+                .xor(&w[i - 15].rotr(18))?
+                .xor(&shr(&w[i - 15], 3))?;
+            let s1 = w[i - 2]
+                .rotr(17)
+                .xor(&w[i - 2].rotr(19))?
+                .xor(&shr(&w[i - 2], 10))?;
+            let word = UInt32::addmany(&[w[i - 16].clone(), s0, w[i - 7].clone(), s1])?;
+            w.push(word);
+        }
+
+        let mut v = state.to_vec();
+        for (k, w) in ROUND_CONSTANTS.iter().zip(w) {
+            let (a, b, c, e, f, g) = (&v[0], &v[1], &v[2], &v[4], &v[5], &v[6]);
+            let s1 = e.rotr(6).xor(&e.rotr(11))?.xor(&e.rotr(25))?;
+            let ch = bitwise(e, f, g, |e, f, g| Boolean::conditionally_select(e, f, g))?;
+            let t1 = UInt32::addmany(&[v[7].clone(), s1, ch, UInt32::constant(*k), w])?;
+            let s0 = a.rotr(2).xor(&a.rotr(13))?.xor(&a.rotr(22))?;
+            // The majority is `c` where `a` and `b` differ and `a` elsewhere.
+            let maj = bitwise(a, b, c, |a, b, c| {
+                Boolean::conditionally_select(&a.xor(b)?, c, a)
+            })?;
+            let t2 = UInt32::addmany(&[s0, maj])?;
+
+            let e = UInt32::addmany(&[v[3].clone(), t1.clone()])?;
+            let a = UInt32::addmany(&[t1, t2])?;
+            v.rotate_right(1);
+            v[0] = a;
+            v[4] = e;
+        }
+
+        state
+            .iter()
+            .zip(v)
+            .map(|(s, x)| UInt32::addmany(&[s.clone(), x]))
+            .collect()
+    }
+
+    /// The SHA-256 digest of a message whose length is fixed by the circuit,
+    /// padded with constants as natively.
+    pub fn digest<F: PrimeField>(message: &[UInt8<F>]) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let mut padded = message.to_vec();
+        padded.extend(UInt8::constant_vec(&padding(message.len())));
+
+        let mut state = iv();
+        for block in padded.chunks(64) {
+            state = Self::compress(&state, block)?;
+        }
+        state_to_bytes(state)
+    }
+}
+
+impl<F: PrimeField> FixedLengthCRHGadget<super::CRH, F> for CRHGadget {
+    type OutputVar = OutputVar<F>;
+    type ParametersVar = ParametersVar;
+
+    fn evaluate(
+        _parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let block = pad_input(input)?;
+        Ok(OutputVar(state_to_bytes(Self::compress(&iv(), &block)?)?))
+    }
+}
+
+/// The counterpart of the native `DigestCRH`.
+pub struct DigestCRHGadget;
+
+impl<F: PrimeField> FixedLengthCRHGadget<super::DigestCRH, F> for DigestCRHGadget {
+    type OutputVar = OutputVar<F>;
+    type ParametersVar = ParametersVar;
+
+    fn evaluate(
+        _parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let block = pad_input(input)?;
+        Ok(OutputVar(CRHGadget::digest(&block)?))
+    }
+}
+
+/// The bytes of `input` padded with zero constants to `INPUT_BYTES`.
+fn pad_input<F: PrimeField>(input: &[UInt8<F>]) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    if input.len() > INPUT_BYTES {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let mut padded = input.to_vec();
+    padded.resize(INPUT_BYTES, UInt8::constant(0u8));
+    Ok(padded)
+}
+
+/// The initial hash value, as constants.
+fn iv<F: PrimeField>() -> Vec<UInt32<F>> {
+    IV.iter().map(|x| UInt32::constant(*x)).collect()
+}
+
+/// The state as big-endian words, as the digest is output.
+fn state_to_bytes<F: PrimeField>(state: Vec<UInt32<F>>) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    let mut result = Vec::with_capacity(32);
+    for word in state {
+        result.extend(word.to_bytes()?.into_iter().rev());
+    }
+    Ok(result)
+}
+
+/// The word of four big-endian bytes.
+fn word_from_be_bytes<F: PrimeField>(bytes: &[UInt8<F>]) -> Result<UInt32<F>, SynthesisError> {
+    let mut bits = Vec::with_capacity(32);
+    for byte in bytes.iter().rev() {
+        bits.extend(byte.to_bits_le()?);
+    }
+    Ok(UInt32::from_bits_le(&bits))
+}
+
+/// A right shift, which costs no constraints: the top bits become constants.
+fn shr<F: PrimeField>(x: &UInt32<F>, by: usize) -> UInt32<F> {
+    let mut bits = x.to_bits_le().split_off(by);
+    bits.resize(32, Boolean::FALSE);
+    UInt32::from_bits_le(&bits)
+}
+
+/// Applies `op` to the bits of three words at each position.
+fn bitwise<F: PrimeField>(
+    x: &UInt32<F>,
+    y: &UInt32<F>,
+    z: &UInt32<F>,
+    op: impl Fn(&Boolean<F>, &Boolean<F>, &Boolean<F>) -> Result<Boolean<F>, SynthesisError>,
+) -> Result<UInt32<F>, SynthesisError> {
+    let bits = x
+        .to_bits_le()
+        .iter()
+        .zip(y.to_bits_le())
+        .zip(z.to_bits_le())
+        .map(|((x, y), z)| op(x, &y, &z))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(UInt32::from_bits_le(&bits))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::sha256::test::{to_hex, NIST_VECTORS};
+    use crate::crh::sha256::{compress, digest, DigestCRH, CRH};
+    use crate::crh::FixedLengthCRH;
+    use crate::merkle_tree::{constraints::PathVar, Config, MerkleTree};
+    use ark_ed_on_bls12_381::Fq;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::Rng;
+
+    #[test]
+    fn nist_vectors_test() {
+        for (message, expected) in NIST_VECTORS.iter() {
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let message_var = UInt8::new_witness_vec(cs.clone(), message).unwrap();
+            let digest_var = CRHGadget::digest(&message_var).unwrap();
+            assert_eq!(to_hex(&digest_var.value().unwrap()), *expected);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn native_equality_test() {
+        let mut rng = ark_std::test_rng();
+        let block = (0..64).map(|_| rng.gen()).collect::<Vec<u8>>();
+        let state = [(); 8].map(|_| rng.gen::<u32>());
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let state_var = state
+            .iter()
+            .map(|x| UInt32::new_witness(cs.clone(), || Ok(*x)).unwrap())
+            .collect::<Vec<_>>();
+        let block_var = UInt8::new_witness_vec(cs.clone(), &block).unwrap();
+        let output = CRHGadget::compress(&state_var, &block_var).unwrap();
+        let mut expected = state;
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(&block);
+        compress(&mut expected, &bytes);
+        assert_eq!(output.value().unwrap(), expected.to_vec());
+        assert!(cs.is_satisfied().unwrap());
+
+        for len in [0, 32, 64].iter() {
+            let input = &block[..*len];
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let input_var = UInt8::new_witness_vec(cs.clone(), input).unwrap();
+            let output_var =
+                <CRHGadget as FixedLengthCRHGadget<CRH, Fq>>::evaluate(&ParametersVar, &input_var)
+                    .unwrap();
+            assert_eq!(
+                output_var.value().unwrap(),
+                CRH::evaluate(&(), input).unwrap()
+            );
+            let compression_constraints = cs.num_constraints();
+            assert!(cs.is_satisfied().unwrap());
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let input_var = UInt8::new_witness_vec(cs.clone(), input).unwrap();
+            let output_var = <DigestCRHGadget as FixedLengthCRHGadget<DigestCRH, Fq>>::evaluate(
+                &ParametersVar,
+                &input_var,
+            )
+            .unwrap();
+            assert_eq!(
+                output_var.value().unwrap(),
+                DigestCRH::evaluate(&(), input).unwrap()
+            );
+            // Over a witness input, the digest also compresses the padding.
+            if *len > 0 {
+                assert!(cs.num_constraints() > compression_constraints);
+            }
+            assert!(cs.is_satisfied().unwrap());
+        }
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let input_var = UInt8::new_witness_vec(cs.clone(), &[0u8; 65]).unwrap();
+        assert!(matches!(
+            <CRHGadget as FixedLengthCRHGadget<CRH, Fq>>::evaluate(&ParametersVar, &input_var),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+        assert!(matches!(
+            <DigestCRHGadget as FixedLengthCRHGadget<DigestCRH, Fq>>::evaluate(
+                &ParametersVar,
+                &input_var
+            ),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    struct Sha256MerkleTreeParams;
+
+    impl Config for Sha256MerkleTreeParams {
+        const HEIGHT: usize = 3;
+        type H = DigestCRH;
+    }
+
+    #[test]
+    fn membership_test() {
+        let leaves = (0..4u8).map(|i| [i; 32]).collect::<Vec<_>>();
+
+        // The same tree by hand: leaves are padded with zeros to a block and
+        // each node hashes the concatenation of its children.
+        let leaf_digests = leaves
+            .iter()
+            .map(|leaf| digest(&[&leaf[..], &[0u8; 32]].concat()))
+            .collect::<Vec<_>>();
+        let left = digest(&[leaf_digests[0], leaf_digests[1]].concat());
+        let right = digest(&[leaf_digests[2], leaf_digests[3]].concat());
+        let root = digest(&[left, right].concat());
+
+        let tree = MerkleTree::<Sha256MerkleTreeParams>::new((), &leaves).unwrap();
+        assert_eq!(tree.root(), root);
+
+        for (index, leaf, member) in [(2, leaves[2], true), (2, leaves[3], false)].iter() {
+            let proof = tree
+                .generate_proof(*index, &leaves[*index as usize])
+                .unwrap();
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let root_var = OutputVar::new_input(cs.clone(), || Ok(root)).unwrap();
+            let leaf_var = UInt8::new_witness_vec(cs.clone(), leaf).unwrap();
+            let path_var =
+                PathVar::<_, DigestCRHGadget, _>::new_witness(cs.clone(), || Ok(&proof)).unwrap();
+            path_var
+                .check_membership(&ParametersVar, &root_var, leaf_var.as_slice())
+                .unwrap()
+                .enforce_equal(&Boolean::TRUE)
+                .unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), *member);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! SHA-256 as a [`FixedLengthCRH`] over a single 64-byte block, the two
+//! 32-byte children of a Merkle tree node. Inputs shorter than 64 bytes are
+//! padded with zeros.
+//!
+//! [`CRH`] is the SHA-256 compression function applied once to the block,
+//! starting from the initial hash value. [`DigestCRH`] is the standard
+//! digest of the 64 bytes, which also compresses the length padding and so
+//! costs twice as much in a circuit. Used as the `H` of a
+//! `merkle_tree::Config` with the legacy domain separation, `DigestCRH`
+//! gives inner nodes `SHA-256(left || right)`, which matches trees built by
+//! other SHA-256 implementations.
+
+use crate::crh::FixedLengthCRH;
+use crate::{CryptoError, Error, Vec};
+use ark_std::rand::Rng;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The number of bytes hashed by the [`CRH`], after padding.
+pub const INPUT_BYTES: usize = 64;
+
+/// The initial hash value, from section 5.3.3 of FIPS 180-4.
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// The round constants, from section 4.2.2 of FIPS 180-4.
+pub const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// The SHA-256 compression function: updates `state` with one 64-byte block,
+/// read as sixteen big-endian words.
+pub fn compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut w = [0u32; 64];
+    for (word, bytes) in w.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in ROUND_CONSTANTS.iter().zip(w.iter()) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (s, x) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+        *s = s.wrapping_add(*x);
+    }
+}
+
+/// The padding appended to a message of `len` bytes, as in section 5.1.1 of
+/// FIPS 180-4: a one bit, zeros, and the length in bits as a big-endian
+/// `u64`.
+pub fn padding(len: usize) -> Vec<u8> {
+    let mut padding = vec![0x80];
+    while (len + padding.len()) % 64 != 56 {
+        padding.push(0);
+    }
+    padding.extend_from_slice(&((len as u64) * 8).to_be_bytes());
+    padding
+}
+
+/// The SHA-256 digest of a message of any length.
+pub fn digest(message: &[u8]) -> [u8; 32] {
+    let mut state = IV;
+    let padded = [message, &padding(message.len())].concat();
+    for block in padded.chunks(64) {
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(block);
+        compress(&mut state, &bytes);
+    }
+    state_to_bytes(&state)
+}
+
+/// The state as big-endian words, as the digest is output.
+fn state_to_bytes(state: &[u32; 8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    for (bytes, word) in result.chunks_mut(4).zip(state.iter()) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    result
+}
+
+/// The bytes of `input` padded with zeros to [`INPUT_BYTES`], or an error if
+/// it is longer.
+fn pad_input(input: &[u8]) -> Result<[u8; INPUT_BYTES], Error> {
+    if input.len() > INPUT_BYTES {
+        return Err(CryptoError::InputTooLong {
+            bits: input.len() * 8,
+            max_bits: INPUT_BYTES * 8,
+        }
+        .into());
+    }
+    let mut block = [0u8; INPUT_BYTES];
+    block[..input.len()].copy_from_slice(input);
+    Ok(block)
+}
+
+/// The SHA-256 compression of the input, padded with zeros to
+/// [`INPUT_BYTES`] bytes, over the initial hash value. The output is the
+/// resulting state as big-endian words. The parameters are empty.
+pub struct CRH;
+
+impl FixedLengthCRH for CRH {
+    const INPUT_SIZE_BITS: usize = INPUT_BYTES * 8;
+    type Output = [u8; 32];
+    type Parameters = ();
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn evaluate(_parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
+        let block = pad_input(input)?;
+        let eval_time = start_timer!(|| "Sha256CRH::Eval");
+
+        let mut state = IV;
+        compress(&mut state, &block);
+        let result = state_to_bytes(&state);
+
+        end_timer!(eval_time);
+
+        Ok(result)
+    }
+}
+
+/// SHA-256 of the input padded with zeros to [`INPUT_BYTES`] bytes. The
+/// parameters are empty.
+pub struct DigestCRH;
+
+impl FixedLengthCRH for DigestCRH {
+    const INPUT_SIZE_BITS: usize = INPUT_BYTES * 8;
+    type Output = [u8; 32];
+    type Parameters = ();
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn evaluate(_parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
+        let block = pad_input(input)?;
+        let eval_time = start_timer!(|| "Sha256DigestCRH::Eval");
+
+        let result = digest(&block);
+
+        end_timer!(eval_time);
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    pub(super) fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Messages and digests from the examples of the NIST Cryptographic
+    /// Standards and Guidelines for SHA-256; the second needs two blocks.
+    pub(super) const NIST_VECTORS: [(&[u8], &str); 3] = [
+        (
+            b"",
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        (
+            b"abc",
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+    ];
+
+    #[test]
+    fn nist_vectors_test() {
+        for (message, expected) in NIST_VECTORS.iter() {
+            assert_eq!(to_hex(&digest(message)), *expected);
+        }
+        // A million repetitions of "a", from the same examples.
+        assert_eq!(
+            to_hex(&digest(&vec![b'a'; 1_000_000])),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn compression_test() {
+        // A message of at most 55 bytes pads to a single block, whose
+        // compression is the digest.
+        for (message, expected) in NIST_VECTORS[..2].iter() {
+            let block = [*message, &padding(message.len())].concat();
+            assert_eq!(to_hex(&CRH::evaluate(&(), &block).unwrap()), *expected);
+        }
+
+        let input = [7u8; 64];
+        let mut state = IV;
+        compress(&mut state, &input);
+        let output = CRH::evaluate(&(), &input).unwrap();
+        assert_eq!(output[..4], state[0].to_be_bytes());
+        assert_ne!(output, DigestCRH::evaluate(&(), &input).unwrap());
+    }
+
+    #[test]
+    fn padding_test() {
+        let input = [7u8; 32];
+        let output = DigestCRH::evaluate(&(), &input).unwrap();
+        assert_eq!(output, digest(&[&input[..], &[0u8; 32]].concat()));
+        assert_eq!(
+            output,
+            DigestCRH::evaluate(&(), &[&input[..], &[0u8; 32]].concat()).unwrap()
+        );
+        assert_eq!(
+            CRH::evaluate(&(), &input).unwrap(),
+            CRH::evaluate(&(), &[&input[..], &[0u8; 32]].concat()).unwrap()
+        );
+
+        for output in [
+            CRH::evaluate(&(), &[0u8; 65]),
+            DigestCRH::evaluate(&(), &[0u8; 65]),
+        ] {
+            assert!(matches!(
+                output,
+                Err(Error::Crypto(CryptoError::InputTooLong {
+                    bits: 520,
+                    max_bits: 512
+                }))
+            ));
+        }
+    }
+}
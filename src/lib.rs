@@ -15,7 +15,10 @@ extern crate ark_std;
 #[macro_use]
 extern crate derivative;
 
-pub(crate) use ark_std::{borrow::ToOwned, boxed::Box, vec::Vec};
+pub(crate) use ark_std::{borrow::ToOwned, boxed::Box, string::String, vec::Vec};
+
+use ark_serialize::SerializationError;
+use ark_std::any::Any;
 
 pub mod commitment;
 pub mod crh;
@@ -23,6 +26,7 @@ pub mod merkle_tree;
 pub mod prf;
 pub mod signature;
 pub mod snark;
+pub mod utils;
 
 pub use self::{
     commitment::CommitmentScheme,
@@ -35,26 +39,262 @@ pub use self::{
 
 #[cfg(feature = "r1cs")]
 pub use self::{
-    commitment::CommitmentGadget, crh::FixedLengthCRHGadget, merkle_tree::constraints::PathVar,
-    prf::PRFGadget, signature::SigRandomizePkGadget, snark::SNARKGadget,
+    commitment::CommitmentGadget,
+    crh::FixedLengthCRHGadget,
+    merkle_tree::constraints::PathVar,
+    prf::PRFGadget,
+    signature::{SigRandomizePkGadget, SigVerifyGadget},
+    snark::SNARKGadget,
 };
 
-pub type Error = Box<dyn ark_std::error::Error>;
+/// The error of the fallible functions of this crate. Every error of the
+/// crate is a [`CryptoError`], so failures from any module can be matched
+/// without downcasting. Code written for the boxed error it replaces keeps
+/// compiling, with deprecation warnings, through [`Error::downcast_ref`],
+/// [`Error::is`] and [`CryptoError::downcast`].
+#[derive(Debug)]
+pub enum Error {
+    Crypto(CryptoError),
+    /// An error from another crate, such as a SNARK, kept as its message.
+    Custom(String),
+}
+
+/// The boxed error that [`Error`] replaces.
+#[deprecated(note = "use `Error`, which can be matched without downcasting")]
+pub type BoxError = Box<dyn ark_std::error::Error>;
+
+impl Error {
+    /// Wraps an error from another crate as [`Error::Custom`].
+    pub fn custom(error: impl core::fmt::Display) -> Self {
+        Error::Custom(format!("{}", error))
+    }
+
+    /// Returns the error of type `T` that `self` is or wraps, as
+    /// `downcast_ref` did on the boxed error. Every error of this crate is
+    /// found, however deeply it is nested.
+    #[deprecated(note = "match on the variants of `Error` instead")]
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        let mut layers: Vec<&dyn Any> = vec![self];
+        if let Error::Crypto(e) = self {
+            layers.push(e);
+            match e {
+                CryptoError::Serialization(e) => layers.push(e),
+                CryptoError::Merkle(e) => layers.push(e),
+                CryptoError::Signature(e) => layers.push(e),
+                CryptoError::Snark(e) => layers.push(e),
+                _ => {}
+            }
+        }
+        layers.into_iter().find_map(|layer| layer.downcast_ref())
+    }
+
+    /// Whether `self` is or wraps an error of type `T`, as `is` did on the
+    /// boxed error.
+    #[deprecated(note = "match on the variants of `Error` instead")]
+    #[allow(deprecated)]
+    pub fn is<T: Any>(&self) -> bool {
+        self.downcast_ref::<T>().is_some()
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Crypto(e) => write!(f, "{}", e),
+            Error::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl ark_std::error::Error for Error {}
+
+impl From<CryptoError> for Error {
+    fn from(e: CryptoError) -> Self {
+        Error::Crypto(e)
+    }
+}
+
+impl From<SerializationError> for Error {
+    fn from(e: SerializationError) -> Self {
+        Error::Crypto(e.into())
+    }
+}
+
+impl From<ark_std::io::Error> for Error {
+    fn from(e: ark_std::io::Error) -> Self {
+        Error::Crypto(e.into())
+    }
+}
+
+impl From<merkle_tree::Error> for Error {
+    fn from(e: merkle_tree::Error) -> Self {
+        Error::Crypto(e.into())
+    }
+}
+
+impl From<signature::SignatureError> for Error {
+    fn from(e: signature::SignatureError) -> Self {
+        Error::Crypto(e.into())
+    }
+}
+
+impl From<snark::SnarkError> for Error {
+    fn from(e: snark::SnarkError) -> Self {
+        Error::Crypto(e.into())
+    }
+}
+
+/// Recovers the variant of an error of this crate where it can be
+/// downcast, and keeps the message of any other error.
+#[allow(deprecated)]
+impl From<BoxError> for Error {
+    fn from(error: BoxError) -> Self {
+        #[cfg(feature = "std")]
+        {
+            let error = match error.downcast::<Error>() {
+                Ok(e) => return *e,
+                Err(error) => error,
+            };
+            let error = match error.downcast::<CryptoError>() {
+                Ok(e) => return (*e).into(),
+                Err(error) => error,
+            };
+            let error = match error.downcast::<SerializationError>() {
+                Ok(e) => return (*e).into(),
+                Err(error) => error,
+            };
+            let error = match error.downcast::<merkle_tree::Error>() {
+                Ok(e) => return (*e).into(),
+                Err(error) => error,
+            };
+            let error = match error.downcast::<signature::SignatureError>() {
+                Ok(e) => return (*e).into(),
+                Err(error) => error,
+            };
+            match error.downcast::<snark::SnarkError>() {
+                Ok(e) => (*e).into(),
+                Err(error) => Error::custom(error),
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        Error::custom(error)
+    }
+}
 
 #[derive(Debug)]
 pub enum CryptoError {
     IncorrectInputLength(usize),
+    /// The input has more bits than the primitive is configured for.
+    InputTooLong {
+        bits: usize,
+        max_bits: usize,
+    },
     NotPrimeOrder,
+    /// No valid group element was found when hashing to the curve.
+    HashToCurveFailed,
+    /// The same generator appears more than once in the parameters.
+    DuplicateGenerator,
+    /// The scheme does not support the requested operation.
+    UnsupportedOperation,
+    /// The parameters do not have the shape, such as the width or the
+    /// numbers of rounds and constants, that the configuration requires.
+    InvalidParameters,
+    /// The S-box is not a permutation of the field that this crate
+    /// implements.
+    SboxError,
+    /// The challenge digest does not encode a scalar.
+    InvalidChallenge,
+    /// The field elements are not the packing of a byte string.
+    InvalidPacking,
+    /// An input has `got` elements where `expected` are required.
+    InvalidLength {
+        expected: usize,
+        got: usize,
+    },
+    /// The string is not a hexadecimal number that fits the field.
+    InvalidHex,
+    /// The value is not smaller than the modulus of the field.
+    NotInField,
+    /// Encoding or decoding a value failed.
+    Serialization(SerializationError),
+    Merkle(merkle_tree::Error),
+    Signature(signature::SignatureError),
+    Snark(snark::SnarkError),
 }
 
 impl core::fmt::Display for CryptoError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let msg = match self {
             CryptoError::IncorrectInputLength(len) => format!("input length is wrong: {}", len),
+            CryptoError::InputTooLong { bits, max_bits } => format!(
+                "input of {} bits is longer than the maximum of {} bits",
+                bits, max_bits
+            ),
             CryptoError::NotPrimeOrder => "element is not prime order".to_owned(),
+            CryptoError::HashToCurveFailed => "could not hash to the curve".to_owned(),
+            CryptoError::DuplicateGenerator => "generators are not distinct".to_owned(),
+            CryptoError::UnsupportedOperation => "operation is not supported".to_owned(),
+            CryptoError::InvalidParameters => {
+                "parameters do not match the configuration".to_owned()
+            }
+            CryptoError::SboxError => "s-box is not a supported permutation".to_owned(),
+            CryptoError::InvalidChallenge => "challenge is not a valid scalar".to_owned(),
+            CryptoError::InvalidPacking => "elements are not a packing of bytes".to_owned(),
+            CryptoError::InvalidLength { expected, got } => {
+                format!("expected {} elements, got {}", expected, got)
+            }
+            CryptoError::InvalidHex => "string is not a hexadecimal field element".to_owned(),
+            CryptoError::NotInField => "value is not smaller than the field modulus".to_owned(),
+            CryptoError::Serialization(e) => format!("serialization failed: {}", e),
+            CryptoError::Merkle(e) => format!("merkle tree error: {}", e),
+            CryptoError::Signature(e) => format!("signature error: {}", e),
+            CryptoError::Snark(e) => format!("snark error: {}", e),
         };
         write!(f, "{}", msg)
     }
 }
 
 impl ark_std::error::Error for CryptoError {}
+
+impl From<SerializationError> for CryptoError {
+    fn from(e: SerializationError) -> Self {
+        CryptoError::Serialization(e)
+    }
+}
+
+impl From<ark_std::io::Error> for CryptoError {
+    fn from(e: ark_std::io::Error) -> Self {
+        CryptoError::Serialization(e.into())
+    }
+}
+
+impl From<merkle_tree::Error> for CryptoError {
+    fn from(e: merkle_tree::Error) -> Self {
+        CryptoError::Merkle(e)
+    }
+}
+
+impl From<signature::SignatureError> for CryptoError {
+    fn from(e: signature::SignatureError) -> Self {
+        CryptoError::Signature(e)
+    }
+}
+
+impl From<snark::SnarkError> for CryptoError {
+    fn from(e: snark::SnarkError) -> Self {
+        CryptoError::Snark(e)
+    }
+}
+
+impl CryptoError {
+    /// Recovers the error of this crate behind an [`Error`], so that the
+    /// failures of every module can be matched through `CryptoError`. Errors
+    /// of other crates are given back unchanged.
+    #[deprecated(note = "match on `Error::Crypto` instead")]
+    pub fn downcast(error: Error) -> Result<Self, Error> {
+        match error {
+            Error::Crypto(e) => Ok(e),
+            error => Err(error),
+        }
+    }
+}
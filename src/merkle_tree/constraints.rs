@@ -1,6 +1,6 @@
-use ark_ff::Field;
-use ark_r1cs_std::prelude::*;
-use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_ff::{Field, PrimeField};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSystem, Namespace, SynthesisError};
 
 use crate::{
     crh::{FixedLengthCRH, FixedLengthCRHGadget},
@@ -15,7 +15,11 @@ where
     HGadget: FixedLengthCRHGadget<P::H, ConstraintF>,
     ConstraintF: Field,
 {
-    path: Vec<(HGadget::OutputVar, HGadget::OutputVar)>,
+    /// The sibling of the node at every level, from the leaf up.
+    siblings: Vec<HGadget::OutputVar>,
+    /// `is_right[i]` is true iff the node at level `i` is a right child, i.e.
+    /// these are the bits of the leaf index, least significant first.
+    is_right: Vec<Boolean<ConstraintF>>,
 }
 
 impl<P, CRHGadget, ConstraintF> PathVar<P, CRHGadget, ConstraintF>
@@ -32,59 +36,433 @@ where
         root: &CRHGadget::OutputVar,
         leaf: impl ToBytesGadget<ConstraintF>,
     ) -> Result<Boolean<ConstraintF>, SynthesisError> {
-        assert_eq!(self.path.len(), P::HEIGHT - 1);
+        root.is_eq(&self.root_var(parameters, leaf)?)
+    }
+
+    /// Computes the root of the tree that `leaf` is a member of according to
+    /// this path, so that it can be compared against several candidate roots
+    /// or hashed further.
+    ///
+    /// Every level costs one two-to-one hash plus a conditional swap of the
+    /// current node and its sibling, which is two selects (two constraints
+    /// for field elements).
+    #[tracing::instrument(target = "r1cs", skip(self, parameters, leaf))]
+    pub fn root_var(
+        &self,
+        parameters: &CRHGadget::ParametersVar,
+        leaf: impl ToBytesGadget<ConstraintF>,
+    ) -> Result<CRHGadget::OutputVar, SynthesisError> {
         // Check that the hash of the given leaf matches the leaf hash in the membership
         // proof.
-        let leaf_bits = leaf.to_bytes()?;
-        let leaf_hash = CRHGadget::evaluate(parameters, &leaf_bits)?;
-        let cs = leaf_hash.cs().or(root.cs());
-
-        // Check if leaf is one of the bottom-most siblings.
-        let leaf_is_left = Boolean::new_witness(ark_relations::ns!(cs, "leaf_is_left"), || {
-            Ok(leaf_hash.value()?.eq(&self.path[0].0.value()?))
-        })?;
-
-        let mut result =
-            leaf_hash.is_eq(&leaf_is_left.select(&self.path[0].0, &self.path[0].1)?)?;
-
-        // Check levels between leaf level and root.
-        let mut previous_hash = leaf_hash;
-        for &(ref left_hash, ref right_hash) in &self.path {
-            // Check if the previous_hash matches the correct current hash.
-            let previous_is_left =
-                Boolean::new_witness(ark_relations::ns!(cs, "previous_is_left"), || {
-                    Ok(previous_hash.value()?.eq(&left_hash.value()?))
-                })?;
-
-            let ns = ark_relations::ns!(cs, "enforcing that inner hash is correct");
-            let equality_cmp = previous_is_left.select(left_hash, right_hash)?;
-            result = result.and(&previous_hash.is_eq(&equality_cmp)?)?;
-            drop(ns);
-
-            previous_hash =
-                hash_inner_node::<P::H, CRHGadget, ConstraintF>(parameters, left_hash, right_hash)?;
+        let leaf_hash = hash_leaf::<P, CRHGadget, ConstraintF>(parameters, &leaf.to_bytes()?)?;
+        self.root_from_leaf_digest(parameters, &leaf_hash)
+    }
+
+    /// Like [`PathVar::check_membership`], but starts from the hash of the
+    /// leaf instead of its bytes.
+    ///
+    /// Use this when the leaf digest is already computed elsewhere in the
+    /// circuit: it skips the leaf hash and keeps the preimage away from the
+    /// path. The digest has to be computed exactly as the native tree hashes
+    /// its leaves, including the leaf encoding and domain separation of `P`.
+    /// Otherwise use `check_membership`, which does that from the raw bytes.
+    #[tracing::instrument(target = "r1cs", skip(self, parameters, root, leaf_digest))]
+    pub fn check_membership_with_leaf_digest(
+        &self,
+        parameters: &CRHGadget::ParametersVar,
+        root: &CRHGadget::OutputVar,
+        leaf_digest: &CRHGadget::OutputVar,
+    ) -> Result<Boolean<ConstraintF>, SynthesisError> {
+        root.is_eq(&self.root_from_leaf_digest(parameters, leaf_digest)?)
+    }
+
+    fn root_from_leaf_digest(
+        &self,
+        parameters: &CRHGadget::ParametersVar,
+        leaf_digest: &CRHGadget::OutputVar,
+    ) -> Result<CRHGadget::OutputVar, SynthesisError> {
+        if P::HEIGHT == 0 || self.siblings.len() != P::HEIGHT - 1 {
+            tracing::error!(
+                target: "r1cs",
+                expected = P::HEIGHT - 1,
+                actual = self.siblings.len(),
+                "Merkle path has the wrong number of levels"
+            );
+            return Err(SynthesisError::Unsatisfiable);
         }
+        fold_path::<P, CRHGadget, ConstraintF>(
+            parameters,
+            leaf_digest,
+            &self.siblings,
+            &self.is_right,
+        )
+    }
+
+    /// Enforces that every leaf is a member of the tree with root `root`
+    /// according to its path, with the same `parameters` for every path.
+    ///
+    /// Each path is folded on its own, exactly as by `check_membership`:
+    /// which nodes two paths have in common depends on their index bits,
+    /// which are witnesses, so no hashing is shared. The only saving is that
+    /// each computed root is enforced equal to `root` directly, rather than
+    /// through the `Boolean` that `check_membership` returns.
+    pub fn check_membership_batch<L: ToBytesGadget<ConstraintF>>(
+        parameters: &CRHGadget::ParametersVar,
+        root: &CRHGadget::OutputVar,
+        items: &[(L, Self)],
+    ) -> Result<(), SynthesisError> {
+        for (leaf, path) in items {
+            path.root_var(parameters, leaf)?.enforce_equal(root)?;
+        }
+        Ok(())
+    }
 
-        result.and(&root.is_eq(&previous_hash)?)
+    /// Allocates a dummy path of `P::HEIGHT - 1` default siblings for the
+    /// leaf at index 0 as witnesses. The path has the same shape as any
+    /// other, so it can stand in for a real one whose check is disabled with
+    /// [`PathVar::conditionally_check_membership`].
+    pub fn blank(cs: impl Into<Namespace<ConstraintF>>) -> Result<Self, SynthesisError> {
+        Self::new_witness(cs, || Ok(Path::<P>::default()))
+    }
+
+    /// Enforces that `leaf` is a member of the tree with root `root` if
+    /// `should_enforce` is true, and enforces nothing otherwise.
+    pub fn conditionally_check_membership(
+        &self,
+        should_enforce: &Boolean<ConstraintF>,
+        parameters: &CRHGadget::ParametersVar,
+        root: &CRHGadget::OutputVar,
+        leaf: impl ToBytesGadget<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        self.check_membership(parameters, root, leaf)?
+            .conditional_enforce_equal(&Boolean::TRUE, should_enforce)
+    }
+
+    /// Returns a `Boolean` that is true if `leaf` is a member of the tree
+    /// with root `root` or if `should_check` is false.
+    pub fn conditional_membership(
+        &self,
+        should_check: &Boolean<ConstraintF>,
+        parameters: &CRHGadget::ParametersVar,
+        root: &CRHGadget::OutputVar,
+        leaf: impl ToBytesGadget<ConstraintF>,
+    ) -> Result<Boolean<ConstraintF>, SynthesisError> {
+        should_check
+            .not()
+            .or(&self.check_membership(parameters, root, leaf)?)
     }
 }
 
-pub(crate) fn hash_inner_node<H, HG, ConstraintF>(
+impl<P, CRHGadget, ConstraintF> PathVar<P, CRHGadget, ConstraintF>
+where
+    P: Config,
+    ConstraintF: PrimeField,
+    CRHGadget: FixedLengthCRHGadget<P::H, ConstraintF>,
+{
+    /// Packs the bits that decide the order of the siblings at every level
+    /// into the leaf index. These are the same variables that are used when
+    /// folding the path, so the index is bound to the membership check.
+    pub fn index_var(&self) -> Result<FpVar<ConstraintF>, SynthesisError> {
+        Boolean::le_bits_to_fp_var(&self.is_right)
+    }
+}
+
+/// Proves that replacing one leaf transforms an old root into a new root.
+/// Both roots are recomputed from the same siblings and index bits, so the
+/// rest of the tree is bound to stay unchanged.
+pub struct UpdateProofVar<P, HGadget, ConstraintF>
+where
+    P: Config,
+    HGadget: FixedLengthCRHGadget<P::H, ConstraintF>,
+    ConstraintF: Field,
+{
+    path: PathVar<P, HGadget, ConstraintF>,
+}
+
+impl<P, CRHGadget, ConstraintF> UpdateProofVar<P, CRHGadget, ConstraintF>
+where
+    P: Config,
+    ConstraintF: Field,
+    CRHGadget: FixedLengthCRHGadget<P::H, ConstraintF>,
+    <CRHGadget::OutputVar as R1CSVar<ConstraintF>>::Value: PartialEq,
+{
+    /// Builds the proof from the siblings of the updated leaf, from the leaf
+    /// up, and the bits of its index, least significant first. Both have to
+    /// cover the `P::HEIGHT - 1` levels of the tree.
+    pub fn new(
+        siblings: Vec<CRHGadget::OutputVar>,
+        is_right: Vec<Boolean<ConstraintF>>,
+    ) -> Result<Self, SynthesisError> {
+        if P::HEIGHT == 0 || siblings.len() != P::HEIGHT - 1 || is_right.len() != P::HEIGHT - 1 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        Ok(Self {
+            path: PathVar { siblings, is_right },
+        })
+    }
+
+    /// Enforces that `old_leaf` is a member of the tree with root `old_root`
+    /// and that putting `new_leaf` in its place yields `new_root`.
+    #[tracing::instrument(
+        target = "r1cs",
+        skip(self, parameters, old_root, new_root, old_leaf, new_leaf)
+    )]
+    pub fn enforce_update(
+        &self,
+        parameters: &CRHGadget::ParametersVar,
+        old_root: &CRHGadget::OutputVar,
+        new_root: &CRHGadget::OutputVar,
+        old_leaf: impl ToBytesGadget<ConstraintF>,
+        new_leaf: impl ToBytesGadget<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        self.path
+            .root_var(parameters, old_leaf)?
+            .enforce_equal(old_root)?;
+        self.path
+            .root_var(parameters, new_leaf)?
+            .enforce_equal(new_root)
+    }
+}
+
+impl<P, HGadget, ConstraintF> From<PathVar<P, HGadget, ConstraintF>>
+    for UpdateProofVar<P, HGadget, ConstraintF>
+where
+    P: Config,
+    HGadget: FixedLengthCRHGadget<P::H, ConstraintF>,
+    ConstraintF: Field,
+{
+    fn from(path: PathVar<P, HGadget, ConstraintF>) -> Self {
+        Self { path }
+    }
+}
+
+/// Checks that an element, typically a root, is one of a set of candidates,
+/// typically historical roots supplied as public inputs.
+pub struct SetMembershipGadget;
+
+impl SetMembershipGadget {
+    /// Enforces that `element` is in `set` by constraining the product of
+    /// the differences `element - set[i]` to zero. Costs one constraint per
+    /// element of `set`.
+    pub fn enforce_member<F: PrimeField>(
+        element: &FpVar<F>,
+        set: &[FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        Self::product_of_differences(element, set)?.enforce_equal(&FpVar::zero())
+    }
+
+    /// Returns a `Boolean` that is true iff `element` is in `set`.
+    pub fn is_member<F: PrimeField>(
+        element: &FpVar<F>,
+        set: &[FpVar<F>],
+    ) -> Result<Boolean<F>, SynthesisError> {
+        if set.is_empty() {
+            return Ok(Boolean::FALSE);
+        }
+        Self::product_of_differences(element, set)?.is_zero()
+    }
+
+    /// Enforces that `element` is in `set` using a selector witnessed by the
+    /// caller: exactly one of `selector` has to be set, and `element` has to
+    /// equal the matching element of `set`. Products of a selector and a
+    /// constant element are free, so this is cheaper than
+    /// [`SetMembershipGadget::enforce_member`] when `set` is known at setup
+    /// time.
+    pub fn enforce_member_with_selector<F: PrimeField>(
+        element: &FpVar<F>,
+        set: &[FpVar<F>],
+        selector: &[Boolean<F>],
+    ) -> Result<(), SynthesisError> {
+        if set.is_empty() || set.len() != selector.len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut count = FpVar::zero();
+        let mut selected = FpVar::zero();
+        for (candidate, is_selected) in set.iter().zip(selector) {
+            let is_selected = FpVar::from(is_selected.clone());
+            count += &is_selected;
+            selected += is_selected * candidate;
+        }
+        count.enforce_equal(&FpVar::one())?;
+        selected.enforce_equal(element)
+    }
+
+    fn product_of_differences<F: PrimeField>(
+        element: &FpVar<F>,
+        set: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let (first, rest) = set.split_first().ok_or(SynthesisError::Unsatisfiable)?;
+        let mut product = element - first;
+        for candidate in rest {
+            product *= element - candidate;
+        }
+        Ok(product)
+    }
+}
+
+/// Returns `(height, num_constraints)` for a membership check of `leaf` in
+/// a tree of each of the given `heights`, each synthesized on a fresh
+/// constraint system. The hash, leaf encoding and domain separation are
+/// those of `P`; `P::HEIGHT` is ignored.
+///
+/// The count covers [`PathVar::check_membership`] and enforcing its result,
+/// but not the allocation of the leaf, the path and the root.
+pub fn merkle_constraint_report<P, HG, ConstraintF>(
+    parameters: &<P::H as FixedLengthCRH>::Parameters,
+    leaf: &[u8],
+    heights: &[usize],
+) -> Result<Vec<(usize, usize)>, SynthesisError>
+where
+    ConstraintF: Field,
+    P: Config,
+    HG: FixedLengthCRHGadget<P::H, ConstraintF>,
+{
+    heights
+        .iter()
+        .map(|&height| {
+            if height == 0 {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+            let cs = ConstraintSystem::<ConstraintF>::new_ref();
+            let parameters =
+                HG::ParametersVar::new_constant(ark_relations::ns!(cs, "parameters"), parameters)?;
+            let leaf = UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), leaf)?;
+            let root = HG::OutputVar::new_input(ark_relations::ns!(cs, "root"), || {
+                Ok(<P::H as FixedLengthCRH>::Output::default())
+            })?;
+            let mut siblings = Vec::with_capacity(height - 1);
+            let mut is_right = Vec::with_capacity(height - 1);
+            for _ in 0..height - 1 {
+                is_right.push(Boolean::new_witness(
+                    ark_relations::ns!(cs, "is_right"),
+                    || Ok(false),
+                )?);
+                siblings.push(HG::OutputVar::new_witness(
+                    ark_relations::ns!(cs, "sibling"),
+                    || Ok(<P::H as FixedLengthCRH>::Output::default()),
+                )?);
+            }
+
+            let before = cs.num_constraints();
+            let leaf_hash = hash_leaf::<P, HG, ConstraintF>(&parameters, &leaf)?;
+            let computed_root =
+                fold_path::<P, HG, ConstraintF>(&parameters, &leaf_hash, &siblings, &is_right)?;
+            root.is_eq(&computed_root)?.enforce_equal(&Boolean::TRUE)?;
+            Ok((height, cs.num_constraints() - before))
+        })
+        .collect()
+}
+
+fn hash_leaf<P, HG, ConstraintF>(
+    parameters: &HG::ParametersVar,
+    leaf: &[UInt8<ConstraintF>],
+) -> Result<HG::OutputVar, SynthesisError>
+where
+    ConstraintF: Field,
+    P: Config,
+    HG: FixedLengthCRHGadget<P::H, ConstraintF>,
+{
+    let mut leaf_bits = leaf.to_vec();
+    if P::LEAF_ENCODING == LeafEncoding::Chained {
+        leaf_bits = chain_leaf::<P, HG, ConstraintF>(parameters, &leaf_bits)?;
+    }
+    let leaf_input =
+        domain_separated_input::<P, ConstraintF>(leaf_bits, P::DOMAIN_SEPARATION.leaf_tag())?;
+    HG::evaluate(parameters, &leaf_input)
+}
+
+/// Folds a path from the hash of the leaf up to the root. The bits of the
+/// leaf index decide on which side the hash of the level below goes.
+fn fold_path<P, HG, ConstraintF>(
+    parameters: &HG::ParametersVar,
+    leaf_digest: &HG::OutputVar,
+    siblings: &[HG::OutputVar],
+    is_right: &[Boolean<ConstraintF>],
+) -> Result<HG::OutputVar, SynthesisError>
+where
+    ConstraintF: Field,
+    P: Config,
+    HG: FixedLengthCRHGadget<P::H, ConstraintF>,
+{
+    let mut previous_hash = leaf_digest.clone();
+    for (sibling, is_right) in siblings.iter().zip(is_right) {
+        let left_hash = is_right.select(sibling, &previous_hash)?;
+        let right_hash = is_right.select(&previous_hash, sibling)?;
+        previous_hash = hash_inner_node::<P, HG, ConstraintF>(parameters, &left_hash, &right_hash)?;
+    }
+    Ok(previous_hash)
+}
+
+pub(crate) fn hash_inner_node<P, HG, ConstraintF>(
     parameters: &HG::ParametersVar,
     left_child: &HG::OutputVar,
     right_child: &HG::OutputVar,
 ) -> Result<HG::OutputVar, SynthesisError>
 where
     ConstraintF: Field,
-    H: FixedLengthCRH,
-    HG: FixedLengthCRHGadget<H, ConstraintF>,
+    P: Config,
+    HG: FixedLengthCRHGadget<P::H, ConstraintF>,
 {
     let left_bytes = left_child.to_bytes()?;
     let right_bytes = right_child.to_bytes()?;
     let mut bytes = left_bytes;
     bytes.extend_from_slice(&right_bytes);
 
-    HG::evaluate(parameters, &bytes)
+    let input = domain_separated_input::<P, ConstraintF>(bytes, P::DOMAIN_SEPARATION.node_tag())?;
+    HG::evaluate(parameters, &input)
+}
+
+/// Mirrors the native `LeafEncoding::Chained`. The length of the leaf is
+/// fixed by the circuit, so it enters as a constant.
+fn chain_leaf<P, HG, ConstraintF>(
+    parameters: &HG::ParametersVar,
+    leaf: &[UInt8<ConstraintF>],
+) -> Result<Vec<UInt8<ConstraintF>>, SynthesisError>
+where
+    ConstraintF: Field,
+    P: Config,
+    HG: FixedLengthCRHGadget<P::H, ConstraintF>,
+{
+    let input_len = <P::H as FixedLengthCRH>::INPUT_SIZE_BITS / 8;
+    let digest_len = chained_digest_len::<P>().map_err(|_| SynthesisError::Unsatisfiable)?;
+    let chunk_len = input_len - digest_len;
+
+    let mut state = UInt8::constant_vec(&(leaf.len() as u64).to_le_bytes());
+    state.resize(digest_len, UInt8::constant(0));
+    let mut chunks = leaf.chunks(chunk_len).collect::<Vec<_>>();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    for chunk in chunks {
+        let mut input = state;
+        input.extend_from_slice(chunk);
+        input.resize(input_len, UInt8::constant(0));
+        state = HG::evaluate(parameters, &input)?.to_bytes()?;
+    }
+    Ok(state)
+}
+
+/// Mirrors the native domain separation: with a `tag`, the capacity of the
+/// input holds the tag as a constant and the bytes follow it, zero-padded to
+/// the CRH input size. Without one, the bytes are passed on untouched.
+fn domain_separated_input<P: Config, ConstraintF: Field>(
+    bytes: Vec<UInt8<ConstraintF>>,
+    tag: Option<u8>,
+) -> Result<Vec<UInt8<ConstraintF>>, SynthesisError> {
+    let tag = match tag {
+        Some(tag) => tag,
+        None => return Ok(bytes),
+    };
+    let input_len = <P::H as FixedLengthCRH>::INPUT_SIZE_BITS / 8;
+    let capacity = <P::H as FixedLengthCRH>::CAPACITY_BYTES;
+    if bytes.len() > input_len.saturating_sub(capacity) {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let mut input = UInt8::constant_vec(&[tag]);
+    input.resize(capacity, UInt8::constant(0));
+    input.extend(bytes);
+    input.resize(input_len, UInt8::constant(0));
+    Ok(input)
 }
 
 impl<P, HGadget, ConstraintF> AllocVar<Path<P>, ConstraintF> for PathVar<P, HGadget, ConstraintF>
@@ -100,28 +478,59 @@ where
     ) -> Result<Self, SynthesisError> {
         let ns = cs.into();
         let cs = ns.cs();
-        f().and_then(|val| {
-            let mut path = Vec::new();
-            for &(ref l, ref r) in val.borrow().path.iter() {
-                let l_hash = HGadget::OutputVar::new_variable(
-                    ark_relations::ns!(cs, "l_child"),
-                    || Ok(l),
-                    mode,
-                )?;
-                let r_hash = HGadget::OutputVar::new_variable(
-                    ark_relations::ns!(cs, "r_child"),
-                    || Ok(r),
-                    mode,
-                )?;
-                path.push((l_hash, r_hash));
+        // Every level is allocated even without a value, so that the shape of
+        // the circuit only depends on `P::HEIGHT`.
+        if P::HEIGHT == 0 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let value = f();
+        if let Ok(val) = &value {
+            if val.borrow().validate_structure(P::HEIGHT).is_err() {
+                tracing::error!(
+                    target: "r1cs",
+                    expected = P::HEIGHT - 1,
+                    actual = val.borrow().path.len(),
+                    "Merkle path has the wrong number of levels"
+                );
+                return Err(SynthesisError::Unsatisfiable);
             }
-            Ok(PathVar { path })
-        })
+        }
+        let is_right_at = |level: usize| {
+            value
+                .as_ref()
+                .map_err(|e| *e)
+                .map(|val| (val.borrow().leaf_index >> level) & 1 == 1)
+        };
+        let mut siblings = Vec::with_capacity(P::HEIGHT - 1);
+        let mut is_right = Vec::with_capacity(P::HEIGHT - 1);
+        for level in 0..P::HEIGHT - 1 {
+            is_right.push(Boolean::new_variable(
+                ark_relations::ns!(cs, "is_right"),
+                || is_right_at(level),
+                mode,
+            )?);
+            siblings.push(HGadget::OutputVar::new_variable(
+                ark_relations::ns!(cs, "sibling"),
+                || {
+                    let (l, r) = &value.as_ref().map_err(|e| *e)?.borrow().path[level];
+                    Ok(if is_right_at(level)? { l } else { r }.clone())
+                },
+                mode,
+            )?);
+        }
+        Ok(PathVar { siblings, is_right })
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::crh::poseidon::{
+        self,
+        constraints::PoseidonParametersVar,
+        sbox::PoseidonSbox,
+        test_data::{get_mds_3, get_rounds_3},
+        PoseidonParameters,
+    };
     use crate::{
         crh::{
             pedersen::{self, constraints::CRHGadget},
@@ -130,7 +539,9 @@ mod test {
         merkle_tree::*,
     };
     use ark_ed_on_bls12_381::{constraints::EdwardsVar, EdwardsProjective as JubJub, Fq};
-    use ark_relations::r1cs::ConstraintSystem;
+    use ark_ed_on_bn254::Fq as BnFq;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
 
     use super::*;
 
@@ -161,7 +572,7 @@ mod test {
         let root = tree.root();
         let cs = ConstraintSystem::<Fq>::new_ref();
         for (i, leaf) in leaves.iter().enumerate() {
-            let proof = tree.generate_proof(i, &leaf).unwrap();
+            let proof = tree.generate_proof(i as u64, &leaf).unwrap();
             assert!(proof.verify(&crh_parameters, &root, &leaf).unwrap());
 
             // Allocate Merkle Tree Root
@@ -206,9 +617,14 @@ mod test {
                     Ok(&proof)
                 })
                 .unwrap();
-            for (i, (l, r)) in cw.path.iter().enumerate() {
-                assert_eq!(l.value().unwrap(), proof.path[i].0);
-                assert_eq!(r.value().unwrap(), proof.path[i].1);
+            for (i, sibling) in cw.siblings.iter().enumerate() {
+                let (l, r) = &proof.path[i];
+                let expected = if (proof.leaf_index() >> i) & 1 == 1 {
+                    l
+                } else {
+                    r
+                };
+                assert_eq!(&sibling.value().unwrap(), expected);
             }
 
             let constraints_from_path = cs.num_constraints()
@@ -234,6 +650,847 @@ mod test {
         assert!(cs.is_satisfied().unwrap());
     }
 
+    #[derive(Default, Clone)]
+    struct PoseidonRounds3;
+
+    impl poseidon::Rounds for PoseidonRounds3 {
+        const WIDTH: usize = 3;
+        const PARTIAL_ROUNDS: usize = 57;
+        const FULL_ROUNDS: usize = 8;
+        const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+    }
+
+    type PoseidonH = poseidon::CRH<BnFq, PoseidonRounds3>;
+    type PoseidonHG = poseidon::constraints::CRHGadget<BnFq, PoseidonRounds3>;
+
+    struct TaggedPoseidonParams;
+
+    impl Config for TaggedPoseidonParams {
+        const HEIGHT: usize = 4;
+        const DOMAIN_SEPARATION: DomainSeparation = DomainSeparation::Tagged { leaf: 0, node: 1 };
+        type H = PoseidonH;
+    }
+
+    #[test]
+    fn domain_separated_poseidon_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = (0..3u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let tree =
+            MerkleTree::<TaggedPoseidonParams>::new(crh_parameters.clone(), &leaves).unwrap();
+        let proof = tree.generate_proof(2, &leaves[2]).unwrap();
+
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        let root = FpVar::new_witness(ark_relations::ns!(cs, "root"), || Ok(tree.root())).unwrap();
+        let params_var =
+            PoseidonParametersVar::new_constant(ark_relations::ns!(cs, "params"), &crh_parameters)
+                .unwrap();
+        let leaf_var = UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaves[2]).unwrap();
+        let path_var =
+            PathVar::<_, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || Ok(&proof))
+                .unwrap();
+        path_var
+            .check_membership(&params_var, &root, leaf_var.as_slice())
+            .unwrap()
+            .enforce_equal(&Boolean::TRUE)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    struct ChainedPoseidonParams;
+
+    impl Config for ChainedPoseidonParams {
+        const HEIGHT: usize = 4;
+        const DOMAIN_SEPARATION: DomainSeparation = DomainSeparation::Tagged { leaf: 0, node: 1 };
+        const LEAF_ENCODING: LeafEncoding = LeafEncoding::Chained;
+        type H = PoseidonH;
+    }
+
+    #[test]
+    fn chained_leaf_poseidon_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = vec![(0..100u8).collect::<Vec<_>>(), vec![7u8; 10]];
+        let tree =
+            MerkleTree::<ChainedPoseidonParams>::new(crh_parameters.clone(), &leaves).unwrap();
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = tree.generate_proof(i as u64, leaf).unwrap();
+            for &good_leaf in &[true, false] {
+                let cs = ConstraintSystem::<BnFq>::new_ref();
+                let root =
+                    FpVar::new_witness(ark_relations::ns!(cs, "root"), || Ok(tree.root())).unwrap();
+                let params_var = PoseidonParametersVar::new_constant(
+                    ark_relations::ns!(cs, "params"),
+                    &crh_parameters,
+                )
+                .unwrap();
+                let mut leaf = leaf.clone();
+                if !good_leaf {
+                    leaf[3] ^= 1;
+                }
+                let leaf_var =
+                    UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaf).unwrap();
+                let path_var = PathVar::<_, PoseidonHG, _>::new_witness(
+                    ark_relations::ns!(cs, "path"),
+                    || Ok(&proof),
+                )
+                .unwrap();
+                path_var
+                    .check_membership(&params_var, &root, leaf_var.as_slice())
+                    .unwrap()
+                    .enforce_equal(&Boolean::TRUE)
+                    .unwrap();
+                assert_eq!(cs.is_satisfied().unwrap(), good_leaf);
+            }
+        }
+    }
+
+    #[test]
+    fn conditional_membership_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = (0..3u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let tree =
+            MerkleTree::<TaggedPoseidonParams>::new(crh_parameters.clone(), &leaves).unwrap();
+        let proof = tree.generate_proof(2, &leaves[2]).unwrap();
+
+        for &(leaf, cond) in &[(2, true), (2, false), (1, true), (1, false)] {
+            for &enforce in &[true, false] {
+                let cs = ConstraintSystem::<BnFq>::new_ref();
+                let root =
+                    FpVar::new_witness(ark_relations::ns!(cs, "root"), || Ok(tree.root())).unwrap();
+                let params_var = PoseidonParametersVar::new_constant(
+                    ark_relations::ns!(cs, "params"),
+                    &crh_parameters,
+                )
+                .unwrap();
+                let leaf_var =
+                    UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaves[leaf]).unwrap();
+                let path_var = PathVar::<_, PoseidonHG, _>::new_witness(
+                    ark_relations::ns!(cs, "path"),
+                    || Ok(&proof),
+                )
+                .unwrap();
+                let cond_var =
+                    Boolean::new_witness(ark_relations::ns!(cs, "cond"), || Ok(cond)).unwrap();
+
+                if enforce {
+                    path_var
+                        .conditionally_check_membership(
+                            &cond_var,
+                            &params_var,
+                            &root,
+                            leaf_var.as_slice(),
+                        )
+                        .unwrap();
+                } else {
+                    let result = path_var
+                        .conditional_membership(&cond_var, &params_var, &root, leaf_var.as_slice())
+                        .unwrap();
+                    assert_eq!(result.value().unwrap(), leaf == 2 || !cond);
+                }
+                // A bad path only makes the circuit unsatisfiable when the
+                // check is enabled.
+                assert_eq!(cs.is_satisfied().unwrap(), !enforce || leaf == 2 || !cond);
+            }
+        }
+    }
+
+    #[test]
+    fn root_var_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = (0..3u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let tree =
+            MerkleTree::<TaggedPoseidonParams>::new(crh_parameters.clone(), &leaves).unwrap();
+        let proof = tree.generate_proof(1, &leaves[1]).unwrap();
+
+        for &(root, leaf) in &[(tree.root(), 1), (BnFq::from(1u64), 1), (tree.root(), 2)] {
+            let cs = ConstraintSystem::<BnFq>::new_ref();
+            let params_var = PoseidonParametersVar::new_constant(
+                ark_relations::ns!(cs, "params"),
+                &crh_parameters,
+            )
+            .unwrap();
+            let leaf_var =
+                UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaves[leaf]).unwrap();
+            let path_var =
+                PathVar::<_, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || {
+                    Ok(&proof)
+                })
+                .unwrap();
+            let root_var = path_var.root_var(&params_var, leaf_var.as_slice()).unwrap();
+            assert_eq!(root_var.value().unwrap() == tree.root(), leaf == 1);
+
+            let expected_root =
+                FpVar::new_input(ark_relations::ns!(cs, "root"), || Ok(root)).unwrap();
+            root_var.enforce_equal(&expected_root).unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), root == tree.root() && leaf == 1);
+        }
+    }
+
+    /// A root allocated as a public input takes the slots of its native
+    /// `ToConstraintField` elements.
+    #[test]
+    fn root_public_input_order_test() {
+        use ark_ff::ToConstraintField;
+        use ark_r1cs_std::ToConstraintFieldGadget;
+
+        let rng = &mut ark_std::test_rng();
+        let crh_parameters = H::setup(rng).unwrap();
+        let leaves = (0..4u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let tree = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+        let proof = tree.generate_proof(2, &leaves[2]).unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let params_var = <HG as FixedLengthCRHGadget<H, Fq>>::ParametersVar::new_constant(
+            cs.clone(),
+            &crh_parameters,
+        )
+        .unwrap();
+        let leaf_var = UInt8::new_witness_vec(cs.clone(), &leaves[2]).unwrap();
+        let path_var = PathVar::<_, HG, _>::new_witness(cs.clone(), || Ok(&proof)).unwrap();
+        let root_var = path_var.root_var(&params_var, leaf_var.as_slice()).unwrap();
+        let public_root =
+            <HG as FixedLengthCRHGadget<H, Fq>>::OutputVar::new_input(cs.clone(), || {
+                Ok(tree.root())
+            })
+            .unwrap();
+        root_var.enforce_equal(&public_root).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        let native: Vec<Fq> = tree.root().to_field_elements().unwrap();
+        assert_eq!(cs.borrow().unwrap().instance_assignment[1..], native[..]);
+        assert_eq!(
+            root_var.to_constraint_field().unwrap().value().unwrap(),
+            native
+        );
+    }
+
+    #[test]
+    fn index_var_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = (1..9u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let tree =
+            MerkleTree::<TaggedPoseidonParams>::new(crh_parameters.clone(), &leaves).unwrap();
+        let proof = tree.generate_proof(5, &leaves[5]).unwrap();
+        let (_, siblings) = proof.clone().into();
+        let wrong_index = Path::<TaggedPoseidonParams>::try_from((4, siblings)).unwrap();
+        // The siblings are those of leaf 5, so they do not prove leaf 5 at
+        // index 4, natively or in the circuit below.
+        assert!(proof
+            .verify(&crh_parameters, &tree.root(), &leaves[5])
+            .unwrap());
+        assert!(!wrong_index
+            .verify(&crh_parameters, &tree.root(), &leaves[5])
+            .unwrap());
+
+        for &(path, claimed_index) in &[(&proof, 5u64), (&proof, 4), (&wrong_index, 4)] {
+            let cs = ConstraintSystem::<BnFq>::new_ref();
+            let root =
+                FpVar::new_input(ark_relations::ns!(cs, "root"), || Ok(tree.root())).unwrap();
+            let params_var = PoseidonParametersVar::new_constant(
+                ark_relations::ns!(cs, "params"),
+                &crh_parameters,
+            )
+            .unwrap();
+            let leaf_var =
+                UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaves[5]).unwrap();
+            let path_var =
+                PathVar::<_, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || {
+                    Ok(path)
+                })
+                .unwrap();
+            path_var
+                .check_membership(&params_var, &root, leaf_var.as_slice())
+                .unwrap()
+                .enforce_equal(&Boolean::TRUE)
+                .unwrap();
+
+            let index_var = path_var.index_var().unwrap();
+            assert_eq!(index_var.value().unwrap(), BnFq::from(path.leaf_index()));
+            let claimed_index_var = FpVar::new_input(ark_relations::ns!(cs, "index"), || {
+                Ok(BnFq::from(claimed_index))
+            })
+            .unwrap();
+            index_var.enforce_equal(&claimed_index_var).unwrap();
+
+            // Only the genuine index both folds to the root and matches.
+            assert_eq!(
+                cs.is_satisfied().unwrap(),
+                path.leaf_index() == 5 && claimed_index == 5
+            );
+        }
+    }
+
+    struct PoseidonHeight<const HEIGHT: usize>;
+
+    impl<const HEIGHT: usize> Config for PoseidonHeight<HEIGHT> {
+        const HEIGHT: usize = HEIGHT;
+        const DOMAIN_SEPARATION: DomainSeparation = DomainSeparation::Tagged { leaf: 0, node: 1 };
+        type H = PoseidonH;
+    }
+
+    /// Returns the number of constraints of `root_var` for the leaf at
+    /// `index`, after checking the computed root against the native one.
+    fn root_var_constraints<P: Config<H = PoseidonH>>(index: u64) -> usize {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = (1..=16u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let leaves = &leaves[..ark_std::cmp::min(16, 1 << (P::HEIGHT - 1))];
+        let tree = MerkleTree::<P>::new(crh_parameters.clone(), leaves).unwrap();
+        let leaf = &leaves[index as usize];
+        let proof = tree.generate_proof(index, leaf).unwrap();
+        assert!(proof.verify(&crh_parameters, &tree.root(), leaf).unwrap());
+
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        let params_var =
+            PoseidonParametersVar::new_constant(ark_relations::ns!(cs, "params"), &crh_parameters)
+                .unwrap();
+        let leaf_var = UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), leaf).unwrap();
+        let path_var =
+            PathVar::<P, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || Ok(&proof))
+                .unwrap();
+        let before = cs.num_constraints();
+        let root_var = path_var.root_var(&params_var, leaf_var.as_slice()).unwrap();
+        assert_eq!(root_var.value().unwrap(), tree.root());
+        assert!(cs.is_satisfied().unwrap());
+        cs.num_constraints() - before
+    }
+
+    #[test]
+    fn conditional_swap_cost_test() {
+        // The cost of one two-to-one hash on its own.
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let params_var =
+            PoseidonParametersVar::new_constant(ark_relations::ns!(cs, "params"), &crh_parameters)
+                .unwrap();
+        let left =
+            FpVar::new_witness(ark_relations::ns!(cs, "l"), || Ok(BnFq::from(1u64))).unwrap();
+        let right =
+            FpVar::new_witness(ark_relations::ns!(cs, "r"), || Ok(BnFq::from(2u64))).unwrap();
+        let before = cs.num_constraints();
+        let _ =
+            super::hash_inner_node::<PoseidonHeight<2>, PoseidonHG, _>(&params_var, &left, &right)
+                .unwrap();
+        let hash_cost = cs.num_constraints() - before;
+
+        // Every leaf of a full tree goes through both orientations at every
+        // level, and each level costs the swap plus one hash.
+        let base = root_var_constraints::<PoseidonHeight<2>>(0);
+        assert_eq!(root_var_constraints::<PoseidonHeight<2>>(1), base);
+        for index in 0..8 {
+            assert_eq!(
+                root_var_constraints::<PoseidonHeight<4>>(index),
+                base + 2 * (2 + hash_cost)
+            );
+        }
+        for &index in &[0, 5, 10, 15] {
+            assert_eq!(
+                root_var_constraints::<PoseidonHeight<8>>(index),
+                base + 6 * (2 + hash_cost)
+            );
+        }
+    }
+
+    #[test]
+    fn set_membership_test() {
+        let set = [3u64, 5, 8, 13];
+        let alloc = |cs: ConstraintSystemRef<BnFq>, values: &[u64]| {
+            values
+                .iter()
+                .map(|v| FpVar::new_input(cs.clone(), || Ok(BnFq::from(*v))).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // Head, tail, absent and a set of size 1.
+        for &(element, set, expected) in &[
+            (3u64, &set[..], true),
+            (13, &set[..], true),
+            (7, &set[..], false),
+            (5, &set[1..2], true),
+            (3, &set[1..2], false),
+        ] {
+            let cs = ConstraintSystem::<BnFq>::new_ref();
+            let element_var = FpVar::new_witness(cs.clone(), || Ok(BnFq::from(element))).unwrap();
+            let set_var = alloc(cs.clone(), set);
+            let is_member = SetMembershipGadget::is_member(&element_var, &set_var).unwrap();
+            assert_eq!(is_member.value().unwrap(), expected);
+            assert!(cs.is_satisfied().unwrap());
+
+            let before = cs.num_constraints();
+            SetMembershipGadget::enforce_member(&element_var, &set_var).unwrap();
+            assert_eq!(cs.num_constraints() - before, set.len());
+            assert_eq!(cs.is_satisfied().unwrap(), expected);
+
+            // With a selector pointing at the element, or at the head when
+            // the element is absent.
+            let cs = ConstraintSystem::<BnFq>::new_ref();
+            let element_var = FpVar::new_witness(cs.clone(), || Ok(BnFq::from(element))).unwrap();
+            let position = set.iter().position(|v| *v == element).unwrap_or(0);
+            let selector = (0..set.len())
+                .map(|i| Boolean::new_witness(cs.clone(), || Ok(i == position)).unwrap())
+                .collect::<Vec<_>>();
+            let set_var = set
+                .iter()
+                .map(|v| FpVar::new_constant(cs.clone(), BnFq::from(*v)).unwrap())
+                .collect::<Vec<_>>();
+            SetMembershipGadget::enforce_member_with_selector(&element_var, &set_var, &selector)
+                .unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), expected);
+        }
+
+        // Selecting two elements at once is rejected.
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        let element_var = FpVar::new_witness(cs.clone(), || Ok(BnFq::from(8u64))).unwrap();
+        let set_var = alloc(cs.clone(), &[8, 0]);
+        let selector = (0..2)
+            .map(|_| Boolean::new_witness(cs.clone(), || Ok(true)).unwrap())
+            .collect::<Vec<_>>();
+        SetMembershipGadget::enforce_member_with_selector(&element_var, &set_var, &selector)
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        let element_var = FpVar::new_witness(cs, || Ok(BnFq::from(8u64))).unwrap();
+        assert!(SetMembershipGadget::enforce_member(&element_var, &[]).is_err());
+        assert!(!SetMembershipGadget::is_member(&element_var, &[])
+            .unwrap()
+            .value()
+            .unwrap());
+    }
+
+    #[test]
+    fn check_membership_batch_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = (1..6u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let tree =
+            MerkleTree::<TaggedPoseidonParams>::new(crh_parameters.clone(), &leaves).unwrap();
+        let proofs = [1, 4]
+            .iter()
+            .map(|&i| tree.generate_proof(i, &leaves[i as usize]).unwrap())
+            .collect::<Vec<_>>();
+
+        // Returns whether the circuit is satisfied and its number of
+        // constraints, checking the leaves at `indices` against the proofs
+        // for leaves 1 and 4.
+        let synthesize = |indices: [usize; 2], batch: bool| {
+            let cs = ConstraintSystem::<BnFq>::new_ref();
+            let root =
+                FpVar::new_input(ark_relations::ns!(cs, "root"), || Ok(tree.root())).unwrap();
+            let items = indices
+                .iter()
+                .zip(&proofs)
+                .map(|(&i, proof)| {
+                    let leaf_var =
+                        UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaves[i]).unwrap();
+                    let path_var = PathVar::<_, PoseidonHG, _>::new_witness(
+                        ark_relations::ns!(cs, "path"),
+                        || Ok(proof),
+                    )
+                    .unwrap();
+                    (leaf_var, path_var)
+                })
+                .collect::<Vec<_>>();
+            // Constants cost no constraints, so both variants share one
+            // allocation of the parameters.
+            let params_var = PoseidonParametersVar::new_constant(
+                ark_relations::ns!(cs, "params"),
+                &crh_parameters,
+            )
+            .unwrap();
+            let before = cs.num_constraints();
+            if batch {
+                let (leaf_vars, path_vars): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+                let items = leaf_vars
+                    .iter()
+                    .map(Vec::as_slice)
+                    .zip(path_vars)
+                    .collect::<Vec<_>>();
+                PathVar::check_membership_batch(&params_var, &root, &items).unwrap();
+            } else {
+                for (leaf_var, path_var) in &items {
+                    path_var
+                        .check_membership(&params_var, &root, leaf_var.as_slice())
+                        .unwrap()
+                        .enforce_equal(&Boolean::TRUE)
+                        .unwrap();
+                }
+            }
+            (cs.is_satisfied().unwrap(), cs.num_constraints() - before)
+        };
+
+        // The paths are folded independently either way; the batch only
+        // skips the `Boolean` of each equality check, three constraints per
+        // path.
+        let (satisfied, batch_cost) = synthesize([1, 4], true);
+        assert!(satisfied);
+        let (satisfied, independent_cost) = synthesize([1, 4], false);
+        assert!(satisfied);
+        assert_eq!(independent_cost - batch_cost, 2 * 3);
+
+        // Only one of the two paths is wrong.
+        assert!(!synthesize([1, 3], true).0);
+        assert!(!synthesize([2, 4], true).0);
+    }
+
+    #[test]
+    fn update_proof_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let old_leaves = (1..6u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let mut new_leaves = old_leaves.clone();
+        new_leaves[2] = [42u8; 30];
+        let old_tree =
+            MerkleTree::<TaggedPoseidonParams>::new(crh_parameters.clone(), &old_leaves).unwrap();
+        let new_tree =
+            MerkleTree::<TaggedPoseidonParams>::new(crh_parameters.clone(), &new_leaves).unwrap();
+        let proof = old_tree.generate_proof(2, &old_leaves[2]).unwrap();
+
+        // Returns whether the update of leaf 2 is accepted when the index
+        // bits are those of `index` and the new root is `new_root`.
+        let synthesize = |index: u64, new_root: BnFq| {
+            let cs = ConstraintSystem::<BnFq>::new_ref();
+            let params_var = PoseidonParametersVar::new_constant(
+                ark_relations::ns!(cs, "params"),
+                &crh_parameters,
+            )
+            .unwrap();
+            let old_root =
+                FpVar::new_input(ark_relations::ns!(cs, "old_root"), || Ok(old_tree.root()))
+                    .unwrap();
+            let new_root =
+                FpVar::new_input(ark_relations::ns!(cs, "new_root"), || Ok(new_root)).unwrap();
+            let old_leaf =
+                UInt8::new_witness_vec(ark_relations::ns!(cs, "old_leaf"), &old_leaves[2]).unwrap();
+            let new_leaf =
+                UInt8::new_witness_vec(ark_relations::ns!(cs, "new_leaf"), &new_leaves[2]).unwrap();
+            let path_var =
+                PathVar::<_, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || {
+                    Ok(&proof)
+                })
+                .unwrap();
+            let is_right = (0..TaggedPoseidonParams::HEIGHT - 1)
+                .map(|level| {
+                    Boolean::new_witness(ark_relations::ns!(cs, "is_right"), || {
+                        Ok((index >> level) & 1 == 1)
+                    })
+                    .unwrap()
+                })
+                .collect();
+            let update = UpdateProofVar::<TaggedPoseidonParams, PoseidonHG, _>::new(
+                path_var.siblings,
+                is_right,
+            )
+            .unwrap();
+            update
+                .enforce_update(
+                    &params_var,
+                    &old_root,
+                    &new_root,
+                    old_leaf.as_slice(),
+                    new_leaf.as_slice(),
+                )
+                .unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        assert!(synthesize(2, new_tree.root()));
+        // The siblings of leaf 2 reused for another index.
+        assert!(!synthesize(3, new_tree.root()));
+        // The new root is stale.
+        assert!(!synthesize(2, old_tree.root()));
+
+        assert!(
+            UpdateProofVar::<TaggedPoseidonParams, PoseidonHG, BnFq>::new(
+                vec![FpVar::zero(); TaggedPoseidonParams::HEIGHT - 1],
+                vec![Boolean::FALSE; TaggedPoseidonParams::HEIGHT - 2],
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn leaf_digest_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = (1..6u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let tree =
+            MerkleTree::<TaggedPoseidonParams>::new(crh_parameters.clone(), &leaves).unwrap();
+        let proof = tree.generate_proof(3, &leaves[3]).unwrap();
+
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        let params_var =
+            PoseidonParametersVar::new_constant(ark_relations::ns!(cs, "params"), &crh_parameters)
+                .unwrap();
+        let root = FpVar::new_input(ark_relations::ns!(cs, "root"), || Ok(tree.root())).unwrap();
+        let leaf_var = UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaves[3]).unwrap();
+        let path_var =
+            PathVar::<_, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || Ok(&proof))
+                .unwrap();
+
+        // The leaf commitment, computed as the native tree hashes leaves.
+        let leaf_input = domain_separated_input::<TaggedPoseidonParams, BnFq>(
+            leaf_var.clone(),
+            <TaggedPoseidonParams as Config>::DOMAIN_SEPARATION.leaf_tag(),
+        )
+        .unwrap();
+        let leaf_digest = PoseidonHG::evaluate(&params_var, &leaf_input).unwrap();
+        let before = cs.num_constraints();
+        assert!(path_var
+            .check_membership_with_leaf_digest(&params_var, &root, &leaf_digest)
+            .unwrap()
+            .value()
+            .unwrap());
+        let digest_cost = cs.num_constraints() - before;
+
+        let before = cs.num_constraints();
+        assert!(path_var
+            .check_membership(&params_var, &root, leaf_var.as_slice())
+            .unwrap()
+            .value()
+            .unwrap());
+        assert!(digest_cost < cs.num_constraints() - before);
+
+        let other_digest = FpVar::new_witness(ark_relations::ns!(cs, "other_digest"), || {
+            Ok(BnFq::from(7u64))
+        })
+        .unwrap();
+        assert!(!path_var
+            .check_membership_with_leaf_digest(&params_var, &root, &other_digest)
+            .unwrap()
+            .value()
+            .unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn height_mismatch_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaves = (1..6u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let short_tree =
+            MerkleTree::<PoseidonHeight<7>>::new(crh_parameters.clone(), &leaves).unwrap();
+        let tree = MerkleTree::<PoseidonHeight<8>>::new(crh_parameters.clone(), &leaves).unwrap();
+        let short_proof = short_tree.generate_proof(1, &leaves[1]).unwrap();
+        let proof = tree.generate_proof(1, &leaves[1]).unwrap();
+        // A height-7 path presented as a height-8 one.
+        let mismatched = Path::<PoseidonHeight<8>> {
+            path: short_proof.path,
+            leaf_index: short_proof.leaf_index,
+        };
+
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        assert!(matches!(
+            PathVar::<_, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || {
+                Ok(&mismatched)
+            }),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+
+        // The constraint system is still usable afterwards.
+        let params_var =
+            PoseidonParametersVar::new_constant(ark_relations::ns!(cs, "params"), &crh_parameters)
+                .unwrap();
+        let root = FpVar::new_input(ark_relations::ns!(cs, "root"), || Ok(tree.root())).unwrap();
+        let leaf_var = UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaves[1]).unwrap();
+        let path_var =
+            PathVar::<_, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || Ok(&proof))
+                .unwrap();
+        path_var
+            .check_membership(&params_var, &root, leaf_var.as_slice())
+            .unwrap()
+            .enforce_equal(&Boolean::TRUE)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // Without a witness, the configured number of levels is allocated.
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        cs.set_mode(ark_relations::r1cs::SynthesisMode::Setup);
+        let path_var = PathVar::<PoseidonHeight<8>, PoseidonHG, _>::new_witness(
+            ark_relations::ns!(cs, "path"),
+            || Err::<Path<PoseidonHeight<8>>, _>(SynthesisError::AssignmentMissing),
+        )
+        .unwrap();
+        assert_eq!(path_var.siblings.len(), 7);
+        assert_eq!(path_var.is_right.len(), 7);
+    }
+
+    #[test]
+    fn constraint_report_test() {
+        let crh_parameters = PoseidonParameters::new(get_rounds_3::<BnFq>(), get_mds_3::<BnFq>());
+        let leaf = [1u8; 30];
+        let report = merkle_constraint_report::<TaggedPoseidonParams, PoseidonHG, BnFq>(
+            &crh_parameters,
+            &leaf,
+            &[20, 26, 30, 32],
+        )
+        .unwrap();
+        // Regression values: update them deliberately when an optimization
+        // changes the cost of the Merkle or Poseidon gadgets.
+        assert_eq!(
+            report,
+            vec![(20, 29995), (26, 39391), (30, 45655), (32, 48787)]
+        );
+
+        // The report agrees with a membership check through `PathVar`.
+        let leaves = vec![leaf];
+        let tree = MerkleTree::<PoseidonHeight<20>>::new(crh_parameters.clone(), &leaves).unwrap();
+        let proof = tree.generate_proof(0, &leaf).unwrap();
+        let cs = ConstraintSystem::<BnFq>::new_ref();
+        let params_var =
+            PoseidonParametersVar::new_constant(ark_relations::ns!(cs, "params"), &crh_parameters)
+                .unwrap();
+        let root = FpVar::new_input(ark_relations::ns!(cs, "root"), || Ok(tree.root())).unwrap();
+        let leaf_var = UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaf).unwrap();
+        let path_var =
+            PathVar::<_, PoseidonHG, _>::new_witness(ark_relations::ns!(cs, "path"), || Ok(&proof))
+                .unwrap();
+        let before = cs.num_constraints();
+        path_var
+            .check_membership(&params_var, &root, leaf_var.as_slice())
+            .unwrap()
+            .enforce_equal(&Boolean::TRUE)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(report[0], (20, cs.num_constraints() - before));
+
+        assert!(
+            merkle_constraint_report::<TaggedPoseidonParams, PoseidonHG, BnFq>(
+                &crh_parameters,
+                &leaf,
+                &[0]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn fixed_height_path_var_test() {
+        let mut rng = ark_std::test_rng();
+        let leaves = (0..4u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let crh_parameters = H::setup(&mut rng).unwrap();
+        let tree = JubJubMerkleTree::new(crh_parameters, &leaves).unwrap();
+        let proof = tree.generate_proof(2, &leaves[2]).unwrap();
+
+        // Without a value, the same number of levels is allocated.
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        PathVar::<_, HG, _>::new_witness(cs.clone(), || Ok(&proof)).unwrap();
+        let witnesses = cs.num_witness_variables();
+        let setup_cs = ConstraintSystem::<Fq>::new_ref();
+        setup_cs.set_mode(ark_relations::r1cs::SynthesisMode::Setup);
+        let path_var =
+            PathVar::<JubJubMerkleTreeParams, HG, _>::new_witness(setup_cs.clone(), || {
+                Err::<Path<JubJubMerkleTreeParams>, _>(SynthesisError::AssignmentMissing)
+            })
+            .unwrap();
+        assert_eq!(path_var.siblings.len(), JubJubMerkleTreeParams::HEIGHT - 1);
+        assert_eq!(setup_cs.num_witness_variables(), witnesses);
+
+        // A path with a missing level cannot be allocated.
+        let mut short = proof;
+        short.path.pop();
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        assert_eq!(
+            PathVar::<_, HG, _>::new_witness(cs, || Ok(&short)).err(),
+            Some(SynthesisError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn path_var_allocation_modes_test() {
+        use ark_relations::r1cs::SynthesisMode;
+
+        let mut rng = ark_std::test_rng();
+        let leaves = (0..4u8).map(|i| [i; 30]).collect::<Vec<_>>();
+        let crh_parameters = H::setup(&mut rng).unwrap();
+        let tree = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+        let proof = tree.generate_proof(3, &leaves[3]).unwrap();
+
+        // Synthesizes a membership check, or in setup mode the same circuit
+        // without any values, and returns the number of constraints.
+        let synthesize = |mode: SynthesisMode| {
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            cs.set_mode(mode);
+            let has_values = !cs.is_in_setup_mode();
+            let params_var = <HG as FixedLengthCRHGadget<H, Fq>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "params"),
+                &crh_parameters,
+            )
+            .unwrap();
+            let root = <HG as FixedLengthCRHGadget<H, Fq>>::OutputVar::new_input(
+                ark_relations::ns!(cs, "root"),
+                || {
+                    if has_values {
+                        Ok(tree.root())
+                    } else {
+                        Err(SynthesisError::AssignmentMissing)
+                    }
+                },
+            )
+            .unwrap();
+            let leaf_value = leaves[3].iter().map(|b| Some(*b).filter(|_| has_values));
+            let leaf_var = UInt8::new_witness_vec(
+                ark_relations::ns!(cs, "leaf"),
+                &leaf_value.collect::<Vec<_>>(),
+            )
+            .unwrap();
+            let path_var = PathVar::<_, HG, _>::new_witness(ark_relations::ns!(cs, "path"), || {
+                if has_values {
+                    Ok(&proof)
+                } else {
+                    Err(SynthesisError::AssignmentMissing)
+                }
+            })
+            .unwrap();
+            path_var
+                .check_membership(&params_var, &root, leaf_var.as_slice())
+                .unwrap()
+                .enforce_equal(&Boolean::TRUE)
+                .unwrap();
+            if has_values {
+                assert!(cs.is_satisfied().unwrap());
+            }
+            cs.num_constraints()
+        };
+        let num_constraints = synthesize(SynthesisMode::Prove {
+            construct_matrices: true,
+        });
+        assert_eq!(synthesize(SynthesisMode::Setup), num_constraints);
+
+        // As a public input, the coordinates of the siblings and the index
+        // bits become instance variables. Nothing is allocated for constants.
+        let levels = JubJubMerkleTreeParams::HEIGHT - 1;
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        PathVar::<_, HG, _>::new_input(cs.clone(), || Ok(&proof)).unwrap();
+        assert_eq!(cs.num_instance_variables(), 1 + levels * 3);
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        PathVar::<_, HG, _>::new_constant(cs.clone(), &proof).unwrap();
+        assert_eq!(cs.num_instance_variables() + cs.num_witness_variables(), 1);
+
+        // A blank path has the same shape as a real one, and can be gated off.
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        PathVar::<_, HG, _>::new_witness(cs.clone(), || Ok(&proof)).unwrap();
+        let witnesses = cs.num_witness_variables();
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let blank = PathVar::<JubJubMerkleTreeParams, HG, _>::blank(cs.clone()).unwrap();
+        assert_eq!(cs.num_witness_variables(), witnesses);
+        let params_var = <HG as FixedLengthCRHGadget<H, Fq>>::ParametersVar::new_constant(
+            ark_relations::ns!(cs, "params"),
+            &crh_parameters,
+        )
+        .unwrap();
+        let root = <HG as FixedLengthCRHGadget<H, Fq>>::OutputVar::new_input(
+            ark_relations::ns!(cs, "root"),
+            || Ok(tree.root()),
+        )
+        .unwrap();
+        let leaf_var = UInt8::new_witness_vec(ark_relations::ns!(cs, "leaf"), &leaves[3]).unwrap();
+        blank
+            .conditionally_check_membership(
+                &Boolean::FALSE,
+                &params_var,
+                &root,
+                leaf_var.as_slice(),
+            )
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn good_root_test() {
         let mut leaves = Vec::new();
@@ -0,0 +1,499 @@
+//! A Merkle Mountain Range: an append-only list of perfect binary trees
+//! ("mountains") whose roots ("peaks") are bagged into a single root.
+//!
+//! Nodes are numbered from 0 in the order in which they are appended, i.e.
+//! in post-order within each mountain. Appending a leaf merges equally high
+//! mountains, so a range with `n` leaves has `2n - popcount(n)` nodes and
+//! one peak per bit set in `n`.
+//!
+//! Every CRH input carries a tag in its capacity, as in the tagged domain
+//! separation of `MerkleTree`, so that the four kinds of hashes cannot be
+//! confused: leaves are `H(leaf, 0)`, nodes `H(left || right, 1)`,
+//! the peaks are bagged from right to left into
+//! `B = H(p_0 || H(p_1 || ... H(p_{k-2} || p_{k-1}, 2) ..., 2), 2)`, and the
+//! root `H(LE64(size) || B, 3)` commits to the number of nodes.
+//!
+//! A proof is bound to the size of the range it was created for, and stays
+//! valid against the root of that size after further appends. Since the root
+//! commits to the size, a proof claiming another size does not verify.
+use crate::{crh::FixedLengthCRH, Vec};
+use ark_ff::{bytes::ToBytes, to_bytes};
+use ark_std::{fmt, marker::PhantomData};
+
+use super::{
+    fill_input, hash_inner_node, hash_leaf, DomainSeparation, Error, HashConfig, LeafEncoding,
+};
+
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+const BAG_TAG: u8 = 2;
+const ROOT_TAG: u8 = 3;
+
+/// Hashes leaves and nodes like a `MerkleTree` over `H` with tagged domain
+/// separation.
+struct RangeConfig<H: FixedLengthCRH>(PhantomData<H>);
+
+impl<H: FixedLengthCRH> HashConfig for RangeConfig<H> {
+    const DOMAIN_SEPARATION: DomainSeparation = DomainSeparation::Tagged {
+        leaf: LEAF_TAG,
+        node: NODE_TAG,
+    };
+    const LEAF_ENCODING: LeafEncoding = LeafEncoding::Direct;
+    type H = H;
+}
+
+pub struct MerkleMountainRange<H: FixedLengthCRH> {
+    parameters: H::Parameters,
+    nodes: Vec<H::Output>,
+    num_leaves: u64,
+}
+
+/// Proves that a leaf is part of a range of `size` nodes.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "H: FixedLengthCRH"),
+    PartialEq(bound = "H: FixedLengthCRH"),
+    Debug(bound = "H: FixedLengthCRH, H::Output: fmt::Debug")
+)]
+pub struct MMRProof<H: FixedLengthCRH> {
+    /// The number of nodes of the range the proof was created for.
+    pub size: u64,
+    /// The siblings from the leaf up to the peak of its mountain.
+    pub siblings: Vec<H::Output>,
+    /// The peaks of all other mountains, from left to right.
+    pub peaks: Vec<H::Output>,
+}
+
+impl<H: FixedLengthCRH> MerkleMountainRange<H> {
+    pub fn new(parameters: H::Parameters) -> Self {
+        Self {
+            parameters,
+            nodes: Vec::new(),
+            num_leaves: 0,
+        }
+    }
+
+    /// The number of nodes, which is also the position of the next leaf.
+    pub fn size(&self) -> u64 {
+        self.nodes.len() as u64
+    }
+
+    pub fn num_leaves(&self) -> u64 {
+        self.num_leaves
+    }
+
+    /// Appends a leaf and returns its position.
+    pub fn append<L: ToBytes>(&mut self, leaf: &L) -> Result<u64, crate::Error> {
+        let mut buffer = [0u8; 128];
+        let position = self.size();
+        let mut current = hash_leaf::<RangeConfig<H>, _>(&self.parameters, leaf, &mut buffer)?;
+        self.nodes.push(current.clone());
+
+        // Every trailing one of the old leaf count is a mountain of the
+        // same height as the one being built, so they merge.
+        for height in 0..self.num_leaves.trailing_ones() {
+            let left_position = self.nodes.len() - (1 << (height + 1));
+            current = hash_inner_node::<RangeConfig<H>>(
+                &self.parameters,
+                &self.nodes[left_position],
+                &current,
+                &mut buffer,
+            )?;
+            self.nodes.push(current.clone());
+        }
+        self.num_leaves += 1;
+        Ok(position)
+    }
+
+    /// The positions of the peaks, from left to right.
+    pub fn peaks(&self) -> Vec<u64> {
+        let mut offset = 0;
+        mountain_heights(self.size())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|height| {
+                offset += mountain_size(height);
+                offset - 1
+            })
+            .collect()
+    }
+
+    /// Bags the peaks into the root of the range, or returns `None` if the
+    /// range is empty.
+    pub fn root(&self) -> Result<Option<H::Output>, crate::Error> {
+        let peaks = self
+            .peaks()
+            .into_iter()
+            .map(|position| self.nodes[position as usize].clone())
+            .collect::<Vec<_>>();
+        compute_root::<H>(&self.parameters, self.size(), &peaks)
+    }
+
+    /// Generates a proof for the leaf at `position` against the current root.
+    pub fn gen_proof(&self, position: u64) -> Result<MMRProof<H>, crate::Error> {
+        let size = self.size();
+        let (mountain, path) =
+            locate_leaf(position, size).ok_or(Error::IncorrectLeafIndex(position))?;
+        let siblings = path
+            .iter()
+            .map(|&(_, sibling_position)| self.nodes[sibling_position as usize].clone())
+            .collect();
+        let peaks = self
+            .peaks()
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| *i != mountain)
+            .map(|(_, position)| self.nodes[position as usize].clone())
+            .collect();
+        Ok(MMRProof {
+            size,
+            siblings,
+            peaks,
+        })
+    }
+}
+
+/// Verifies that `leaf` is at `position` in the range with root `root`.
+///
+/// Returns an error if `position` is not a leaf of a range of `proof.size`
+/// nodes, or if the proof has the wrong number of siblings or peaks.
+pub fn verify<H: FixedLengthCRH, L: ToBytes>(
+    parameters: &H::Parameters,
+    root: &H::Output,
+    position: u64,
+    leaf: &L,
+    proof: &MMRProof<H>,
+) -> Result<bool, crate::Error> {
+    let heights = mountain_heights(proof.size).ok_or(Error::IncorrectLeafIndex(position))?;
+    let (mountain, path) =
+        locate_leaf(position, proof.size).ok_or(Error::IncorrectLeafIndex(position))?;
+    if proof.siblings.len() != path.len() {
+        return Err(Error::IncorrectPathLength(proof.siblings.len()).into());
+    }
+    if proof.peaks.len() + 1 != heights.len() {
+        return Err(Error::IncorrectPathLength(proof.peaks.len()).into());
+    }
+
+    let mut buffer = [0u8; 128];
+    let mut current = hash_leaf::<RangeConfig<H>, _>(parameters, leaf, &mut buffer)?;
+    for (&(is_right, _), sibling) in path.iter().zip(&proof.siblings) {
+        current = if is_right {
+            hash_inner_node::<RangeConfig<H>>(parameters, sibling, &current, &mut buffer)?
+        } else {
+            hash_inner_node::<RangeConfig<H>>(parameters, &current, sibling, &mut buffer)?
+        };
+    }
+
+    let mut peaks = proof.peaks.clone();
+    peaks.insert(mountain, current);
+    Ok(compute_root::<H>(parameters, proof.size, &peaks)?.as_ref() == Some(root))
+}
+
+/// Bags `peaks` from right to left and hashes the result with `size`.
+fn compute_root<H: FixedLengthCRH>(
+    parameters: &H::Parameters,
+    size: u64,
+    peaks: &[H::Output],
+) -> Result<Option<H::Output>, crate::Error> {
+    let mut peaks = peaks.iter().rev();
+    let mut bag = match peaks.next() {
+        Some(peak) => peak.clone(),
+        None => return Ok(None),
+    };
+    for peak in peaks {
+        bag = hash_tagged::<H>(parameters, &to_bytes![peak, bag]?, BAG_TAG)?;
+    }
+    Ok(Some(hash_tagged::<H>(
+        parameters,
+        &to_bytes![size, bag]?,
+        ROOT_TAG,
+    )?))
+}
+
+fn hash_tagged<H: FixedLengthCRH>(
+    parameters: &H::Parameters,
+    payload: &[u8],
+    tag: u8,
+) -> Result<H::Output, crate::Error> {
+    let mut buffer = vec![0u8; H::INPUT_SIZE_BITS / 8];
+    let input = fill_input::<RangeConfig<H>>(&mut buffer, payload, Some(tag))?;
+    H::evaluate(parameters, input)
+}
+
+/// The number of nodes of a mountain of the given height.
+#[inline]
+fn mountain_size(height: u32) -> u64 {
+    (1 << (height + 1)) - 1
+}
+
+/// Splits a range of `size` nodes into the heights of its mountains, from
+/// left to right. Returns `None` if no number of leaves produces `size`.
+fn mountain_heights(size: u64) -> Option<Vec<u32>> {
+    let mut heights = Vec::new();
+    let mut remaining = size;
+    for height in (0..63).rev() {
+        if mountain_size(height) <= remaining {
+            heights.push(height);
+            remaining -= mountain_size(height);
+        }
+    }
+    if remaining == 0 {
+        Some(heights)
+    } else {
+        None
+    }
+}
+
+/// Finds the mountain holding the leaf at `position` in a range of `size`
+/// nodes, and the path from the leaf to its peak as `(is_right_child,
+/// sibling_position)` pairs. Returns `None` if `position` is not a leaf.
+fn locate_leaf(position: u64, size: u64) -> Option<(usize, Vec<(bool, u64)>)> {
+    let mut offset = 0;
+    for (mountain, height) in mountain_heights(size)?.into_iter().enumerate() {
+        if position >= offset + mountain_size(height) {
+            offset += mountain_size(height);
+            continue;
+        }
+
+        // Descend from the peak, keeping track of the subtree that holds
+        // `position`. In post-order, the left subtree comes first, then the
+        // right subtree, then the subtree root.
+        let mut path = Vec::new();
+        let mut height = height;
+        while height > 0 {
+            let child_size = mountain_size(height - 1);
+            let left_root = offset + child_size - 1;
+            let right_root = left_root + child_size;
+            if position <= left_root {
+                path.push((false, right_root));
+            } else if position <= right_root {
+                path.push((true, left_root));
+                offset += child_size;
+            } else {
+                // `position` is the root of the current subtree.
+                return None;
+            }
+            height -= 1;
+        }
+        path.reverse();
+        return Some((mountain, path));
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::{
+        blake2s::{self, InputSize},
+        poseidon::{
+            sbox::PoseidonSbox,
+            test_data::{get_mds_3, get_rounds_3},
+            PoseidonParameters, Rounds, CRH,
+        },
+    };
+    use ark_ed_on_bn254::Fq;
+
+    #[derive(Default, Clone)]
+    struct PoseidonRounds3;
+
+    impl Rounds for PoseidonRounds3 {
+        const WIDTH: usize = 3;
+        const PARTIAL_ROUNDS: usize = 57;
+        const FULL_ROUNDS: usize = 8;
+        const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+    }
+
+    type PoseidonCRH3 = CRH<Fq, PoseidonRounds3>;
+
+    /// The tag byte and two 32-byte children.
+    #[derive(Clone)]
+    struct SixtyFiveBytes;
+    impl InputSize for SixtyFiveBytes {
+        const BYTES: usize = 65;
+    }
+
+    type Blake2sCRH = blake2s::CRH<SixtyFiveBytes>;
+
+    fn leaf(i: u64) -> [u8; 8] {
+        i.to_le_bytes()
+    }
+
+    #[test]
+    fn mmr_test() {
+        let parameters = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let mut mmr = MerkleMountainRange::<PoseidonCRH3>::new(parameters.clone());
+        assert_eq!(mmr.root().unwrap(), None);
+
+        // Sizes, leaf positions and peak positions follow the usual
+        // zero-based MMR numbering.
+        let expected_positions = [0, 1, 3, 4, 7, 8, 10, 11, 15, 16, 18];
+        let expected = [
+            (1, vec![0]),
+            (2, vec![2]),
+            (3, vec![2, 3]),
+            (7, vec![6, 9, 10]),
+            (8, vec![14]),
+            (11, vec![14, 17, 18]),
+        ];
+        let expected_sizes = [1, 3, 4, 11, 15, 19];
+
+        let mut positions = Vec::new();
+        let mut old_proofs = Vec::new();
+        for (&(num_leaves, ref peaks), &size) in expected.iter().zip(&expected_sizes) {
+            while mmr.num_leaves() < num_leaves {
+                positions.push(mmr.append(&leaf(mmr.num_leaves())).unwrap());
+            }
+            assert_eq!(mmr.size(), size);
+            assert_eq!(&mmr.peaks(), peaks);
+
+            let root = mmr.root().unwrap().unwrap();
+            for (i, &position) in positions.iter().enumerate() {
+                let proof = mmr.gen_proof(position).unwrap();
+                assert!(verify(&parameters, &root, position, &leaf(i as u64), &proof).unwrap());
+                assert!(!verify(&parameters, &root, position, &leaf(100), &proof).unwrap());
+            }
+            let last = *positions.last().unwrap();
+            old_proofs.push((root, last, mmr.gen_proof(last).unwrap()));
+        }
+        assert_eq!(positions, expected_positions);
+
+        // Proofs stay valid against the root of the size they were made for.
+        let current_root = mmr.root().unwrap().unwrap();
+        for (i, (root, position, proof)) in old_proofs.iter().enumerate() {
+            let leaf_index = expected[i].0 - 1;
+            assert!(verify(&parameters, root, *position, &leaf(leaf_index), proof).unwrap());
+            if proof.size != mmr.size() {
+                assert!(!verify(
+                    &parameters,
+                    &current_root,
+                    *position,
+                    &leaf(leaf_index),
+                    proof
+                )
+                .unwrap());
+            }
+        }
+
+        // Inner nodes and positions beyond the range are not leaves.
+        assert!(mmr.gen_proof(2).is_err());
+        assert!(mmr.gen_proof(14).is_err());
+        assert!(mmr.gen_proof(19).is_err());
+    }
+
+    #[test]
+    fn mmr_single_mountain_test() {
+        let parameters = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let mut mmr = MerkleMountainRange::<PoseidonCRH3>::new(parameters.clone());
+        for i in 0..4 {
+            mmr.append(&leaf(i)).unwrap();
+        }
+
+        // With a single mountain, the root is the root of a 4-leaf tree.
+        let mut buffer = [0u8; 128];
+        let hashes = (0..4)
+            .map(|i| hash_leaf::<RangeConfig<PoseidonCRH3>, _>(&parameters, &leaf(i), &mut buffer))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let node = |l: &Fq, r: &Fq| {
+            hash_inner_node::<RangeConfig<PoseidonCRH3>>(&parameters, l, r, &mut [0u8; 128])
+                .unwrap()
+        };
+        let peak = node(&node(&hashes[0], &hashes[1]), &node(&hashes[2], &hashes[3]));
+        let expected =
+            hash_tagged::<PoseidonCRH3>(&parameters, &to_bytes![7u64, peak].unwrap(), ROOT_TAG)
+                .unwrap();
+        assert_eq!(mmr.root().unwrap(), Some(expected));
+
+        // A proof with a missing sibling is malformed.
+        let mut proof = mmr.gen_proof(3).unwrap();
+        proof.siblings.pop();
+        assert!(verify(&parameters, &expected, 3, &leaf(2), &proof).is_err());
+    }
+
+    #[test]
+    fn mmr_forged_size_test() {
+        let parameters = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let mut mmr = MerkleMountainRange::<PoseidonCRH3>::new(parameters.clone());
+        for i in 0..11 {
+            mmr.append(&leaf(i)).unwrap();
+        }
+        let root = mmr.root().unwrap().unwrap();
+
+        // The root commits to the size, so no other size is accepted.
+        for position in [0, 7, 10, 15, 16, 18].iter() {
+            let proof = mmr.gen_proof(*position).unwrap();
+            for size in 1..40 {
+                if size == proof.size {
+                    continue;
+                }
+                let forged = MMRProof {
+                    size,
+                    ..proof.clone()
+                };
+                assert!(!matches!(
+                    verify(&parameters, &root, *position, &leaf(0), &forged),
+                    Ok(true)
+                ));
+            }
+        }
+
+        // A node is not the hash of a leaf made of the bytes of its
+        // children, so a proof cannot stop one level short.
+        let children = to_bytes![mmr.nodes[0], mmr.nodes[1]].unwrap();
+        let as_leaf =
+            hash_leaf::<RangeConfig<PoseidonCRH3>, _>(&parameters, &children, &mut [0u8; 128])
+                .unwrap();
+        assert_ne!(as_leaf, mmr.nodes[2]);
+    }
+
+    /// Roots of ranges over Blake2s, whose leaves are the indices as
+    /// little-endian `u64`s, computed by an independent implementation that
+    /// builds each mountain recursively from the leaves with Python's
+    /// `hashlib.blake2s`.
+    #[test]
+    fn mmr_reference_vectors_test() {
+        let expected = [
+            (
+                1,
+                1,
+                "d152bf3ec0332b8fcba19084d488ec02d15464cb6db76436d7256f27265c4df2",
+            ),
+            (
+                2,
+                3,
+                "9b3695a6eef22f68e4bae4ac9e2459f6c9f872f48cc38b230624e94c3b232158",
+            ),
+            (
+                3,
+                4,
+                "49f04ef23a77c1d76409a4b71d9d0e7cff22f662de2a197a586bf721bf7322a7",
+            ),
+            (
+                7,
+                11,
+                "88333af093927c54847ac3c36d2378997ab3e6a30c9f0c69076951109d309b72",
+            ),
+            (
+                8,
+                15,
+                "338c27f8822c897fb9e566d6f18d1f46b96838453f4e5e9a1c173959e94d7f03",
+            ),
+            (
+                11,
+                19,
+                "b9fe8bbd49381a1470f8d39797542abf876815b439fd2ef865f63a0d924289ed",
+            ),
+        ];
+        let mut mmr = MerkleMountainRange::<Blake2sCRH>::new(());
+        for &(num_leaves, size, root) in expected.iter() {
+            while mmr.num_leaves() < num_leaves {
+                mmr.append(&leaf(mmr.num_leaves())).unwrap();
+            }
+            assert_eq!(mmr.size(), size);
+            let actual = mmr.root().unwrap().unwrap();
+            let hex: String = actual.iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(hex, root);
+        }
+    }
+}
@@ -1,28 +1,151 @@
-use crate::{crh::FixedLengthCRH, Vec};
-use ark_ff::bytes::ToBytes;
-use ark_std::fmt;
+use crate::{crh::FixedLengthCRH, CryptoError, Vec};
+use ark_ff::{
+    bytes::{FromBytes, ToBytes},
+    PrimeField,
+};
+use ark_std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt,
+};
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
+pub mod mmr;
 
 pub trait Config {
     const HEIGHT: usize;
+    /// How leaf hashes are separated from inner-node hashes. Defaults to
+    /// `DomainSeparation::Legacy`, which keeps existing roots valid.
+    const DOMAIN_SEPARATION: DomainSeparation = DomainSeparation::Legacy;
+    /// How the bytes of a leaf are turned into CRH input. Defaults to
+    /// `LeafEncoding::Direct`, which keeps existing roots valid.
+    const LEAF_ENCODING: LeafEncoding = LeafEncoding::Direct;
     type H: FixedLengthCRH;
 }
 
+/// How a tree hashes its leaves and inner nodes, without its shape. Every
+/// [`Config`] is one; [`mmr::MerkleMountainRange`], which has no fixed
+/// height, only needs this.
+pub(crate) trait HashConfig {
+    const DOMAIN_SEPARATION: DomainSeparation;
+    const LEAF_ENCODING: LeafEncoding;
+    type H: FixedLengthCRH;
+}
+
+type HashParameters<P> = <<P as HashConfig>::H as FixedLengthCRH>::Parameters;
+type HashDigest<P> = <<P as HashConfig>::H as FixedLengthCRH>::Output;
+
+impl<P: Config> HashConfig for P {
+    const DOMAIN_SEPARATION: DomainSeparation = <P as Config>::DOMAIN_SEPARATION;
+    const LEAF_ENCODING: LeafEncoding = <P as Config>::LEAF_ENCODING;
+    type H = <P as Config>::H;
+}
+
+/// Turns the bytes of a leaf into the payload of its leaf hash. The payload
+/// is then hashed according to the `DomainSeparation` of the tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeafEncoding {
+    /// The leaf bytes are the payload, so they have to fit the CRH input.
+    Direct,
+    /// Leaves of any length are first chained through the CRH, and the bytes
+    /// of the resulting digest are the payload. With `n` the CRH input size
+    /// and `d` the size of a digest in bytes (`8 <= d < n`), a leaf of `len`
+    /// bytes is split into `k = max(1, ceil(len / (n - d)))` chunks of
+    /// `n - d` bytes, the last one padded with zeros, and
+    ///
+    /// ```text
+    /// s_1 = H(LE64(len) || 0^(d - 8) || c_1)
+    /// s_i = H(bytes(s_{i-1}) || c_i)          for 1 < i <= k
+    /// ```
+    ///
+    /// where `LE64` is the little-endian encoding of a `u64` and `bytes` is
+    /// `ToBytes`. The payload is `bytes(s_k)`.
+    Chained,
+}
+
+/// Distinguishes the hash of a leaf from the hash of two children, so that
+/// an inner node can never be passed off as a leaf (or vice versa).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DomainSeparation {
+    /// Leaves and inner nodes are hashed in the same way.
+    Legacy,
+    /// The capacity of every CRH input, its first
+    /// [`FixedLengthCRH::CAPACITY_BYTES`] bytes, holds `leaf` when hashing a
+    /// leaf and `node` when hashing two children, as a little-endian
+    /// integer. The payload follows it, so for Poseidon the state is
+    /// `[tag, left, right]`. Payloads longer than the rest of the input are
+    /// rejected.
+    Tagged { leaf: u8, node: u8 },
+}
+
+impl DomainSeparation {
+    /// The tag written into the CRH input of a leaf hash.
+    pub fn leaf_tag(&self) -> Option<u8> {
+        match self {
+            DomainSeparation::Legacy => None,
+            DomainSeparation::Tagged { leaf, .. } => Some(*leaf),
+        }
+    }
+
+    /// The tag written into the CRH input of an inner-node hash.
+    pub fn node_tag(&self) -> Option<u8> {
+        match self {
+            DomainSeparation::Legacy => None,
+            DomainSeparation::Tagged { node, .. } => Some(*node),
+        }
+    }
+}
+
 /// Stores the hashes of a particular path (in order) from leaf to root.
 /// Our path `is_left_child()` if the boolean in `path` is true.
+///
+/// A `Path<P>` always has exactly `P::HEIGHT - 1` levels: paths are only
+/// produced by `MerkleTree` or checked on the way in by `Path::try_from`, so
+/// a path of one height cannot be used with a config of another.
 #[derive(Derivative)]
-#[derivative(
-    Clone(bound = "P: Config"),
-    Debug(bound = "P: Config, <P::H as FixedLengthCRH>::Output: fmt::Debug")
-)]
+#[derivative(Clone(bound = "P: Config"), PartialEq(bound = "P: Config"))]
 pub struct Path<P: Config> {
     pub(crate) path: Vec<(Digest<P>, Digest<P>)>,
+    /// The index of the leaf. Bit `i` is set iff the node at level `i` of
+    /// the path is a right child.
+    pub(crate) leaf_index: u64,
+}
+
+/// Formats every digest of the path as hex-encoded bytes.
+impl<P: Config> fmt::Debug for Path<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_hex = |f: &mut fmt::Formatter<'_>, digest: &Digest<P>| -> fmt::Result {
+            write!(f, "0x")?;
+            for byte in ark_ff::to_bytes![digest].map_err(|_| fmt::Error)? {
+                write!(f, "{:02x}", byte)?;
+            }
+            Ok(())
+        };
+        write!(f, "Path {{ path: [")?;
+        for (i, (left, right)) in self.path.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "(")?;
+            write_hex(f, left)?;
+            write!(f, ", ")?;
+            write_hex(f, right)?;
+            write!(f, ")")?;
+        }
+        write!(f, "], leaf_index: {} }}", self.leaf_index)
+    }
 }
 
 pub type Parameters<P> = <<P as Config>::H as FixedLengthCRH>::Parameters;
+/// A node of the tree. A root converts to public inputs through
+/// `ToConstraintField` for the CRH output: a single element for Poseidon,
+/// `[x, y]` for Pedersen over a twisted Edwards curve.
 pub type Digest<P> = <<P as Config>::H as FixedLengthCRH>::Output;
+/// A node as `(level, index, digest)`, with levels counted from the leaves
+/// as in [`MerkleTree::subtree_root`]. The level is a `u32`, so that stored
+/// nodes have the same form on every platform.
+pub type NodeUpdate<P> = (u32, u64, Digest<P>);
 
 impl<P: Config> Default for Path<P> {
     fn default() -> Self {
@@ -33,49 +156,116 @@ impl<P: Config> Default for Path<P> {
                 <P::H as FixedLengthCRH>::Output::default(),
             ));
         }
-        Self { path }
+        Self {
+            path,
+            leaf_index: 0,
+        }
     }
 }
 
 impl<P: Config> Path<P> {
+    /// The index of the leaf this path was generated for.
+    pub fn leaf_index(&self) -> u64 {
+        self.leaf_index
+    }
+
+    /// Checks that the path has the shape of a path in a tree of the given
+    /// `height`, without hashing anything: there has to be one pair of
+    /// siblings per level below the root, and the leaf index has to fit in
+    /// as many bits.
+    ///
+    /// The index bits also pick the side of the current node at each level,
+    /// but that can only be checked while hashing, so it is left to
+    /// [`verify`].
+    ///
+    /// This is always true for `height == P::HEIGHT`. Callers that carry the
+    /// height at runtime should move it into their `Config` and build paths
+    /// with `Path::try_from` instead.
+    pub fn validate_structure(&self, height: usize) -> Result<(), Error> {
+        if height == 0 || self.path.len() != height - 1 {
+            return Err(Error::IncorrectPathLength(self.path.len()));
+        }
+        if self
+            .leaf_index
+            .checked_shr(self.path.len() as u32)
+            .unwrap_or(0)
+            != 0
+        {
+            return Err(Error::IndexOutOfBounds {
+                index: self.leaf_index,
+                len: 1 << self.path.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Verifies that `leaf` is a member of the tree with root `root_hash`.
+    /// See [`verify`] for details.
     pub fn verify<L: ToBytes>(
         &self,
         parameters: &<P::H as FixedLengthCRH>::Parameters,
         root_hash: &<P::H as FixedLengthCRH>::Output,
         leaf: &L,
     ) -> Result<bool, crate::Error> {
-        if self.path.len() != (P::HEIGHT - 1) as usize {
-            return Ok(false);
-        }
-        // Check that the given leaf matches the leaf in the membership proof.
-        let mut buffer = [0u8; 128];
+        verify(parameters, root_hash, leaf, self)
+    }
+}
 
-        if !self.path.is_empty() {
-            let claimed_leaf_hash = hash_leaf::<P::H, L>(parameters, leaf, &mut buffer)?;
+/// Builds a path from a leaf index and its sibling pairs. Rejects sibling
+/// lists that do not have `P::HEIGHT - 1` levels and indices that do not fit
+/// in them.
+impl<P: Config> TryFrom<(u64, Vec<(Digest<P>, Digest<P>)>)> for Path<P> {
+    type Error = Error;
 
-            // Check if leaf is one of the bottom-most siblings.
-            if claimed_leaf_hash != self.path[0].0 && claimed_leaf_hash != self.path[0].1 {
-                return Ok(false);
-            };
+    fn try_from((leaf_index, path): (u64, Vec<(Digest<P>, Digest<P>)>)) -> Result<Self, Error> {
+        let path = Self { path, leaf_index };
+        path.validate_structure(P::HEIGHT)?;
+        Ok(path)
+    }
+}
 
-            let mut prev = claimed_leaf_hash;
-            // Check levels between leaf level and root.
-            for &(ref hash, ref sibling_hash) in &self.path {
-                // Check if the previous hash matches the correct current hash.
-                if &prev != hash && &prev != sibling_hash {
-                    return Ok(false);
-                };
-                prev = hash_inner_node::<P::H>(parameters, hash, sibling_hash, &mut buffer)?;
-            }
+impl<P: Config> From<Path<P>> for (u64, Vec<(Digest<P>, Digest<P>)>) {
+    fn from(path: Path<P>) -> Self {
+        (path.leaf_index, path.path)
+    }
+}
 
-            if root_hash != &prev {
-                return Ok(false);
-            }
-            Ok(true)
+/// Verifies that `leaf` is a member of the tree with root `root_hash`, using
+/// only the CRH parameters and the authentication `path`.
+///
+/// At level `i`, the node computed so far must be the right element of
+/// `path[i]` if bit `i` of the leaf index is set, and the left one
+/// otherwise, as in `PathVar`. A path is thus only accepted for the index it
+/// was generated for.
+///
+/// Returns an error if `path` does not have `P::HEIGHT - 1` levels or its
+/// leaf index does not fit in them, and `Ok(false)` if the path is
+/// well-formed but does not lead to `root_hash`.
+pub fn verify<P: Config, L: ToBytes>(
+    parameters: &<P::H as FixedLengthCRH>::Parameters,
+    root_hash: &<P::H as FixedLengthCRH>::Output,
+    leaf: &L,
+    path: &Path<P>,
+) -> Result<bool, crate::Error> {
+    path.validate_structure(P::HEIGHT)?;
+    if path.path.is_empty() {
+        return Ok(false);
+    }
+    let mut buffer = [0u8; 128];
+    let mut prev = hash_leaf::<P, L>(parameters, leaf, &mut buffer)?;
+    for (level, &(ref left, ref right)) in path.path.iter().enumerate() {
+        // The previous hash has to be on the side given by the index.
+        let current = if (path.leaf_index >> level) & 1 == 1 {
+            right
         } else {
-            Ok(false)
+            left
+        };
+        if &prev != current {
+            return Ok(false);
         }
+        prev = hash_inner_node::<P>(parameters, left, right, &mut buffer)?;
     }
+    Ok(root_hash == &prev)
 }
 
 pub struct MerkleTree<P: Config> {
@@ -103,11 +293,25 @@ impl<P: Config> MerkleTree<P> {
         let last_level_size = leaves.len().next_power_of_two();
         let tree_size = 2 * last_level_size - 1;
         let tree_height = tree_height(tree_size);
-        assert!(tree_height as u8 <= Self::HEIGHT);
+        if tree_height > P::HEIGHT {
+            end_timer!(new_time);
+            return Err(Error::TooManyLeaves {
+                height: P::HEIGHT,
+                count: leaves.len() as u64,
+            }
+            .into());
+        }
+
+        // Compute the hash values for each leaf.
+        let mut buffer = [0u8; 128];
+        let leaf_hashes = leaves
+            .iter()
+            .map(|leaf| hash_leaf::<P, _>(&parameters, leaf, &mut buffer))
+            .collect::<Result<Vec<_>, _>>()?;
 
         // Initialize the merkle tree.
         let mut tree = Vec::with_capacity(tree_size);
-        let empty_hash = hash_empty::<P::H>(&parameters)?;
+        let empty_hash = hash_empty::<P>(&parameters)?;
         for _ in 0..tree_size {
             tree.push(empty_hash.clone());
         }
@@ -120,16 +324,14 @@ impl<P: Config> MerkleTree<P> {
             index = left_child(index);
         }
 
-        // Compute and store the hash values for each leaf.
+        // Store the hash values for each leaf.
         let last_level_index = level_indices.pop().unwrap_or(0);
-        let mut buffer = [0u8; 128];
-        for (i, leaf) in leaves.iter().enumerate() {
-            tree[last_level_index + i] = hash_leaf::<P::H, _>(&parameters, leaf, &mut buffer)?;
+        for (i, leaf_hash) in leaf_hashes.into_iter().enumerate() {
+            tree[last_level_index + i] = leaf_hash;
         }
 
         // Compute the hash values for every node in the tree.
         let mut upper_bound = last_level_index;
-        let mut buffer = [0u8; 128];
         level_indices.reverse();
         for &start_index in &level_indices {
             // Iterate over the current level.
@@ -138,7 +340,7 @@ impl<P: Config> MerkleTree<P> {
                 let right_index = right_child(current_index);
 
                 // Compute Hash(left || right).
-                tree[current_index] = hash_inner_node::<P::H>(
+                tree[current_index] = hash_inner_node::<P>(
                     &parameters,
                     &tree[left_index],
                     &tree[right_index],
@@ -149,20 +351,14 @@ impl<P: Config> MerkleTree<P> {
         }
         // Finished computing actual tree.
         // Now, we compute the dummy nodes until we hit our HEIGHT goal.
-        let mut cur_height = tree_height;
         let mut padding_tree = Vec::new();
-        let mut cur_hash = tree[0].clone();
-        let root_hash = if cur_height < Self::HEIGHT as usize {
-            while cur_height < (Self::HEIGHT - 1) as usize {
-                cur_hash =
-                    hash_inner_node::<P::H>(&parameters, &cur_hash, &empty_hash, &mut buffer)?;
-                padding_tree.push((cur_hash.clone(), empty_hash.clone()));
-                cur_height += 1;
-            }
-            hash_inner_node::<P::H>(&parameters, &cur_hash, &empty_hash, &mut buffer)?
-        } else {
-            cur_hash
-        };
+        let root_hash = compute_padding::<P>(
+            &parameters,
+            &tree[0],
+            tree_height,
+            &empty_hash,
+            &mut padding_tree,
+        )?;
         end_timer!(new_time);
 
         Ok(MerkleTree {
@@ -173,6 +369,36 @@ impl<P: Config> MerkleTree<P> {
         })
     }
 
+    /// Builds the tree of height `P::HEIGHT` from `leaves` without holding
+    /// it: leaves are hashed as they are consumed and only the frontier, the
+    /// roots of the completed subtrees still waiting for a right sibling, is
+    /// kept, so memory stays logarithmic in the number of leaves. Returns
+    /// the root and `storage`.
+    ///
+    /// If `storage` is given, every node of the tree, including the empty
+    /// leaves that pad the last level and the nodes above the actual tree,
+    /// is added to it once, as soon as it is computed, as
+    /// `(level, index, digest)` in the format of
+    /// [`MerkleTree::export_nodes`]. [`MerkleTree::import_nodes`] rebuilds
+    /// the tree from them, with a height of one more than the base-2
+    /// logarithm of the number of leaves rounded up to a power of two.
+    /// [`root_from_leaf_iter`] computes the root alone.
+    pub fn from_leaf_iter<L, I, S>(
+        parameters: &Parameters<P>,
+        leaves: I,
+        mut storage: Option<S>,
+    ) -> Result<(Digest<P>, Option<S>), crate::Error>
+    where
+        L: ToBytes,
+        I: IntoIterator<Item = L>,
+        S: Extend<NodeUpdate<P>>,
+    {
+        let stream_time = start_timer!(|| "MerkleTree::FromLeafIter");
+        let root = stream_tree::<P, _, _, _>(parameters, leaves, storage.as_mut());
+        end_timer!(stream_time);
+        Ok((root?, storage))
+    }
+
     #[inline]
     pub fn root(&self) -> Digest<P> {
         self.root.clone().unwrap()
@@ -180,21 +406,20 @@ impl<P: Config> MerkleTree<P> {
 
     pub fn generate_proof<L: ToBytes>(
         &self,
-        index: usize,
+        index: u64,
         leaf: &L,
     ) -> Result<Path<P>, crate::Error> {
         let prove_time = start_timer!(|| "MerkleTree::GenProof");
         let mut path = Vec::new();
 
         let mut buffer = [0u8; 128];
-        let leaf_hash = hash_leaf::<P::H, _>(&self.parameters, leaf, &mut buffer)?;
-        let tree_height = tree_height(self.tree.len());
-        let tree_index = convert_index_to_last_level(index, tree_height);
-        let empty_hash = hash_empty::<P::H>(&self.parameters)?;
+        let leaf_hash = hash_leaf::<P, _>(&self.parameters, leaf, &mut buffer)?;
+        let tree_index = self.leaf_tree_index(index)?;
+        let empty_hash = hash_empty::<P>(&self.parameters)?;
 
         // Check that the given index corresponds to the correct leaf.
         if leaf_hash != self.tree[tree_index] {
-            return Err(Error::IncorrectLeafIndex(tree_index).into());
+            return Err(Error::IncorrectLeafIndex(index).into());
         }
 
         // Iterate from the leaf up to the root, storing all intermediate hash values.
@@ -215,7 +440,6 @@ impl<P: Config> MerkleTree<P> {
 
         // Store the root node. Set boolean as true for consistency with digest
         // location.
-        assert!(path.len() < Self::HEIGHT as usize);
         if path.len() != (Self::HEIGHT - 1) as usize {
             path.push((self.tree[0].clone(), empty_hash));
             for &(ref hash, ref sibling_hash) in &self.padding_tree {
@@ -226,15 +450,766 @@ impl<P: Config> MerkleTree<P> {
         if path.len() != (Self::HEIGHT - 1) as usize {
             Err(Error::IncorrectPathLength(path.len()).into())
         } else {
-            Ok(Path { path })
+            Ok(Path {
+                path,
+                leaf_index: index,
+            })
+        }
+    }
+
+    /// Replaces the leaf at `index` and recomputes every node on its path,
+    /// including the padding nodes above the actual tree.
+    pub fn update<L: ToBytes>(&mut self, index: u64, new_leaf: &L) -> Result<(), crate::Error> {
+        self.update_batch(&[(index, new_leaf)]).map(|_| ())
+    }
+
+    /// Replaces several leaves at once, hashing every affected inner node
+    /// exactly once. If an index appears more than once, the last leaf wins.
+    ///
+    /// Returns every node that was recomputed as a `(level, index, digest)`
+    /// triple, with levels counted as in [`MerkleTree::subtree_root`]. Each
+    /// node appears once, ordered by level and then by index, so the result
+    /// can be applied to a copy of the old nodes to obtain the new tree.
+    /// Nothing is modified if any of the indices is out of bounds.
+    pub fn update_batch<L: ToBytes>(
+        &mut self,
+        updates: &[(u64, L)],
+    ) -> Result<Vec<NodeUpdate<P>>, crate::Error> {
+        let update_time = start_timer!(|| "MerkleTree::UpdateBatch");
+        let tree_height = tree_height(self.tree.len());
+        let mut buffer = [0u8; 128];
+
+        // Check every index and hash every leaf before touching the tree.
+        let mut leaf_hashes = BTreeMap::new();
+        for (index, leaf) in updates {
+            let tree_index = self.leaf_tree_index(*index)?;
+            let leaf_hash = hash_leaf::<P, _>(&self.parameters, leaf, &mut buffer)?;
+            leaf_hashes.insert(tree_index, leaf_hash);
+        }
+        if leaf_hashes.is_empty() {
+            end_timer!(update_time);
+            return Ok(Vec::new());
+        }
+
+        let mut changed = Vec::new();
+        let mut dirty = BTreeSet::new();
+        for (tree_index, leaf_hash) in leaf_hashes {
+            self.tree[tree_index] = leaf_hash;
+            dirty.insert(tree_index);
+        }
+
+        // Recompute the hashes level by level, from the leaves to the root
+        // of the actual tree.
+        let mut level = 0;
+        while !dirty.is_empty() {
+            let mut parents = BTreeSet::new();
+            for &tree_index in &dirty {
+                let depth = tree_height - 1 - level;
+                let index = (tree_index + 1 - (1 << depth)) as u64;
+                changed.push((level as u32, index, self.tree[tree_index].clone()));
+                if let Some(parent_node) = parent(tree_index) {
+                    parents.insert(parent_node);
+                }
+            }
+            for &parent_node in &parents {
+                self.tree[parent_node] = hash_inner_node::<P>(
+                    &self.parameters,
+                    &self.tree[left_child(parent_node)],
+                    &self.tree[right_child(parent_node)],
+                    &mut buffer,
+                )?;
+            }
+            dirty = parents;
+            level += 1;
+        }
+
+        let empty_hash = hash_empty::<P>(&self.parameters)?;
+        self.padding_tree.clear();
+        let root_hash = compute_padding::<P>(
+            &self.parameters,
+            &self.tree[0],
+            tree_height,
+            &empty_hash,
+            &mut self.padding_tree,
+        )?;
+        for (i, (hash, _)) in self.padding_tree.iter().enumerate() {
+            changed.push(((tree_height + i) as u32, 0, hash.clone()));
+        }
+        if tree_height < P::HEIGHT {
+            changed.push(((P::HEIGHT - 1) as u32, 0, root_hash.clone()));
+        }
+        self.root = Some(root_hash);
+        end_timer!(update_time);
+        Ok(changed)
+    }
+
+    /// Returns the root of the subtree whose top node sits at `level` (counted
+    /// from the leaves at level 0 up to the root at `P::HEIGHT - 1`) and
+    /// `index` (counted from the left within that level).
+    pub fn subtree_root(&self, level: usize, index: u64) -> Result<Digest<P>, crate::Error> {
+        let tree_height = tree_height(self.tree.len());
+        if self.tree.is_empty() || level >= P::HEIGHT {
+            return Err(Error::IncorrectSubtreePosition(level, index).into());
+        }
+        if level < tree_height - 1 {
+            let tree_index = self.node_tree_index(level, index)?;
+            Ok(self.tree[tree_index].clone())
+        } else if index != 0 {
+            // Above the actual tree, only the leftmost node is populated.
+            Err(Error::IncorrectSubtreePosition(level, index).into())
+        } else if level == P::HEIGHT - 1 {
+            Ok(self.root())
+        } else if level == tree_height - 1 {
+            Ok(self.tree[0].clone())
+        } else {
+            Ok(self.padding_tree[level - tree_height].0.clone())
+        }
+    }
+
+    /// Generates a proof that the subtree at `level` and `index` (see
+    /// [`MerkleTree::subtree_root`]) is part of this tree. For `level == 0`
+    /// this is the same path as the one returned by `generate_proof`, and for
+    /// the root the path is empty.
+    pub fn generate_subtree_proof(
+        &self,
+        level: usize,
+        index: u64,
+    ) -> Result<SubtreePath<P>, crate::Error> {
+        let prove_time = start_timer!(|| "MerkleTree::GenSubtreeProof");
+        // Checks the position as a side effect.
+        self.subtree_root(level, index)?;
+        let tree_height = tree_height(self.tree.len());
+
+        let mut path = Vec::new();
+        if level < tree_height - 1 {
+            let mut current_node = self.node_tree_index(level, index)?;
+            while let Some(sibling_node) = sibling(current_node) {
+                let (curr_hash, sibling_hash) = (
+                    self.tree[current_node].clone(),
+                    self.tree[sibling_node].clone(),
+                );
+                if is_left_child(current_node) {
+                    path.push((curr_hash, sibling_hash));
+                } else {
+                    path.push((sibling_hash, curr_hash));
+                }
+                current_node = parent(current_node).unwrap();
+            }
+        }
+
+        // Add the padding levels that are above `level`.
+        if tree_height < P::HEIGHT {
+            let empty_hash = hash_empty::<P>(&self.parameters)?;
+            let padding_path = ark_std::iter::once((self.tree[0].clone(), empty_hash))
+                .chain(self.padding_tree.iter().cloned());
+            let skip = (level + 1).saturating_sub(tree_height);
+            path.extend(padding_path.skip(skip));
+        }
+        end_timer!(prove_time);
+
+        if path.len() != P::HEIGHT - 1 - level {
+            Err(Error::IncorrectPathLength(path.len()).into())
+        } else {
+            Ok(SubtreePath { level, index, path })
+        }
+    }
+
+    /// Returns the position of the node at `level` and `index` in
+    /// `self.tree`, for levels inside the actual tree.
+    fn node_tree_index(&self, level: usize, index: u64) -> Result<usize, crate::Error> {
+        let depth = tree_height(self.tree.len()) - 1 - level;
+        match usize::try_from(index) {
+            Ok(i) if i < 1 << depth => Ok((1 << depth) - 1 + i),
+            _ => Err(Error::IncorrectSubtreePosition(level, index).into()),
+        }
+    }
+
+    /// Generates a proof that replacing the leaf at `index` with `new_leaf`
+    /// moves the root of this tree from its current value to a new one.
+    /// The tree itself is left unchanged. The proof only holds the index and
+    /// the siblings; the old and new leaves are given to
+    /// [`UpdateProof::verify`], and `new_leaf` is only checked to fit the
+    /// CRH here.
+    pub fn generate_update_proof<L: ToBytes>(
+        &self,
+        index: u64,
+        new_leaf: &L,
+    ) -> Result<UpdateProof<P>, crate::Error> {
+        let prove_time = start_timer!(|| "MerkleTree::GenUpdateProof");
+        let tree_index = self.leaf_tree_index(index)?;
+
+        // Hash the new leaf only to reject leaves that do not fit the CRH.
+        let mut buffer = [0u8; 128];
+        hash_leaf::<P, _>(&self.parameters, new_leaf, &mut buffer)?;
+
+        // Collect the siblings inside the actual tree.
+        let mut siblings = Vec::with_capacity(P::HEIGHT - 1);
+        let mut current_node = tree_index;
+        while let Some(sibling_node) = sibling(current_node) {
+            siblings.push(self.tree[sibling_node].clone());
+            current_node = parent(current_node).unwrap();
+        }
+
+        // Every padding level has the empty hash as its (right) sibling.
+        let empty_hash = hash_empty::<P>(&self.parameters)?;
+        while siblings.len() < P::HEIGHT - 1 {
+            siblings.push(empty_hash.clone());
+        }
+        end_timer!(prove_time);
+
+        if siblings.len() != P::HEIGHT - 1 {
+            Err(Error::IncorrectPathLength(siblings.len()).into())
+        } else {
+            Ok(UpdateProof { index, siblings })
+        }
+    }
+
+    /// Iterates over every node as `(level, index, digest)`, level by level
+    /// from the leaves up to the root and from left to right within a level.
+    /// Levels are counted as in [`MerkleTree::subtree_root`], so the padding
+    /// nodes above the actual tree appear with index 0. The order only
+    /// depends on the shape of the tree, which makes it suitable for
+    /// resumable exports.
+    pub fn export_nodes(&self) -> impl Iterator<Item = NodeUpdate<P>> + '_ {
+        let tree_height = if self.tree.is_empty() {
+            0
+        } else {
+            tree_height(self.tree.len())
+        };
+        let tree_nodes = (0..tree_height).flat_map(move |level| {
+            let width = 1usize << (tree_height - 1 - level);
+            (0..width).map(move |i| (level as u32, i as u64, self.tree[width - 1 + i].clone()))
+        });
+        let padding_nodes = self
+            .padding_tree
+            .iter()
+            .enumerate()
+            .map(move |(i, (hash, _))| ((tree_height + i) as u32, 0, hash.clone()));
+        let root = self
+            .root
+            .clone()
+            .filter(|_| tree_height < P::HEIGHT)
+            .map(|root| ((P::HEIGHT - 1) as u32, 0, root));
+        tree_nodes.chain(padding_nodes).chain(root)
+    }
+
+    /// Rebuilds a tree from the output of [`MerkleTree::export_nodes`],
+    /// without hashing any node. `height` is the number of levels of the
+    /// stored tree, i.e. one more than the base-2 logarithm of the number of
+    /// leaves (padded to a power of two), and `0` for a blank tree.
+    ///
+    /// The nodes may come in any order, but every node must be present
+    /// exactly once. If `verify` is set, every inner node is checked against
+    /// the hash of its children, which costs as much as building the tree
+    /// from its leaves.
+    pub fn import_nodes<I: IntoIterator<Item = NodeUpdate<P>>>(
+        parameters: Parameters<P>,
+        height: u32,
+        nodes: I,
+        verify: bool,
+    ) -> Result<Self, crate::Error> {
+        let import_time = start_timer!(|| "MerkleTree::ImportNodes");
+        let tree_height = height as usize;
+        if tree_height > P::HEIGHT {
+            return Err(Error::TooManyLeaves {
+                height: P::HEIGHT,
+                count: 1u64.checked_shl(height - 1).unwrap_or(u64::MAX),
+            }
+            .into());
+        }
+
+        // Every level above the stored tree holds a single node.
+        let tree_size = if tree_height == 0 {
+            0
+        } else {
+            (1 << tree_height) - 1
+        };
+        let num_padding = if tree_height == 0 {
+            0
+        } else {
+            P::HEIGHT - tree_height
+        };
+        let mut tree = vec![None; tree_size];
+        let mut padding = vec![None; num_padding];
+        for (level, index, digest) in nodes {
+            let level = level as usize;
+            let slot = if level < tree_height {
+                let depth = tree_height - 1 - level;
+                match usize::try_from(index) {
+                    Ok(i) if i < 1 << depth => &mut tree[(1 << depth) - 1 + i],
+                    _ => return Err(Error::IncorrectSubtreePosition(level, index).into()),
+                }
+            } else if index == 0 && level - tree_height < num_padding {
+                &mut padding[level - tree_height]
+            } else {
+                return Err(Error::IncorrectSubtreePosition(level, index).into());
+            };
+            if slot.replace(digest).is_some() {
+                return Err(Error::IncorrectSubtreePosition(level, index).into());
+            }
+        }
+
+        if let Some(i) = tree.iter().position(Option::is_none) {
+            let (level, index) = tree_index_to_position(i, tree_height);
+            return Err(Error::MissingNode(level, index).into());
+        }
+        if let Some(i) = padding.iter().position(Option::is_none) {
+            return Err(Error::MissingNode(tree_height + i, 0).into());
+        }
+        let tree = tree.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+        let padding = padding.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+
+        let empty_hash = hash_empty::<P>(&parameters)?;
+        if verify {
+            let mut buffer = [0u8; 128];
+            for i in (0..tree_size / 2).rev() {
+                let expected = hash_inner_node::<P>(
+                    &parameters,
+                    &tree[left_child(i)],
+                    &tree[right_child(i)],
+                    &mut buffer,
+                )?;
+                if expected != tree[i] {
+                    let (level, index) = tree_index_to_position(i, tree_height);
+                    return Err(Error::InconsistentNode(level, index).into());
+                }
+            }
+            for (i, hash) in padding.iter().enumerate() {
+                let below = if i == 0 { &tree[0] } else { &padding[i - 1] };
+                let expected = hash_inner_node::<P>(&parameters, below, &empty_hash, &mut buffer)?;
+                if &expected != hash {
+                    return Err(Error::InconsistentNode(tree_height + i, 0).into());
+                }
+            }
+        }
+
+        let root = if tree_height == 0 {
+            None
+        } else {
+            Some(padding.last().unwrap_or(&tree[0]).clone())
+        };
+        let padding_tree = match padding.split_last() {
+            Some((_, below_root)) => below_root
+                .iter()
+                .map(|hash| (hash.clone(), empty_hash.clone()))
+                .collect(),
+            None => Vec::new(),
+        };
+        end_timer!(import_time);
+        Ok(MerkleTree {
+            tree,
+            padding_tree,
+            root,
+            parameters,
+        })
+    }
+
+    /// Returns the position of the leaf at `index` in `self.tree`.
+    fn leaf_tree_index(&self, index: u64) -> Result<usize, crate::Error> {
+        if self.tree.is_empty() {
+            return Err(Error::IndexOutOfBounds { index, len: 0 }.into());
+        }
+        let tree_height = tree_height(self.tree.len());
+        let len = 1usize << (tree_height - 1);
+        match usize::try_from(index) {
+            Ok(i) if i < len => Ok(convert_index_to_last_level(i, tree_height)),
+            _ => Err(Error::IndexOutOfBounds {
+                index,
+                len: len as u64,
+            }
+            .into()),
+        }
+    }
+}
+
+/// Hashes the root of the actual tree together with empty siblings until
+/// the tree reaches `P::HEIGHT`. The padding nodes are pushed onto
+/// `padding_tree` in the order they appear in a `Path`, and the final root is
+/// returned.
+fn compute_padding<P: Config>(
+    parameters: &Parameters<P>,
+    tree_root: &Digest<P>,
+    tree_height: usize,
+    empty_hash: &Digest<P>,
+    padding_tree: &mut Vec<(Digest<P>, Digest<P>)>,
+) -> Result<Digest<P>, crate::Error> {
+    let mut buffer = [0u8; 128];
+    let mut cur_height = tree_height;
+    let mut cur_hash = tree_root.clone();
+    let root_hash = if cur_height < P::HEIGHT {
+        while cur_height < P::HEIGHT - 1 {
+            cur_hash = hash_inner_node::<P>(parameters, &cur_hash, empty_hash, &mut buffer)?;
+            padding_tree.push((cur_hash.clone(), empty_hash.clone()));
+            cur_height += 1;
+        }
+        hash_inner_node::<P>(parameters, &cur_hash, empty_hash, &mut buffer)?
+    } else {
+        cur_hash
+    };
+    Ok(root_hash)
+}
+
+/// Computes the root of the tree built from `leaves`, without storing the
+/// tree, as [`MerkleTree::from_leaf_iter`] does. The result equals
+/// `MerkleTree::new(parameters, &leaves)?.root()`.
+pub fn root_from_leaf_iter<P: Config, L: ToBytes, I: IntoIterator<Item = L>>(
+    parameters: &Parameters<P>,
+    leaves: I,
+) -> Result<Digest<P>, crate::Error> {
+    let root_time = start_timer!(|| "MerkleTree::RootFromLeafIter");
+    let root = stream_tree::<P, _, _, Vec<_>>(parameters, leaves, None);
+    end_timer!(root_time);
+    root
+}
+
+/// Hashes `leaves` into the root of their tree, keeping only the frontier.
+/// If `nodes` is given, every node of the tree is added to it once, as
+/// `(level, index, digest)`.
+fn stream_tree<P, L, I, S>(
+    parameters: &Parameters<P>,
+    leaves: I,
+    mut nodes: Option<&mut S>,
+) -> Result<Digest<P>, crate::Error>
+where
+    P: Config,
+    L: ToBytes,
+    I: IntoIterator<Item = L>,
+    S: Extend<NodeUpdate<P>>,
+{
+    let mut buffer = [0u8; 128];
+    let capacity = 1u64
+        .checked_shl(P::HEIGHT.saturating_sub(1) as u32)
+        .unwrap_or(u64::MAX);
+
+    // `frontier[level]` holds the root of a completed subtree of that level
+    // that is still waiting for its right sibling.
+    let mut frontier: Vec<Option<Digest<P>>> = Vec::new();
+    let mut num_leaves = 0u64;
+    for leaf in leaves {
+        if num_leaves == capacity {
+            return Err(Error::TooManyLeaves {
+                height: P::HEIGHT,
+                count: num_leaves + 1,
+            }
+            .into());
+        }
+        let mut carry = hash_leaf::<P, _>(parameters, &leaf, &mut buffer)?;
+        let mut index = num_leaves;
+        store_node::<P, _>(&mut nodes, 0, index, &carry);
+        let mut level = 0;
+        while let Some(Some(left)) = frontier.get(level) {
+            carry = hash_inner_node::<P>(parameters, left, &carry, &mut buffer)?;
+            frontier[level] = None;
+            level += 1;
+            index >>= 1;
+            store_node::<P, _>(&mut nodes, level, index, &carry);
+        }
+        if level == frontier.len() {
+            frontier.push(None);
+        }
+        frontier[level] = Some(carry);
+        num_leaves += 1;
+    }
+
+    // The last level is padded with empty leaves up to a power of two;
+    // `empty[level]` is the root of an empty subtree of that level.
+    let depth = num_leaves.next_power_of_two().trailing_zeros() as usize;
+    let mut empty = vec![hash_empty::<P>(parameters)?];
+    for level in 0..depth {
+        let node = hash_inner_node::<P>(parameters, &empty[level], &empty[level], &mut buffer)?;
+        empty.push(node);
+    }
+    if num_leaves == 0 {
+        store_node::<P, _>(&mut nodes, 0, 0, &empty[0]);
+    }
+    frontier.resize(depth + 1, None);
+    let mut carry: Option<Digest<P>> = None;
+    for level in 0..depth {
+        // The first node of this level that is not a completed subtree. It
+        // is `carry` if leaves remain below it, and empty otherwise.
+        let index = num_leaves >> level;
+        let (left, right) = match (frontier[level].take(), carry.take()) {
+            (Some(left), Some(right)) => (left, right),
+            (Some(left), None) => {
+                store_empty_subtree::<P, _>(&mut nodes, &empty, level, index);
+                (left, empty[level].clone())
+            }
+            (None, Some(left)) => {
+                store_empty_subtree::<P, _>(&mut nodes, &empty, level, index + 1);
+                (left, empty[level].clone())
+            }
+            (None, None) => continue,
+        };
+        let parent = hash_inner_node::<P>(parameters, &left, &right, &mut buffer)?;
+        store_node::<P, _>(&mut nodes, level + 1, index >> 1, &parent);
+        carry = Some(parent);
+    }
+    let tree_root = match frontier[depth].take().or(carry) {
+        Some(root) => root,
+        // Without leaves, the tree consists of a single empty leaf.
+        None => empty[0].clone(),
+    };
+
+    let mut padding_tree = Vec::new();
+    let root_hash = compute_padding::<P>(
+        parameters,
+        &tree_root,
+        depth + 1,
+        &empty[0],
+        &mut padding_tree,
+    )?;
+    for (i, (hash, _)) in padding_tree.iter().enumerate() {
+        store_node::<P, _>(&mut nodes, depth + 1 + i, 0, hash);
+    }
+    if depth + 1 < P::HEIGHT {
+        store_node::<P, _>(&mut nodes, P::HEIGHT - 1, 0, &root_hash);
+    }
+    Ok(root_hash)
+}
+
+fn store_node<P: Config, S: Extend<NodeUpdate<P>>>(
+    nodes: &mut Option<&mut S>,
+    level: usize,
+    index: u64,
+    digest: &Digest<P>,
+) {
+    if let Some(nodes) = nodes {
+        nodes.extend(Some((level as u32, index, digest.clone())));
+    }
+}
+
+/// Stores every node of the empty subtree whose top node sits at `level`
+/// and `index`.
+fn store_empty_subtree<P: Config, S: Extend<NodeUpdate<P>>>(
+    nodes: &mut Option<&mut S>,
+    empty: &[Digest<P>],
+    level: usize,
+    index: u64,
+) {
+    if nodes.is_none() {
+        return;
+    }
+    for (below, digest) in empty.iter().enumerate().take(level + 1) {
+        let width = 1u64 << (level - below);
+        for i in index * width..(index + 1) * width {
+            store_node::<P, _>(nodes, below, i, digest);
         }
     }
 }
 
+/// Returns the canonical byte encoding of a root, which is its `ToBytes`
+/// encoding. For field elements this is little-endian, padded to the size of
+/// the modulus (32 bytes for 256-bit fields).
+pub fn root_to_bytes<P: Config>(root: &Digest<P>) -> Result<Vec<u8>, crate::Error> {
+    Ok(ark_ff::to_bytes![root]?)
+}
+
+/// Parses a root from its canonical byte encoding (see [`root_to_bytes`]).
+/// Encodings of the wrong length and non-canonical field elements are
+/// rejected.
+pub fn root_from_bytes<P: Config>(bytes: &[u8]) -> Result<Digest<P>, crate::Error>
+where
+    Digest<P>: FromBytes,
+{
+    let digest_len = ark_ff::to_bytes![Digest::<P>::default()]?.len();
+    if bytes.len() != digest_len {
+        return Err(CryptoError::IncorrectInputLength(bytes.len()).into());
+    }
+    Ok(Digest::<P>::read(bytes)?)
+}
+
+/// Interprets the canonical encoding of a root (see [`root_to_bytes`]) as a
+/// little-endian integer and returns it as an element of `F`. Fails if the
+/// integer is not smaller than the modulus of `F`, so distinct roots never
+/// map to the same field element.
+pub fn root_to_field<P: Config, F: PrimeField>(root: &Digest<P>) -> Result<F, crate::Error> {
+    let bytes = root_to_bytes::<P>(root)?;
+    let field_len = ark_ff::to_bytes![F::zero()]?.len();
+    Ok(F::read(&resize_le(bytes, field_len)?[..])?)
+}
+
+/// Inverse of [`root_to_field`].
+pub fn root_from_field<P: Config, F: PrimeField>(element: &F) -> Result<Digest<P>, crate::Error>
+where
+    Digest<P>: FromBytes,
+{
+    let bytes = ark_ff::to_bytes![element]?;
+    let digest_len = ark_ff::to_bytes![Digest::<P>::default()]?.len();
+    root_from_bytes::<P>(&resize_le(bytes, digest_len)?)
+}
+
+/// Pads or truncates a little-endian integer to `len` bytes, failing if
+/// truncating would drop a non-zero byte.
+fn resize_le(mut bytes: Vec<u8>, len: usize) -> Result<Vec<u8>, crate::Error> {
+    if bytes.iter().skip(len).any(|b| *b != 0) {
+        return Err(CryptoError::IncorrectInputLength(bytes.len()).into());
+    }
+    bytes.resize(len, 0);
+    Ok(bytes)
+}
+
+/// Proves that a tree with root `old_root` becomes a tree with root
+/// `new_root` when the leaf at `index` changes. Both roots are recomputed
+/// over the same siblings, so every other leaf is unchanged.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "P: Config"),
+    Debug(bound = "P: Config, <P::H as FixedLengthCRH>::Output: fmt::Debug")
+)]
+pub struct UpdateProof<P: Config> {
+    pub(crate) index: u64,
+    pub(crate) siblings: Vec<Digest<P>>,
+}
+
+impl<P: Config> UpdateProof<P> {
+    /// The index of the updated leaf.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Verifies that replacing `old_leaf` at `self.index()` in the tree with
+    /// root `old_root` by `new_leaf` yields the tree with root `new_root`.
+    ///
+    /// Returns an error if the proof does not have `P::HEIGHT - 1` siblings
+    /// or its index does not fit in them, and `Ok(false)` if the proof is
+    /// well-formed but does not connect the two roots.
+    pub fn verify<L: ToBytes>(
+        &self,
+        parameters: &<P::H as FixedLengthCRH>::Parameters,
+        old_root: &<P::H as FixedLengthCRH>::Output,
+        new_root: &<P::H as FixedLengthCRH>::Output,
+        old_leaf: &L,
+        new_leaf: &L,
+    ) -> Result<bool, crate::Error> {
+        if P::HEIGHT == 0 || self.siblings.len() != P::HEIGHT - 1 {
+            return Err(Error::IncorrectPathLength(self.siblings.len()).into());
+        }
+        if self
+            .index
+            .checked_shr(self.siblings.len() as u32)
+            .unwrap_or(0)
+            != 0
+        {
+            return Err(Error::IndexOutOfBounds {
+                index: self.index,
+                len: 1 << self.siblings.len(),
+            }
+            .into());
+        }
+        let mut buffer = [0u8; 128];
+        let old_leaf_hash = hash_leaf::<P, _>(parameters, old_leaf, &mut buffer)?;
+        let new_leaf_hash = hash_leaf::<P, _>(parameters, new_leaf, &mut buffer)?;
+        let claimed_old_root = self.fold(parameters, &old_leaf_hash)?;
+        let claimed_new_root = self.fold(parameters, &new_leaf_hash)?;
+        Ok(&claimed_old_root == old_root && &claimed_new_root == new_root)
+    }
+
+    /// Hashes `leaf_hash` up to the root, using the bits of `self.index` to
+    /// decide on which side the siblings go.
+    fn fold(
+        &self,
+        parameters: &<P::H as FixedLengthCRH>::Parameters,
+        leaf_hash: &Digest<P>,
+    ) -> Result<Digest<P>, crate::Error> {
+        let mut buffer = [0u8; 128];
+        let mut current = leaf_hash.clone();
+        for (level, sibling_hash) in self.siblings.iter().enumerate() {
+            current = if (self.index >> level) & 1 == 1 {
+                hash_inner_node::<P>(parameters, sibling_hash, &current, &mut buffer)?
+            } else {
+                hash_inner_node::<P>(parameters, &current, sibling_hash, &mut buffer)?
+            };
+        }
+        Ok(current)
+    }
+}
+
+/// Stores the hashes of the path from the top node of a subtree at `level`
+/// and `index` up to the root, in the same layout as `Path`.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "P: Config"),
+    PartialEq(bound = "P: Config"),
+    Debug(bound = "P: Config, <P::H as FixedLengthCRH>::Output: fmt::Debug")
+)]
+pub struct SubtreePath<P: Config> {
+    pub(crate) level: usize,
+    /// The index of the subtree root within its level. Bit `i` is set iff
+    /// the node at level `i` of the path is a right child.
+    pub(crate) index: u64,
+    pub(crate) path: Vec<(Digest<P>, Digest<P>)>,
+}
+
+impl<P: Config> SubtreePath<P> {
+    /// The level of the subtree root this path starts from.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// The index of the subtree root within its level.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Verifies that `subtree_root` is part of the tree with root `root_hash`.
+    /// See [`verify_subtree`] for details.
+    pub fn verify(
+        &self,
+        parameters: &<P::H as FixedLengthCRH>::Parameters,
+        root_hash: &<P::H as FixedLengthCRH>::Output,
+        subtree_root: &<P::H as FixedLengthCRH>::Output,
+    ) -> Result<bool, crate::Error> {
+        verify_subtree(parameters, root_hash, subtree_root, self)
+    }
+}
+
+/// Verifies that `subtree_root` is the root of the subtree at `path.level()`
+/// and `path.index()` of the tree with root `root_hash`.
+///
+/// As in [`verify`], the bits of the index pick the side of the current node
+/// at each level, so a path is only accepted for the position it was
+/// generated for.
+///
+/// Returns an error if `path` does not have one level per hash between
+/// `path.level()` and the root, or if its index does not fit in them.
+pub fn verify_subtree<P: Config>(
+    parameters: &<P::H as FixedLengthCRH>::Parameters,
+    root_hash: &<P::H as FixedLengthCRH>::Output,
+    subtree_root: &<P::H as FixedLengthCRH>::Output,
+    path: &SubtreePath<P>,
+) -> Result<bool, crate::Error> {
+    if path.level >= P::HEIGHT || path.path.len() != P::HEIGHT - 1 - path.level {
+        return Err(Error::IncorrectPathLength(path.path.len()).into());
+    }
+    if path.index.checked_shr(path.path.len() as u32).unwrap_or(0) != 0 {
+        return Err(Error::IncorrectSubtreePosition(path.level, path.index).into());
+    }
+    let mut buffer = [0u8; 128];
+    let mut prev = subtree_root.clone();
+    for (level, (left, right)) in path.path.iter().enumerate() {
+        // The previous hash has to be on the side given by the index.
+        let current = if (path.index >> level) & 1 == 1 {
+            right
+        } else {
+            left
+        };
+        if &prev != current {
+            return Ok(false);
+        }
+        prev = hash_inner_node::<P>(parameters, left, right, &mut buffer)?;
+    }
+    Ok(root_hash == &prev)
+}
+
 #[derive(Debug)]
 pub enum Error {
-    IncorrectLeafIndex(usize),
+    IncorrectLeafIndex(u64),
     IncorrectPathLength(usize),
+    IncorrectSubtreePosition(usize, u64),
+    TooManyLeaves { height: usize, count: u64 },
+    IndexOutOfBounds { index: u64, len: u64 },
+    MissingNode(usize, u64),
+    InconsistentNode(usize, u64),
 }
 
 impl core::fmt::Display for Error {
@@ -242,6 +1217,23 @@ impl core::fmt::Display for Error {
         let msg = match self {
             Error::IncorrectLeafIndex(index) => format!("incorrect leaf index: {}", index),
             Error::IncorrectPathLength(len) => format!("incorrect path length: {}", len),
+            Error::IncorrectSubtreePosition(level, index) => format!(
+                "incorrect subtree position: index {} at level {}",
+                index, level
+            ),
+            Error::TooManyLeaves { height, count } => {
+                format!("too many leaves for a tree of height {}: {}", height, count)
+            }
+            Error::IndexOutOfBounds { index, len } => {
+                format!("leaf index {} out of bounds for {} leaves", index, len)
+            }
+            Error::MissingNode(level, index) => {
+                format!("missing node: index {} at level {}", index, level)
+            }
+            Error::InconsistentNode(level, index) => format!(
+                "node does not match its children: index {} at level {}",
+                index, level
+            ),
         };
         write!(f, "{}", msg)
     }
@@ -249,6 +1241,17 @@ impl core::fmt::Display for Error {
 
 impl ark_std::error::Error for Error {}
 
+/// Returns the `(level, index)` of the node at `tree_index` in a tree with
+/// `tree_height` levels, with levels counted from the leaves.
+#[inline]
+fn tree_index_to_position(tree_index: usize, tree_height: usize) -> (usize, u64) {
+    let depth = (0usize.leading_zeros() - 1 - (tree_index + 1).leading_zeros()) as usize;
+    (
+        tree_height - 1 - depth,
+        (tree_index + 1 - (1 << depth)) as u64,
+    )
+}
+
 /// Returns the height of the tree, given the size of the tree.
 #[inline]
 fn tree_height(tree_size: usize) -> usize {
@@ -311,37 +1314,106 @@ fn convert_index_to_last_level(index: usize, tree_height: usize) -> usize {
 }
 
 /// Returns the output hash, given a left and right hash value.
-pub(crate) fn hash_inner_node<H: FixedLengthCRH>(
-    parameters: &H::Parameters,
-    left: &H::Output,
-    right: &H::Output,
+pub(crate) fn hash_inner_node<P: HashConfig>(
+    parameters: &HashParameters<P>,
+    left: &HashDigest<P>,
+    right: &HashDigest<P>,
     buffer: &mut [u8],
-) -> Result<H::Output, crate::Error> {
-    let bytes = ark_ff::to_bytes![left]?
-        .into_iter()
-        .chain(ark_ff::to_bytes![right]?);
-    buffer.iter_mut().zip(bytes).for_each(|(b, l_b)| *b = l_b);
-    H::evaluate(parameters, &buffer[..(H::INPUT_SIZE_BITS / 8)])
+) -> Result<HashDigest<P>, crate::Error> {
+    let mut bytes = ark_ff::to_bytes![left]?;
+    bytes.extend_from_slice(&ark_ff::to_bytes![right]?);
+    let input = fill_input::<P>(buffer, &bytes, P::DOMAIN_SEPARATION.node_tag())?;
+    P::H::evaluate(parameters, input)
 }
 
 /// Returns the hash of a leaf.
-pub(crate) fn hash_leaf<H: FixedLengthCRH, L: ToBytes>(
-    parameters: &H::Parameters,
+pub(crate) fn hash_leaf<P: HashConfig, L: ToBytes>(
+    parameters: &HashParameters<P>,
     leaf: &L,
     buffer: &mut [u8],
-) -> Result<H::Output, crate::Error> {
-    buffer
-        .iter_mut()
-        .zip(&ark_ff::to_bytes![leaf]?)
-        .for_each(|(b, l_b)| *b = *l_b);
-    H::evaluate(parameters, &buffer[..(H::INPUT_SIZE_BITS / 8)])
+) -> Result<HashDigest<P>, crate::Error> {
+    let mut bytes = ark_ff::to_bytes![leaf]?;
+    if P::LEAF_ENCODING == LeafEncoding::Chained {
+        bytes = chain_leaf::<P>(parameters, &bytes)?;
+    }
+    let input = fill_input::<P>(buffer, &bytes, P::DOMAIN_SEPARATION.leaf_tag())?;
+    P::H::evaluate(parameters, input)
 }
 
-pub(crate) fn hash_empty<H: FixedLengthCRH>(
-    parameters: &H::Parameters,
-) -> Result<H::Output, crate::Error> {
-    let empty_buffer = vec![0u8; H::INPUT_SIZE_BITS / 8];
-    H::evaluate(parameters, &empty_buffer)
+/// Implements `LeafEncoding::Chained`.
+fn chain_leaf<P: HashConfig>(
+    parameters: &HashParameters<P>,
+    leaf: &[u8],
+) -> Result<Vec<u8>, crate::Error> {
+    let input_len = <P::H as FixedLengthCRH>::INPUT_SIZE_BITS / 8;
+    let digest_len = chained_digest_len::<P>()?;
+    let chunk_len = input_len - digest_len;
+
+    let mut state = Vec::with_capacity(digest_len);
+    state.extend_from_slice(&(leaf.len() as u64).to_le_bytes());
+    state.resize(digest_len, 0);
+    let mut input = Vec::with_capacity(input_len);
+    let mut chunks = leaf.chunks(chunk_len).collect::<Vec<_>>();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    for chunk in chunks {
+        input.clear();
+        input.extend_from_slice(&state);
+        input.extend_from_slice(chunk);
+        input.resize(input_len, 0);
+        state = ark_ff::to_bytes![P::H::evaluate(parameters, &input)?]?;
+    }
+    Ok(state)
+}
+
+/// The size of a digest in bytes, checked against the requirements of
+/// `LeafEncoding::Chained`.
+pub(crate) fn chained_digest_len<P: HashConfig>() -> Result<usize, crate::Error> {
+    let input_len = <P::H as FixedLengthCRH>::INPUT_SIZE_BITS / 8;
+    let digest_len = ark_ff::to_bytes![HashDigest::<P>::default()]?.len();
+    if digest_len < 8 || digest_len >= input_len {
+        return Err(CryptoError::IncorrectInputLength(digest_len).into());
+    }
+    Ok(digest_len)
+}
+
+/// Returns the hash of an empty leaf.
+pub(crate) fn hash_empty<P: HashConfig>(
+    parameters: &HashParameters<P>,
+) -> Result<HashDigest<P>, crate::Error> {
+    let mut empty_buffer = vec![0u8; <P::H as FixedLengthCRH>::INPUT_SIZE_BITS / 8];
+    let input = fill_input::<P>(&mut empty_buffer, &[], P::DOMAIN_SEPARATION.leaf_tag())?;
+    P::H::evaluate(parameters, input)
+}
+
+/// Writes `payload` into `buffer` and zero-pads it to the CRH input size.
+/// With a domain `tag`, the payload follows the capacity of the input,
+/// which holds the tag.
+fn fill_input<'a, P: HashConfig>(
+    buffer: &'a mut [u8],
+    payload: &[u8],
+    tag: Option<u8>,
+) -> Result<&'a [u8], crate::Error> {
+    let input_len = <P::H as FixedLengthCRH>::INPUT_SIZE_BITS / 8;
+    let input = &mut buffer[..input_len];
+    let offset = match tag {
+        Some(_) => <P::H as FixedLengthCRH>::CAPACITY_BYTES,
+        None => 0,
+    };
+    if offset > 0 && payload.len() > input_len.saturating_sub(offset) {
+        return Err(CryptoError::IncorrectInputLength(payload.len()).into());
+    }
+    for (i, b) in input.iter_mut().enumerate() {
+        *b = i
+            .checked_sub(offset)
+            .and_then(|i| payload.get(i).cloned())
+            .unwrap_or(0);
+    }
+    if let Some(tag) = tag {
+        input[0] = tag;
+    }
+    Ok(input)
 }
 
 #[cfg(test)]
@@ -384,7 +1456,7 @@ mod test {
         let tree = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
         let root = root.unwrap_or(tree.root());
         for (i, leaf) in leaves.iter().enumerate() {
-            let proof = tree.generate_proof(i, &leaf).unwrap();
+            let proof = tree.generate_proof(i as u64, &leaf).unwrap();
             assert!(proof.verify(&crh_parameters, &root, &leaf).unwrap());
         }
     }
@@ -417,6 +1489,445 @@ mod test {
         generate_pedersen_merkle_tree(&[[1u8; 8]], None);
     }
 
+    #[test]
+    fn standalone_verify_test() {
+        let mut rng = ark_std::test_rng();
+        let leaves = (0..6u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let crh_parameters = PedersenH::setup(&mut rng).unwrap();
+        let tree = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+        let root = tree.root();
+
+        let proof = tree.generate_proof(2, &leaves[2]).unwrap();
+        assert!(verify(&crh_parameters, &root, &leaves[2], &proof).unwrap());
+        assert!(!verify(&crh_parameters, &root, &[9u8; 8], &proof).unwrap());
+
+        // The siblings of leaf 2 do not prove it at any other index.
+        let (_, siblings) = proof.clone().into();
+        for index in [0, 3, 5, 6, 66].iter() {
+            let moved =
+                Path::<JubJubMerkleTreeParams>::try_from((*index, siblings.clone())).unwrap();
+            assert!(!verify(&crh_parameters, &root, &leaves[2], &moved).unwrap());
+        }
+
+        // A path with a level missing is an error, not a failed verification.
+        let mut short = proof.clone();
+        short.path.pop();
+        match verify(&crh_parameters, &root, &leaves[2], &short) {
+            Err(crate::Error::Crypto(CryptoError::Merkle(Error::IncorrectPathLength(6)))) => {}
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("a short path must be rejected"),
+        }
+
+        // So is a path with an extra level.
+        let mut long = proof.clone();
+        long.path.extend_from_slice(&proof.path[..1]);
+        assert!(verify(&crh_parameters, &root, &leaves[2], &long).is_err());
+        assert!(long.verify(&crh_parameters, &root, &leaves[2]).is_err());
+
+        // A height of zero is a misconfiguration, reported as an error.
+        struct ZeroHeightParams;
+        impl Config for ZeroHeightParams {
+            const HEIGHT: usize = 0;
+            type H = PedersenH;
+        }
+        let empty = Path::<ZeroHeightParams> {
+            path: Vec::new(),
+            leaf_index: 0,
+        };
+        assert!(verify(&crh_parameters, &root, &leaves[2], &empty).is_err());
+    }
+
+    #[test]
+    fn path_structure_test() {
+        let mut rng = ark_std::test_rng();
+        let leaves = (0..3u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let crh_parameters = PedersenH::setup(&mut rng).unwrap();
+        let tree = JubJubMerkleTree::new(crh_parameters, &leaves).unwrap();
+
+        let proof = tree.generate_proof(1, &leaves[1]).unwrap();
+        let cloned = proof.clone();
+        assert_eq!(proof, cloned);
+        assert_ne!(proof, tree.generate_proof(2, &leaves[2]).unwrap());
+        assert!(format!("{:?}", proof).starts_with("Path { path: [(0x"));
+
+        assert!(proof
+            .validate_structure(JubJubMerkleTreeParams::HEIGHT)
+            .is_ok());
+        assert!(proof.validate_structure(0).is_err());
+
+        let mut missing_level = cloned;
+        missing_level.path.pop();
+        assert_ne!(proof, missing_level);
+        match missing_level.validate_structure(JubJubMerkleTreeParams::HEIGHT) {
+            Err(Error::IncorrectPathLength(6)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // The index has to fit in the seven levels below the root.
+        let mut wide_index = proof.clone();
+        wide_index.leaf_index = 1 << 7;
+        match wide_index.validate_structure(JubJubMerkleTreeParams::HEIGHT) {
+            Err(Error::IndexOutOfBounds {
+                index: 128,
+                len: 128,
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        wide_index.leaf_index = (1 << 7) - 1;
+        assert!(wide_index
+            .validate_structure(JubJubMerkleTreeParams::HEIGHT)
+            .is_ok());
+    }
+
+    #[test]
+    fn fixed_height_path_test() {
+        struct JubJubHeight9Params;
+        impl Config for JubJubHeight9Params {
+            const HEIGHT: usize = 9;
+            type H = PedersenH;
+        }
+
+        let mut rng = ark_std::test_rng();
+        let leaves = (0..3u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let crh_parameters = PedersenH::setup(&mut rng).unwrap();
+        let tree = MerkleTree::<JubJubHeight9Params>::new(crh_parameters.clone(), &leaves).unwrap();
+        let (index, siblings) = tree.generate_proof(1, &leaves[1]).unwrap().into();
+        assert_eq!(index, 1);
+        assert_eq!(siblings.len(), 8);
+
+        // A height-9 path is rejected by a height-8 config...
+        match Path::<JubJubMerkleTreeParams>::try_from((1, siblings.clone())) {
+            Err(Error::IncorrectPathLength(8)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // ...and accepted by a height-9 one.
+        let path = Path::<JubJubHeight9Params>::try_from((1, siblings.clone())).unwrap();
+        assert!(path
+            .verify(&crh_parameters, &tree.root(), &leaves[1])
+            .unwrap());
+
+        let mut short = siblings;
+        short.pop();
+        assert!(Path::<JubJubMerkleTreeParams>::try_from((127, short.clone())).is_ok());
+        match Path::<JubJubMerkleTreeParams>::try_from((128, short)) {
+            Err(Error::IndexOutOfBounds {
+                index: 128,
+                len: 128,
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unified_error_test() {
+        let mut rng = ark_std::test_rng();
+        let crh_parameters = PedersenH::setup(&mut rng).unwrap();
+        let leaves = (0..4u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let tree = JubJubMerkleTree::new(crh_parameters, &leaves).unwrap();
+        let error = tree.generate_proof(200, &leaves[0]).unwrap_err();
+        match &error {
+            crate::Error::Crypto(CryptoError::Merkle(Error::IndexOutOfBounds {
+                index: 200,
+                ..
+            })) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // The helpers of the boxed error still find every layer.
+        #[allow(deprecated)]
+        {
+            assert!(error.is::<crate::Error>());
+            assert!(error.is::<CryptoError>());
+            assert!(!error.is::<crate::signature::SignatureError>());
+            match error.downcast_ref::<Error>() {
+                Some(Error::IndexOutOfBounds { index: 200, .. }) => {}
+                other => panic!("unexpected result: {:?}", other),
+            }
+            match CryptoError::downcast(error) {
+                Ok(CryptoError::Merkle(Error::IndexOutOfBounds { index: 200, .. })) => {}
+                other => panic!("unexpected result: {:?}", other),
+            }
+            assert!(CryptoError::downcast(crate::Error::custom("snark")).is_err());
+        }
+
+        // Decoding failures are serialization errors, whichever conversion
+        // produced them.
+        let error = root_from_bytes::<PoseidonMerkleTreeParams>(&[0xFF; 32]).unwrap_err();
+        match error {
+            crate::Error::Crypto(CryptoError::Serialization(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn subtree_proof_test() {
+        let mut rng = ark_std::test_rng();
+        let leaves = (0..7u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let crh_parameters = PedersenH::setup(&mut rng).unwrap();
+        let tree = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+        let root = tree.root();
+        let height = JubJubMerkleTreeParams::HEIGHT;
+
+        // Level 0 coincides with an ordinary membership proof.
+        let mut buffer = [0u8; 128];
+        let leaf_hash =
+            hash_leaf::<JubJubMerkleTreeParams, _>(&crh_parameters, &leaves[5], &mut buffer)
+                .unwrap();
+        assert_eq!(tree.subtree_root(0, 5).unwrap(), leaf_hash);
+        let leaf_proof = tree.generate_subtree_proof(0, 5).unwrap();
+        assert_eq!(
+            leaf_proof.path,
+            tree.generate_proof(5, &leaves[5]).unwrap().path
+        );
+        assert!(verify_subtree(&crh_parameters, &root, &leaf_hash, &leaf_proof).unwrap());
+
+        // The root is its own subtree, with an empty path.
+        assert_eq!(tree.subtree_root(height - 1, 0).unwrap(), root);
+        let root_proof = tree.generate_subtree_proof(height - 1, 0).unwrap();
+        assert!(root_proof.path.is_empty());
+        assert!(root_proof.verify(&crh_parameters, &root, &root).unwrap());
+
+        // A node in the middle of the actual tree, covering leaves 4..8.
+        let mid_root = tree.subtree_root(2, 1).unwrap();
+        let mid_proof = tree.generate_subtree_proof(2, 1).unwrap();
+        assert_eq!(mid_proof.level(), 2);
+        assert_eq!(mid_proof.path.len(), height - 3);
+        assert!(mid_proof.verify(&crh_parameters, &root, &mid_root).unwrap());
+        assert!(!mid_proof
+            .verify(&crh_parameters, &root, &tree.subtree_root(1, 0).unwrap())
+            .unwrap());
+
+        // The same siblings do not prove the subtree at any other offset, in
+        // particular not at its mirror (level 2, index 0).
+        for index in 0..(1u64 << mid_proof.path.len()) {
+            let mut moved = mid_proof.clone();
+            moved.index = index;
+            assert_eq!(
+                moved.verify(&crh_parameters, &root, &mid_root).unwrap(),
+                index == 1
+            );
+        }
+        let mut wide = mid_proof.clone();
+        wide.index = 1 << mid_proof.path.len();
+        match wide.verify(&crh_parameters, &root, &mid_root) {
+            Err(crate::Error::Crypto(CryptoError::Merkle(Error::IncorrectSubtreePosition(
+                2,
+                32,
+            )))) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // A padding node above the actual tree.
+        let padding_root = tree.subtree_root(4, 0).unwrap();
+        let padding_proof = tree.generate_subtree_proof(4, 0).unwrap();
+        assert!(padding_proof
+            .verify(&crh_parameters, &root, &padding_root)
+            .unwrap());
+
+        // Positions outside of the tree are rejected.
+        assert!(tree.subtree_root(2, 2).is_err());
+        assert!(tree.subtree_root(4, 1).is_err());
+        assert!(tree.subtree_root(height, 0).is_err());
+        assert!(tree.generate_subtree_proof(height, 0).is_err());
+    }
+
+    #[test]
+    fn update_batch_test() {
+        let mut rng = ark_std::test_rng();
+        let mut leaves = (0..8u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let crh_parameters = PedersenH::setup(&mut rng).unwrap();
+        let mut tree = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+        let height = JubJubMerkleTreeParams::HEIGHT;
+
+        // Keep a copy of every node of the old tree.
+        let mut nodes = ark_std::collections::BTreeMap::new();
+        for level in 0..height {
+            let width = if level < 4 { 1u64 << (3 - level) } else { 1 };
+            for index in 0..width {
+                nodes.insert((level, index), tree.subtree_root(level, index).unwrap());
+            }
+        }
+
+        // Leaves 0 and 2 share their grandparent (level 2, index 0).
+        let updates = [(0, [20u8; 8]), (2, [22u8; 8])];
+        let changed = tree.update_batch(&updates).unwrap();
+        leaves[0] = [20u8; 8];
+        leaves[2] = [22u8; 8];
+        let rebuilt = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+        assert_eq!(tree.root(), rebuilt.root());
+
+        let positions = changed
+            .iter()
+            .map(|(level, index, _)| (*level as usize, *index))
+            .collect::<Vec<_>>();
+        let mut expected = vec![(0, 0), (0, 2), (1, 0), (1, 1), (2, 0), (3, 0)];
+        expected.extend((4..height).map(|level| (level, 0)));
+        assert_eq!(positions, expected);
+
+        // Applying the changed nodes to the old ones reproduces the new tree.
+        for (level, index, digest) in changed {
+            nodes.insert((level as usize, index), digest);
+        }
+        assert_eq!(nodes[&(height - 1, 0)], rebuilt.root());
+        for (&(level, index), digest) in &nodes {
+            assert_eq!(&rebuilt.subtree_root(level, index).unwrap(), digest);
+        }
+
+        // An invalid index leaves the tree untouched.
+        let root = tree.root();
+        assert!(tree.update_batch(&[(1, [0u8; 8]), (8, [0u8; 8])]).is_err());
+        assert_eq!(tree.root(), root);
+        assert!(tree.update_batch::<[u8; 8]>(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn update_proof_test() {
+        let mut rng = ark_std::test_rng();
+        let mut leaves = Vec::new();
+        for i in 0..5u8 {
+            leaves.push([i, i, i, i, i, i, i, i]);
+        }
+        let crh_parameters = PedersenH::setup(&mut rng).unwrap();
+        let mut tree = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+
+        // Update the same leaf twice, checking each transition.
+        let old_leaf = leaves[3];
+        let old_root = tree.root();
+        let new_leaf = [9u8; 8];
+        let proof = tree.generate_update_proof(3, &new_leaf).unwrap();
+        tree.update(3, &new_leaf).unwrap();
+        let mid_root = tree.root();
+        assert_eq!(proof.index(), 3);
+        assert!(proof
+            .verify(&crh_parameters, &old_root, &mid_root, &old_leaf, &new_leaf)
+            .unwrap());
+
+        let newer_leaf = [10u8; 8];
+        let second_proof = tree.generate_update_proof(3, &newer_leaf).unwrap();
+        tree.update(3, &newer_leaf).unwrap();
+        let new_root = tree.root();
+        assert!(second_proof
+            .verify(
+                &crh_parameters,
+                &mid_root,
+                &new_root,
+                &new_leaf,
+                &newer_leaf
+            )
+            .unwrap());
+
+        // The updated tree matches a tree built from the final leaves.
+        leaves[3] = newer_leaf;
+        let rebuilt = JubJubMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+        assert_eq!(rebuilt.root(), new_root);
+        let path = tree.generate_proof(3, &newer_leaf).unwrap();
+        assert!(path
+            .verify(&crh_parameters, &new_root, &newer_leaf)
+            .unwrap());
+
+        // Mismatched roots must be rejected.
+        let verify = |proof: &UpdateProof<_>, from, to, old: &[u8; 8], new: &[u8; 8]| {
+            proof.verify(&crh_parameters, from, to, old, new).unwrap()
+        };
+        assert!(!verify(&proof, &mid_root, &new_root, &old_leaf, &new_leaf));
+        assert!(!verify(&proof, &old_root, &new_root, &old_leaf, &new_leaf));
+        assert!(!verify(
+            &second_proof,
+            &old_root,
+            &new_root,
+            &new_leaf,
+            &newer_leaf
+        ));
+        assert!(!verify(&proof, &mid_root, &old_root, &old_leaf, &new_leaf));
+
+        // So must leaves other than the ones that were swapped.
+        assert!(!verify(
+            &proof,
+            &old_root,
+            &mid_root,
+            &old_leaf,
+            &newer_leaf
+        ));
+        assert!(!verify(&proof, &old_root, &mid_root, &leaves[2], &new_leaf));
+
+        // Malformed proofs are errors, not failed verifications.
+        let mut short = proof.clone();
+        short.siblings.pop();
+        match short.verify(&crh_parameters, &old_root, &mid_root, &old_leaf, &new_leaf) {
+            Err(crate::Error::Crypto(CryptoError::Merkle(Error::IncorrectPathLength(6)))) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let mut wide_index = proof.clone();
+        wide_index.index = 1 << 7;
+        match wide_index.verify(&crh_parameters, &old_root, &mid_root, &old_leaf, &new_leaf) {
+            Err(crate::Error::Crypto(CryptoError::Merkle(Error::IndexOutOfBounds {
+                index: 128,
+                len: 128,
+            }))) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // Leaves outside of the tree cannot be updated.
+        assert!(tree.generate_update_proof(8, &new_leaf).is_err());
+        assert!(tree.update(8, &new_leaf).is_err());
+    }
+
+    #[test]
+    fn structured_error_test() {
+        let mut rng = ark_std::test_rng();
+        let crh_parameters = PedersenH::setup(&mut rng).unwrap();
+        let leaf = |i: u8| [i; 8];
+
+        // A tree of height 8 holds at most 128 leaves.
+        let leaves = (0..129u8).map(leaf).collect::<Vec<_>>();
+        let err = JubJubMerkleTree::new(crh_parameters.clone(), &leaves)
+            .err()
+            .unwrap();
+        match &err {
+            crate::Error::Crypto(CryptoError::Merkle(Error::TooManyLeaves {
+                height: 8,
+                count: 129,
+            })) => {}
+            _ => panic!("unexpected error: {}", err),
+        }
+        let err = root_from_leaf_iter::<JubJubMerkleTreeParams, _, _>(&crh_parameters, &leaves)
+            .unwrap_err();
+        match &err {
+            crate::Error::Crypto(CryptoError::Merkle(Error::TooManyLeaves {
+                height: 8,
+                count: 129,
+            })) => {}
+            _ => panic!("unexpected error: {}", err),
+        }
+        assert!(JubJubMerkleTree::new(crh_parameters.clone(), &leaves[..128]).is_ok());
+
+        // Five leaves are padded to eight, so index 8 is the first one out of
+        // bounds.
+        let mut tree = JubJubMerkleTree::new(crh_parameters.clone(), &leaves[..5]).unwrap();
+        let is_out_of_bounds = |err: crate::Error, expected_len: u64| match err {
+            crate::Error::Crypto(CryptoError::Merkle(Error::IndexOutOfBounds {
+                index: 8,
+                len,
+            })) => len == expected_len,
+            _ => false,
+        };
+        assert!(is_out_of_bounds(
+            tree.generate_proof(8, &leaf(8)).unwrap_err(),
+            8
+        ));
+        assert!(is_out_of_bounds(tree.update(8, &leaf(8)).unwrap_err(), 8));
+        assert!(is_out_of_bounds(
+            tree.generate_update_proof(8, &leaf(8)).unwrap_err(),
+            8
+        ));
+
+        let blank = JubJubMerkleTree::blank(crh_parameters);
+        assert!(is_out_of_bounds(
+            blank.generate_proof(8, &leaf(8)).unwrap_err(),
+            0
+        ));
+    }
+
     #[should_panic]
     #[test]
     fn bad_root_test() {
@@ -465,11 +1976,381 @@ mod test {
         let tree = PoseidonMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
         let root = root.unwrap_or(tree.root());
         for (i, leaf) in leaves.iter().enumerate() {
-            let proof = tree.generate_proof(i, &leaf).unwrap();
+            let proof = tree.generate_proof(i as u64, &leaf).unwrap();
             assert!(proof.verify(&crh_parameters, &root, &leaf).unwrap());
         }
     }
 
+    struct PoseidonHeight20Params;
+
+    impl Config for PoseidonHeight20Params {
+        const HEIGHT: usize = 20;
+        type H = PoseidonCRH3;
+    }
+
+    struct ChainedPoseidonParams;
+
+    impl Config for ChainedPoseidonParams {
+        const HEIGHT: usize = 4;
+        const LEAF_ENCODING: LeafEncoding = LeafEncoding::Chained;
+        type H = PoseidonCRH3;
+    }
+
+    #[test]
+    fn leaf_encoding_test() {
+        let crh_parameters = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let long_leaf = (0..100u8).collect::<Vec<_>>();
+        let short_leaf = vec![7u8; 10];
+        let leaves = [long_leaf, short_leaf];
+        let tree =
+            MerkleTree::<ChainedPoseidonParams>::new(crh_parameters.clone(), &leaves).unwrap();
+        let root = tree.root();
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = tree.generate_proof(i as u64, leaf).unwrap();
+            assert!(proof.verify(&crh_parameters, &root, leaf).unwrap());
+        }
+
+        // Follow the rule by hand: 32-byte digests leave 64 bytes per chunk,
+        // so the short leaf takes a single hash and the long one two.
+        let mut first_block = 10u64.to_le_bytes().to_vec();
+        first_block.resize(32, 0);
+        first_block.extend_from_slice(&leaves[1]);
+        first_block.resize(96, 0);
+        let digest = PoseidonCRH3::evaluate(&crh_parameters, &first_block).unwrap();
+        let mut buffer = [0u8; 128];
+        let expected =
+            hash_leaf::<PoseidonMerkleTreeParams, _>(&crh_parameters, &digest, &mut buffer)
+                .unwrap();
+        assert_eq!(tree.subtree_root(0, 1).unwrap(), expected);
+
+        let mut first_block = 100u64.to_le_bytes().to_vec();
+        first_block.resize(32, 0);
+        first_block.extend_from_slice(&leaves[0][..64]);
+        let digest = PoseidonCRH3::evaluate(&crh_parameters, &first_block).unwrap();
+        let mut second_block = ark_ff::to_bytes![digest].unwrap();
+        second_block.extend_from_slice(&leaves[0][64..]);
+        second_block.resize(96, 0);
+        let digest = PoseidonCRH3::evaluate(&crh_parameters, &second_block).unwrap();
+        let expected =
+            hash_leaf::<PoseidonMerkleTreeParams, _>(&crh_parameters, &digest, &mut buffer)
+                .unwrap();
+        assert_eq!(tree.subtree_root(0, 0).unwrap(), expected);
+
+        // Pin the root, so that the encoding cannot change unnoticed.
+        let root_hex = ark_ff::to_bytes![root]
+            .unwrap()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        assert_eq!(
+            root_hex,
+            "433f06306f84014724ff5a65c462262c873449339ad65f065308945746f1b703"
+        );
+    }
+
+    #[test]
+    fn root_conversion_test() {
+        use ark_bls12_377::Fq as WideFq;
+        use ark_ed_on_bls12_381::Fq as BlsFq;
+
+        let crh_parameters = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let leaves = (0..5u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let root = PoseidonMerkleTree::new(crh_parameters, &leaves)
+            .unwrap()
+            .root();
+
+        let bytes = root_to_bytes::<PoseidonMerkleTreeParams>(&root).unwrap();
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(
+            root_from_bytes::<PoseidonMerkleTreeParams>(&bytes).unwrap(),
+            root
+        );
+        assert!(root_from_bytes::<PoseidonMerkleTreeParams>(&bytes[1..]).is_err());
+        assert_eq!(
+            root_to_field::<PoseidonMerkleTreeParams, Fq>(&root).unwrap(),
+            root
+        );
+        let wide = root_to_field::<PoseidonMerkleTreeParams, WideFq>(&root).unwrap();
+        assert_eq!(
+            root_from_field::<PoseidonMerkleTreeParams, _>(&wide).unwrap(),
+            root
+        );
+
+        // The encoding is little-endian: the first byte is the lowest one.
+        let mut bytes = vec![0u8; 32];
+        bytes[0] = 0xF0;
+        assert_eq!(
+            root_from_bytes::<PoseidonMerkleTreeParams>(&bytes).unwrap(),
+            Fq::from(0xF0u64)
+        );
+
+        // No 256-bit modulus used here reaches 0xF0 << 248, so a top byte of
+        // 0xF0 has to be rejected instead of being reduced.
+        bytes[0] = 0;
+        bytes[31] = 0xF0;
+        assert!(root_from_bytes::<PoseidonMerkleTreeParams>(&bytes).is_err());
+        let mut wide_bytes = bytes.clone();
+        wide_bytes.resize(48, 0);
+        let wide = WideFq::read(&wide_bytes[..]).unwrap();
+        assert!(root_from_field::<PoseidonMerkleTreeParams, _>(&wide).is_err());
+
+        // The largest top byte that still fits both 32-byte fields.
+        bytes[31] = 0x30;
+        let root = root_from_bytes::<PoseidonMerkleTreeParams>(&bytes).unwrap();
+        let bls = root_to_field::<PoseidonMerkleTreeParams, BlsFq>(&root).unwrap();
+        assert_eq!(ark_ff::to_bytes![bls].unwrap(), bytes);
+        assert_eq!(
+            root_from_field::<PoseidonMerkleTreeParams, _>(&bls).unwrap(),
+            root
+        );
+
+        // Wider elements only fit if their high bytes are zero.
+        wide_bytes[31] = 0x30;
+        wide_bytes[40] = 1;
+        let wide = WideFq::read(&wide_bytes[..]).unwrap();
+        assert!(root_from_field::<PoseidonMerkleTreeParams, _>(&wide).is_err());
+    }
+
+    #[test]
+    fn export_import_test() {
+        let crh_parameters = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let leaf = |i: u64| i.to_le_bytes();
+        let leaves = (0..4096).map(leaf).collect::<Vec<_>>();
+        let tree =
+            MerkleTree::<PoseidonHeight20Params>::new(crh_parameters.clone(), &leaves).unwrap();
+
+        let nodes = tree.export_nodes().collect::<Vec<_>>();
+        assert_eq!(nodes.len(), 2 * 4096 - 1 + 7);
+        assert_eq!(nodes[0], (0, 0, tree.subtree_root(0, 0).unwrap()));
+        assert_eq!(nodes[4096], (1, 0, tree.subtree_root(1, 0).unwrap()));
+        assert_eq!(nodes.last(), Some(&(19, 0, tree.root())));
+        for (level, index, digest) in &nodes {
+            assert_eq!(&tree.subtree_root(*level as usize, *index).unwrap(), digest);
+        }
+
+        // Import in reverse order, with and without verification.
+        for &verify in &[false, true] {
+            let imported = MerkleTree::<PoseidonHeight20Params>::import_nodes(
+                crh_parameters.clone(),
+                13,
+                nodes.iter().rev().cloned(),
+                verify,
+            )
+            .unwrap();
+            assert_eq!(imported.root(), tree.root());
+            assert!(imported.export_nodes().eq(nodes.iter().cloned()));
+            assert_eq!(
+                imported.generate_proof(1234, &leaf(1234)).unwrap(),
+                tree.generate_proof(1234, &leaf(1234)).unwrap()
+            );
+        }
+
+        // A tampered node is only caught with verification on.
+        let mut tampered = nodes.clone();
+        tampered[4096 + 5].2 = Fq::zero();
+        let import = |nodes: Vec<_>, verify| {
+            MerkleTree::<PoseidonHeight20Params>::import_nodes(
+                crh_parameters.clone(),
+                13,
+                nodes,
+                verify,
+            )
+        };
+        assert!(import(tampered.clone(), false).is_ok());
+        let err = import(tampered, true).err().unwrap();
+        match &err {
+            crate::Error::Crypto(CryptoError::Merkle(Error::InconsistentNode(1, 5))) => {}
+            _ => panic!("unexpected error: {}", err),
+        }
+
+        let mut tampered_root = nodes.clone();
+        tampered_root.last_mut().unwrap().2 = Fq::zero();
+        let err = import(tampered_root, true).err().unwrap();
+        match &err {
+            crate::Error::Crypto(CryptoError::Merkle(Error::InconsistentNode(19, 0))) => {}
+            _ => panic!("unexpected error: {}", err),
+        }
+
+        // Incomplete dumps and duplicate nodes are rejected either way.
+        let mut missing = nodes.clone();
+        missing.remove(4096 + 5);
+        let err = import(missing, false).err().unwrap();
+        match &err {
+            crate::Error::Crypto(CryptoError::Merkle(Error::MissingNode(1, 5))) => {}
+            _ => panic!("unexpected error: {}", err),
+        }
+        let mut duplicate = nodes;
+        duplicate.push(duplicate[0]);
+        assert!(import(duplicate, false).is_err());
+    }
+
+    #[test]
+    fn leaf_iter_test_poseidon() {
+        let crh_parameters = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        let leaf = |i: u64| {
+            let b = i.to_le_bytes();
+            [b[0], b[1], 0, 0, 0, 0, 0, 7]
+        };
+
+        // Around powers of two the frontier has to be completed with empty
+        // subtrees.
+        for &num_leaves in &[0u64, 1, 2, 3, 4, 5, 8, 13] {
+            let leaves = (0..num_leaves).map(leaf).collect::<Vec<_>>();
+            let tree = PoseidonMerkleTree::new(crh_parameters.clone(), &leaves).unwrap();
+            let root = root_from_leaf_iter::<PoseidonMerkleTreeParams, _, _>(
+                &crh_parameters,
+                (0..num_leaves).map(leaf),
+            )
+            .unwrap();
+            assert_eq!(tree.root(), root);
+        }
+
+        // Streaming stores exactly the nodes of the tree built in memory.
+        for &num_leaves in &[0u64, 1, 5, 8, 13] {
+            let leaves = (0..num_leaves).map(leaf).collect::<Vec<_>>();
+            let in_memory =
+                MerkleTree::<PoseidonHeight20Params>::new(crh_parameters.clone(), &leaves).unwrap();
+            let (root, nodes) = MerkleTree::<PoseidonHeight20Params>::from_leaf_iter(
+                &crh_parameters,
+                (0..num_leaves).map(leaf),
+                Some(Vec::new()),
+            )
+            .unwrap();
+            assert_eq!(root, in_memory.root());
+            let mut nodes = nodes.unwrap();
+            nodes.sort_by_key(|&(level, index, _)| (level, index));
+            assert!(in_memory.export_nodes().eq(nodes.into_iter()));
+        }
+
+        // A height-20 tree, with leaves produced lazily and indexed by `u64`,
+        // rebuilt from the streamed nodes.
+        let num_leaves = 600u64;
+        let leaves = (0..num_leaves).map(leaf).collect::<Vec<_>>();
+        let in_memory =
+            MerkleTree::<PoseidonHeight20Params>::new(crh_parameters.clone(), &leaves).unwrap();
+        let (root, nodes) = MerkleTree::<PoseidonHeight20Params>::from_leaf_iter(
+            &crh_parameters,
+            (0..num_leaves).map(leaf),
+            Some(Vec::new()),
+        )
+        .unwrap();
+        let frontier_root = root_from_leaf_iter::<PoseidonHeight20Params, _, _>(
+            &crh_parameters,
+            (0..num_leaves).map(leaf),
+        )
+        .unwrap();
+        assert_eq!(in_memory.root(), root);
+        assert_eq!(in_memory.root(), frontier_root);
+        let streamed = MerkleTree::<PoseidonHeight20Params>::import_nodes(
+            crh_parameters.clone(),
+            11,
+            nodes.unwrap(),
+            true,
+        )
+        .unwrap();
+
+        let index = num_leaves - 1;
+        let proof = streamed.generate_proof(index, &leaf(index)).unwrap();
+        assert_eq!(
+            proof,
+            in_memory.generate_proof(index, &leaf(index)).unwrap()
+        );
+        assert!(proof
+            .verify(&crh_parameters, &frontier_root, &leaf(index))
+            .unwrap());
+        assert!(streamed.generate_proof(u64::MAX, &leaf(index)).is_err());
+    }
+
+    struct LegacyHeight3;
+    impl Config for LegacyHeight3 {
+        const HEIGHT: usize = 3;
+        type H = PoseidonCRH3;
+    }
+
+    struct LegacyHeight2;
+    impl Config for LegacyHeight2 {
+        const HEIGHT: usize = 2;
+        type H = PoseidonCRH3;
+    }
+
+    struct TaggedHeight3;
+    impl Config for TaggedHeight3 {
+        const HEIGHT: usize = 3;
+        const DOMAIN_SEPARATION: DomainSeparation = DomainSeparation::Tagged { leaf: 0, node: 1 };
+        type H = PoseidonCRH3;
+    }
+
+    struct TaggedHeight2;
+    impl Config for TaggedHeight2 {
+        const HEIGHT: usize = 2;
+        const DOMAIN_SEPARATION: DomainSeparation = DomainSeparation::Tagged { leaf: 0, node: 1 };
+        type H = PoseidonCRH3;
+    }
+
+    /// Builds a tree of four leaves, then tries to prove that the bytes of
+    /// the two bottom-left children form a leaf of the same root, one level
+    /// higher up.
+    fn forge_node_as_leaf<Deep, Shallow>(crh_parameters: &PoseidonParameters<Fq>) -> bool
+    where
+        Deep: Config<H = PoseidonCRH3>,
+        Shallow: Config<H = PoseidonCRH3>,
+    {
+        let leaves = (0..4u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let deep = MerkleTree::<Deep>::new(crh_parameters.clone(), &leaves).unwrap();
+
+        let mut buffer = [0u8; 128];
+        let forged_leaves = leaves
+            .chunks(2)
+            .map(|pair| {
+                let left = hash_leaf::<Deep, _>(crh_parameters, &pair[0], &mut buffer).unwrap();
+                let right = hash_leaf::<Deep, _>(crh_parameters, &pair[1], &mut buffer).unwrap();
+                ark_ff::to_bytes![left, right].unwrap()
+            })
+            .collect::<Vec<_>>();
+        let shallow = MerkleTree::<Shallow>::new(crh_parameters.clone(), &forged_leaves).unwrap();
+        let proof = shallow.generate_proof(0, &forged_leaves[0]).unwrap();
+        proof
+            .verify(crh_parameters, &deep.root(), &forged_leaves[0])
+            .unwrap()
+    }
+
+    #[test]
+    fn domain_separation_test() {
+        let crh_parameters = PoseidonParameters::<Fq>::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+
+        assert!(forge_node_as_leaf::<LegacyHeight3, LegacyHeight2>(
+            &crh_parameters
+        ));
+        assert!(!forge_node_as_leaf::<TaggedHeight3, TaggedHeight2>(
+            &crh_parameters
+        ));
+
+        // Tagged trees still produce valid proofs, under a different root.
+        let leaves = (0..5u8).map(|i| [i; 8]).collect::<Vec<_>>();
+        let legacy =
+            MerkleTree::<LegacyHeight3>::new(crh_parameters.clone(), &leaves[..4]).unwrap();
+        let tagged =
+            MerkleTree::<TaggedHeight3>::new(crh_parameters.clone(), &leaves[..4]).unwrap();
+        assert_ne!(legacy.root(), tagged.root());
+        for (i, leaf) in leaves[..4].iter().enumerate() {
+            let proof = tagged.generate_proof(i as u64, leaf).unwrap();
+            assert!(proof.verify(&crh_parameters, &tagged.root(), leaf).unwrap());
+            assert!(!proof.verify(&crh_parameters, &legacy.root(), leaf).unwrap());
+        }
+
+        // The tag is the capacity element: a node hashes `[1, left, right]`.
+        let (left, right) = (Fq::from(3u64), Fq::from(4u64));
+        assert_eq!(
+            hash_inner_node::<TaggedHeight3>(&crh_parameters, &left, &right, &mut [0u8; 128])
+                .unwrap(),
+            PoseidonCRH3::evaluate_elements(&crh_parameters, vec![Fq::from(1u64), left, right])
+        );
+
+        // Leaves may fill the rest of the input, and no more.
+        assert!(MerkleTree::<TaggedHeight3>::new(crh_parameters.clone(), &[vec![7u8; 64]]).is_ok());
+        assert!(MerkleTree::<TaggedHeight3>::new(crh_parameters, &[vec![0u8; 65]]).is_err());
+    }
+
     #[test]
     fn good_root_test_poseidon() {
         let mut leaves = Vec::new();
@@ -2,7 +2,7 @@ use ark_ff::PrimeField;
 use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
 
 use crate::{prf::PRFGadget, Vec};
-use ark_r1cs_std::prelude::*;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*, ToConstraintFieldGadget};
 
 use core::borrow::Borrow;
 
@@ -324,6 +324,23 @@ impl<ConstraintF: PrimeField> EqGadget<ConstraintF> for OutputVar<ConstraintF> {
     }
 }
 
+impl<ConstraintF: PrimeField> CondSelectGadget<ConstraintF> for OutputVar<ConstraintF> {
+    #[tracing::instrument(target = "r1cs")]
+    fn conditionally_select(
+        cond: &Boolean<ConstraintF>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let bytes = true_value
+            .0
+            .iter()
+            .zip(&false_value.0)
+            .map(|(t, f)| UInt8::conditionally_select(cond, t, f))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(bytes))
+    }
+}
+
 impl<ConstraintF: PrimeField> ToBytesGadget<ConstraintF> for OutputVar<ConstraintF> {
     #[inline]
     fn to_bytes(&self) -> Result<Vec<UInt8<ConstraintF>>, SynthesisError> {
@@ -331,6 +348,15 @@ impl<ConstraintF: PrimeField> ToBytesGadget<ConstraintF> for OutputVar<Constrain
     }
 }
 
+/// Packs the digest as `ToConstraintField` does for `[u8; 32]`: little-endian
+/// chunks of `CAPACITY / 8` bytes, one element per chunk.
+impl<ConstraintF: PrimeField> ToConstraintFieldGadget<ConstraintF> for OutputVar<ConstraintF> {
+    #[inline]
+    fn to_constraint_field(&self) -> Result<Vec<FpVar<ConstraintF>>, SynthesisError> {
+        self.0.to_constraint_field()
+    }
+}
+
 impl<ConstraintF: PrimeField> AllocVar<[u8; 32], ConstraintF> for OutputVar<ConstraintF> {
     #[tracing::instrument(target = "r1cs", skip(cs, f))]
     fn new_variable<T: Borrow<[u8; 32]>>(
@@ -449,6 +475,30 @@ mod test {
         assert!(cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn output_to_constraint_field_test() {
+        use crate::prf::{PRFGadget, PRF};
+        use ark_ff::ToConstraintField;
+        use ark_r1cs_std::ToConstraintFieldGadget;
+
+        let mut rng = ark_std::test_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let seed: [u8; 32] = rng.gen();
+        let input: [u8; 32] = rng.gen();
+        let out = B2SPRF::evaluate(&seed, &input).unwrap();
+
+        let seed_var = Blake2sGadget::new_seed(cs.clone(), &seed);
+        let input_var = UInt8::new_witness_vec(cs.clone(), &input).unwrap();
+        let output_var = Blake2sGadget::evaluate(&seed_var, &input_var).unwrap();
+        let native: Vec<Fr> = out.to_field_elements().unwrap();
+        assert_eq!(native.len(), 2);
+        assert_eq!(
+            output_var.to_constraint_field().unwrap().value().unwrap(),
+            native
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_blake2s_precomp_constraints() {
         // Test that 512 fixed leading bits (constants)
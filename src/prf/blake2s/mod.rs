@@ -1,7 +1,11 @@
 use crate::Vec;
+use ark_std::cfg_iter;
 use ark_std::convert::TryFrom;
+use ark_std::marker::PhantomData;
 use blake2::{Blake2s as B2s, VarBlake2s};
 use digest::Digest;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use super::PRF;
 use crate::CryptoError;
@@ -27,6 +31,26 @@ impl PRF for Blake2s {
         end_timer!(eval_time);
         Ok(result)
     }
+
+    fn evaluate_batch(
+        seed: &Self::Seed,
+        inputs: &[Self::Input],
+    ) -> Result<Vec<Self::Output>, CryptoError> {
+        let eval_time = start_timer!(|| "Blake2s::EvalBatch");
+        let mut seeded = B2s::new();
+        seeded.update(seed.as_ref());
+        let result = cfg_iter!(inputs)
+            .map(|input| {
+                let mut h = seeded.clone();
+                h.update(input.as_ref());
+                let mut result = [0u8; 32];
+                result.copy_from_slice(&h.finalize());
+                result
+            })
+            .collect();
+        end_timer!(eval_time);
+        Ok(result)
+    }
 }
 
 #[derive(Clone)]
@@ -86,3 +110,164 @@ impl Blake2sWithParameterBlock {
         buf
     }
 }
+
+/// Blake2s PRF with a configurable output length of 1 to 65535 bytes, by
+/// counter-mode expansion.
+///
+/// This is a construction of this crate, not BLAKE2Xs: it hashes the whole
+/// message for every block instead of expanding a root hash, and its
+/// outputs differ from those of BLAKE2Xs.
+///
+/// With `seed` and `input` as in [`Blake2s`], the output is computed as
+/// follows:
+/// * `output_len <= 32`: Blake2s over `seed || input` with the digest length
+///   in the parameter block set to `output_len` and every other parameter at
+///   its default. A 32-byte output is therefore exactly that of [`Blake2s`].
+/// * `output_len > 32`: the concatenation of blocks `B_0, B_1, ...`, where
+///   `B_i` is Blake2s over `seed || input` with node offset `i`,
+///   personalization `LE16(output_len)` zero-padded to 8 bytes, and digest
+///   length `min(32, output_len - 32 * i)`.
+///
+/// Since the output length is part of the parameter block, outputs of
+/// different lengths are unrelated rather than prefixes of one another.
+///
+/// The length is chosen at runtime here; [`Blake2sExpand`] is the [`PRF`]
+/// with the length fixed at compile time.
+#[derive(Clone)]
+pub struct Blake2sCounterExpand {
+    pub output_len: usize,
+}
+
+impl Blake2sCounterExpand {
+    const BLOCK_LEN: usize = 32;
+
+    pub fn evaluate(&self, seed: &[u8; 32], input: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+        if self.output_len == 0 || self.output_len > u16::MAX as usize {
+            return Err(CryptoError::IncorrectInputLength(self.output_len));
+        }
+        let eval_time = start_timer!(|| "Blake2sCounterExpand::Eval");
+        let mut message = Vec::with_capacity(seed.len() + input.len());
+        message.extend_from_slice(seed);
+        message.extend_from_slice(input);
+
+        let mut personalization = [0u8; 8];
+        if self.output_len > Self::BLOCK_LEN {
+            personalization[..2].copy_from_slice(&(self.output_len as u16).to_le_bytes());
+        }
+        let mut result = Vec::with_capacity(self.output_len);
+        let mut node_offset = 0;
+        while result.len() < self.output_len {
+            let block = Blake2sWithParameterBlock {
+                digest_length: (self.output_len - result.len()).min(Self::BLOCK_LEN) as u8,
+                key_length: 0,
+                fan_out: 1,
+                depth: 1,
+                leaf_length: 0,
+                node_offset,
+                xof_digest_length: 0,
+                node_depth: 0,
+                inner_length: 0,
+                salt: [0; 8],
+                personalization,
+            };
+            result.extend_from_slice(&block.evaluate(&message));
+            node_offset += 1;
+        }
+        end_timer!(eval_time);
+        Ok(result)
+    }
+}
+
+/// The output length, in bytes, of a [`Blake2sExpand`].
+pub trait ExpandLength: Clone {
+    const OUTPUT_LEN: usize;
+}
+
+/// [`Blake2sCounterExpand`] as a [`PRF`], for generic users of the trait.
+/// `PRF::evaluate` takes no instance, so the output length is fixed by `L`
+/// instead of a field.
+#[derive(Clone)]
+pub struct Blake2sExpand<L: ExpandLength> {
+    _length: PhantomData<L>,
+}
+
+impl<L: ExpandLength> PRF for Blake2sExpand<L> {
+    type Input = [u8; 32];
+    type Output = Vec<u8>;
+    type Seed = [u8; 32];
+
+    fn evaluate(seed: &Self::Seed, input: &Self::Input) -> Result<Self::Output, CryptoError> {
+        Blake2sCounterExpand {
+            output_len: L::OUTPUT_LEN,
+        }
+        .evaluate(seed, input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_std::rand::Rng;
+
+    #[test]
+    fn evaluate_batch_test() {
+        let mut rng = ark_std::test_rng();
+        let seed: [u8; 32] = rng.gen();
+        let inputs: Vec<[u8; 32]> = (0..4096).map(|_| rng.gen()).collect();
+        let outputs = Blake2s::evaluate_batch(&seed, &inputs).unwrap();
+        assert_eq!(outputs.len(), inputs.len());
+        for (input, output) in inputs.iter().zip(&outputs) {
+            assert_eq!(*output, Blake2s::evaluate(&seed, input).unwrap());
+        }
+        assert!(Blake2s::evaluate_batch(&seed, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn blake2s_counter_expand_test() {
+        let mut rng = ark_std::test_rng();
+        let seed: [u8; 32] = rng.gen();
+        let input: [u8; 32] = rng.gen();
+
+        let evaluate = |output_len| Blake2sCounterExpand { output_len }.evaluate(&seed, &input);
+        let out_16 = evaluate(16).unwrap();
+        let out_32 = evaluate(32).unwrap();
+        let out_64 = evaluate(64).unwrap();
+        assert_eq!(out_16.len(), 16);
+        assert_eq!(out_64.len(), 64);
+        assert_eq!(out_32, Blake2s::evaluate(&seed, &input).unwrap().to_vec());
+
+        assert_ne!(out_16[..], out_32[..16]);
+        assert_ne!(out_32[..], out_64[..32]);
+        assert_ne!(out_64[..32], out_64[32..]);
+        assert_ne!(evaluate(33).unwrap()[..], out_64[..33]);
+
+        assert!(evaluate(0).is_err());
+        assert!(evaluate(1 << 16).is_err());
+    }
+
+    #[test]
+    fn blake2s_expand_prf_test() {
+        #[derive(Clone)]
+        struct SixtyFourBytes;
+        impl ExpandLength for SixtyFourBytes {
+            const OUTPUT_LEN: usize = 64;
+        }
+        #[derive(Clone)]
+        struct Empty;
+        impl ExpandLength for Empty {
+            const OUTPUT_LEN: usize = 0;
+        }
+
+        let mut rng = ark_std::test_rng();
+        let seed: [u8; 32] = rng.gen();
+        let inputs: Vec<[u8; 32]> = (0..4).map(|_| rng.gen()).collect();
+        let outputs = Blake2sExpand::<SixtyFourBytes>::evaluate_batch(&seed, &inputs).unwrap();
+        for (input, output) in inputs.iter().zip(&outputs) {
+            let expected = Blake2sCounterExpand { output_len: 64 }
+                .evaluate(&seed, input)
+                .unwrap();
+            assert_eq!(*output, expected);
+        }
+        assert!(Blake2sExpand::<Empty>::evaluate(&seed, &inputs[0]).is_err());
+    }
+}
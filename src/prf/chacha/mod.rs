@@ -0,0 +1,239 @@
+use super::PRF;
+use crate::CryptoError;
+use ark_std::{
+    convert::TryInto,
+    rand::{CryptoRng, Error as RandError, RngCore, SeedableRng},
+};
+
+/// PRF based on the ChaCha20 block function of RFC 8439, for native use
+/// only.
+///
+/// The seed is the 256-bit key. The 16-byte input fills the last four words
+/// of the initial state: bytes `0..4` are the little-endian block counter
+/// and bytes `4..16` are the nonce. The output is the first 32 bytes of the
+/// resulting keystream block; [`ChaChaPRF::evaluate_block`] returns all 64.
+#[derive(Clone)]
+pub struct ChaChaPRF;
+
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+impl ChaChaPRF {
+    /// Returns the full 64-byte ChaCha20 block for `seed` and `input`.
+    pub fn evaluate_block(seed: &[u8; 32], input: &[u8; 16]) -> [u8; 64] {
+        let mut state = [0u32; 16];
+        state[..4].copy_from_slice(&CONSTANTS);
+        for (word, bytes) in state[4..]
+            .iter_mut()
+            .zip(seed.chunks(4).chain(input.chunks(4)))
+        {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        let mut working = state;
+        for _ in 0..10 {
+            quarter_round(&mut working, 0, 4, 8, 12);
+            quarter_round(&mut working, 1, 5, 9, 13);
+            quarter_round(&mut working, 2, 6, 10, 14);
+            quarter_round(&mut working, 3, 7, 11, 15);
+            quarter_round(&mut working, 0, 5, 10, 15);
+            quarter_round(&mut working, 1, 6, 11, 12);
+            quarter_round(&mut working, 2, 7, 8, 13);
+            quarter_round(&mut working, 3, 4, 9, 14);
+        }
+
+        let mut block = [0u8; 64];
+        for ((bytes, word), initial) in block.chunks_mut(4).zip(&working).zip(&state) {
+            bytes.copy_from_slice(&word.wrapping_add(*initial).to_le_bytes());
+        }
+        block
+    }
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// A deterministic RNG that expands a 32-byte seed into the ChaCha20
+/// keystream, with a zero nonce and a 64-bit block counter. It needs no
+/// entropy source, so it works under `no_std`; the `*_from_seed` setups of
+/// this crate use it.
+#[derive(Clone)]
+pub struct ChaChaRng {
+    seed: [u8; 32],
+    counter: u64,
+    block: [u8; 64],
+    position: usize,
+}
+
+impl ChaChaRng {
+    fn refill(&mut self) {
+        let mut input = [0u8; 16];
+        input[..8].copy_from_slice(&self.counter.to_le_bytes());
+        self.block = ChaChaPRF::evaluate_block(&self.seed, &input);
+        self.counter = self.counter.wrapping_add(1);
+        self.position = 0;
+    }
+}
+
+impl SeedableRng for ChaChaRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            seed,
+            counter: 0,
+            block: [0u8; 64],
+            position: 64,
+        }
+    }
+}
+
+impl RngCore for ChaChaRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut filled = 0;
+        while filled < dest.len() {
+            if self.position == 64 {
+                self.refill();
+            }
+            let n = (dest.len() - filled).min(64 - self.position);
+            dest[filled..filled + n].copy_from_slice(&self.block[self.position..self.position + n]);
+            self.position += n;
+            filled += n;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RandError> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for ChaChaRng {}
+
+impl PRF for ChaChaPRF {
+    type Input = [u8; 16];
+    type Output = [u8; 32];
+    type Seed = [u8; 32];
+
+    fn evaluate(seed: &Self::Seed, input: &Self::Input) -> Result<Self::Output, CryptoError> {
+        let eval_time = start_timer!(|| "ChaChaPRF::Eval");
+        let mut result = [0u8; 32];
+        result.copy_from_slice(&Self::evaluate_block(seed, input)[..32]);
+        end_timer!(eval_time);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prf::Blake2s;
+    use crate::Vec;
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn rfc8439_test() {
+        // RFC 8439, section 2.3.2.
+        let mut seed = [0u8; 32];
+        for (i, byte) in seed.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let input = [
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+        let block = ChaChaPRF::evaluate_block(&seed, &input);
+        assert_eq!(
+            to_hex(&block),
+            "10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e\
+             d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e"
+        );
+        assert_eq!(ChaChaPRF::evaluate(&seed, &input).unwrap()[..], block[..32]);
+
+        // RFC 8439, appendix A.1, test vector #2.
+        let mut input = [0u8; 16];
+        input[0] = 1;
+        assert_eq!(
+            to_hex(&ChaChaPRF::evaluate_block(&[0u8; 32], &input)),
+            "9f07e7be5551387a98ba977c732d080dcb0f29a048e3656912c6533e32ee7aed\
+             29b721769ce64e43d57133b074d839d531ed1f28510afb45ace10a1f4b794d6f"
+        );
+    }
+
+    #[test]
+    fn rng_test() {
+        // The keystream for the all-zero key and nonce, from RFC 8439,
+        // appendix A.1, test vectors #1 and #2.
+        let mut rng = ChaChaRng::from_seed([0u8; 32]);
+        let mut bytes = [0u8; 128];
+        rng.fill_bytes(&mut bytes[..5]);
+        rng.fill_bytes(&mut bytes[5..100]);
+        rng.fill_bytes(&mut bytes[100..]);
+        assert_eq!(
+            to_hex(&bytes[..64]),
+            "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7\
+             da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586"
+        );
+        assert_eq!(
+            bytes[64..],
+            ChaChaPRF::evaluate_block(
+                &[0u8; 32],
+                &[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+            )[..]
+        );
+
+        let mut rng = ChaChaRng::from_seed([0u8; 32]);
+        assert_eq!(rng.next_u32(), u32::from_le_bytes([0x76, 0xb8, 0xe0, 0xad]));
+        assert_eq!(
+            rng.next_u64(),
+            u64::from_le_bytes([0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5])
+        );
+    }
+
+    fn evaluate_all<P: PRF>(seed: &P::Seed, inputs: &[P::Input]) -> Vec<P::Output> {
+        inputs
+            .iter()
+            .map(|input| P::evaluate(seed, input).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn generic_prf_test() {
+        let seed = [3u8; 32];
+        let chacha_inputs: Vec<_> = (0u8..4).map(|i| [i; 16]).collect();
+        let blake2s_inputs: Vec<_> = (0u8..4).map(|i| [i; 32]).collect();
+        let chacha = evaluate_all::<ChaChaPRF>(&seed, &chacha_inputs);
+        let blake2s = evaluate_all::<Blake2s>(&seed, &blake2s_inputs);
+        assert_eq!(
+            chacha,
+            ChaChaPRF::evaluate_batch(&seed, &chacha_inputs).unwrap()
+        );
+        assert_eq!(
+            blake2s,
+            Blake2s::evaluate_batch(&seed, &blake2s_inputs).unwrap()
+        );
+        assert_ne!(chacha, blake2s);
+    }
+}
@@ -1,9 +1,10 @@
-use ark_ff::Field;
+use ark_ff::{Field, FpParameters, PrimeField};
 use core::fmt::Debug;
 
-use crate::{prf::PRF, Vec};
+use crate::{prf::PRF, utils::constraints as utils, Vec};
 use ark_relations::r1cs::{Namespace, SynthesisError};
 
+use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
 
 pub trait PRFGadget<P: PRF, F: Field> {
@@ -18,3 +19,48 @@ pub trait PRFGadget<P: PRF, F: Field> {
 
     fn evaluate(seed: &[UInt8<F>], input: &[UInt8<F>]) -> Result<Self::OutputVar, SynthesisError>;
 }
+
+/// Converts the bytes of a PRF output to a field element with the semantics
+/// of [`output_to_field`](crate::prf::output_to_field): the little-endian
+/// integer is reduced modulo the field order rather than range checked.
+pub fn output_var_to_field<F: PrimeField>(output: &[UInt8<F>]) -> Result<FpVar<F>, SynthesisError> {
+    if output.len() * 8 < F::Params::CAPACITY as usize {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    Ok(utils::le_bits_to_fp(&output.to_bits_le()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prf::output_to_field;
+    use ark_ed_on_bls12_381::Fq as Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::Rng;
+
+    #[test]
+    fn output_to_field_test() {
+        let mut rng = ark_std::test_rng();
+        let mut outputs: Vec<Vec<u8>> = (0..8).map(|_| rng.gen::<[u8; 32]>().to_vec()).collect();
+        // Both exceed the modulus.
+        outputs.push(vec![0xff; 32]);
+        outputs.push(vec![0xff; 64]);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        for output in &outputs {
+            let native = output_to_field::<Fr>(output).unwrap();
+            let output_var = UInt8::new_witness_vec(cs.clone(), output).unwrap();
+            let field_var = output_var_to_field(&output_var).unwrap();
+            assert_eq!(field_var.value().unwrap(), native);
+        }
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(
+            output_to_field::<Fr>(&[0xff; 32]).unwrap(),
+            Fr::from_le_bytes_mod_order(&[0xff; 32])
+        );
+
+        assert!(output_to_field::<Fr>(&[0u8; 31]).is_err());
+        let short = UInt8::new_witness_vec(cs, &[0u8; 31]).unwrap();
+        assert!(output_var_to_field(&short).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! Key derivation on top of any [`PRF`].
+//!
+//! The key with index `index` in domain `domain` is `PRF(seed, input)`,
+//! where `input` is read from the byte string
+//!
+//! ```text
+//! len(domain) as u8 || domain || index as little-endian u64 || 0x00 ...
+//! ```
+//!
+//! zero-padded to the length of the PRF input. The encoding must fit in one
+//! PRF input, so for [`Blake2s`](super::Blake2s) domains are at most 23
+//! bytes long.
+
+use super::PRF;
+use crate::{CryptoError, Vec};
+use ark_ff::bytes::FromBytes;
+use ark_std::io::{Read, Result as IoResult};
+
+/// Derives the key with index `index` in `domain` from `seed`.
+pub fn derive_key<P: PRF>(
+    seed: &P::Seed,
+    domain: &[u8],
+    index: u64,
+) -> Result<P::Output, CryptoError> {
+    let input = encode_input::<P>(domain, index)?;
+    P::evaluate(seed, &input)
+}
+
+/// Derives the keys with indices `0..count` in `domain` from `seed`.
+pub fn derive_many<P: PRF>(
+    seed: &P::Seed,
+    domain: &[u8],
+    count: u64,
+) -> Result<Vec<P::Output>, CryptoError> {
+    (0..count)
+        .map(|index| derive_key::<P>(seed, domain, index))
+        .collect()
+}
+
+fn encode_input<P: PRF>(domain: &[u8], index: u64) -> Result<P::Input, CryptoError> {
+    if domain.len() > u8::MAX as usize {
+        return Err(CryptoError::IncorrectInputLength(domain.len()));
+    }
+    let mut encoding = Vec::with_capacity(1 + domain.len() + 8);
+    encoding.push(domain.len() as u8);
+    encoding.extend_from_slice(domain);
+    encoding.extend_from_slice(&index.to_le_bytes());
+
+    let mut reader = ZeroPadded {
+        bytes: &encoding,
+        position: 0,
+    };
+    let input = P::Input::read(&mut reader)
+        .map_err(|_| CryptoError::IncorrectInputLength(encoding.len()))?;
+    // Any byte the input did not consume would be silently dropped.
+    if reader.position < encoding.len() {
+        return Err(CryptoError::IncorrectInputLength(encoding.len()));
+    }
+    Ok(input)
+}
+
+/// Reads `bytes` followed by as many zeros as requested.
+struct ZeroPadded<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Read for ZeroPadded<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        for byte in buf.iter_mut() {
+            *byte = self.bytes.get(self.position).copied().unwrap_or(0);
+            self.position += 1;
+        }
+        Ok(buf.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prf::Blake2s;
+
+    #[test]
+    fn derive_key_test() {
+        let seed = [7u8; 32];
+        let nullifier = derive_many::<Blake2s>(&seed, b"nullifier", 4).unwrap();
+        let viewing = derive_many::<Blake2s>(&seed, b"viewing", 4).unwrap();
+        for (i, key) in nullifier.iter().enumerate() {
+            assert_eq!(
+                *key,
+                derive_key::<Blake2s>(&seed, b"nullifier", i as u64).unwrap()
+            );
+            assert!(!viewing.contains(key));
+            assert!(!nullifier[i + 1..].contains(key));
+        }
+
+        let mut input = [0u8; 32];
+        input[0] = 9;
+        input[1..10].copy_from_slice(b"nullifier");
+        input[10] = 3;
+        assert_eq!(nullifier[3], Blake2s::evaluate(&seed, &input).unwrap());
+        // Pin the output, so that the encoding cannot change unnoticed.
+        let key_hex = nullifier[0]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        assert_eq!(
+            key_hex,
+            "643d50499eda222a9648142aff99680bae6cbb0c5ec24945aaa2b8cf7d1c9480"
+        );
+
+        assert!(derive_key::<Blake2s>(&seed, &[0u8; 23], 0).is_ok());
+        assert!(derive_key::<Blake2s>(&seed, &[0u8; 24], 0).is_err());
+    }
+}
@@ -1,7 +1,8 @@
 use ark_ff::bytes::{FromBytes, ToBytes};
+use ark_ff::{FpParameters, PrimeField};
 use core::{fmt::Debug, hash::Hash};
 
-use crate::CryptoError;
+use crate::{CryptoError, Vec};
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
@@ -9,7 +10,10 @@ pub mod constraints;
 pub use constraints::*;
 
 pub mod blake2s;
+pub mod chacha;
+pub mod kdf;
 pub use self::blake2s::*;
+pub use self::chacha::{ChaChaPRF, ChaChaRng};
 
 pub trait PRF {
     type Input: FromBytes + Default;
@@ -17,4 +21,34 @@ pub trait PRF {
     type Seed: FromBytes + ToBytes + Clone + Default + Debug;
 
     fn evaluate(seed: &Self::Seed, input: &Self::Input) -> Result<Self::Output, CryptoError>;
+
+    /// Evaluates the PRF under one seed on each of `inputs`, in order.
+    fn evaluate_batch(
+        seed: &Self::Seed,
+        inputs: &[Self::Input],
+    ) -> Result<Vec<Self::Output>, CryptoError> {
+        inputs
+            .iter()
+            .map(|input| Self::evaluate(seed, input))
+            .collect()
+    }
+}
+
+/// Converts a PRF output to a field element, for use as a nullifier. The
+/// output is read as a little-endian integer and reduced modulo the field
+/// order `p`. Outputs with fewer bits than the field's capacity are
+/// rejected, since their images would not cover the field.
+///
+/// The result is not uniform. For an `n`-bit output, the residues below
+/// `2^n mod p` are `(q + 1) / q` times as likely as the others, where
+/// `q = 2^n / p` rounded down: a 32-byte output makes them 50% more likely
+/// in the BLS12-381 scalar field and 20% more likely in the BN254 one. The
+/// bias only becomes negligible from `MODULUS_BITS + 128` bits, so values
+/// that must be uniform, such as blinding factors, should not be derived
+/// this way from shorter outputs.
+pub fn output_to_field<F: PrimeField>(output: &[u8]) -> Result<F, CryptoError> {
+    if output.len() * 8 < F::Params::CAPACITY as usize {
+        return Err(CryptoError::IncorrectInputLength(output.len()));
+    }
+    Ok(F::from_le_bytes_mod_order(output))
 }
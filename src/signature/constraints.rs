@@ -18,3 +18,21 @@ pub trait SigRandomizePkGadget<S: SignatureScheme, ConstraintF: Field> {
         randomness: &[UInt8<ConstraintF>],
     ) -> Result<Self::PublicKeyVar, SynthesisError>;
 }
+
+pub trait SigVerifyGadget<S: SignatureScheme, ConstraintF: Field> {
+    type ParametersVar: AllocVar<S::Parameters, ConstraintF> + Clone;
+
+    type PublicKeyVar: ToBytesGadget<ConstraintF>
+        + EqGadget<ConstraintF>
+        + AllocVar<S::PublicKey, ConstraintF>
+        + Clone;
+
+    type SignatureVar: AllocVar<S::Signature, ConstraintF> + Clone;
+
+    fn verify(
+        parameters: &Self::ParametersVar,
+        public_key: &Self::PublicKeyVar,
+        message: &[UInt8<ConstraintF>],
+        signature: &Self::SignatureVar,
+    ) -> Result<Boolean<ConstraintF>, SynthesisError>;
+}
@@ -1,7 +1,7 @@
-use crate::Error;
+use crate::{prf::ChaChaRng, Box, Error};
 use ark_ff::bytes::ToBytes;
 use ark_std::hash::Hash;
-use ark_std::rand::Rng;
+use ark_std::rand::{Rng, SeedableRng};
 
 #[cfg(feature = "r1cs")]
 pub mod constraints;
@@ -10,6 +10,46 @@ pub use constraints::*;
 
 pub mod schnorr;
 
+/// The reason a signature is rejected.
+#[derive(Debug)]
+pub enum SignatureError {
+    /// The public key is the identity or outside the prime order subgroup.
+    InvalidPublicKey,
+    /// The signature does not decode, or the values derived from it cannot
+    /// be encoded for hashing.
+    InvalidSignatureEncoding,
+    /// The challenge recomputed from the signature is not its challenge.
+    ChallengeMismatch,
+    /// The item at `index` of a batch is rejected.
+    BatchItem {
+        index: usize,
+        error: Box<SignatureError>,
+    },
+    /// A signing session was opened while another one is open.
+    SessionInProgress,
+    /// A signing session was answered while none is open.
+    NoSession,
+}
+
+impl core::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SignatureError::InvalidPublicKey => write!(f, "public key is not valid"),
+            SignatureError::InvalidSignatureEncoding => {
+                write!(f, "signature encoding is not valid")
+            }
+            SignatureError::ChallengeMismatch => write!(f, "signature challenge does not match"),
+            SignatureError::BatchItem { index, error } => {
+                write!(f, "batch item {} is not valid: {}", index, error)
+            }
+            SignatureError::SessionInProgress => write!(f, "a session is already open"),
+            SignatureError::NoSession => write!(f, "no session is open"),
+        }
+    }
+}
+
+impl ark_std::error::Error for SignatureError {}
+
 pub trait SignatureScheme {
     type Parameters: Clone + Send + Sync;
     type PublicKey: ToBytes + Hash + Eq + Clone + Default + Send + Sync;
@@ -18,11 +58,26 @@ pub trait SignatureScheme {
 
     fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error>;
 
+    /// Runs [`setup`](Self::setup) with a [`ChaChaRng`] seeded by `seed`, so
+    /// that the same seed always gives the same parameters.
+    fn setup_from_seed(seed: [u8; 32]) -> Result<Self::Parameters, Error> {
+        Self::setup(&mut ChaChaRng::from_seed(seed))
+    }
+
     fn keygen<R: Rng>(
         pp: &Self::Parameters,
         rng: &mut R,
     ) -> Result<(Self::PublicKey, Self::SecretKey), Error>;
 
+    /// Runs [`keygen`](Self::keygen) with a [`ChaChaRng`] seeded by `seed`.
+    /// The seed is then as secret as the key it derives.
+    fn keygen_from_seed(
+        pp: &Self::Parameters,
+        seed: [u8; 32],
+    ) -> Result<(Self::PublicKey, Self::SecretKey), Error> {
+        Self::keygen(pp, &mut ChaChaRng::from_seed(seed))
+    }
+
     fn sign<R: Rng>(
         pp: &Self::Parameters,
         sk: &Self::SecretKey,
@@ -37,6 +92,29 @@ pub trait SignatureScheme {
         signature: &Self::Signature,
     ) -> Result<bool, Error>;
 
+    /// Verifies every `(public_key, message, signature)` item, returning
+    /// `true` only if all of them are valid. Schemes override this to share
+    /// work between the items.
+    ///
+    /// `rng` is for schemes that check the items with one random linear
+    /// combination of their verification equations. Implementations without
+    /// such an equation may ignore it: a Schnorr signature is `(s, e)`, so
+    /// each commitment must be recomputed and hashed on its own and there is
+    /// nothing to randomize. Callers must still pass a cryptographically
+    /// secure generator, since the scheme behind the trait may use it.
+    fn batch_verify<R: Rng>(
+        pp: &Self::Parameters,
+        items: &[(Self::PublicKey, &[u8], Self::Signature)],
+        _rng: &mut R,
+    ) -> Result<bool, Error> {
+        for (pk, message, signature) in items {
+            if !Self::verify(pp, pk, message, signature)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     fn randomize_public_key(
         pp: &Self::Parameters,
         public_key: &Self::PublicKey,
@@ -53,9 +131,12 @@ pub trait SignatureScheme {
 #[cfg(test)]
 mod test {
     use crate::signature::{schnorr, *};
-    use ark_ec::group::Group;
+    use ark_ec::{group::Group, AffineCurve, ProjectiveCurve};
     use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_ed_on_bls12_381::{EdwardsAffine, Fq};
     use ark_ff::to_bytes;
+    use ark_ff::Field;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
     use ark_std::{test_rng, UniformRand};
     use blake2::Blake2s;
 
@@ -84,6 +165,334 @@ mod test {
         let randomized_pk = S::randomize_public_key(&parameters, &pk, randomness).unwrap();
         let randomized_sig = S::randomize_signature(&parameters, &sig, randomness).unwrap();
         assert!(S::verify(&parameters, &randomized_pk, &message, &randomized_sig).unwrap());
+        assert!(!S::verify(&parameters, &pk, message, &randomized_sig).unwrap());
+        assert!(!S::verify(&parameters, &randomized_pk, message, &sig).unwrap());
+
+        // Randomizing with different randomness does not verify.
+        let mut other_randomness = randomness.to_vec();
+        other_randomness[0] ^= 1;
+        let other_sig = S::randomize_signature(&parameters, &sig, &other_randomness).unwrap();
+        assert!(!S::verify(&parameters, &randomized_pk, message, &other_sig).unwrap());
+    }
+
+    fn batch_verify<S: SignatureScheme>(batch_size: usize) {
+        let rng = &mut test_rng();
+        let parameters = S::setup::<_>(rng).unwrap();
+        let mut items = Vec::new();
+        for i in 0..batch_size {
+            let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+            let message = if i % 2 == 0 {
+                &b"even"[..]
+            } else {
+                &b"odd"[..]
+            };
+            let sig = S::sign(&parameters, &sk, message, rng).unwrap();
+            items.push((pk, message, sig));
+        }
+        assert!(S::batch_verify(&parameters, &items, rng).unwrap());
+        if batch_size == 0 {
+            return;
+        }
+
+        // Forge the last signature by moving it to another message.
+        let forged = batch_size - 1;
+        items[forged].1 = b"forged";
+        for _ in 0..4 {
+            assert!(!S::batch_verify(&parameters, &items, rng).unwrap());
+        }
+        // And by signing with the wrong key.
+        let (_, other_sk) = S::keygen(&parameters, rng).unwrap();
+        items[forged].1 = b"even";
+        items[forged].2 = S::sign(&parameters, &other_sk, b"even", rng).unwrap();
+        assert!(!S::batch_verify(&parameters, &items, rng).unwrap());
+    }
+
+    #[test]
+    fn schnorr_from_seed_test() {
+        type S = schnorr::Schnorr<JubJub, Blake2s>;
+        let parameters = S::setup_from_seed([1u8; 32]).unwrap();
+        let again = S::setup_from_seed([1u8; 32]).unwrap();
+        assert_eq!(parameters.generator, again.generator);
+        assert_eq!(parameters.salt, again.salt);
+        assert_ne!(parameters.salt, S::setup_from_seed([2u8; 32]).unwrap().salt);
+
+        let (pk, sk) = S::keygen_from_seed(&parameters, [3u8; 32]).unwrap();
+        let (again_pk, again_sk) = S::keygen_from_seed(&parameters, [3u8; 32]).unwrap();
+        assert_eq!(pk, again_pk);
+        assert_eq!(sk.0, again_sk.0);
+        assert_ne!(pk, S::keygen_from_seed(&parameters, [4u8; 32]).unwrap().0);
+
+        let sig = S::sign(&parameters, &sk, b"message", &mut test_rng()).unwrap();
+        assert!(S::verify(&parameters, &pk, b"message", &sig).unwrap());
+    }
+
+    #[test]
+    fn schnorr_batch_verify_test() {
+        for batch_size in [0, 1, 3, 16, 33].iter() {
+            batch_verify::<schnorr::Schnorr<JubJub, Blake2s>>(*batch_size);
+        }
+    }
+
+    #[test]
+    fn schnorr_deterministic_signature_test() {
+        type S = schnorr::Schnorr<JubJub, Blake2s>;
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let prover_commitment = |sig: &schnorr::Signature<JubJub>| {
+            AffineCurve::mul(&parameters.generator, sig.prover_response)
+                + AffineCurve::mul(&pk, sig.verifier_challenge)
+        };
+
+        let sig = S::sign_deterministic(&parameters, &sk, b"message").unwrap();
+        let same_sig = S::sign_deterministic(&parameters, &sk, b"message").unwrap();
+        let other_sig = S::sign_deterministic(&parameters, &sk, b"other message").unwrap();
+        assert_eq!(sig.prover_response, same_sig.prover_response);
+        assert_eq!(sig.verifier_challenge, same_sig.verifier_challenge);
+        assert_ne!(prover_commitment(&sig), prover_commitment(&other_sig));
+        assert!(S::verify(&parameters, &pk, b"message", &sig).unwrap());
+        assert!(S::verify(&parameters, &pk, b"other message", &other_sig).unwrap());
+
+        // The same key and message under another salt get another nonce.
+        let mut salted = parameters.clone();
+        salted.salt[0] ^= 1;
+        let salted_sig = S::sign_deterministic(&salted, &sk, b"message").unwrap();
+        assert!(S::verify(&salted, &pk, b"message", &salted_sig).unwrap());
+        assert_ne!(prover_commitment(&sig), prover_commitment(&salted_sig));
+
+        let random_sig = S::sign(&parameters, &sk, b"message", rng).unwrap();
+        let other_random_sig = S::sign(&parameters, &sk, b"message", rng).unwrap();
+        assert_ne!(prover_commitment(&random_sig), prover_commitment(&sig));
+        assert_ne!(
+            prover_commitment(&random_sig),
+            prover_commitment(&other_random_sig)
+        );
+        assert!(S::verify(&parameters, &pk, b"message", &random_sig).unwrap());
+    }
+
+    #[test]
+    fn schnorr_prehashed_signature_test() {
+        use digest::Digest;
+        type S = schnorr::Schnorr<JubJub, Blake2s>;
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let digest = Blake2s::digest(b"a very long payload");
+        let other_digest = Blake2s::digest(b"another very long payload");
+
+        let sig = S::sign_prehashed(&parameters, &sk, &digest, rng).unwrap();
+        assert!(S::verify_prehashed(&parameters, &pk, &digest, &sig).unwrap());
+        assert!(!S::verify_prehashed(&parameters, &pk, &other_digest, &sig).unwrap());
+
+        // A prehashed signature is not a signature over the digest bytes,
+        // and a signature over the digest bytes is not a prehashed one.
+        assert!(!S::verify(&parameters, &pk, &digest, &sig).unwrap());
+        let raw_sig = S::sign(&parameters, &sk, &digest, rng).unwrap();
+        assert!(S::verify(&parameters, &pk, &digest, &raw_sig).unwrap());
+        assert!(!S::verify_prehashed(&parameters, &pk, &digest, &raw_sig).unwrap());
+    }
+
+    #[test]
+    fn schnorr_context_signature_test() {
+        type S = schnorr::Schnorr<JubJub, Blake2s>;
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let message = b"withdraw";
+        let staging = b"webb-bridge-v1:chain-4";
+        let production = b"webb-bridge-v1:chain-5";
+
+        let sig = S::sign_with_context(&parameters, &sk, staging, message, rng).unwrap();
+        assert!(S::verify_with_context(&parameters, &pk, staging, message, &sig).unwrap());
+        assert!(!S::verify_with_context(&parameters, &pk, production, message, &sig).unwrap());
+        assert!(!S::verify_with_context(&parameters, &pk, b"", message, &sig).unwrap());
+        assert!(!S::verify(&parameters, &pk, message, &sig).unwrap());
+
+        // The empty context is the plain API.
+        let sig = S::sign(&parameters, &sk, message, rng).unwrap();
+        assert!(S::verify_with_context(&parameters, &pk, b"", message, &sig).unwrap());
+        assert!(!S::verify_with_context(&parameters, &pk, staging, message, &sig).unwrap());
+        let sig = S::sign_with_context(&parameters, &sk, b"", message, rng).unwrap();
+        assert!(S::verify(&parameters, &pk, message, &sig).unwrap());
+    }
+
+    #[test]
+    fn schnorr_public_key_validation_test() {
+        use ark_ff::{One, Zero};
+        type S = schnorr::Schnorr<JubJub, Blake2s>;
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let message = b"message";
+        let sig = S::sign(&parameters, &sk, message, rng).unwrap();
+        assert!(schnorr::validate_public_key::<JubJub>(&pk).is_ok());
+
+        // (0, -1) has order 2.
+        let small_order = EdwardsAffine::new(Fq::zero(), -Fq::one());
+        assert!(small_order.is_on_curve());
+        let bad_keys = vec![
+            EdwardsAffine::zero(),
+            small_order,
+            (pk.into_projective() + small_order.into_projective()).into_affine(),
+        ];
+        for bad_pk in bad_keys {
+            assert_eq!(
+                format!(
+                    "{}",
+                    schnorr::validate_public_key::<JubJub>(&bad_pk).unwrap_err()
+                ),
+                format!("{}", crate::CryptoError::NotPrimeOrder)
+            );
+            assert!(S::verify(&parameters, &bad_pk, message, &sig).is_err());
+            let items = vec![(bad_pk, &message[..], sig.clone()); schnorr::BATCH_VERIFY_THRESHOLD];
+            assert!(S::batch_verify(&parameters, &items, rng).is_err());
+        }
+
+        // Keys validated once at registration skip the check when verifying.
+        let validated = schnorr::ValidatedPublicKey::<JubJub>::new(pk).unwrap();
+        assert_eq!(validated.public_key(), &pk);
+        assert!(schnorr::ValidatedPublicKey::<JubJub>::new(small_order).is_err());
+        assert!(S::verify_validated(&parameters, &validated, message, &sig).is_ok());
+        assert!(matches!(
+            S::verify_validated(&parameters, &validated, b"other message", &sig),
+            Err(SignatureError::ChallengeMismatch)
+        ));
+        for batch_size in [4, schnorr::BATCH_VERIFY_THRESHOLD].iter() {
+            let mut items = vec![(validated, &message[..], sig.clone()); *batch_size];
+            assert!(S::batch_verify_validated(&parameters, &items).is_ok());
+            items[2].1 = b"other message";
+            match S::batch_verify_validated(&parameters, &items) {
+                Err(SignatureError::BatchItem { index, error }) => {
+                    assert_eq!(index, 2);
+                    assert!(matches!(*error, SignatureError::ChallengeMismatch));
+                }
+                _ => panic!("batch item 2 should be rejected"),
+            }
+        }
+
+        let mut bytes = Vec::new();
+        pk.serialize(&mut bytes).unwrap();
+        assert_eq!(
+            schnorr::deserialize_public_key::<JubJub, _>(&bytes[..]).unwrap(),
+            pk
+        );
+        let mut bytes = Vec::new();
+        EdwardsAffine::zero().serialize(&mut bytes).unwrap();
+        assert!(schnorr::deserialize_public_key::<JubJub, _>(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn schnorr_detailed_verification_test() {
+        use ark_ff::Zero;
+        type S = schnorr::Schnorr<JubJub, Blake2s>;
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let message = b"message";
+        let sig = S::sign(&parameters, &sk, message, rng).unwrap();
+        assert!(S::verify_detailed(&parameters, &pk, message, &sig).is_ok());
+
+        let identity = EdwardsAffine::zero();
+        assert!(matches!(
+            S::verify_detailed(&parameters, &identity, message, &sig),
+            Err(SignatureError::InvalidPublicKey)
+        ));
+        assert!(matches!(
+            S::verify_detailed(&parameters, &pk, b"other message", &sig),
+            Err(SignatureError::ChallengeMismatch)
+        ));
+        assert!(matches!(
+            S::verify_detailed(&parameters, &pk, b"other message", &sig)
+                .map_err(crate::CryptoError::from),
+            Err(crate::CryptoError::Signature(
+                SignatureError::ChallengeMismatch
+            ))
+        ));
+
+        let mut bytes = Vec::new();
+        sig.serialize(&mut bytes).unwrap();
+        assert!(S::verify_serialized(&parameters, &pk, message, &bytes).is_ok());
+        assert!(matches!(
+            S::verify_serialized(&parameters, &pk, message, &bytes[1..]),
+            Err(SignatureError::InvalidSignatureEncoding)
+        ));
+        bytes.push(0);
+        assert!(matches!(
+            S::verify_serialized(&parameters, &pk, message, &bytes),
+            Err(SignatureError::InvalidSignatureEncoding)
+        ));
+
+        // Batches report the first rejected item, on both paths.
+        for batch_size in [4, schnorr::BATCH_VERIFY_THRESHOLD].iter() {
+            let mut items = vec![(pk, &message[..], sig.clone()); *batch_size];
+            assert!(S::batch_verify_detailed(&parameters, &items).is_ok());
+            items[2].1 = b"other message";
+            items[3].0 = identity;
+            match S::batch_verify_detailed(&parameters, &items) {
+                Err(SignatureError::BatchItem { index, error }) => {
+                    assert_eq!(index, 2);
+                    assert!(matches!(*error, SignatureError::ChallengeMismatch));
+                }
+                _ => panic!("batch item 2 should be rejected"),
+            }
+            assert!(!S::batch_verify(&parameters, &items[..3], rng).unwrap());
+            items[2].1 = message;
+            match S::batch_verify_detailed(&parameters, &items) {
+                Err(SignatureError::BatchItem { index, error }) => {
+                    assert_eq!(index, 3);
+                    assert!(matches!(*error, SignatureError::InvalidPublicKey));
+                }
+                _ => panic!("batch item 3 should be rejected"),
+            }
+        }
+    }
+
+    #[test]
+    fn schnorr_serialization_test() {
+        type S = schnorr::Schnorr<JubJub, Blake2s>;
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let sig = S::sign(&parameters, &sk, b"message", rng).unwrap();
+
+        let mut bytes = Vec::new();
+        parameters.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), parameters.serialized_size());
+        let parameters = schnorr::Parameters::<JubJub, Blake2s>::deserialize(&bytes[..]).unwrap();
+
+        let mut bytes = Vec::new();
+        pk.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 32);
+        let pk = schnorr::PublicKey::<JubJub>::deserialize(&bytes[..]).unwrap();
+
+        let mut bytes = Vec::new();
+        sk.serialize(&mut bytes).unwrap();
+        let sk = schnorr::SecretKey::<JubJub>::deserialize(&bytes[..]).unwrap();
+
+        let mut bytes = Vec::new();
+        sig.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), sig.serialized_size());
+        let sig = schnorr::Signature::<JubJub>::deserialize(&bytes[..]).unwrap();
+        assert!(S::verify(&parameters, &pk, b"message", &sig).unwrap());
+        let other_sig = S::sign(&parameters, &sk, b"other message", rng).unwrap();
+        assert!(S::verify(&parameters, &pk, b"other message", &other_sig).unwrap());
+
+        // A prover response equal to the group order is not canonical.
+        let mut bad_sig = <JubJub as ProjectiveCurve>::ScalarField::characteristic()
+            .iter()
+            .flat_map(|limb| limb.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        bad_sig.extend_from_slice(&bytes[32..]);
+        assert!(schnorr::Signature::<JubJub>::deserialize(&bad_sig[..]).is_err());
+
+        // The smallest x coordinate without a point on the curve.
+        let off_curve_x = (2u64..)
+            .map(Fq::from)
+            .find(|x| EdwardsAffine::get_point_from_x(*x, false).is_none())
+            .unwrap();
+        let mut bytes = Vec::new();
+        off_curve_x.serialize(&mut bytes).unwrap();
+        assert!(schnorr::PublicKey::<JubJub>::deserialize(&bytes[..]).is_err());
     }
 
     #[test]
@@ -100,5 +509,35 @@ mod test {
             message.as_bytes(),
             &random_scalar.as_slice(),
         );
+
+        // The randomness is read as a little-endian integer.
+        type S = schnorr::Schnorr<JubJub, Blake2s>;
+        let parameters = S::setup(rng).unwrap();
+        let (pk, _) = S::keygen(&parameters, rng).unwrap();
+        let scalar = <JubJub as Group>::ScalarField::rand(rng);
+        let randomized_pk =
+            S::randomize_public_key(&parameters, &pk, &to_bytes![scalar].unwrap()).unwrap();
+        assert_eq!(
+            randomized_pk,
+            (AffineCurve::mul(&parameters.generator, scalar) + pk.into_projective()).into_affine()
+        );
+    }
+
+    #[test]
+    fn schnorr_short_weierstrass_test() {
+        use ark_bls12_377::G1Projective;
+        type S = schnorr::Schnorr<G1Projective, Blake2s>;
+        let message = "Hi, I am a Schnorr signature!";
+        sign_and_verify::<S>(message.as_bytes());
+        failed_verification::<S>(message.as_bytes(), "Bad message".as_bytes());
+
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let sig = S::sign(&parameters, &sk, message.as_bytes(), rng).unwrap();
+        let mut bytes = Vec::new();
+        sig.serialize(&mut bytes).unwrap();
+        let sig = schnorr::Signature::<G1Projective>::deserialize(&bytes[..]).unwrap();
+        assert!(S::verify(&parameters, &pk, message.as_bytes(), &sig).unwrap());
     }
 }
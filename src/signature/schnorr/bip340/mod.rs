@@ -0,0 +1,535 @@
+//! BIP340 Schnorr signatures over [`secp256k1`], as used by Bitcoin
+//! Taproot: public keys are the 32-byte x-coordinate of a point with an even
+//! y-coordinate, signatures are the 64 bytes `bytes(R.x) || bytes(s)`, and
+//! the nonce and the challenge are tagged SHA-256 hashes. Signing and
+//! verification follow the reference algorithms of BIP340 and reproduce its
+//! test vectors.
+//!
+//! A BIP340 signature is `(R.x, s)` with `s = k + e · d`, where [`Schnorr`]
+//! signatures are `(s, e)` with `s = k - e · x`, and the challenge hashes the
+//! public key. It is therefore a [`SignatureScheme`] of its own rather than a
+//! [`ChallengeHash`](super::ChallengeHash) of [`Schnorr`], and its keys and
+//! signatures cannot be randomized.
+//!
+//! [`Schnorr`]: super::Schnorr
+
+pub mod secp256k1;
+
+use self::secp256k1::{Affine, Fq, Fr, Projective};
+use super::{verification_result, SecretKey};
+use crate::crh::sha256;
+use crate::signature::SignatureError;
+use crate::{CryptoError, Error, SignatureScheme, Vec};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{
+    biginteger::BigInteger,
+    bytes::{FromBytes, ToBytes},
+    fields::{Field, FpParameters, PrimeField, SquareRootField},
+    UniformRand, Zero,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::io::{Read, Result as IoResult, Write};
+use ark_std::rand::Rng;
+
+/// The tag of the hash that masks the secret key with the auxiliary
+/// randomness.
+pub const AUX_TAG: &str = "BIP0340/aux";
+
+/// The tag of the hash from which the nonce is derived.
+pub const NONCE_TAG: &str = "BIP0340/nonce";
+
+/// The tag of the hash from which the challenge is derived.
+pub const CHALLENGE_TAG: &str = "BIP0340/challenge";
+
+/// BIP340 signatures. The scheme has no parameters: the generator is the
+/// one of secp256k1 and the hashes are fixed.
+pub struct Bip340;
+
+/// A public key: the x-coordinate of the point `d · G` or `-d · G` with an
+/// even y-coordinate, as 32 big-endian bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct XOnlyPublicKey(pub [u8; 32]);
+
+impl XOnlyPublicKey {
+    /// The public key of `sk`. Fails for the zero key, which has none.
+    pub fn from_secret_key(sk: &SecretKey<Projective>) -> Result<Self, Error> {
+        if sk.0.is_zero() {
+            return Err(SignatureError::InvalidPublicKey.into());
+        }
+        let point = Affine::prime_subgroup_generator().mul(sk.0).into_affine();
+        Ok(XOnlyPublicKey(to_be_bytes(&point.x)))
+    }
+
+    /// The point with this x-coordinate and an even y-coordinate, which is
+    /// BIP340's `lift_x`. Fails if the bytes are not below the field order
+    /// or no such point exists.
+    pub fn to_point(&self) -> Result<Affine, SignatureError> {
+        let x = from_be_bytes::<Fq>(&self.0).ok_or(SignatureError::InvalidPublicKey)?;
+        let y = (x.square() * x + Fq::from(7u64))
+            .sqrt()
+            .ok_or(SignatureError::InvalidPublicKey)?;
+        let y = if has_even_y(&y) { y } else { -y };
+        Ok(Affine::new(x, y, false))
+    }
+}
+
+impl ToBytes for XOnlyPublicKey {
+    #[inline]
+    fn write<W: Write>(&self, mut writer: W) -> IoResult<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+/// A signature `(r, s)`, where `r` is the x-coordinate of the nonce point
+/// `R`, which has an even y-coordinate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Signature {
+    pub r: Fq,
+    pub s: Fr,
+}
+
+impl Signature {
+    /// The 64-byte encoding `bytes(r) || bytes(s)`, both big-endian.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&to_be_bytes(&self.r));
+        bytes[32..].copy_from_slice(&to_be_bytes(&self.s));
+        bytes
+    }
+
+    /// Decodes [`Signature::to_bytes`]. Rejects an `r` that is not below the
+    /// field order and an `s` that is not below the group order.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, SignatureError> {
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..]);
+        Ok(Signature {
+            r: from_be_bytes(&r).ok_or(SignatureError::InvalidSignatureEncoding)?,
+            s: from_be_bytes(&s).ok_or(SignatureError::InvalidSignatureEncoding)?,
+        })
+    }
+}
+
+/// A signature is serialized as its 64 bytes, see [`Signature::to_bytes`].
+impl CanonicalSerialize for Signature {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        Ok(writer.write_all(&self.to_bytes())?)
+    }
+
+    fn serialized_size(&self) -> usize {
+        64
+    }
+}
+
+impl CanonicalDeserialize for Signature {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut bytes = [0u8; 64];
+        reader.read_exact(&mut bytes)?;
+        Signature::from_bytes(&bytes).map_err(|_| SerializationError::InvalidData)
+    }
+}
+
+impl SignatureScheme for Bip340 {
+    type Parameters = ();
+    type PublicKey = XOnlyPublicKey;
+    type SecretKey = SecretKey<Projective>;
+    type Signature = Signature;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn keygen<R: Rng>(
+        _parameters: &Self::Parameters,
+        rng: &mut R,
+    ) -> Result<(Self::PublicKey, Self::SecretKey), Error> {
+        let keygen_time = start_timer!(|| "Bip340::KeyGen");
+        let secret_key = loop {
+            let candidate = Fr::rand(rng);
+            if !candidate.is_zero() {
+                break SecretKey(candidate);
+            }
+        };
+        let public_key = XOnlyPublicKey::from_secret_key(&secret_key)?;
+        end_timer!(keygen_time);
+        Ok((public_key, secret_key))
+    }
+
+    /// Signs with 32 bytes of auxiliary randomness from `rng`, see
+    /// [`Bip340::sign_with_aux_rand`].
+    fn sign<R: Rng>(
+        _parameters: &Self::Parameters,
+        sk: &Self::SecretKey,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Self::Signature, Error> {
+        let mut aux_rand = [0u8; 32];
+        rng.fill_bytes(&mut aux_rand);
+        Self::sign_with_aux_rand(sk, message, &aux_rand)
+    }
+
+    fn verify(
+        _parameters: &Self::Parameters,
+        pk: &Self::PublicKey,
+        message: &[u8],
+        signature: &Self::Signature,
+    ) -> Result<bool, Error> {
+        verification_result(Self::verify_detailed(pk, message, signature))
+    }
+
+    fn randomize_public_key(
+        _parameters: &Self::Parameters,
+        _public_key: &Self::PublicKey,
+        _randomness: &[u8],
+    ) -> Result<Self::PublicKey, Error> {
+        Err(CryptoError::UnsupportedOperation.into())
+    }
+
+    fn randomize_signature(
+        _parameters: &Self::Parameters,
+        _signature: &Self::Signature,
+        _randomness: &[u8],
+    ) -> Result<Self::Signature, Error> {
+        Err(CryptoError::UnsupportedOperation.into())
+    }
+}
+
+impl Bip340 {
+    /// Signs `message` as BIP340's `Sign(sk, m, a)`, with `aux_rand` as `a`.
+    /// The nonce is derived from the secret key, the public key, the message
+    /// and `aux_rand`, so signing is deterministic for a fixed `aux_rand`;
+    /// fresh randomness protects against side channels.
+    ///
+    /// Fails for the zero key, and if the nonce hash is zero modulo the
+    /// group order, which BIP340 reports as a failure and happens with
+    /// negligible probability; the latter is [`CryptoError::InvalidChallenge`].
+    pub fn sign_with_aux_rand(
+        sk: &SecretKey<Projective>,
+        message: &[u8],
+        aux_rand: &[u8; 32],
+    ) -> Result<Signature, Error> {
+        let sign_time = start_timer!(|| "Bip340::Sign");
+        if sk.0.is_zero() {
+            end_timer!(sign_time);
+            return Err(SignatureError::InvalidPublicKey.into());
+        }
+        let generator = Affine::prime_subgroup_generator();
+        let pk_point = generator.mul(sk.0).into_affine();
+        let d = if has_even_y(&pk_point.y) { sk.0 } else { -sk.0 };
+        let pk = to_be_bytes(&pk_point.x);
+
+        let mut masked_key = to_be_bytes(&d);
+        for (byte, mask) in masked_key
+            .iter_mut()
+            .zip(&tagged_hash(AUX_TAG, &[aux_rand]))
+        {
+            *byte ^= mask;
+        }
+        let k = reduce_be_bytes(&tagged_hash(NONCE_TAG, &[&masked_key, &pk, message]));
+        if k.is_zero() {
+            end_timer!(sign_time);
+            return Err(CryptoError::InvalidChallenge.into());
+        }
+        let nonce_point = generator.mul(k).into_affine();
+        let k = if has_even_y(&nonce_point.y) { k } else { -k };
+        let r = to_be_bytes(&nonce_point.x);
+
+        let e = reduce_be_bytes(&tagged_hash(CHALLENGE_TAG, &[&r, &pk, message]));
+        end_timer!(sign_time);
+        Ok(Signature {
+            r: nonce_point.x,
+            s: k + e * d,
+        })
+    }
+
+    /// Verifies `signature` as BIP340's `Verify`, reporting why it is
+    /// rejected: [`SignatureError::InvalidPublicKey`] if `pk` is not the
+    /// x-coordinate of a point, and [`SignatureError::ChallengeMismatch`] if
+    /// `s · G - e · P` is not the point with x-coordinate `r` and an even
+    /// y-coordinate.
+    pub fn verify_detailed(
+        pk: &XOnlyPublicKey,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        let pk_point = pk.to_point()?;
+        let verify_time = start_timer!(|| "Bip340::Verify");
+        let r = to_be_bytes(&signature.r);
+        let e = reduce_be_bytes(&tagged_hash(CHALLENGE_TAG, &[&r, &pk.0, message]));
+        let nonce_point =
+            (Affine::prime_subgroup_generator().mul(signature.s) - pk_point.mul(e)).into_affine();
+        end_timer!(verify_time);
+        if nonce_point.is_zero() || !has_even_y(&nonce_point.y) || nonce_point.x != signature.r {
+            return Err(SignatureError::ChallengeMismatch);
+        }
+        Ok(())
+    }
+
+    /// Verifies a signature given as its 64 bytes, rejecting encodings
+    /// of another length or out of range as
+    /// [`SignatureError::InvalidSignatureEncoding`].
+    pub fn verify_serialized(
+        pk: &XOnlyPublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), SignatureError> {
+        if signature.len() != 64 {
+            return Err(SignatureError::InvalidSignatureEncoding);
+        }
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(signature);
+        Self::verify_detailed(pk, message, &Signature::from_bytes(&bytes)?)
+    }
+}
+
+/// BIP340's `hash_tag(x) = SHA-256(SHA-256(tag) || SHA-256(tag) || x)`,
+/// with `x` the concatenation of `parts`.
+pub fn tagged_hash(tag: &str, parts: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = sha256::digest(tag.as_bytes());
+    let mut input = Vec::with_capacity(64 + parts.iter().map(|p| p.len()).sum::<usize>());
+    input.extend_from_slice(&tag_hash);
+    input.extend_from_slice(&tag_hash);
+    for part in parts {
+        input.extend_from_slice(part);
+    }
+    sha256::digest(&input)
+}
+
+fn has_even_y(y: &Fq) -> bool {
+    y.into_repr().is_even()
+}
+
+/// The 32-byte big-endian encoding of an element of one of the fields of
+/// secp256k1.
+fn to_be_bytes<F: PrimeField>(element: &F) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    let le = element.into_repr().to_bytes_le();
+    for (byte, le_byte) in bytes.iter_mut().rev().zip(&le) {
+        *byte = *le_byte;
+    }
+    bytes
+}
+
+/// Decodes a 32-byte big-endian integer, or `None` if it is not below the
+/// modulus. `PrimeField::from_be_bytes_mod_order` is not available for these
+/// fields, see [`secp256k1`].
+fn from_be_bytes<F: PrimeField>(bytes: &[u8; 32]) -> Option<F> {
+    F::from_repr(be_bytes_to_repr::<F>(bytes))
+}
+
+/// Reduces a 32-byte big-endian integer modulo the group order. Since the
+/// order exceeds `2^255`, one subtraction suffices.
+fn reduce_be_bytes(bytes: &[u8; 32]) -> Fr {
+    let mut repr = be_bytes_to_repr::<Fr>(bytes);
+    let modulus = <Fr as PrimeField>::Params::MODULUS;
+    if repr >= modulus {
+        repr.sub_noborrow(&modulus);
+    }
+    Fr::from_repr(repr).unwrap()
+}
+
+fn be_bytes_to_repr<F: PrimeField>(bytes: &[u8; 32]) -> F::BigInt {
+    let mut le = vec![0u8; <F::BigInt as BigInteger>::NUM_LIMBS * 8];
+    for (le_byte, byte) in le.iter_mut().zip(bytes.iter().rev()) {
+        *le_byte = *byte;
+    }
+    F::BigInt::read(&le[..]).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ff::One;
+    use ark_std::test_rng;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn array32(hex: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&from_hex(hex));
+        bytes
+    }
+
+    fn array64(hex: &str) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(&from_hex(hex));
+        bytes
+    }
+
+    /// Vectors 0 to 3 of the BIP340 `test-vectors.csv`, as
+    /// `(secret key, public key, aux_rand, message, signature)`.
+    const SIGNING_VECTORS: [(&str, &str, &str, &str, &str); 4] = [
+        (
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA8215\
+             25F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+        ),
+        (
+            "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE3341\
+             8906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A",
+        ),
+        (
+            "C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9",
+            "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906",
+            "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+            "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1B\
+             AB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7",
+        ),
+        (
+            "0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710",
+            "25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+            "7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC\
+             97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3",
+        ),
+    ];
+
+    #[test]
+    fn signing_vectors_test() {
+        for (sk, pk, aux_rand, message, signature) in SIGNING_VECTORS.iter() {
+            let sk = SecretKey(from_be_bytes::<Fr>(&array32(sk)).unwrap());
+            let pk = XOnlyPublicKey(array32(pk));
+            assert_eq!(XOnlyPublicKey::from_secret_key(&sk).unwrap(), pk);
+
+            let message = from_hex(message);
+            let signature = array64(signature);
+            let signed = Bip340::sign_with_aux_rand(&sk, &message, &array32(aux_rand)).unwrap();
+            assert_eq!(signed.to_bytes()[..], signature[..]);
+            assert!(Bip340::verify(&(), &pk, &message, &signed).unwrap());
+            assert!(Bip340::verify_serialized(&pk, &message, &signature).is_ok());
+        }
+    }
+
+    #[test]
+    fn verification_vectors_test() {
+        let message = from_hex("243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89");
+        let pk = XOnlyPublicKey(array32(
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        ));
+        let verify = |pk: &str, message: &[u8], signature: &str| {
+            Bip340::verify_serialized(&XOnlyPublicKey(array32(pk)), message, &from_hex(signature))
+        };
+
+        // Vector 4: a signature whose `r` has leading zero bytes.
+        assert!(verify(
+            "D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9",
+            &from_hex("4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703"),
+            "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C63\
+             76AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4",
+        )
+        .is_ok());
+
+        // Vector 5: the public key is not the x-coordinate of a point.
+        assert!(matches!(
+            verify(
+                "EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34",
+                &message,
+                "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+                 69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            ),
+            Err(SignatureError::InvalidPublicKey)
+        ));
+
+        // Vector 12: `r` is the field order.
+        assert!(matches!(
+            Bip340::verify_serialized(
+                &pk,
+                &message,
+                &from_hex(
+                    "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F\
+                     69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B"
+                )
+            ),
+            Err(SignatureError::InvalidSignatureEncoding)
+        ));
+
+        // Vector 13: `s` is the group order.
+        assert!(matches!(
+            Bip340::verify_serialized(
+                &pk,
+                &message,
+                &from_hex(
+                    "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+                     FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141"
+                )
+            ),
+            Err(SignatureError::InvalidSignatureEncoding)
+        ));
+
+        // Vector 14: the public key is not below the field order.
+        assert!(matches!(
+            verify(
+                "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+                &message,
+                "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+                 69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            ),
+            Err(SignatureError::InvalidPublicKey)
+        ));
+    }
+
+    #[test]
+    fn sign_and_verify_test() {
+        let rng = &mut test_rng();
+        let message = b"Hi, I am a BIP340 signature!";
+        for _ in 0..4 {
+            // Half of the keys have a public point with an odd y-coordinate.
+            let (pk, sk) = Bip340::keygen(&(), rng).unwrap();
+            let signature = Bip340::sign(&(), &sk, message, rng).unwrap();
+            assert!(Bip340::verify(&(), &pk, message, &signature).unwrap());
+            assert!(!Bip340::verify(&(), &pk, b"Bad message", &signature).unwrap());
+
+            let mut bytes = Vec::new();
+            signature.serialize(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), 64);
+            assert_eq!(Signature::deserialize(&bytes[..]).unwrap(), signature);
+            assert!(matches!(
+                Bip340::verify_serialized(&pk, message, &bytes[..63]),
+                Err(SignatureError::InvalidSignatureEncoding)
+            ));
+        }
+
+        let (pk, sk) = Bip340::keygen(&(), rng).unwrap();
+        assert!(Bip340::sign_with_aux_rand(&SecretKey(Fr::zero()), b"", &[0u8; 32]).is_err());
+        assert!(Bip340::randomize_public_key(&(), &pk, &[1u8; 32]).is_err());
+        let signature = Bip340::sign(&(), &sk, b"", rng).unwrap();
+        assert!(Bip340::randomize_signature(&(), &signature, &[1u8; 32]).is_err());
+    }
+
+    #[test]
+    fn secp256k1_arithmetic_test() {
+        // Sums of elements close to the modulus carry out of 256 bits.
+        let minus_one = -Fq::one();
+        assert_eq!(minus_one + minus_one, -Fq::from(2u64));
+        assert_eq!(minus_one.double(), -Fq::from(2u64));
+        assert_eq!(minus_one * minus_one, Fq::one());
+        assert_eq!(-Fr::one() + -Fr::one(), -Fr::from(2u64));
+
+        let generator = Affine::prime_subgroup_generator();
+        assert!(generator.is_on_curve());
+        let order = <Fr as PrimeField>::Params::MODULUS;
+        assert!(generator.mul(order).is_zero());
+        let mut order_bytes = [0u8; 32];
+        for (byte, le_byte) in order_bytes.iter_mut().rev().zip(order.to_bytes_le()) {
+            *byte = le_byte;
+        }
+        assert!(reduce_be_bytes(&order_bytes).is_zero());
+        order_bytes[31] += 1;
+        assert_eq!(reduce_be_bytes(&order_bytes), Fr::one());
+    }
+}
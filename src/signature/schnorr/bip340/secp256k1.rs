@@ -0,0 +1,263 @@
+//! The secp256k1 curve `y^2 = x^3 + 7` of SEC 2, for [`Bip340`](super::Bip340).
+//!
+//! arkworks 0.2 has no secp256k1, and its `Fp256` cannot hold either field:
+//! both moduli use all 256 bits, and `Fp256` addition drops the carry out of
+//! the top limb. The fields are therefore `Fp320`s with a 256-bit modulus,
+//! whose spare limb absorbs the carry. Arithmetic, sampling and
+//! serialization work, but `Field::from_random_bytes`, and with it
+//! `PrimeField::from_{be,le}_bytes_mod_order`, is not supported: it assumes
+//! that the modulus ends in the top limb. This rules out
+//! [`Schnorr`](crate::signature::schnorr::Schnorr) with a digest challenge,
+//! which decodes the digest that way.
+
+use ark_ec::{
+    models::{ModelParameters, SWModelParameters},
+    short_weierstrass_jacobian::{GroupAffine, GroupProjective},
+};
+use ark_ff::{
+    biginteger::BigInteger320 as BigInteger,
+    field_new,
+    fields::{FftParameters, Fp320, Fp320Parameters, FpParameters},
+    Zero,
+};
+
+/// The base field, of order `p = 2^256 - 2^32 - 977`.
+pub type Fq = Fp320<FqParameters>;
+
+pub struct FqParameters;
+
+impl Fp320Parameters for FqParameters {}
+impl FftParameters for FqParameters {
+    type BigInt = BigInteger;
+
+    const TWO_ADICITY: u32 = 1;
+
+    #[rustfmt::skip]
+    const TWO_ADIC_ROOT_OF_UNITY: BigInteger = BigInteger([
+        0xfffffffefffffc2f,
+        0xfffffffefffffc2e,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0x0000000000000000,
+    ]);
+}
+impl FpParameters for FqParameters {
+    /// MODULUS = 115792089237316195423570985008687907853269984665640564039457584007908834671663
+    #[rustfmt::skip]
+    const MODULUS: BigInteger = BigInteger([
+        0xfffffffefffffc2f,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0x0000000000000000,
+    ]);
+
+    const MODULUS_BITS: u32 = 256;
+
+    const CAPACITY: u32 = Self::MODULUS_BITS - 1;
+
+    /// The 64 unused bits of the top limb, less one: `u64::MAX >> 64`
+    /// would overflow. Sampling keeps one bit too many and rejects more
+    /// often, which does not bias it.
+    const REPR_SHAVE_BITS: u32 = 63;
+
+    #[rustfmt::skip]
+    const R: BigInteger = BigInteger([
+        0x0000000000000000,
+        0x00000001000003d1,
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0000000000000000,
+    ]);
+
+    #[rustfmt::skip]
+    const R2: BigInteger = BigInteger([
+        0x0000000000000000,
+        0x0000000000000000,
+        0x000007a2000e90a1,
+        0x0000000000000001,
+        0x0000000000000000,
+    ]);
+
+    const INV: u64 = 15580212934572586289u64;
+
+    /// GENERATOR = 3
+    #[rustfmt::skip]
+    const GENERATOR: BigInteger = BigInteger([
+        0x0000000000000000,
+        0x0000000300000b73,
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0000000000000000,
+    ]);
+
+    #[rustfmt::skip]
+    const MODULUS_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([
+        0xffffffff7ffffe17,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0x7fffffffffffffff,
+        0x0000000000000000,
+    ]);
+
+    #[rustfmt::skip]
+    const T: BigInteger = BigInteger([
+        0xffffffff7ffffe17,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0x7fffffffffffffff,
+        0x0000000000000000,
+    ]);
+
+    #[rustfmt::skip]
+    const T_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([
+        0xffffffffbfffff0b,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0x3fffffffffffffff,
+        0x0000000000000000,
+    ]);
+}
+
+/// The scalar field, of the prime order `n` of the curve.
+pub type Fr = Fp320<FrParameters>;
+
+pub struct FrParameters;
+
+impl Fp320Parameters for FrParameters {}
+impl FftParameters for FrParameters {
+    type BigInt = BigInteger;
+
+    const TWO_ADICITY: u32 = 6;
+
+    #[rustfmt::skip]
+    const TWO_ADIC_ROOT_OF_UNITY: BigInteger = BigInteger([
+        0x0112cb0f605a214a,
+        0x92225daffb794500,
+        0x7e42003a6ccb6212,
+        0x55980b07bc222114,
+        0x0000000000000000,
+    ]);
+}
+impl FpParameters for FrParameters {
+    /// MODULUS = 115792089237316195423570985008687907852837564279074904382605163141518161494337
+    #[rustfmt::skip]
+    const MODULUS: BigInteger = BigInteger([
+        0xbfd25e8cd0364141,
+        0xbaaedce6af48a03b,
+        0xfffffffffffffffe,
+        0xffffffffffffffff,
+        0x0000000000000000,
+    ]);
+
+    const MODULUS_BITS: u32 = 256;
+
+    const CAPACITY: u32 = Self::MODULUS_BITS - 1;
+
+    /// The 64 unused bits of the top limb, less one: `u64::MAX >> 64`
+    /// would overflow. Sampling keeps one bit too many and rejects more
+    /// often, which does not bias it.
+    const REPR_SHAVE_BITS: u32 = 63;
+
+    #[rustfmt::skip]
+    const R: BigInteger = BigInteger([
+        0x0000000000000000,
+        0x402da1732fc9bebf,
+        0x4551231950b75fc4,
+        0x0000000000000001,
+        0x0000000000000000,
+    ]);
+
+    #[rustfmt::skip]
+    const R2: BigInteger = BigInteger([
+        0x1e004f504dfd7f79,
+        0x08fcf59774a052ea,
+        0x27c4120fc94e1653,
+        0x3c1a6191e5702644,
+        0x0000000000000000,
+    ]);
+
+    const INV: u64 = 5408259542528602431u64;
+
+    /// GENERATOR = 7
+    #[rustfmt::skip]
+    const GENERATOR: BigInteger = BigInteger([
+        0x0000000000000000,
+        0xc13f6a264e843739,
+        0xe537f5b135039e5d,
+        0x0000000000000008,
+        0x0000000000000000,
+    ]);
+
+    #[rustfmt::skip]
+    const MODULUS_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([
+        0xdfe92f46681b20a0,
+        0x5d576e7357a4501d,
+        0xffffffffffffffff,
+        0x7fffffffffffffff,
+        0x0000000000000000,
+    ]);
+
+    #[rustfmt::skip]
+    const T: BigInteger = BigInteger([
+        0xeeff497a3340d905,
+        0xfaeabb739abd2280,
+        0xffffffffffffffff,
+        0x03ffffffffffffff,
+        0x0000000000000000,
+    ]);
+
+    #[rustfmt::skip]
+    const T_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([
+        0x777fa4bd19a06c82,
+        0xfd755db9cd5e9140,
+        0xffffffffffffffff,
+        0x01ffffffffffffff,
+        0x0000000000000000,
+    ]);
+}
+
+pub type Affine = GroupAffine<Parameters>;
+pub type Projective = GroupProjective<Parameters>;
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Parameters;
+
+impl ModelParameters for Parameters {
+    type BaseField = Fq;
+    type ScalarField = Fr;
+}
+
+impl SWModelParameters for Parameters {
+    /// COEFF_A = 0
+    const COEFF_A: Fq = field_new!(Fq, "0");
+
+    /// COEFF_B = 7
+    const COEFF_B: Fq = field_new!(Fq, "7");
+
+    /// COFACTOR = 1
+    const COFACTOR: &'static [u64] = &[0x1];
+
+    /// COFACTOR_INV = COFACTOR^{-1} mod n = 1
+    const COFACTOR_INV: Fr = field_new!(Fr, "1");
+
+    /// AFFINE_GENERATOR_COEFFS = (GENERATOR_X, GENERATOR_Y)
+    const AFFINE_GENERATOR_COEFFS: (Self::BaseField, Self::BaseField) = (GENERATOR_X, GENERATOR_Y);
+
+    #[inline(always)]
+    fn mul_by_a(_: &Self::BaseField) -> Self::BaseField {
+        Self::BaseField::zero()
+    }
+}
+
+/// GENERATOR_X = 0x79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798
+pub const GENERATOR_X: Fq = field_new!(
+    Fq,
+    "55066263022277343669578718895168534326250603453777594175500187360389116729240"
+);
+
+/// GENERATOR_Y = 0x483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8
+pub const GENERATOR_Y: Fq = field_new!(
+    Fq,
+    "32670510020758816978083085130507043184471273380659243275938904335757337482424"
+);
@@ -0,0 +1,277 @@
+//! Blind signatures for [`Schnorr`](super::Schnorr): the signer signs a
+//! message without seeing it, and the user obtains an ordinary signature
+//! that verifies with `Schnorr::verify`.
+//!
+//! The protocol takes three moves, each an explicit step:
+//! 1. the signer samples a nonce `k` with [`SignerState::commit`] and sends
+//!    `R = k · G`;
+//! 2. the user samples blinding factors `α, β` with [`BlindingState::blind`],
+//!    computes `R' = R + α · G + β · X` and `e' = H(salt || R' || m)`, and
+//!    sends the blinded challenge `e = e' - β`;
+//! 3. the signer answers with `s = k - e · x` from [`SignerState::respond`],
+//!    and the user checks it and unblinds it into the signature
+//!    `(s + α, e')` with [`BlindingState::unblind`].
+//!
+//! Since `α` and `β` are uniform, the signer's view `(R, e, s)` is
+//! independent of the signature and the message.
+//!
+//! # Sessions must not overlap
+//!
+//! This is plain blind Schnorr, which is only secure if the signer runs one
+//! session at a time. With `ℓ` sessions open concurrently, for `ℓ` around
+//! the bit length of the group order, the ROS attack of Benhamouda et al.
+//! (2020) lets the user pick blinded challenges from which it forges
+//! `ℓ + 1` signatures out of `ℓ` responses. [`SignerState`] therefore holds
+//! the secret key and opens at most one session: [`SignerState::commit`]
+//! fails while a session is open, until it is answered with
+//! [`SignerState::respond`] or dropped with [`SignerState::abort`]. Keep a
+//! single `SignerState` per key; signers that need concurrent sessions need
+//! a ROS-resistant scheme instead.
+
+use super::{
+    compute_verifier_challenge, validate_public_key, ChallengeHash, Parameters, PublicKey,
+    SecretKey, Signature,
+};
+use crate::signature::SignatureError;
+use crate::Error;
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{PrimeField, UniformRand};
+use ark_std::rand::Rng;
+
+/// The signer of blind signatures, holding the secret key and the nonce of
+/// the open session, if any. See the [module documentation](self) for why
+/// sessions cannot overlap.
+pub struct SignerState<C: ProjectiveCurve> {
+    sk: SecretKey<C>,
+    nonce: Option<C::ScalarField>,
+}
+
+/// The challenge sent by the user in the second move.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "C: ProjectiveCurve"),
+    Debug(bound = "C: ProjectiveCurve")
+)]
+pub struct BlindedChallenge<C: ProjectiveCurve>(pub C::ScalarField);
+
+/// The signer's answer to a [`BlindedChallenge`].
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "C: ProjectiveCurve"),
+    Debug(bound = "C: ProjectiveCurve")
+)]
+pub struct BlindedResponse<C: ProjectiveCurve>(pub C::ScalarField);
+
+impl<C: ProjectiveCurve> SignerState<C> {
+    pub fn new(sk: SecretKey<C>) -> Self {
+        Self { sk, nonce: None }
+    }
+
+    /// Whether a session is open, i.e. a commitment has been sent and not
+    /// yet answered or aborted.
+    pub fn is_open(&self) -> bool {
+        self.nonce.is_some()
+    }
+
+    /// The first move: opens a session by sampling the nonce, and returns
+    /// the commitment `R` sent to the user. Fails if a session is open.
+    pub fn commit<H: ChallengeHash<C>, R: Rng>(
+        &mut self,
+        parameters: &Parameters<C, H>,
+        rng: &mut R,
+    ) -> Result<C::Affine, Error> {
+        if self.is_open() {
+            return Err(SignatureError::SessionInProgress.into());
+        }
+        let nonce = C::ScalarField::rand(rng);
+        self.nonce = Some(nonce);
+        Ok(parameters.generator.mul(nonce).into_affine())
+    }
+
+    /// The third move: answers `challenge` and closes the session, so that
+    /// the nonce cannot be reused. Fails if no session is open.
+    pub fn respond(
+        &mut self,
+        challenge: &BlindedChallenge<C>,
+    ) -> Result<BlindedResponse<C>, Error> {
+        let nonce = self.nonce.take().ok_or(SignatureError::NoSession)?;
+        Ok(BlindedResponse(nonce - challenge.0 * self.sk.0))
+    }
+
+    /// Closes the open session, if any, without answering it. Its nonce is
+    /// discarded, so the user gets nothing from it.
+    pub fn abort(&mut self) {
+        self.nonce = None;
+    }
+}
+
+/// The user's blinding factors and the challenge of the final signature.
+pub struct BlindingState<C: ProjectiveCurve> {
+    public_key: PublicKey<C>,
+    commitment: C::Affine,
+    blinded_challenge: C::ScalarField,
+    verifier_challenge: C::ScalarField,
+    alpha: C::ScalarField,
+}
+
+impl<C: ProjectiveCurve> BlindingState<C>
+where
+    C::ScalarField: PrimeField,
+{
+    /// The second move: blinds the signer's `commitment` and `message` into
+    /// the challenge sent to the signer holding the key `pk`. Blinding
+    /// factors whose challenge digest is not a scalar are redrawn.
+    pub fn blind<H: ChallengeHash<C>, R: Rng>(
+        parameters: &Parameters<C, H>,
+        pk: &PublicKey<C>,
+        commitment: &C::Affine,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, BlindedChallenge<C>), Error> {
+        validate_public_key::<C>(pk)?;
+        validate_public_key::<C>(commitment)?;
+        loop {
+            let alpha = C::ScalarField::rand(rng);
+            let beta = C::ScalarField::rand(rng);
+            let blinded_commitment =
+                (parameters.generator.mul(alpha) + pk.mul(beta)).add_mixed(commitment);
+            if let Some(verifier_challenge) = compute_verifier_challenge(
+                parameters,
+                &blinded_commitment.into_affine(),
+                pk,
+                message,
+            )? {
+                let blinded_challenge = verifier_challenge - beta;
+                let state = Self {
+                    public_key: *pk,
+                    commitment: *commitment,
+                    blinded_challenge,
+                    verifier_challenge,
+                    alpha,
+                };
+                return Ok((state, BlindedChallenge(blinded_challenge)));
+            }
+        }
+    }
+
+    /// Checks the signer's `response` and unblinds it into a signature on
+    /// the message passed to [`BlindingState::blind`].
+    pub fn unblind<H: ChallengeHash<C>>(
+        self,
+        parameters: &Parameters<C, H>,
+        response: &BlindedResponse<C>,
+    ) -> Result<Signature<C>, Error> {
+        let claimed_commitment =
+            parameters.generator.mul(response.0) + self.public_key.mul(self.blinded_challenge);
+        if claimed_commitment.into_affine() != self.commitment {
+            return Err(SignatureError::ChallengeMismatch.into());
+        }
+        Ok(Signature {
+            prover_response: response.0 + self.alpha,
+            verifier_challenge: self.verifier_challenge,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signature::{schnorr::Schnorr, SignatureScheme};
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_std::test_rng;
+    use blake2::Blake2s;
+
+    type S = Schnorr<JubJub, Blake2s>;
+
+    #[test]
+    fn blind_signature_test() {
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let message = b"token serial number";
+
+        let mut signer = SignerState::new(sk);
+        let commitment = signer.commit(&parameters, rng).unwrap();
+        let (user, challenge) =
+            BlindingState::blind(&parameters, &pk, &commitment, message, rng).unwrap();
+        let response = signer.respond(&challenge).unwrap();
+        let signature = user.unblind(&parameters, &response).unwrap();
+        assert!(S::verify(&parameters, &pk, message, &signature).unwrap());
+        assert!(!S::verify(&parameters, &pk, b"other message", &signature).unwrap());
+
+        // Nothing the signer saw appears in the signature.
+        assert_ne!(signature.verifier_challenge, challenge.0);
+        assert_ne!(signature.prover_response, response.0);
+        let signed_commitment = parameters.generator.mul(signature.prover_response)
+            + pk.mul(signature.verifier_challenge);
+        assert_ne!(signed_commitment.into_affine(), commitment);
+    }
+
+    #[test]
+    fn tampered_challenge_test() {
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let message = b"token serial number";
+
+        let mut signer = SignerState::new(sk);
+        let commitment = signer.commit(&parameters, rng).unwrap();
+        let (user, challenge) =
+            BlindingState::blind(&parameters, &pk, &commitment, message, rng).unwrap();
+        let tampered =
+            BlindedChallenge(challenge.0 + <JubJub as ProjectiveCurve>::ScalarField::from(1u64));
+        let response = signer.respond(&tampered).unwrap();
+        assert!(matches!(
+            user.unblind(&parameters, &response),
+            Err(Error::Crypto(crate::CryptoError::Signature(
+                SignatureError::ChallengeMismatch
+            )))
+        ));
+    }
+
+    #[test]
+    fn sequential_sessions_test() {
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let mut signer = SignerState::new(sk);
+
+        // A second session cannot open while the first one is.
+        let commitment = signer.commit(&parameters, rng).unwrap();
+        assert!(signer.is_open());
+        assert!(matches!(
+            signer.commit(&parameters, rng),
+            Err(Error::Crypto(crate::CryptoError::Signature(
+                SignatureError::SessionInProgress
+            )))
+        ));
+        let (user, challenge) =
+            BlindingState::blind(&parameters, &pk, &commitment, b"first", rng).unwrap();
+        let response = signer.respond(&challenge).unwrap();
+        assert!(!signer.is_open());
+        assert!(matches!(
+            signer.respond(&challenge),
+            Err(Error::Crypto(crate::CryptoError::Signature(
+                SignatureError::NoSession
+            )))
+        ));
+        let signature = user.unblind(&parameters, &response).unwrap();
+        assert!(S::verify(&parameters, &pk, b"first", &signature).unwrap());
+
+        // An aborted session frees the signer without a response.
+        let _ = signer.commit(&parameters, rng).unwrap();
+        signer.abort();
+        assert!(matches!(
+            signer.respond(&challenge),
+            Err(Error::Crypto(crate::CryptoError::Signature(
+                SignatureError::NoSession
+            )))
+        ));
+        let commitment = signer.commit(&parameters, rng).unwrap();
+        let (user, challenge) =
+            BlindingState::blind(&parameters, &pk, &commitment, b"second", rng).unwrap();
+        let response = signer.respond(&challenge).unwrap();
+        let signature = user.unblind(&parameters, &response).unwrap();
+        assert!(S::verify(&parameters, &pk, b"second", &signature).unwrap());
+    }
+}
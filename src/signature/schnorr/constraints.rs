@@ -1,14 +1,21 @@
 use crate::Vec;
-use ark_ec::ProjectiveCurve;
-use ark_ff::Field;
-use ark_r1cs_std::prelude::*;
+use ark_ec::{
+    twisted_edwards_extended::GroupProjective as TEProjective, ProjectiveCurve, TEModelParameters,
+};
+use ark_ff::{BigInteger, Field, FpParameters, PrimeField};
+use ark_r1cs_std::{
+    fields::fp::FpVar, groups::curves::twisted_edwards::AffineVar, prelude::*,
+    ToConstraintFieldGadget,
+};
 use ark_relations::r1cs::{Namespace, SynthesisError};
 
-use crate::signature::SigRandomizePkGadget;
+use crate::prf::blake2s::constraints::evaluate_blake2s;
+use crate::signature::{SigRandomizePkGadget, SigVerifyGadget};
 
-use core::{borrow::Borrow, marker::PhantomData};
+use core::{borrow::Borrow, hash::Hash, marker::PhantomData};
 
-use crate::signature::schnorr::{Parameters, PublicKey, Schnorr};
+use crate::signature::schnorr::{Parameters, PublicKey, Schnorr, Signature};
+use blake2::Blake2s;
 use digest::Digest;
 
 type ConstraintF<C> = <<C as ProjectiveCurve>::BaseField as Field>::BasePrimeField;
@@ -19,6 +26,7 @@ where
     for<'a> &'a GC: GroupOpsBounds<'a, C, GC>,
 {
     generator: GC,
+    salt: Vec<UInt8<ConstraintF<C>>>,
     _curve: PhantomData<C>,
 }
 
@@ -31,11 +39,42 @@ pub struct PublicKeyVar<C: ProjectiveCurve, GC: CurveVar<C, ConstraintF<C>>>
 where
     for<'a> &'a GC: GroupOpsBounds<'a, C, GC>,
 {
-    pub_key: GC,
+    pub(crate) pub_key: GC,
     #[doc(hidden)]
     _group: PhantomData<*const C>,
 }
 
+impl<C, GC> PublicKeyVar<C, GC>
+where
+    C: ProjectiveCurve,
+    GC: CurveVar<C, ConstraintF<C>>,
+    for<'a> &'a GC: GroupOpsBounds<'a, C, GC>,
+{
+    pub fn pub_key(&self) -> &GC {
+        &self.pub_key
+    }
+}
+
+/// The leaf of a public key on a twisted Edwards curve is `[x, y]`, as for
+/// Pedersen commitments.
+impl<P> PublicKeyVar<TEProjective<P>, AffineVar<P, FpVar<P::BaseField>>>
+where
+    P: TEModelParameters,
+    P::BaseField: PrimeField,
+{
+    pub fn to_leaf(&self) -> Vec<FpVar<P::BaseField>> {
+        vec![self.pub_key.x.clone(), self.pub_key.y.clone()]
+    }
+}
+
+/// A Schnorr signature as the little-endian bits of its two scalars.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "C: ProjectiveCurve"))]
+pub struct SignatureVar<C: ProjectiveCurve> {
+    pub(crate) prover_response: Vec<Boolean<ConstraintF<C>>>,
+    pub(crate) verifier_challenge: Vec<Boolean<ConstraintF<C>>>,
+}
+
 pub struct SchnorrRandomizePkGadget<C: ProjectiveCurve, GC: CurveVar<C, ConstraintF<C>>>
 where
     for<'a> &'a GC: GroupOpsBounds<'a, C, GC>,
@@ -76,6 +115,89 @@ where
     }
 }
 
+impl<C, GC> SchnorrRandomizePkGadget<C, GC>
+where
+    C: ProjectiveCurve,
+    GC: CurveVar<C, ConstraintF<C>>,
+    for<'a> &'a GC: GroupOpsBounds<'a, C, GC>,
+{
+    /// Randomizes `public_key` with randomness that is already a field
+    /// element in the circuit, e.g. a PRF output, instead of bytes. It is
+    /// decomposed once into the little-endian bits of its canonical integer,
+    /// so the result is the native
+    /// `randomize_public_key(pk, to_bytes![randomness])`.
+    #[tracing::instrument(target = "r1cs", skip(parameters, public_key, randomness))]
+    pub fn randomize_field(
+        parameters: &ParametersVar<C, GC>,
+        public_key: &PublicKeyVar<C, GC>,
+        randomness: &FpVar<ConstraintF<C>>,
+    ) -> Result<PublicKeyVar<C, GC>, SynthesisError> {
+        let randomness = randomness.to_bits_le()?;
+        let rand_pk =
+            &public_key.pub_key + &parameters.generator.scalar_mul_le(randomness.iter())?;
+        Ok(PublicKeyVar {
+            pub_key: rand_pk,
+            _group: PhantomData,
+        })
+    }
+}
+
+pub struct SchnorrSignatureVerifyGadget<C: ProjectiveCurve, GC: CurveVar<C, ConstraintF<C>>>
+where
+    for<'a> &'a GC: GroupOpsBounds<'a, C, GC>,
+{
+    #[doc(hidden)]
+    _group: PhantomData<*const C>,
+    #[doc(hidden)]
+    _group_gadget: PhantomData<*const GC>,
+}
+
+impl<C, GC> SigVerifyGadget<Schnorr<C, Blake2s>, ConstraintF<C>>
+    for SchnorrSignatureVerifyGadget<C, GC>
+where
+    C: ProjectiveCurve + Hash,
+    GC: CurveVar<C, ConstraintF<C>>,
+    for<'a> &'a GC: GroupOpsBounds<'a, C, GC>,
+{
+    type ParametersVar = ParametersVar<C, GC>;
+    type PublicKeyVar = PublicKeyVar<C, GC>;
+    type SignatureVar = SignatureVar<C>;
+
+    /// Recomputes the prover commitment `s · G + e · pk` and checks that the
+    /// low `MODULUS_BITS` bits of `Blake2s(salt || commitment || message)`
+    /// are `e`, as `from_random_bytes` does natively.
+    #[tracing::instrument(target = "r1cs", skip(parameters, public_key, message, signature))]
+    fn verify(
+        parameters: &Self::ParametersVar,
+        public_key: &Self::PublicKeyVar,
+        message: &[UInt8<ConstraintF<C>>],
+        signature: &Self::SignatureVar,
+    ) -> Result<Boolean<ConstraintF<C>>, SynthesisError> {
+        let claimed_prover_commitment = parameters
+            .generator
+            .scalar_mul_le(signature.prover_response.iter())?
+            + public_key
+                .pub_key
+                .scalar_mul_le(signature.verifier_challenge.iter())?;
+
+        let mut hash_input = parameters.salt.clone();
+        hash_input.extend(claimed_prover_commitment.to_bytes()?);
+        hash_input.extend_from_slice(message);
+        let hash_input_bits = hash_input
+            .iter()
+            .map(|b| b.to_bits_le())
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        let digest_bits = evaluate_blake2s(&hash_input_bits)?
+            .iter()
+            .flat_map(|word| word.to_bits_le())
+            .collect::<Vec<_>>();
+
+        let num_bits = signature.verifier_challenge.len();
+        digest_bits[..num_bits].is_eq(&signature.verifier_challenge)
+    }
+}
+
 impl<C, GC, D> AllocVar<Parameters<C, D>, ConstraintF<C>> for ParametersVar<C, GC>
 where
     C: ProjectiveCurve,
@@ -88,9 +210,26 @@ where
         f: impl FnOnce() -> Result<T, SynthesisError>,
         mode: AllocationMode,
     ) -> Result<Self, SynthesisError> {
-        let generator = GC::new_variable(cs, || f().map(|g| g.borrow().generator), mode)?;
+        let ns = cs.into();
+        let cs = ns.cs();
+        let parameters = f().map(|p| p.borrow().clone());
+        let generator = GC::new_variable(
+            ark_relations::ns!(cs, "generator"),
+            || parameters.as_ref().map(|p| p.generator).map_err(|e| *e),
+            mode,
+        )?;
+        let salt = (0..32)
+            .map(|i| {
+                UInt8::new_variable(
+                    ark_relations::ns!(cs, "salt"),
+                    || parameters.as_ref().map(|p| p.salt[i]).map_err(|e| *e),
+                    mode,
+                )
+            })
+            .collect::<Result<_, _>>()?;
         Ok(Self {
             generator,
+            salt,
             _curve: PhantomData,
         })
     }
@@ -115,6 +254,48 @@ where
     }
 }
 
+impl<C: ProjectiveCurve> AllocVar<Signature<C>, ConstraintF<C>> for SignatureVar<C> {
+    /// Allocates `MODULUS_BITS` bits per scalar. The challenge is also
+    /// constrained to be below the scalar field modulus, so that it matches
+    /// the digest only where the native `from_random_bytes` succeeds.
+    fn new_variable<T: Borrow<Signature<C>>>(
+        cs: impl Into<Namespace<ConstraintF<C>>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        let signature = f().map(|s| s.borrow().clone());
+        let num_bits = <C::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+        let alloc_bits = |scalar: fn(&Signature<C>) -> C::ScalarField| {
+            let bits = signature
+                .as_ref()
+                .map(|s| scalar(s).into_repr().to_bits_le())
+                .map_err(|e| *e);
+            (0..num_bits)
+                .map(|i| {
+                    Boolean::new_variable(
+                        ark_relations::ns!(cs, "bit"),
+                        || bits.as_ref().map(|b| b[i]).map_err(|e| *e),
+                        mode,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let prover_response = alloc_bits(|s| s.prover_response)?;
+        let verifier_challenge = alloc_bits(|s| s.verifier_challenge)?;
+
+        let mut modulus_minus_one = C::ScalarField::characteristic().to_vec();
+        // The modulus is odd, so this does not borrow.
+        modulus_minus_one[0] -= 1;
+        Boolean::enforce_smaller_or_equal_than_le(&verifier_challenge, modulus_minus_one)?;
+        Ok(Self {
+            prover_response,
+            verifier_challenge,
+        })
+    }
+}
+
 impl<C, GC> EqGadget<ConstraintF<C>> for PublicKeyVar<C, GC>
 where
     C: ProjectiveCurve,
@@ -157,3 +338,191 @@ where
         self.pub_key.to_bytes()
     }
 }
+
+/// The public key as public inputs, in the order of `ToConstraintField` for
+/// `PublicKey`: `[x, y]` on twisted Edwards curves.
+impl<C, GC> ToConstraintFieldGadget<ConstraintF<C>> for PublicKeyVar<C, GC>
+where
+    C: ProjectiveCurve,
+    GC: CurveVar<C, ConstraintF<C>> + ToConstraintFieldGadget<ConstraintF<C>>,
+    for<'a> &'a GC: GroupOpsBounds<'a, C, GC>,
+{
+    fn to_constraint_field(&self) -> Result<Vec<FpVar<ConstraintF<C>>>, SynthesisError> {
+        self.pub_key.to_constraint_field()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signature::SignatureScheme;
+    use ark_ed_on_bn254::{constraints::EdwardsVar, EdwardsProjective, Fq};
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+
+    type TestSig = Schnorr<EdwardsProjective, Blake2s>;
+    type TestSigGadget = SchnorrSignatureVerifyGadget<EdwardsProjective, EdwardsVar>;
+
+    fn verify_in_circuit(
+        message: &[u8],
+        signed_message: &[u8],
+    ) -> (Boolean<Fq>, ark_relations::r1cs::ConstraintSystemRef<Fq>) {
+        let rng = &mut test_rng();
+        let parameters = TestSig::setup(rng).unwrap();
+        let (pk, sk) = TestSig::keygen(&parameters, rng).unwrap();
+        let signature = TestSig::sign(&parameters, &sk, signed_message, rng).unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let parameters_var = ParametersVar::new_constant(cs.clone(), &parameters).unwrap();
+        let pk_var = PublicKeyVar::new_witness(cs.clone(), || Ok(pk)).unwrap();
+        let message_var = UInt8::new_witness_vec(cs.clone(), message).unwrap();
+        let signature_var = SignatureVar::new_witness(cs.clone(), || Ok(&signature)).unwrap();
+        let valid =
+            TestSigGadget::verify(&parameters_var, &pk_var, &message_var, &signature_var).unwrap();
+        valid.enforce_equal(&Boolean::TRUE).unwrap();
+        (valid, cs)
+    }
+
+    /// A public key allocated as a public input takes the slots of its
+    /// native `ToConstraintField` elements, `[x, y]`.
+    #[test]
+    fn public_key_input_order_test() {
+        use ark_ff::ToConstraintField;
+
+        let rng = &mut test_rng();
+        let parameters = TestSig::setup(rng).unwrap();
+        let (pk, _) = TestSig::keygen(&parameters, rng).unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let pk_var =
+            PublicKeyVar::<EdwardsProjective, EdwardsVar>::new_input(cs.clone(), || Ok(pk))
+                .unwrap();
+        let native: Vec<Fq> = pk.to_field_elements().unwrap();
+        assert_eq!(native, vec![pk.x, pk.y]);
+        assert_eq!(cs.borrow().unwrap().instance_assignment[1..], native[..]);
+        assert_eq!(
+            pk_var.to_constraint_field().unwrap().value().unwrap(),
+            native
+        );
+    }
+
+    #[test]
+    fn randomize_pk_gadget_test() {
+        use ark_ed_on_bls12_381::{constraints::EdwardsVar as JubJubVar, EdwardsProjective};
+        use ark_std::UniformRand;
+        type S = Schnorr<EdwardsProjective, Blake2s>;
+        type G = SchnorrRandomizePkGadget<EdwardsProjective, JubJubVar>;
+
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let message = b"Hi, I am a Schnorr signature!";
+        let signature = S::sign(&parameters, &sk, message, rng).unwrap();
+        let randomness = ark_ff::to_bytes![ark_ed_on_bls12_381::Fr::rand(rng)].unwrap();
+        let randomized_pk = S::randomize_public_key(&parameters, &pk, &randomness).unwrap();
+        let randomized_signature =
+            S::randomize_signature(&parameters, &signature, &randomness).unwrap();
+        assert!(S::verify(&parameters, &randomized_pk, message, &randomized_signature).unwrap());
+
+        let cs = ConstraintSystem::<ark_ed_on_bls12_381::Fq>::new_ref();
+        let parameters_var =
+            ParametersVar::<EdwardsProjective, JubJubVar>::new_constant(cs.clone(), &parameters)
+                .unwrap();
+        let pk_var =
+            PublicKeyVar::<EdwardsProjective, JubJubVar>::new_witness(cs.clone(), || Ok(pk))
+                .unwrap();
+        let randomness_var = UInt8::new_witness_vec(cs.clone(), &randomness).unwrap();
+        let randomized_pk_var =
+            <G as SigRandomizePkGadget<S, _>>::randomize(&parameters_var, &pk_var, &randomness_var)
+                .unwrap();
+        assert_eq!(
+            randomized_pk_var.pub_key.value().unwrap(),
+            ark_ec::AffineCurve::into_projective(&randomized_pk)
+        );
+        let expected_var =
+            PublicKeyVar::<EdwardsProjective, JubJubVar>::new_input(cs.clone(), || {
+                Ok(randomized_pk)
+            })
+            .unwrap();
+        randomized_pk_var.enforce_equal(&expected_var).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn randomize_field_gadget_test() {
+        use ark_ed_on_bls12_381::{constraints::EdwardsVar as JubJubVar, EdwardsProjective};
+        use ark_std::UniformRand;
+        type S = Schnorr<EdwardsProjective, Blake2s>;
+        type G = SchnorrRandomizePkGadget<EdwardsProjective, JubJubVar>;
+        type F = ark_ed_on_bls12_381::Fq;
+
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, _) = S::keygen(&parameters, rng).unwrap();
+        // The randomness is a base field element, which may exceed the group
+        // order.
+        let randomness = F::rand(rng);
+        let randomized_pk =
+            S::randomize_public_key(&parameters, &pk, &ark_ff::to_bytes![randomness].unwrap())
+                .unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let parameters_var =
+            ParametersVar::<EdwardsProjective, JubJubVar>::new_constant(cs.clone(), &parameters)
+                .unwrap();
+        let pk_var =
+            PublicKeyVar::<EdwardsProjective, JubJubVar>::new_witness(cs.clone(), || Ok(pk))
+                .unwrap();
+        let randomness_var = FpVar::new_witness(cs.clone(), || Ok(randomness)).unwrap();
+        let before = cs.num_constraints();
+        let randomized_pk_var =
+            G::randomize_field(&parameters_var, &pk_var, &randomness_var).unwrap();
+        assert_eq!(cs.num_constraints() - before, 1908);
+        assert_eq!(
+            randomized_pk_var.pub_key().value().unwrap(),
+            ark_ec::AffineCurve::into_projective(&randomized_pk)
+        );
+        let leaf = randomized_pk_var.to_leaf().value().unwrap();
+        assert_eq!(leaf, vec![randomized_pk.x, randomized_pk.y]);
+        assert!(cs.is_satisfied().unwrap());
+
+        // Going through bytes decomposes the element the same way, and
+        // costs no less.
+        let cs = ConstraintSystem::<F>::new_ref();
+        let parameters_var =
+            ParametersVar::<EdwardsProjective, JubJubVar>::new_constant(cs.clone(), &parameters)
+                .unwrap();
+        let pk_var =
+            PublicKeyVar::<EdwardsProjective, JubJubVar>::new_witness(cs.clone(), || Ok(pk))
+                .unwrap();
+        let randomness_var = FpVar::new_witness(cs.clone(), || Ok(randomness)).unwrap();
+        let before = cs.num_constraints();
+        let randomness_bytes = randomness_var.to_bytes().unwrap();
+        let byte_randomized_pk_var = <G as SigRandomizePkGadget<S, _>>::randomize(
+            &parameters_var,
+            &pk_var,
+            &randomness_bytes,
+        )
+        .unwrap();
+        assert!(cs.num_constraints() - before >= 1908);
+        assert_eq!(
+            byte_randomized_pk_var.pub_key().value().unwrap(),
+            ark_ec::AffineCurve::into_projective(&randomized_pk)
+        );
+    }
+
+    #[test]
+    fn schnorr_verify_gadget_test() {
+        let message = b"Hi, I am a Schnorr signature!";
+        let (valid, cs) = verify_in_circuit(message, message);
+        assert!(valid.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(cs.num_constraints(), 49364);
+
+        let mut flipped = message.to_vec();
+        flipped[0] ^= 1;
+        let (valid, cs) = verify_in_circuit(&flipped, message);
+        assert!(!valid.value().unwrap());
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -1,33 +1,149 @@
-use crate::{Error, SignatureScheme, Vec};
-use ark_ec::{AffineCurve, ProjectiveCurve};
+use crate::prf::Blake2sCounterExpand;
+use crate::signature::SignatureError;
+use crate::{Box, CryptoError, Error, SignatureScheme, Vec};
+use ark_ec::{msm::FixedBaseMSM, AffineCurve, ProjectiveCurve};
 use ark_ff::{
     bytes::ToBytes,
-    fields::{Field, PrimeField},
-    to_bytes, One, ToConstraintField, UniformRand, Zero,
+    fields::{Field, FpParameters, PrimeField},
+    to_bytes, ToConstraintField, UniformRand, Zero,
 };
-use ark_std::io::{Result as IoResult, Write};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::io::{Read, Result as IoResult, Write};
 use ark_std::rand::Rng;
 use ark_std::{hash::Hash, marker::PhantomData};
-use digest::Digest;
+use blake2::Blake2s;
+use digest::{Digest, Output};
 
+pub mod bip340;
+pub mod blind;
 #[cfg(feature = "r1cs")]
 pub mod constraints;
+pub mod musig;
+pub mod poseidon;
 
-pub struct Schnorr<C: ProjectiveCurve, D: Digest> {
+/// Schnorr signatures over the group `C`, with the verifier challenge
+/// computed by `H`: any [`Digest`], or the Poseidon hash of
+/// [`PoseidonChallenge`](poseidon::PoseidonChallenge).
+pub struct Schnorr<C: ProjectiveCurve, H: ChallengeHash<C>> {
     _group: PhantomData<C>,
-    _hash: PhantomData<D>,
+    _hash: PhantomData<H>,
 }
 
 #[derive(Derivative)]
-#[derivative(Clone(bound = "C: ProjectiveCurve, H: Digest"), Debug)]
-pub struct Parameters<C: ProjectiveCurve, H: Digest> {
-    _hash: PhantomData<H>,
+#[derivative(
+    Clone(bound = "C: ProjectiveCurve, H: ChallengeHash<C>"),
+    Debug(bound = "C: ProjectiveCurve, H: ChallengeHash<C>")
+)]
+pub struct Parameters<C: ProjectiveCurve, H: ChallengeHash<C>> {
+    #[derivative(Debug = "ignore")]
+    pub hash_parameters: H::Parameters,
     pub generator: C::Affine,
     pub salt: [u8; 32],
 }
 
+/// The hash from which [`Schnorr`] derives the verifier challenge `e` for a
+/// prover commitment `r`, a public key and a message.
+pub trait ChallengeHash<C: ProjectiveCurve>: Sized {
+    type Parameters: Clone + Send + Sync;
+
+    /// Whether the challenge depends on the public key. Public keys and
+    /// signatures cannot be randomized if it does.
+    const BINDS_PUBLIC_KEY: bool;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error>;
+
+    /// Bytes that identify the hash and its parameters.
+    /// [`Schnorr::sign_deterministic`] binds them into the nonce, so that
+    /// a key never signs with the same nonce under two challenge hashes.
+    fn nonce_tag(parameters: &Parameters<C, Self>) -> Result<Vec<u8>, Error>;
+
+    /// Computes the challenge, or `None` if the hash does not yield a
+    /// scalar, in which case the signer draws a new nonce.
+    fn hash(
+        parameters: &Parameters<C, Self>,
+        prover_commitment: &C::Affine,
+        public_key: &PublicKey<C>,
+        message: &[u8],
+    ) -> Result<Option<C::ScalarField>, Error>;
+}
+
+/// A digest computes `e := H(salt || r || msg)`, which is a challenge if it
+/// is the canonical encoding of a scalar. The public key is not hashed.
+impl<C: ProjectiveCurve, D: Digest> ChallengeHash<C> for D {
+    type Parameters = ();
+
+    const BINDS_PUBLIC_KEY: bool = false;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    /// The digest of a fixed string, which differs between digests.
+    fn nonce_tag(_parameters: &Parameters<C, Self>) -> Result<Vec<u8>, Error> {
+        Ok(D::digest(DIGEST_NONCE_TAG).to_vec())
+    }
+
+    fn hash(
+        parameters: &Parameters<C, Self>,
+        prover_commitment: &C::Affine,
+        _public_key: &PublicKey<C>,
+        message: &[u8],
+    ) -> Result<Option<C::ScalarField>, Error> {
+        let mut hash_input = Vec::new();
+        hash_input.extend_from_slice(&parameters.salt);
+        hash_input.extend_from_slice(&to_bytes![prover_commitment]?);
+        hash_input.extend_from_slice(message);
+        Ok(C::ScalarField::from_random_bytes(&D::digest(&hash_input)))
+    }
+}
+
 pub type PublicKey<C> = <C as ProjectiveCurve>::Affine;
 
+/// Checks that `pk` is a non-identity element of the prime order subgroup.
+/// [`Schnorr`] verification rejects keys that fail this check; callers
+/// registering keys can run it up front.
+pub fn validate_public_key<C: ProjectiveCurve>(pk: &PublicKey<C>) -> Result<(), CryptoError> {
+    let order = <C::ScalarField as PrimeField>::Params::MODULUS;
+    if pk.is_zero() || !pk.mul(order).is_zero() {
+        return Err(CryptoError::NotPrimeOrder);
+    }
+    Ok(())
+}
+
+/// A public key that passed [`validate_public_key`]. Keys checked once,
+/// when they are registered, verify with [`Schnorr::verify_validated`] and
+/// [`Schnorr::batch_verify_validated`], which skip the subgroup check.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "C: ProjectiveCurve"),
+    Copy(bound = "C: ProjectiveCurve"),
+    Debug(bound = "C: ProjectiveCurve"),
+    PartialEq(bound = "C: ProjectiveCurve"),
+    Eq(bound = "C: ProjectiveCurve")
+)]
+pub struct ValidatedPublicKey<C: ProjectiveCurve>(PublicKey<C>);
+
+impl<C: ProjectiveCurve> ValidatedPublicKey<C> {
+    /// Checks `pk` with [`validate_public_key`].
+    pub fn new(pk: PublicKey<C>) -> Result<Self, CryptoError> {
+        validate_public_key::<C>(&pk)?;
+        Ok(Self(pk))
+    }
+
+    pub fn public_key(&self) -> &PublicKey<C> {
+        &self.0
+    }
+}
+
+/// Deserializes a public key and checks it with [`validate_public_key`].
+pub fn deserialize_public_key<C: ProjectiveCurve, R: Read>(
+    reader: R,
+) -> Result<PublicKey<C>, SerializationError> {
+    let pk = PublicKey::<C>::deserialize(reader)?;
+    validate_public_key::<C>(&pk).map_err(|_| SerializationError::InvalidData)?;
+    Ok(pk)
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct SecretKey<C: ProjectiveCurve>(pub C::ScalarField);
 
@@ -38,17 +154,84 @@ impl<C: ProjectiveCurve> ToBytes for SecretKey<C> {
     }
 }
 
+impl<C: ProjectiveCurve> CanonicalSerialize for SecretKey<C> {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.0.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.0.serialized_size()
+    }
+}
+
+impl<C: ProjectiveCurve> CanonicalDeserialize for SecretKey<C> {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(SecretKey(C::ScalarField::deserialize(reader)?))
+    }
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct Signature<C: ProjectiveCurve> {
     pub prover_response: C::ScalarField,
     pub verifier_challenge: C::ScalarField,
 }
 
-impl<C: ProjectiveCurve + Hash, D: Digest + Send + Sync> SignatureScheme for Schnorr<C, D>
+/// A signature is serialized as the prover response `s` followed by the
+/// verifier challenge `e`, each in the canonical encoding of the scalar
+/// field. Scalars that are not below the group order are rejected.
+impl<C: ProjectiveCurve> CanonicalSerialize for Signature<C> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.prover_response.serialize(&mut writer)?;
+        self.verifier_challenge.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.prover_response.serialized_size() + self.verifier_challenge.serialized_size()
+    }
+}
+
+impl<C: ProjectiveCurve> CanonicalDeserialize for Signature<C> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Signature {
+            prover_response: C::ScalarField::deserialize(&mut reader)?,
+            verifier_challenge: C::ScalarField::deserialize(&mut reader)?,
+        })
+    }
+}
+
+/// Parameters are serialized as the compressed generator followed by the
+/// 32-byte salt. Public keys use the compressed point encoding of
+/// `C::Affine`, which rejects points off the curve or outside the prime
+/// order subgroup.
+impl<C: ProjectiveCurve, H: Digest> CanonicalSerialize for Parameters<C, H> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.generator.serialize(&mut writer)?;
+        Ok(writer.write_all(&self.salt)?)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.generator.serialized_size() + self.salt.len()
+    }
+}
+
+impl<C: ProjectiveCurve, H: Digest> CanonicalDeserialize for Parameters<C, H> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let generator = C::Affine::deserialize(&mut reader)?;
+        let mut salt = [0u8; 32];
+        reader.read_exact(&mut salt)?;
+        Ok(Parameters {
+            hash_parameters: (),
+            generator,
+            salt,
+        })
+    }
+}
+
+impl<C: ProjectiveCurve + Hash, H: ChallengeHash<C>> SignatureScheme for Schnorr<C, H>
 where
     C::ScalarField: PrimeField,
 {
-    type Parameters = Parameters<C, D>;
+    type Parameters = Parameters<C, H>;
     type PublicKey = PublicKey<C>;
     type SecretKey = SecretKey<C>;
     type Signature = Signature<C>;
@@ -59,10 +242,11 @@ where
         let mut salt = [0u8; 32];
         rng.fill_bytes(&mut salt);
         let generator = C::rand(rng).into();
+        let hash_parameters = H::setup(rng)?;
 
         end_timer!(setup_time);
         Ok(Parameters {
-            _hash: PhantomData,
+            hash_parameters,
             generator,
             salt,
         })
@@ -88,35 +272,9 @@ where
         rng: &mut R,
     ) -> Result<Self::Signature, Error> {
         let sign_time = start_timer!(|| "SchnorrSig::Sign");
-        // (k, e);
-        let (random_scalar, verifier_challenge) = loop {
-            // Sample a random scalar `k` from the prime scalar field.
-            let random_scalar: C::ScalarField = C::ScalarField::rand(rng);
-            // Commit to the random scalar via r := k · G.
-            // This is the prover's first msg in the Sigma protocol.
-            let prover_commitment = parameters.generator.mul(random_scalar).into_affine();
-
-            // Hash everything to get verifier challenge.
-            let mut hash_input = Vec::new();
-            hash_input.extend_from_slice(&parameters.salt);
-            hash_input.extend_from_slice(&to_bytes![prover_commitment]?);
-            hash_input.extend_from_slice(message);
-
-            // Compute the supposed verifier response: e := H(salt || r || msg);
-            if let Some(verifier_challenge) =
-                C::ScalarField::from_random_bytes(&D::digest(&hash_input))
-            {
-                break (random_scalar, verifier_challenge);
-            };
-        };
-
-        // k - xe;
-        let prover_response = random_scalar - (verifier_challenge * sk.0);
-        let signature = Signature {
-            prover_response,
-            verifier_challenge,
-        };
-
+        // Sample a random scalar `k` from the prime scalar field.
+        let signature =
+            sign_with_nonces(parameters, sk, message, || Ok(C::ScalarField::rand(rng)))?;
         end_timer!(sign_time);
         Ok(signature)
     }
@@ -127,79 +285,59 @@ where
         message: &[u8],
         signature: &Self::Signature,
     ) -> Result<bool, Error> {
-        let verify_time = start_timer!(|| "SchnorrSig::Verify");
-
-        let Signature {
-            prover_response,
-            verifier_challenge,
-        } = signature;
-        let mut claimed_prover_commitment = parameters.generator.mul(*prover_response);
-        let public_key_times_verifier_challenge = pk.mul(*verifier_challenge);
-        claimed_prover_commitment += &public_key_times_verifier_challenge;
-        let claimed_prover_commitment = claimed_prover_commitment.into_affine();
-
-        let mut hash_input = Vec::new();
-        hash_input.extend_from_slice(&parameters.salt);
-        hash_input.extend_from_slice(&to_bytes![claimed_prover_commitment]?);
-        hash_input.extend_from_slice(&message);
+        verification_result(Self::verify_detailed(parameters, pk, message, signature))
+    }
 
-        let obtained_verifier_challenge = if let Some(obtained_verifier_challenge) =
-            C::ScalarField::from_random_bytes(&D::digest(&hash_input))
-        {
-            obtained_verifier_challenge
-        } else {
-            return Ok(false);
-        };
-        end_timer!(verify_time);
-        Ok(verifier_challenge == &obtained_verifier_challenge)
+    /// Since the signature carries the challenge rather than the prover
+    /// commitment, every commitment has to be recomputed and hashed, and
+    /// there is no combined equation to randomize. Instead, the `s · G`
+    /// terms share one fixed-base table and the commitments are normalized
+    /// together, which pays off from [`BATCH_VERIFY_THRESHOLD`] signatures.
+    fn batch_verify<R: Rng>(
+        parameters: &Self::Parameters,
+        items: &[(Self::PublicKey, &[u8], Self::Signature)],
+        _rng: &mut R,
+    ) -> Result<bool, Error> {
+        verification_result(Self::batch_verify_detailed(parameters, items))
     }
 
+    /// Returns `pk + r · G`, where `r` is `randomness` read as a
+    /// little-endian integer, as `SchnorrRandomizePkGadget` does in a
+    /// circuit. A signature under `pk` randomized with the same `randomness`
+    /// verifies under the result.
     fn randomize_public_key(
         parameters: &Self::Parameters,
         public_key: &Self::PublicKey,
         randomness: &[u8],
     ) -> Result<Self::PublicKey, Error> {
-        let rand_pk_time = start_timer!(|| "SchnorrSig::RandomizePubKey");
-
-        let randomized_pk = *public_key;
-        let base = parameters.generator;
-        let mut encoded = C::zero();
-        for bit in bytes_to_bits(randomness)
-            .into_iter()
-            .rev()
-            .skip_while(|b| !b)
-        {
-            encoded.double_in_place();
-            if bit {
-                encoded.add_assign_mixed(&base)
-            }
+        if H::BINDS_PUBLIC_KEY {
+            return Err(CryptoError::UnsupportedOperation.into());
         }
-        encoded.add_assign_mixed(&randomized_pk);
-
+        let rand_pk_time = start_timer!(|| "SchnorrSig::RandomizePubKey");
+        let multiplier = C::ScalarField::from_le_bytes_mod_order(randomness);
+        let mut randomized_pk = parameters.generator.mul(multiplier);
+        randomized_pk.add_assign_mixed(public_key);
         end_timer!(rand_pk_time);
-
-        Ok(encoded.into())
+        Ok(randomized_pk.into())
     }
 
+    /// Returns `(s - e · r, e)` for a signature `(s, e)`, with `r` read from
+    /// `randomness` as in `randomize_public_key`, so that the prover
+    /// commitment `s · G + e · pk` is unchanged under the randomized key.
     fn randomize_signature(
         _parameter: &Self::Parameters,
         signature: &Self::Signature,
         randomness: &[u8],
     ) -> Result<Self::Signature, Error> {
+        if H::BINDS_PUBLIC_KEY {
+            return Err(CryptoError::UnsupportedOperation.into());
+        }
         let rand_signature_time = start_timer!(|| "SchnorrSig::RandomizeSig");
         let Signature {
             prover_response,
             verifier_challenge,
         } = signature;
-        let mut base = C::ScalarField::one();
-        let mut multiplier = C::ScalarField::zero();
-        for bit in bytes_to_bits(randomness) {
-            if bit {
-                multiplier += &base;
-            }
-            base.double_in_place();
-        }
-
+        let multiplier = C::ScalarField::from_le_bytes_mod_order(randomness);
         let new_sig = Signature {
             prover_response: *prover_response - (*verifier_challenge * multiplier),
             verifier_challenge: *verifier_challenge,
@@ -209,6 +347,415 @@ where
     }
 }
 
+impl<C: ProjectiveCurve, H: ChallengeHash<C>> Schnorr<C, H>
+where
+    C::ScalarField: PrimeField,
+{
+    /// Signs `message` with a nonce derived from the secret key, the
+    /// parameters and the message instead of an RNG, so that signing is
+    /// reproducible and does not depend on the quality of the randomness.
+    ///
+    /// The `j`-th candidate nonce, for `j = 0, 1, ...`, is the 64-byte
+    /// [`Blake2sCounterExpand`] output with the secret key, as 32 little-endian bytes,
+    /// as the seed and the input
+    ///
+    /// ```text
+    /// Blake2s(NONCE_DOMAIN || LE64(len(tag)) || tag || salt || G || pk
+    ///         || LE64(len(message)) || message || LE32(j))
+    /// ```
+    ///
+    /// reduced modulo the scalar field order, where `tag` is
+    /// [`ChallengeHash::nonce_tag`] and `G` and `pk` are the compressed
+    /// generator and public key. The first candidate that is nonzero and
+    /// yields a challenge is used; almost always, that is `j = 0`.
+    ///
+    /// Everything the challenge depends on is hashed: signing one message
+    /// with one key under two sets of parameters or two challenge hashes
+    /// would otherwise reuse the nonce with different challenges, and two
+    /// such signatures reveal the secret key.
+    ///
+    /// Scalar fields wider than 256 bits are not supported.
+    pub fn sign_deterministic(
+        parameters: &Parameters<C, H>,
+        sk: &SecretKey<C>,
+        message: &[u8],
+    ) -> Result<Signature<C>, Error> {
+        let sign_time = start_timer!(|| "SchnorrSig::SignDeterministic");
+        let sk_bytes = to_bytes![sk]?;
+        if sk_bytes.len() > 32 {
+            end_timer!(sign_time);
+            return Err(CryptoError::IncorrectInputLength(sk_bytes.len()).into());
+        }
+        let mut seed = [0u8; 32];
+        seed[..sk_bytes.len()].copy_from_slice(&sk_bytes);
+
+        let tag = H::nonce_tag(parameters)?;
+        let pk = parameters.generator.mul(sk.0).into_affine();
+        let mut prefix = Blake2s::new();
+        prefix.update(NONCE_DOMAIN);
+        prefix.update((tag.len() as u64).to_le_bytes());
+        prefix.update(&tag);
+        prefix.update(parameters.salt);
+        prefix.update(to_bytes![parameters.generator, pk]?);
+        prefix.update((message.len() as u64).to_le_bytes());
+        prefix.update(message);
+
+        let expand = Blake2sCounterExpand { output_len: 64 };
+        let mut counter = 0u32;
+        let signature = sign_with_nonces(parameters, sk, message, || {
+            let mut h = prefix.clone();
+            h.update(counter.to_le_bytes());
+            counter += 1;
+            let mut input = [0u8; 32];
+            input.copy_from_slice(&h.finalize());
+            Ok(C::ScalarField::from_le_bytes_mod_order(
+                &expand.evaluate(&seed, &input)?,
+            ))
+        })?;
+        end_timer!(sign_time);
+        Ok(signature)
+    }
+}
+
+impl<C: ProjectiveCurve + Hash, H: ChallengeHash<C>> Schnorr<C, H>
+where
+    C::ScalarField: PrimeField,
+{
+    /// Verifies `signature`, reporting why it is rejected.
+    /// [`SignatureScheme::verify`] returns `Ok(false)` for
+    /// [`SignatureError::ChallengeMismatch`] and an error otherwise.
+    pub fn verify_detailed(
+        parameters: &Parameters<C, H>,
+        pk: &PublicKey<C>,
+        message: &[u8],
+        signature: &Signature<C>,
+    ) -> Result<(), SignatureError> {
+        validate_public_key::<C>(pk).map_err(|_| SignatureError::InvalidPublicKey)?;
+        Self::verify_unchecked(parameters, pk, message, signature)
+    }
+
+    /// Verifies `signature` like [`verify_detailed`](Self::verify_detailed),
+    /// without checking the already validated key again.
+    pub fn verify_validated(
+        parameters: &Parameters<C, H>,
+        pk: &ValidatedPublicKey<C>,
+        message: &[u8],
+        signature: &Signature<C>,
+    ) -> Result<(), SignatureError> {
+        Self::verify_unchecked(parameters, &pk.0, message, signature)
+    }
+
+    fn verify_unchecked(
+        parameters: &Parameters<C, H>,
+        pk: &PublicKey<C>,
+        message: &[u8],
+        signature: &Signature<C>,
+    ) -> Result<(), SignatureError> {
+        let verify_time = start_timer!(|| "SchnorrSig::Verify");
+
+        let Signature {
+            prover_response,
+            verifier_challenge,
+        } = signature;
+        let mut claimed_prover_commitment = parameters.generator.mul(*prover_response);
+        let public_key_times_verifier_challenge = pk.mul(*verifier_challenge);
+        claimed_prover_commitment += &public_key_times_verifier_challenge;
+        let claimed_prover_commitment = claimed_prover_commitment.into_affine();
+
+        let obtained_verifier_challenge =
+            compute_verifier_challenge(parameters, &claimed_prover_commitment, pk, message);
+        end_timer!(verify_time);
+        let obtained_verifier_challenge =
+            obtained_verifier_challenge.map_err(|_| SignatureError::InvalidSignatureEncoding)?;
+        if Some(*verifier_challenge) != obtained_verifier_challenge {
+            return Err(SignatureError::ChallengeMismatch);
+        }
+        Ok(())
+    }
+
+    /// Verifies a signature given in its canonical serialization, which
+    /// must be consumed exactly.
+    pub fn verify_serialized(
+        parameters: &Parameters<C, H>,
+        pk: &PublicKey<C>,
+        message: &[u8],
+        mut signature: &[u8],
+    ) -> Result<(), SignatureError> {
+        let decoded = Signature::<C>::deserialize(&mut signature)
+            .map_err(|_| SignatureError::InvalidSignatureEncoding)?;
+        if !signature.is_empty() {
+            return Err(SignatureError::InvalidSignatureEncoding);
+        }
+        Self::verify_detailed(parameters, pk, message, &decoded)
+    }
+
+    /// Verifies every item like [`SignatureScheme::batch_verify`], reporting
+    /// the first rejected item as [`SignatureError::BatchItem`].
+    pub fn batch_verify_detailed(
+        parameters: &Parameters<C, H>,
+        items: &[(PublicKey<C>, &[u8], Signature<C>)],
+    ) -> Result<(), SignatureError> {
+        Self::batch_verify_keys(parameters, items, true, |pk| pk)
+    }
+
+    /// Verifies every item like [`batch_verify_detailed`](Self::batch_verify_detailed),
+    /// without checking the already validated keys again.
+    pub fn batch_verify_validated(
+        parameters: &Parameters<C, H>,
+        items: &[(ValidatedPublicKey<C>, &[u8], Signature<C>)],
+    ) -> Result<(), SignatureError> {
+        Self::batch_verify_keys(parameters, items, false, |pk| &pk.0)
+    }
+
+    /// Verifies a batch whose keys are read with `key`, checking each key
+    /// with [`validate_public_key`] if `validate` is set.
+    fn batch_verify_keys<K>(
+        parameters: &Parameters<C, H>,
+        items: &[(K, &[u8], Signature<C>)],
+        validate: bool,
+        key: impl Fn(&K) -> &PublicKey<C>,
+    ) -> Result<(), SignatureError> {
+        let batch_item = |index, error| SignatureError::BatchItem {
+            index,
+            error: Box::new(error),
+        };
+        if items.len() < BATCH_VERIFY_THRESHOLD {
+            for (index, (pk, message, signature)) in items.iter().enumerate() {
+                let pk = key(pk);
+                if validate {
+                    validate_public_key::<C>(pk)
+                        .map_err(|_| batch_item(index, SignatureError::InvalidPublicKey))?;
+                }
+                Self::verify_unchecked(parameters, pk, message, signature)
+                    .map_err(|error| batch_item(index, error))?;
+            }
+            return Ok(());
+        }
+        let verify_time = start_timer!(|| "SchnorrSig::BatchVerify");
+        let scalar_size = C::ScalarField::size_in_bits();
+        let window = FixedBaseMSM::get_mul_window_size(items.len());
+        let table = FixedBaseMSM::get_window_table(
+            scalar_size,
+            window,
+            parameters.generator.into_projective(),
+        );
+        let prover_responses = items
+            .iter()
+            .map(|(_, _, signature)| signature.prover_response)
+            .collect::<Vec<_>>();
+        let mut claimed_prover_commitments =
+            FixedBaseMSM::multi_scalar_mul::<C>(scalar_size, window, &table, &prover_responses);
+        for (commitment, (pk, _, signature)) in claimed_prover_commitments.iter_mut().zip(items) {
+            *commitment += &key(pk).mul(signature.verifier_challenge);
+        }
+        C::batch_normalization(&mut claimed_prover_commitments);
+
+        let result = claimed_prover_commitments
+            .iter()
+            .zip(items)
+            .enumerate()
+            .try_for_each(|(index, (commitment, (pk, message, signature)))| {
+                let pk = key(pk);
+                if validate {
+                    validate_public_key::<C>(pk)
+                        .map_err(|_| batch_item(index, SignatureError::InvalidPublicKey))?;
+                }
+                let obtained_verifier_challenge =
+                    compute_verifier_challenge(parameters, &commitment.into_affine(), pk, message)
+                        .map_err(|_| batch_item(index, SignatureError::InvalidSignatureEncoding))?;
+                if Some(signature.verifier_challenge) != obtained_verifier_challenge {
+                    return Err(batch_item(index, SignatureError::ChallengeMismatch));
+                }
+                Ok(())
+            });
+        end_timer!(verify_time);
+        result
+    }
+}
+
+impl<C: ProjectiveCurve + Hash, D: Digest + Send + Sync> Schnorr<C, D>
+where
+    C::ScalarField: PrimeField,
+{
+    /// Signs a message that the caller has already hashed with `D`.
+    ///
+    /// The challenge is computed as for [`SignatureScheme::sign`] over
+    /// `digest`, but with the salt replaced by
+    /// `Blake2s(PREHASH_DOMAIN || salt)`. Since the salt is the first 32
+    /// bytes of the challenge input, a prehashed signature does not verify
+    /// as a signature over any raw message, and vice versa.
+    pub fn sign_prehashed<R: Rng>(
+        parameters: &Parameters<C, D>,
+        sk: &SecretKey<C>,
+        digest: &Output<D>,
+        rng: &mut R,
+    ) -> Result<Signature<C>, Error> {
+        Self::sign(&prehash_parameters(parameters), sk, digest, rng)
+    }
+
+    /// Verifies a signature produced by [`Schnorr::sign_prehashed`].
+    pub fn verify_prehashed(
+        parameters: &Parameters<C, D>,
+        pk: &PublicKey<C>,
+        digest: &Output<D>,
+        signature: &Signature<C>,
+    ) -> Result<bool, Error> {
+        Self::verify(&prehash_parameters(parameters), pk, digest, signature)
+    }
+
+    /// Signs `message` bound to `context`, e.g. `b"webb-bridge-v1:chain-5"`,
+    /// so that the signature does not verify under any other context.
+    ///
+    /// For a nonempty context, the salt is replaced by
+    /// `Blake2s(CONTEXT_DOMAIN || LE64(len(context)) || context || salt)`.
+    /// The empty context leaves the parameters unchanged, so it signs
+    /// exactly like [`SignatureScheme::sign`].
+    pub fn sign_with_context<R: Rng>(
+        parameters: &Parameters<C, D>,
+        sk: &SecretKey<C>,
+        context: &[u8],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Signature<C>, Error> {
+        Self::sign(&context_parameters(parameters, context), sk, message, rng)
+    }
+
+    /// Verifies a signature produced by [`Schnorr::sign_with_context`].
+    pub fn verify_with_context(
+        parameters: &Parameters<C, D>,
+        pk: &PublicKey<C>,
+        context: &[u8],
+        message: &[u8],
+        signature: &Signature<C>,
+    ) -> Result<bool, Error> {
+        Self::verify(
+            &context_parameters(parameters, context),
+            pk,
+            message,
+            signature,
+        )
+    }
+}
+
+/// Domain tag of the nonce input of [`Schnorr::sign_deterministic`].
+const NONCE_DOMAIN: &[u8] = b"schnorr-deterministic-nonce";
+
+/// Input hashed by a digest to get its [`ChallengeHash::nonce_tag`].
+const DIGEST_NONCE_TAG: &[u8] = b"schnorr-digest-challenge";
+
+/// Domain tag from which the salt of prehashed signatures is derived.
+const PREHASH_DOMAIN: &[u8] = b"schnorr-prehashed";
+
+/// Domain tag from which the salt of signatures with a context is derived.
+const CONTEXT_DOMAIN: &[u8] = b"schnorr-context";
+
+fn prehash_parameters<C: ProjectiveCurve, D: Digest>(
+    parameters: &Parameters<C, D>,
+) -> Parameters<C, D> {
+    derive_salt(parameters, &[PREHASH_DOMAIN])
+}
+
+fn context_parameters<C: ProjectiveCurve, D: Digest>(
+    parameters: &Parameters<C, D>,
+    context: &[u8],
+) -> Parameters<C, D> {
+    if context.is_empty() {
+        return parameters.clone();
+    }
+    let len = (context.len() as u64).to_le_bytes();
+    derive_salt(parameters, &[CONTEXT_DOMAIN, &len, context])
+}
+
+/// Returns the parameters with the salt replaced by
+/// `Blake2s(prefix[0] || prefix[1] || ... || salt)`.
+fn derive_salt<C: ProjectiveCurve, D: Digest>(
+    parameters: &Parameters<C, D>,
+    prefix: &[&[u8]],
+) -> Parameters<C, D> {
+    let mut h = Blake2s::new();
+    for part in prefix {
+        h.update(part);
+    }
+    h.update(parameters.salt);
+    let mut salt = [0u8; 32];
+    salt.copy_from_slice(&h.finalize());
+    Parameters {
+        salt,
+        ..parameters.clone()
+    }
+}
+
+/// Signs with the first nonce `k` from `next_nonce` that is nonzero and
+/// yields a challenge.
+fn sign_with_nonces<C: ProjectiveCurve, H: ChallengeHash<C>>(
+    parameters: &Parameters<C, H>,
+    sk: &SecretKey<C>,
+    message: &[u8],
+    mut next_nonce: impl FnMut() -> Result<C::ScalarField, Error>,
+) -> Result<Signature<C>, Error> {
+    // Only hashes that bind the public key need it; the others are spared
+    // the scalar multiplication.
+    let pk = if H::BINDS_PUBLIC_KEY {
+        parameters.generator.mul(sk.0).into_affine()
+    } else {
+        C::Affine::zero()
+    };
+    // (k, e);
+    let (random_scalar, verifier_challenge) = loop {
+        let random_scalar = next_nonce()?;
+        if random_scalar.is_zero() {
+            continue;
+        }
+        // Commit to the random scalar via r := k · G.
+        // This is the prover's first msg in the Sigma protocol.
+        let prover_commitment = parameters.generator.mul(random_scalar).into_affine();
+
+        // Compute the supposed verifier response: e := H(salt || r || msg);
+        if let Some(verifier_challenge) =
+            compute_verifier_challenge(parameters, &prover_commitment, &pk, message)?
+        {
+            break (random_scalar, verifier_challenge);
+        };
+    };
+
+    // k - xe;
+    let prover_response = random_scalar - (verifier_challenge * sk.0);
+    Ok(Signature {
+        prover_response,
+        verifier_challenge,
+    })
+}
+
+/// Maps a detailed verification result to the boolean one of
+/// [`SignatureScheme`]: a challenge mismatch is `Ok(false)`, anything else
+/// that is not a valid signature is an error.
+fn verification_result(result: Result<(), SignatureError>) -> Result<bool, Error> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(SignatureError::ChallengeMismatch) => Ok(false),
+        Err(SignatureError::BatchItem { error, .. })
+            if matches!(*error, SignatureError::ChallengeMismatch) =>
+        {
+            Ok(false)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// The batch size from which [`Schnorr`] verifies signatures together.
+pub const BATCH_VERIFY_THRESHOLD: usize = 8;
+
+/// Computes the challenge `e` for the prover commitment `r`, or `None` if
+/// the hash does not yield a scalar.
+fn compute_verifier_challenge<C: ProjectiveCurve, H: ChallengeHash<C>>(
+    parameters: &Parameters<C, H>,
+    prover_commitment: &C::Affine,
+    public_key: &PublicKey<C>,
+    message: &[u8],
+) -> Result<Option<C::ScalarField>, Error> {
+    H::hash(parameters, prover_commitment, public_key, message)
+}
+
 pub fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
     let mut bits = Vec::with_capacity(bytes.len() * 8);
     for byte in bytes {
@@ -220,8 +767,11 @@ pub fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
     bits
 }
 
-impl<ConstraintF: Field, C: ProjectiveCurve + ToConstraintField<ConstraintF>, D: Digest>
-    ToConstraintField<ConstraintF> for Parameters<C, D>
+impl<
+        ConstraintF: Field,
+        C: ProjectiveCurve + ToConstraintField<ConstraintF>,
+        H: ChallengeHash<C>,
+    > ToConstraintField<ConstraintF> for Parameters<C, H>
 {
     #[inline]
     fn to_field_elements(&self) -> Option<Vec<ConstraintF>> {
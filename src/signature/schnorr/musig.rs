@@ -0,0 +1,457 @@
+//! MuSig2 multi-signatures for [`Schnorr`], producing ordinary signatures
+//! under the aggregate public key, but for derived parameters. Verify them
+//! with [`verify_aggregate`], which takes the signers' own parameters and
+//! derives the others; [`Schnorr::verify`] accepts them only given
+//! [`aggregate_key_parameters`], and rejects them under the signers'
+//! parameters.
+//!
+//! Signing takes two rounds, each an explicit step:
+//! 1. every signer generates a [`SecretNonce`] and sends its [`PublicNonce`]
+//!    to the others;
+//! 2. every signer builds the same [`Session`] from all public nonces and
+//!    the message, consumes its secret nonce to produce a
+//!    [`PartialSignature`], which [`Session::verify_partial`] checks, and
+//!    the partial signatures are summed with [`Session::aggregate`].
+//!
+//! All hashes use the scheme's digest `D` and reduce two digests of
+//! `tag || i || data`, for `i = 0, 1`, modulo the group order:
+//! * key aggregation: `L = H("MuSig/keys", X_1 || ... || X_n)` and
+//!   `a_i = H("MuSig/coef", L || X_i)`, with `X~ = sum a_i X_i`;
+//! * nonce aggregation: `R_1 = sum R_i1`, `R_2 = sum R_i2`,
+//!   `b = H("MuSig/noncecoef", X~ || R_1 || R_2 || m)` and `R = R_1 + b R_2`.
+//!
+//! The challenge is that of [`Schnorr`], `e = H(salt' || R || m)`, but
+//! with the salt replaced by `salt' = Blake2s("MuSig/aggkey" || X~ || salt)`,
+//! so that it commits to the aggregate key as MuSig2 requires. Signer `i`
+//! responds with `s_i = k_i1 + b k_i2 - e a_i x_i`.
+//!
+//! The digest challenge does not hash the public key, so without the
+//! derived salt the challenge would not commit to `X~`. The signature is
+//! therefore an ordinary signature under `X~` with the derived parameters
+//! only; [`Schnorr::verify`] with the signers' parameters rejects it.
+
+use super::{
+    compute_verifier_challenge, derive_salt, Parameters, PublicKey, Schnorr, SecretKey, Signature,
+};
+use crate::{CryptoError, Error, SignatureScheme, Vec};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{to_bytes, PrimeField, UniformRand};
+use ark_std::rand::Rng;
+use ark_std::{hash::Hash, marker::PhantomData};
+use digest::Digest;
+
+const KEY_LIST_TAG: &[u8] = b"MuSig/keys";
+const KEY_COEFFICIENT_TAG: &[u8] = b"MuSig/coef";
+const NONCE_COEFFICIENT_TAG: &[u8] = b"MuSig/noncecoef";
+const AGGREGATE_KEY_TAG: &[u8] = b"MuSig/aggkey";
+
+fn hash_to_scalar<F: PrimeField, D: Digest>(tag: &[u8], data: &[u8]) -> F {
+    let mut bytes = Vec::new();
+    for i in 0..2u8 {
+        let mut h = D::new();
+        h.update(tag);
+        h.update([i]);
+        h.update(data);
+        bytes.extend_from_slice(&h.finalize());
+    }
+    F::from_le_bytes_mod_order(&bytes)
+}
+
+/// The public keys of the signers with their aggregation coefficients.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "C: ProjectiveCurve"),
+    Debug(bound = "C: ProjectiveCurve")
+)]
+pub struct KeyAggContext<C: ProjectiveCurve, D: Digest> {
+    public_keys: Vec<PublicKey<C>>,
+    coefficients: Vec<C::ScalarField>,
+    aggregate_key: PublicKey<C>,
+    #[derivative(Debug = "ignore")]
+    _hash: PhantomData<D>,
+}
+
+impl<C: ProjectiveCurve, D: Digest> KeyAggContext<C, D> {
+    pub fn new(public_keys: &[PublicKey<C>]) -> Result<Self, Error> {
+        if public_keys.is_empty() {
+            return Err(CryptoError::IncorrectInputLength(0).into());
+        }
+        let key_list = hash_to_scalar::<C::ScalarField, D>(KEY_LIST_TAG, &to_bytes![public_keys]?);
+        let coefficients = public_keys
+            .iter()
+            .map(|pk| {
+                Ok(hash_to_scalar::<_, D>(
+                    KEY_COEFFICIENT_TAG,
+                    &to_bytes![key_list, pk]?,
+                ))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let aggregate_key = public_keys
+            .iter()
+            .zip(&coefficients)
+            .map(|(pk, a)| pk.mul(*a))
+            .sum::<C>()
+            .into_affine();
+        Ok(Self {
+            public_keys: public_keys.to_vec(),
+            coefficients,
+            aggregate_key,
+            _hash: PhantomData,
+        })
+    }
+
+    pub fn aggregate_key(&self) -> PublicKey<C> {
+        self.aggregate_key
+    }
+
+    fn coefficient(&self, pk: &PublicKey<C>) -> Option<C::ScalarField> {
+        self.public_keys
+            .iter()
+            .position(|key| key == pk)
+            .map(|i| self.coefficients[i])
+    }
+}
+
+/// Aggregates the public keys of the signers, weighting each by a
+/// coefficient bound to the whole key list to prevent rogue-key attacks.
+pub fn aggregate_keys<C: ProjectiveCurve, D: Digest>(
+    public_keys: &[PublicKey<C>],
+) -> Result<PublicKey<C>, Error> {
+    Ok(KeyAggContext::<C, D>::new(public_keys)?.aggregate_key())
+}
+
+/// The parameters under which signatures of the aggregate key `X~`
+/// verify: `parameters` with the salt replaced by
+/// `Blake2s("MuSig/aggkey" || X~ || salt)`, so that the challenge commits
+/// to `X~`. Pass these, not `parameters`, to [`Schnorr::verify`], or use
+/// [`verify_aggregate`], which derives them.
+pub fn aggregate_key_parameters<C: ProjectiveCurve, D: Digest>(
+    parameters: &Parameters<C, D>,
+    aggregate_key: &PublicKey<C>,
+) -> Result<Parameters<C, D>, Error> {
+    Ok(derive_salt(
+        parameters,
+        &[AGGREGATE_KEY_TAG, &to_bytes![aggregate_key]?],
+    ))
+}
+
+/// Verifies a signature produced by the signers of `aggregate_key` under
+/// `parameters`, the parameters the signers used. The parameters of the
+/// aggregate key are derived here with [`aggregate_key_parameters`], so
+/// callers cannot verify under the wrong ones.
+pub fn verify_aggregate<C: ProjectiveCurve + Hash, D: Digest + Send + Sync>(
+    parameters: &Parameters<C, D>,
+    aggregate_key: &PublicKey<C>,
+    message: &[u8],
+    signature: &Signature<C>,
+) -> Result<bool, Error>
+where
+    C::ScalarField: PrimeField,
+{
+    Schnorr::<C, D>::verify(
+        &aggregate_key_parameters(parameters, aggregate_key)?,
+        aggregate_key,
+        message,
+        signature,
+    )
+}
+
+/// The two nonces of a signer for one signing session. It is consumed when
+/// signing, so that it cannot be reused.
+pub struct SecretNonce<C: ProjectiveCurve> {
+    nonces: [C::ScalarField; 2],
+    public_nonce: PublicNonce<C>,
+}
+
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "C: ProjectiveCurve"),
+    Debug(bound = "C: ProjectiveCurve"),
+    PartialEq(bound = "C: ProjectiveCurve")
+)]
+pub struct PublicNonce<C: ProjectiveCurve>(pub [C::Affine; 2]);
+
+impl<C: ProjectiveCurve> SecretNonce<C> {
+    /// The first round: samples the nonces, whose public part is sent to the
+    /// other signers.
+    pub fn generate<D: Digest, R: Rng>(parameters: &Parameters<C, D>, rng: &mut R) -> Self {
+        let nonces = [C::ScalarField::rand(rng), C::ScalarField::rand(rng)];
+        let public_nonce = PublicNonce([
+            parameters.generator.mul(nonces[0]).into_affine(),
+            parameters.generator.mul(nonces[1]).into_affine(),
+        ]);
+        Self {
+            nonces,
+            public_nonce,
+        }
+    }
+
+    pub fn public_nonce(&self) -> &PublicNonce<C> {
+        &self.public_nonce
+    }
+
+    /// The second round: responds to the challenge of `session` with the
+    /// secret key `sk`, whose public key must be one of the aggregated keys.
+    pub fn sign<D: Digest>(
+        self,
+        parameters: &Parameters<C, D>,
+        session: &Session<C, D>,
+        sk: &SecretKey<C>,
+    ) -> Result<PartialSignature<C>, Error> {
+        let pk = parameters.generator.mul(sk.0).into_affine();
+        let coefficient = session
+            .key_agg_context
+            .coefficient(&pk)
+            .ok_or(CryptoError::UnsupportedOperation)?;
+        let nonce = self.nonces[0] + session.nonce_coefficient * self.nonces[1];
+        Ok(PartialSignature(
+            nonce - session.verifier_challenge * coefficient * sk.0,
+        ))
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "C: ProjectiveCurve"),
+    Debug(bound = "C: ProjectiveCurve")
+)]
+pub struct PartialSignature<C: ProjectiveCurve>(pub C::ScalarField);
+
+/// The state shared by the signers in the second round.
+pub struct Session<C: ProjectiveCurve, D: Digest> {
+    key_agg_context: KeyAggContext<C, D>,
+    key_parameters: Parameters<C, D>,
+    nonce_coefficient: C::ScalarField,
+    verifier_challenge: C::ScalarField,
+}
+
+impl<C: ProjectiveCurve, D: Digest> Session<C, D>
+where
+    C::ScalarField: PrimeField,
+{
+    /// Aggregates the public nonces of all signers and computes the
+    /// challenge for `message`. If the challenge digest is not a scalar,
+    /// which the single-signer scheme handles by drawing a new nonce, this
+    /// fails with [`CryptoError::InvalidChallenge`] and the signers must
+    /// start over with fresh nonces.
+    pub fn new(
+        parameters: &Parameters<C, D>,
+        key_agg_context: &KeyAggContext<C, D>,
+        public_nonces: &[PublicNonce<C>],
+        message: &[u8],
+    ) -> Result<Self, Error> {
+        if public_nonces.len() != key_agg_context.public_keys.len() {
+            return Err(CryptoError::IncorrectInputLength(public_nonces.len()).into());
+        }
+        let mut aggregate_nonce = [C::zero(); 2];
+        for nonce in public_nonces {
+            aggregate_nonce[0].add_assign_mixed(&nonce.0[0]);
+            aggregate_nonce[1].add_assign_mixed(&nonce.0[1]);
+        }
+        let aggregate_nonce = [
+            aggregate_nonce[0].into_affine(),
+            aggregate_nonce[1].into_affine(),
+        ];
+
+        let mut nonce_input = to_bytes![
+            key_agg_context.aggregate_key,
+            aggregate_nonce[0],
+            aggregate_nonce[1]
+        ]?;
+        nonce_input.extend_from_slice(message);
+        let nonce_coefficient = hash_to_scalar::<_, D>(NONCE_COEFFICIENT_TAG, &nonce_input);
+        let prover_commitment =
+            aggregate_nonce[0].into_projective() + aggregate_nonce[1].mul(nonce_coefficient);
+        if prover_commitment.is_zero() {
+            return Err(CryptoError::InvalidChallenge.into());
+        }
+        let key_parameters = aggregate_key_parameters(parameters, &key_agg_context.aggregate_key)?;
+        let verifier_challenge = compute_verifier_challenge(
+            &key_parameters,
+            &prover_commitment.into_affine(),
+            &key_agg_context.aggregate_key,
+            message,
+        )?
+        .ok_or(CryptoError::InvalidChallenge)?;
+        Ok(Self {
+            key_agg_context: key_agg_context.clone(),
+            key_parameters,
+            nonce_coefficient,
+            verifier_challenge,
+        })
+    }
+
+    /// The parameters under which the aggregate signature verifies, see
+    /// [`aggregate_key_parameters`].
+    pub fn key_parameters(&self) -> &Parameters<C, D> {
+        &self.key_parameters
+    }
+
+    /// Checks the partial signature of the signer with `public_key` and
+    /// `public_nonce`, `s_i G + e a_i X_i = R_i1 + b R_i2`, so that a
+    /// signer whose response would spoil the aggregate can be identified.
+    /// Fails if `public_key` is not one of the aggregated keys.
+    pub fn verify_partial(
+        &self,
+        parameters: &Parameters<C, D>,
+        public_key: &PublicKey<C>,
+        public_nonce: &PublicNonce<C>,
+        partial_signature: &PartialSignature<C>,
+    ) -> Result<bool, Error> {
+        let coefficient = self
+            .key_agg_context
+            .coefficient(public_key)
+            .ok_or(CryptoError::UnsupportedOperation)?;
+        let lhs = parameters.generator.mul(partial_signature.0)
+            + public_key.mul(self.verifier_challenge * coefficient);
+        let rhs =
+            public_nonce.0[0].into_projective() + public_nonce.0[1].mul(self.nonce_coefficient);
+        Ok(lhs == rhs)
+    }
+
+    /// Sums the partial signatures of all signers into a signature under the
+    /// aggregate key, which verifies with [`verify_aggregate`] or under
+    /// [`Session::key_parameters`]. Fails
+    /// unless there is one partial signature per signer.
+    pub fn aggregate(
+        &self,
+        partial_signatures: &[PartialSignature<C>],
+    ) -> Result<Signature<C>, Error> {
+        if partial_signatures.len() != self.key_agg_context.public_keys.len() {
+            return Err(CryptoError::IncorrectInputLength(partial_signatures.len()).into());
+        }
+        Ok(Signature {
+            prover_response: partial_signatures.iter().map(|s| s.0).sum(),
+            verifier_challenge: self.verifier_challenge,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signature::schnorr::Schnorr;
+    use crate::SignatureScheme;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_std::test_rng;
+    use blake2::Blake2s;
+
+    type S = Schnorr<JubJub, Blake2s>;
+
+    fn musig_sign<R: Rng>(
+        parameters: &Parameters<JubJub, Blake2s>,
+        context: &KeyAggContext<JubJub, Blake2s>,
+        secret_keys: &[SecretKey<JubJub>],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Signature<JubJub> {
+        loop {
+            let secret_nonces: Vec<_> = secret_keys
+                .iter()
+                .map(|_| SecretNonce::generate(parameters, rng))
+                .collect();
+            let public_nonces: Vec<_> = secret_nonces
+                .iter()
+                .map(|nonce| nonce.public_nonce().clone())
+                .collect();
+            let session = match Session::new(parameters, context, &public_nonces, message) {
+                Ok(session) => session,
+                // Start over with fresh nonces.
+                Err(_) => continue,
+            };
+            let partial_signatures: Vec<_> = secret_nonces
+                .into_iter()
+                .zip(secret_keys)
+                .map(|(nonce, sk)| nonce.sign(parameters, &session, sk).unwrap())
+                .collect();
+            for ((sk, nonce), partial) in secret_keys
+                .iter()
+                .zip(&public_nonces)
+                .zip(&partial_signatures)
+            {
+                let pk = parameters.generator.mul(sk.0).into_affine();
+                assert!(session
+                    .verify_partial(parameters, &pk, nonce, partial)
+                    .unwrap());
+            }
+            // A missing partial signature is rejected.
+            assert!(session.aggregate(&partial_signatures[1..]).is_err());
+            return session.aggregate(&partial_signatures).unwrap();
+        }
+    }
+
+    #[test]
+    fn musig_test() {
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (public_keys, secret_keys): (Vec<_>, Vec<_>) =
+            (0..3).map(|_| S::keygen(&parameters, rng).unwrap()).unzip();
+        let context = KeyAggContext::<JubJub, Blake2s>::new(&public_keys).unwrap();
+        let aggregate_key = aggregate_keys::<JubJub, Blake2s>(&public_keys).unwrap();
+        assert_eq!(context.aggregate_key(), aggregate_key);
+
+        for message in [&b"withdraw"[..], b"deposit"].iter() {
+            let signature = musig_sign(&parameters, &context, &secret_keys, message, rng);
+            assert!(verify_aggregate(&parameters, &aggregate_key, message, &signature).unwrap());
+            let key_parameters = aggregate_key_parameters(&parameters, &aggregate_key).unwrap();
+            assert!(S::verify(&key_parameters, &aggregate_key, message, &signature).unwrap());
+            assert!(!verify_aggregate(&parameters, &aggregate_key, b"other", &signature).unwrap());
+            assert!(!verify_aggregate(&parameters, &public_keys[0], message, &signature).unwrap());
+            // The challenge commits to the aggregate key, so the signature
+            // is not one under the plain parameters.
+            assert!(!S::verify(&parameters, &aggregate_key, message, &signature).unwrap());
+        }
+
+        // Signing with a key outside the context fails.
+        let (_, outsider) = S::keygen(&parameters, rng).unwrap();
+        let nonces: Vec<_> = (0..3)
+            .map(|_| SecretNonce::generate(&parameters, rng))
+            .collect();
+        let public_nonces: Vec<_> = nonces.iter().map(|n| n.public_nonce().clone()).collect();
+        if let Ok(session) = Session::new(&parameters, &context, &public_nonces, b"m") {
+            let mut nonces = nonces.into_iter();
+            let nonce = nonces.next().unwrap();
+            let partial = nonce.sign(&parameters, &session, &secret_keys[0]).unwrap();
+            assert!(session
+                .verify_partial(&parameters, &public_keys[0], &public_nonces[0], &partial)
+                .unwrap());
+            // The response does not fit another signer's key or nonce.
+            assert!(!session
+                .verify_partial(&parameters, &public_keys[1], &public_nonces[0], &partial)
+                .unwrap());
+            assert!(!session
+                .verify_partial(&parameters, &public_keys[0], &public_nonces[1], &partial)
+                .unwrap());
+            let outsider_key = parameters.generator.mul(outsider.0).into_affine();
+            assert!(session
+                .verify_partial(&parameters, &outsider_key, &public_nonces[0], &partial)
+                .is_err());
+            let key_parameters =
+                aggregate_key_parameters(&parameters, &context.aggregate_key()).unwrap();
+            assert_eq!(session.key_parameters().salt, key_parameters.salt);
+            let nonce = nonces.next().unwrap();
+            assert!(nonce.sign(&parameters, &session, &outsider).is_err());
+        }
+        assert!(aggregate_keys::<JubJub, Blake2s>(&[]).is_err());
+    }
+
+    #[test]
+    fn rogue_key_test() {
+        let rng = &mut test_rng();
+        let parameters = S::setup(rng).unwrap();
+        let (honest_key, _) = S::keygen(&parameters, rng).unwrap();
+        let (attacker_key, attacker_sk) = S::keygen(&parameters, rng).unwrap();
+        // The attacker claims X_r = X_a - X_h, so that the plain sum of the
+        // keys is X_a.
+        let rogue_key =
+            (attacker_key.into_projective() - honest_key.into_projective()).into_affine();
+        let plain_sum = (honest_key.into_projective() + rogue_key.into_projective()).into_affine();
+        assert_eq!(plain_sum, attacker_key);
+
+        let signature = S::sign(&parameters, &attacker_sk, b"steal", rng).unwrap();
+        assert!(S::verify(&parameters, &plain_sum, b"steal", &signature).unwrap());
+        let aggregate_key = aggregate_keys::<JubJub, Blake2s>(&[honest_key, rogue_key]).unwrap();
+        assert_ne!(aggregate_key, attacker_key);
+        assert!(!verify_aggregate(&parameters, &aggregate_key, b"steal", &signature).unwrap());
+    }
+}
@@ -0,0 +1,236 @@
+use super::{
+    Parameters, PoseidonChallenge, SchnorrPoseidon, FIELD_SIGNATURE_DOMAIN_TAG,
+    SIGNATURE_DOMAIN_TAG,
+};
+use crate::crh::poseidon::{
+    constraints::{to_field_var_elements_packed, CRHGadget, PoseidonParametersVar},
+    domain_element, Rounds,
+};
+use crate::signature::{
+    schnorr::constraints::{PublicKeyVar, SignatureVar},
+    SigVerifyGadget,
+};
+use crate::Vec;
+use ark_ec::{
+    twisted_edwards_extended::GroupProjective as TEProjective, ModelParameters, TEModelParameters,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, groups::curves::twisted_edwards::AffineVar, prelude::*};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use core::{borrow::Borrow, marker::PhantomData};
+
+type EdwardsVar<P> = AffineVar<P, FpVar<<P as ModelParameters>::BaseField>>;
+
+#[derive(Derivative)]
+#[derivative(Clone(bound = "P: TEModelParameters, P::BaseField: PrimeField"))]
+pub struct ParametersVar<P: TEModelParameters>
+where
+    P::BaseField: PrimeField,
+{
+    generator: EdwardsVar<P>,
+    poseidon: PoseidonParametersVar<P::BaseField>,
+}
+
+pub struct SchnorrPoseidonVerifyGadget<P: TEModelParameters, R: Rounds> {
+    #[doc(hidden)]
+    _curve: PhantomData<P>,
+    #[doc(hidden)]
+    _rounds: PhantomData<R>,
+}
+
+impl<P: TEModelParameters, R: Rounds> SchnorrPoseidonVerifyGadget<P, R>
+where
+    P::BaseField: PrimeField,
+{
+    fn point_elements(point: &EdwardsVar<P>) -> Result<[FpVar<P::BaseField>; 2], SynthesisError> {
+        let parity = point.y.to_bits_le()?[0].clone();
+        Ok([point.x.clone(), parity.into()])
+    }
+
+    /// Recomputes the prover commitment `s · G + e · pk` and checks that `e`
+    /// is the challenge for the message elements under `domain`.
+    fn verify_elements(
+        parameters: &ParametersVar<P>,
+        public_key: &PublicKeyVar<TEProjective<P>, EdwardsVar<P>>,
+        domain: P::BaseField,
+        message: &[FpVar<P::BaseField>],
+        signature: &SignatureVar<TEProjective<P>>,
+    ) -> Result<Boolean<P::BaseField>, SynthesisError> {
+        let claimed_prover_commitment = parameters
+            .generator
+            .scalar_mul_le(signature.prover_response.iter())?
+            + public_key
+                .pub_key
+                .scalar_mul_le(signature.verifier_challenge.iter())?;
+
+        let mut elements = Vec::new();
+        elements.extend_from_slice(&Self::point_elements(&claimed_prover_commitment)?);
+        elements.extend_from_slice(&Self::point_elements(&public_key.pub_key)?);
+        elements.extend_from_slice(message);
+        let hash = CRHGadget::<P::BaseField, R>::sponge(
+            &parameters.poseidon,
+            FpVar::constant(domain),
+            &elements,
+        )?;
+
+        // The challenge is the low bits of the hash, padded with zeros to the
+        // length of the allocated challenge.
+        let mut challenge_bits = hash.to_bits_le()?;
+        challenge_bits.truncate(PoseidonChallenge::<P, R>::CHALLENGE_BITS);
+        challenge_bits.resize(signature.verifier_challenge.len(), Boolean::FALSE);
+        challenge_bits.is_eq(&signature.verifier_challenge)
+    }
+
+    /// Verifies a signature from `SchnorrPoseidon::sign_field` on a message
+    /// of field elements. The number of elements is fixed by the circuit.
+    #[tracing::instrument(target = "r1cs", skip(parameters, public_key, message, signature))]
+    pub fn verify_field(
+        parameters: &ParametersVar<P>,
+        public_key: &PublicKeyVar<TEProjective<P>, EdwardsVar<P>>,
+        message: &[FpVar<P::BaseField>],
+        signature: &SignatureVar<TEProjective<P>>,
+    ) -> Result<Boolean<P::BaseField>, SynthesisError> {
+        let domain = domain_element(FIELD_SIGNATURE_DOMAIN_TAG, message.len());
+        Self::verify_elements(parameters, public_key, domain, message, signature)
+    }
+}
+
+impl<P: TEModelParameters, R: Rounds> SigVerifyGadget<SchnorrPoseidon<P, R>, P::BaseField>
+    for SchnorrPoseidonVerifyGadget<P, R>
+where
+    P::BaseField: PrimeField,
+{
+    type ParametersVar = ParametersVar<P>;
+    type PublicKeyVar = PublicKeyVar<TEProjective<P>, EdwardsVar<P>>;
+    type SignatureVar = SignatureVar<TEProjective<P>>;
+
+    /// Verifies a signature on a byte message, packing the bytes as the
+    /// native scheme does. The message length is fixed by the circuit, so
+    /// the domain element is a constant.
+    #[tracing::instrument(target = "r1cs", skip(parameters, public_key, message, signature))]
+    fn verify(
+        parameters: &Self::ParametersVar,
+        public_key: &Self::PublicKeyVar,
+        message: &[UInt8<P::BaseField>],
+        signature: &Self::SignatureVar,
+    ) -> Result<Boolean<P::BaseField>, SynthesisError> {
+        let elements = to_field_var_elements_packed(message)?;
+        let domain = domain_element(SIGNATURE_DOMAIN_TAG, message.len());
+        Self::verify_elements(parameters, public_key, domain, &elements, signature)
+    }
+}
+
+impl<P: TEModelParameters, R: Rounds> AllocVar<Parameters<P, R>, P::BaseField> for ParametersVar<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn new_variable<T: Borrow<Parameters<P, R>>>(
+        cs: impl Into<Namespace<P::BaseField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        let parameters = f().map(|p| p.borrow().clone());
+        let generator = EdwardsVar::<P>::new_variable(
+            ark_relations::ns!(cs, "generator"),
+            || parameters.as_ref().map(|p| p.generator).map_err(|e| *e),
+            mode,
+        )?;
+        let poseidon = PoseidonParametersVar::new_variable(
+            ark_relations::ns!(cs, "poseidon"),
+            || {
+                parameters
+                    .as_ref()
+                    .map(|p| &p.hash_parameters)
+                    .map_err(|e| *e)
+            },
+            mode,
+        )?;
+        Ok(Self {
+            generator,
+            poseidon,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signature::schnorr::poseidon::test::{setup, PoseidonRounds3, TestSig};
+    use crate::signature::SignatureScheme;
+    use ark_ed_on_bn254::{EdwardsParameters, Fq};
+    use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+    use ark_std::test_rng;
+
+    type TestSigGadget = SchnorrPoseidonVerifyGadget<EdwardsParameters, PoseidonRounds3>;
+
+    fn verify_in_circuit(
+        message: &[u8],
+        signed_message: &[u8],
+    ) -> (Boolean<Fq>, ConstraintSystemRef<Fq>) {
+        let rng = &mut test_rng();
+        let parameters = setup(rng);
+        let (pk, sk) = TestSig::keygen(&parameters, rng).unwrap();
+        let signature = TestSig::sign(&parameters, &sk, signed_message, rng).unwrap();
+        assert_eq!(
+            TestSig::verify(&parameters, &pk, message, &signature).unwrap(),
+            message == signed_message
+        );
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let parameters_var = ParametersVar::new_constant(cs.clone(), &parameters).unwrap();
+        let pk_var = PublicKeyVar::new_witness(cs.clone(), || Ok(pk)).unwrap();
+        let message_var = UInt8::new_witness_vec(cs.clone(), message).unwrap();
+        let signature_var = SignatureVar::new_witness(cs.clone(), || Ok(&signature)).unwrap();
+        let valid =
+            TestSigGadget::verify(&parameters_var, &pk_var, &message_var, &signature_var).unwrap();
+        valid.enforce_equal(&Boolean::TRUE).unwrap();
+        (valid, cs)
+    }
+
+    #[test]
+    fn verify_field_gadget_test() {
+        let rng = &mut test_rng();
+        let parameters = setup(rng);
+        let (pk, sk) = TestSig::keygen(&parameters, rng).unwrap();
+        let message = [Fq::from(7u64), Fq::from(11u64)];
+        let signature = TestSig::sign_field(&parameters, &sk, &message, rng).unwrap();
+        let byte_signature = TestSig::sign(&parameters, &sk, b"\x07", rng).unwrap();
+
+        for (signed_message, signature, expected) in [
+            (message, &signature, true),
+            ([message[0], message[0]], &signature, false),
+            (message, &byte_signature, false),
+        ]
+        .iter()
+        {
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let parameters_var = ParametersVar::new_constant(cs.clone(), &parameters).unwrap();
+            let pk_var = PublicKeyVar::new_witness(cs.clone(), || Ok(pk)).unwrap();
+            let message_var = Vec::new_witness(cs.clone(), || Ok(&signed_message[..])).unwrap();
+            let signature_var = SignatureVar::new_witness(cs.clone(), || Ok(*signature)).unwrap();
+            let valid =
+                TestSigGadget::verify_field(&parameters_var, &pk_var, &message_var, &signature_var)
+                    .unwrap();
+            assert_eq!(valid.value().unwrap(), *expected);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn schnorr_poseidon_verify_gadget_test() {
+        let message = b"Hi, I am a Schnorr signature!";
+        let (valid, cs) = verify_in_circuit(message, message);
+        assert!(valid.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+        // Compared to 49364 with the Blake2s challenge.
+        assert_eq!(cs.num_constraints(), 8871);
+
+        let mut flipped = message.to_vec();
+        flipped[0] ^= 1;
+        let (valid, cs) = verify_in_circuit(&flipped, message);
+        assert!(!valid.value().unwrap());
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
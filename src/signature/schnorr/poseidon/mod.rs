@@ -0,0 +1,343 @@
+use super::{validate_public_key, ChallengeHash, PublicKey, Schnorr, SecretKey, Signature};
+use crate::crh::poseidon::{
+    circom, domain_element, to_field_elements_packed, PoseidonParameters, Rounds, CRH,
+};
+use crate::{Error, Vec};
+use ark_ec::{
+    twisted_edwards_extended::{GroupAffine as TEAffine, GroupProjective as TEProjective},
+    AffineCurve, ProjectiveCurve, TEModelParameters,
+};
+use ark_ff::{to_bytes, BigInteger, FpParameters, PrimeField, UniformRand, Zero};
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// Distinguishes signature challenges from other uses of the same Poseidon
+/// parameters.
+pub const SIGNATURE_DOMAIN_TAG: u8 = 4;
+
+/// Distinguishes challenges on messages of field elements, see
+/// [`Schnorr::sign_field`].
+pub const FIELD_SIGNATURE_DOMAIN_TAG: u8 = 5;
+
+/// A Schnorr challenge over a twisted Edwards curve that is a Poseidon hash
+/// over the curve's base field, so that verifying in a circuit over that
+/// field needs no bit-oriented hashing.
+///
+/// The challenge for a commitment `R`, public key `pk` and message `m` is the
+/// Poseidon sponge (see `CRH::sponge`) over
+/// `[R.x, parity(R.y), pk.x, parity(pk.y), m_1, ..., m_k]`, where:
+///
+/// * `parity(y)` is `1` if the canonical integer of `y` is odd and `0`
+///   otherwise; with `x`, it identifies the point;
+/// * `m_1, ..., m_k` are the [packed](to_field_elements_packed) elements
+///   of `m`;
+/// * the domain element packs [`SIGNATURE_DOMAIN_TAG`] in its lowest byte
+///   and the length of `m` in bytes, as a little-endian `u64`, in the next
+///   eight.
+///
+/// The challenge scalar is the low `MODULUS_BITS - 1` bits of the hash, so
+/// it is always below the scalar field modulus. The salt of the parameters
+/// is not hashed; the domain tag separates these challenges from those of
+/// digests, which start with the salt.
+///
+/// Since the challenge binds the public key, public keys and signatures
+/// cannot be randomized.
+pub struct PoseidonChallenge<P: TEModelParameters, R: Rounds> {
+    _curve: PhantomData<P>,
+    _rounds: PhantomData<R>,
+}
+
+/// Schnorr signatures with the Poseidon challenge of [`PoseidonChallenge`].
+pub type SchnorrPoseidon<P, R> = Schnorr<TEProjective<P>, PoseidonChallenge<P, R>>;
+
+pub type Parameters<P, R> = super::Parameters<TEProjective<P>, PoseidonChallenge<P, R>>;
+
+impl<P: TEModelParameters, R: Rounds> PoseidonChallenge<P, R>
+where
+    P::BaseField: PrimeField,
+{
+    /// The number of bits of the challenge scalar.
+    pub const CHALLENGE_BITS: usize =
+        <P::ScalarField as PrimeField>::Params::MODULUS_BITS as usize - 1;
+
+    fn point_elements(point: &TEAffine<P>) -> [P::BaseField; 2] {
+        let parity = point.y.into_repr().is_odd();
+        [point.x, P::BaseField::from(parity as u64)]
+    }
+
+    fn challenge(
+        parameters: &Parameters<P, R>,
+        prover_commitment: &TEAffine<P>,
+        pk: &TEAffine<P>,
+        domain: P::BaseField,
+        message: &[P::BaseField],
+    ) -> Result<P::ScalarField, Error> {
+        let mut elements = Vec::new();
+        elements.extend_from_slice(&Self::point_elements(prover_commitment));
+        elements.extend_from_slice(&Self::point_elements(pk));
+        elements.extend_from_slice(message);
+        let hash = CRH::<P::BaseField, R>::sponge(&parameters.hash_parameters, domain, &elements)?;
+        let bits = hash.into_repr().to_bits_le();
+        Ok(
+            P::ScalarField::from_repr(<P::ScalarField as PrimeField>::BigInt::from_bits_le(
+                &bits[..Self::CHALLENGE_BITS],
+            ))
+            .expect("the challenge is below the modulus"),
+        )
+    }
+}
+
+impl<P: TEModelParameters, R: Rounds> ChallengeHash<TEProjective<P>> for PoseidonChallenge<P, R>
+where
+    P::BaseField: PrimeField,
+{
+    type Parameters = PoseidonParameters<P::BaseField>;
+
+    const BINDS_PUBLIC_KEY: bool = true;
+
+    /// circomlib's constants for `R`, see
+    /// [`parameters_for_rounds`](circom::parameters_for_rounds). Other
+    /// constants are passed to
+    /// [`Schnorr::setup_with_hash_parameters`].
+    fn setup<Rn: Rng>(_rng: &mut Rn) -> Result<Self::Parameters, Error> {
+        Ok(circom::parameters_for_rounds::<P::BaseField, R>()?)
+    }
+
+    /// The Poseidon constants, after a tag that sets them apart from the
+    /// tags of digests.
+    fn nonce_tag(parameters: &Parameters<P, R>) -> Result<Vec<u8>, Error> {
+        let hash_parameters = &parameters.hash_parameters;
+        let mut tag = b"poseidon".to_vec();
+        tag.extend_from_slice(&to_bytes![hash_parameters.round_keys]?);
+        for row in &hash_parameters.mds_matrix {
+            tag.extend_from_slice(&to_bytes![row]?);
+        }
+        Ok(tag)
+    }
+
+    fn hash(
+        parameters: &Parameters<P, R>,
+        prover_commitment: &TEAffine<P>,
+        public_key: &TEAffine<P>,
+        message: &[u8],
+    ) -> Result<Option<P::ScalarField>, Error> {
+        let domain = domain_element(SIGNATURE_DOMAIN_TAG, message.len());
+        let message = to_field_elements_packed(message);
+        Ok(Some(Self::challenge(
+            parameters,
+            prover_commitment,
+            public_key,
+            domain,
+            &message,
+        )?))
+    }
+}
+
+impl<P: TEModelParameters, R: Rounds> Schnorr<TEProjective<P>, PoseidonChallenge<P, R>>
+where
+    P::BaseField: PrimeField,
+{
+    /// Like [`SignatureScheme::setup`](crate::SignatureScheme::setup), with the Poseidon constants
+    /// `hash_parameters` instead of circomlib's. Fails unless they have the
+    /// shape of constants for `R`.
+    pub fn setup_with_hash_parameters<Rn: Rng>(
+        hash_parameters: PoseidonParameters<P::BaseField>,
+        rng: &mut Rn,
+    ) -> Result<Parameters<P, R>, Error> {
+        circom::check::<P::BaseField, R>(&hash_parameters)?;
+        let mut salt = [0u8; 32];
+        rng.fill_bytes(&mut salt);
+        Ok(Parameters {
+            hash_parameters,
+            generator: TEProjective::<P>::rand(rng).into_affine(),
+            salt,
+        })
+    }
+
+    /// Signs a message given as field elements, which are absorbed as they
+    /// are instead of as byte chunks. The domain element carries
+    /// [`FIELD_SIGNATURE_DOMAIN_TAG`] and the number of elements, so these
+    /// signatures never verify as signatures on byte messages or the other
+    /// way round, even when the bytes pack to the same elements.
+    pub fn sign_field<Rn: Rng>(
+        parameters: &Parameters<P, R>,
+        sk: &SecretKey<TEProjective<P>>,
+        message: &[P::BaseField],
+        rng: &mut Rn,
+    ) -> Result<Signature<TEProjective<P>>, Error> {
+        let sign_time = start_timer!(|| "SchnorrPoseidon::SignField");
+        let domain = domain_element(FIELD_SIGNATURE_DOMAIN_TAG, message.len());
+        let pk = parameters.generator.mul(sk.0).into_affine();
+        let random_scalar = loop {
+            let random_scalar = P::ScalarField::rand(rng);
+            if !random_scalar.is_zero() {
+                break random_scalar;
+            }
+        };
+        let prover_commitment = parameters.generator.mul(random_scalar).into_affine();
+        let verifier_challenge = PoseidonChallenge::<P, R>::challenge(
+            parameters,
+            &prover_commitment,
+            &pk,
+            domain,
+            message,
+        )?;
+        end_timer!(sign_time);
+        Ok(Signature {
+            prover_response: random_scalar - (verifier_challenge * sk.0),
+            verifier_challenge,
+        })
+    }
+
+    /// Verifies a signature from [`sign_field`](Self::sign_field).
+    pub fn verify_field(
+        parameters: &Parameters<P, R>,
+        pk: &PublicKey<TEProjective<P>>,
+        message: &[P::BaseField],
+        signature: &Signature<TEProjective<P>>,
+    ) -> Result<bool, Error> {
+        validate_public_key::<TEProjective<P>>(pk)?;
+        let verify_time = start_timer!(|| "SchnorrPoseidon::VerifyField");
+        let domain = domain_element(FIELD_SIGNATURE_DOMAIN_TAG, message.len());
+        let Signature {
+            prover_response,
+            verifier_challenge,
+        } = signature;
+        let claimed_prover_commitment = (parameters.generator.mul(*prover_response)
+            + pk.mul(*verifier_challenge))
+        .into_affine();
+        let obtained_verifier_challenge = PoseidonChallenge::<P, R>::challenge(
+            parameters,
+            &claimed_prover_commitment,
+            pk,
+            domain,
+            message,
+        )?;
+        end_timer!(verify_time);
+        Ok(*verifier_challenge == obtained_verifier_challenge)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crh::poseidon::{
+        sbox::PoseidonSbox,
+        test_data::{get_mds_3, get_rounds_3},
+    };
+    use crate::signature::schnorr::Schnorr;
+    use crate::SignatureScheme;
+    use ark_ed_on_bn254::{EdwardsParameters, EdwardsProjective, Fq};
+    use ark_std::test_rng;
+    use blake2::Blake2s;
+
+    #[derive(Default, Clone)]
+    pub(crate) struct PoseidonRounds3;
+
+    impl Rounds for PoseidonRounds3 {
+        const WIDTH: usize = 3;
+        const PARTIAL_ROUNDS: usize = 57;
+        const FULL_ROUNDS: usize = 8;
+        const SBOX: PoseidonSbox = PoseidonSbox::Exponentiation(5);
+    }
+
+    pub(crate) type TestSig = SchnorrPoseidon<EdwardsParameters, PoseidonRounds3>;
+
+    pub(crate) fn setup<Rn: Rng>(rng: &mut Rn) -> Parameters<EdwardsParameters, PoseidonRounds3> {
+        let hash_parameters = PoseidonParameters::new(get_rounds_3::<Fq>(), get_mds_3::<Fq>());
+        TestSig::setup_with_hash_parameters(hash_parameters, rng).unwrap()
+    }
+
+    #[test]
+    fn setup_test() {
+        let rng = &mut test_rng();
+        let empty = PoseidonParameters::new(Vec::new(), Vec::new());
+        assert!(matches!(
+            TestSig::setup_with_hash_parameters(empty, rng),
+            Err(Error::Crypto(crate::CryptoError::InvalidParameters))
+        ));
+
+        // Without constants to pass, setup needs circomlib's.
+        for parameters in [TestSig::setup(rng), TestSig::setup_from_seed([1u8; 32])] {
+            if cfg!(not(feature = "circom-constants")) {
+                assert!(parameters.is_err());
+                continue;
+            }
+            let parameters = parameters.unwrap();
+            let (pk, sk) = TestSig::keygen(&parameters, rng).unwrap();
+            let signature = TestSig::sign(&parameters, &sk, b"message", rng).unwrap();
+            assert!(TestSig::verify(&parameters, &pk, b"message", &signature).unwrap());
+        }
+    }
+
+    #[test]
+    fn schnorr_poseidon_test() {
+        let rng = &mut test_rng();
+        let parameters = setup(rng);
+        let (pk, sk) = TestSig::keygen(&parameters, rng).unwrap();
+        for message in [&b""[..], b"short", &[7u8; 100]].iter() {
+            let signature = TestSig::sign(&parameters, &sk, message, rng).unwrap();
+            assert!(TestSig::verify(&parameters, &pk, message, &signature).unwrap());
+            assert!(!TestSig::verify(&parameters, &pk, b"Bad message", &signature).unwrap());
+        }
+        // Trailing zero bytes change the length, and so the domain.
+        let signature = TestSig::sign(&parameters, &sk, b"short", rng).unwrap();
+        assert!(!TestSig::verify(&parameters, &pk, b"short\0", &signature).unwrap());
+        let identity = PublicKey::<EdwardsProjective>::default();
+        assert!(TestSig::verify(&parameters, &identity, b"short", &signature).is_err());
+
+        assert!(TestSig::randomize_public_key(&parameters, &pk, &[1]).is_err());
+        assert!(TestSig::randomize_signature(&parameters, &signature, &[1]).is_err());
+    }
+
+    #[test]
+    fn field_message_test() {
+        let rng = &mut test_rng();
+        let parameters = setup(rng);
+        let (pk, sk) = TestSig::keygen(&parameters, rng).unwrap();
+        let message = [Fq::from(1u64), Fq::from(2u64), -Fq::from(3u64)];
+        let signature = TestSig::sign_field(&parameters, &sk, &message, rng).unwrap();
+        assert!(TestSig::verify_field(&parameters, &pk, &message, &signature).unwrap());
+        assert!(!TestSig::verify_field(&parameters, &pk, &message[..2], &signature).unwrap());
+
+        // "abc" packs to a single element; the signatures stay separate.
+        let element = [Fq::from_le_bytes_mod_order(b"abc")];
+        let field_signature = TestSig::sign_field(&parameters, &sk, &element, rng).unwrap();
+        let byte_signature = TestSig::sign(&parameters, &sk, b"abc", rng).unwrap();
+        assert!(TestSig::verify_field(&parameters, &pk, &element, &field_signature).unwrap());
+        assert!(TestSig::verify(&parameters, &pk, b"abc", &byte_signature).unwrap());
+        assert!(!TestSig::verify(&parameters, &pk, b"abc", &field_signature).unwrap());
+        assert!(!TestSig::verify_field(&parameters, &pk, &element, &byte_signature).unwrap());
+    }
+
+    #[test]
+    fn domain_separation_test() {
+        type ByteSig = Schnorr<EdwardsProjective, Blake2s>;
+        let rng = &mut test_rng();
+        let parameters = setup(rng);
+        let mut byte_parameters = ByteSig::setup(rng).unwrap();
+        byte_parameters.generator = parameters.generator;
+        byte_parameters.salt = parameters.salt;
+        let (pk, sk) = TestSig::keygen(&parameters, rng).unwrap();
+
+        let message = b"withdraw";
+        let signature = TestSig::sign(&parameters, &sk, message, rng).unwrap();
+        let byte_signature = ByteSig::sign(&byte_parameters, &sk, message, rng).unwrap();
+        assert!(ByteSig::verify(&byte_parameters, &pk, message, &byte_signature).unwrap());
+        assert!(!ByteSig::verify(&byte_parameters, &pk, message, &signature).unwrap());
+        assert!(!TestSig::verify(&parameters, &pk, message, &byte_signature).unwrap());
+
+        // Deterministic nonces differ between the two challenge hashes.
+        let commitment = |signature: &Signature<EdwardsProjective>| {
+            parameters.generator.mul(signature.prover_response)
+                + pk.mul(signature.verifier_challenge)
+        };
+        let signature = TestSig::sign_deterministic(&parameters, &sk, message).unwrap();
+        let byte_signature = ByteSig::sign_deterministic(&byte_parameters, &sk, message).unwrap();
+        assert!(TestSig::verify(&parameters, &pk, message, &signature).unwrap());
+        assert_ne!(commitment(&signature), commitment(&byte_signature));
+    }
+}
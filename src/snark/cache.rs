@@ -0,0 +1,194 @@
+use crate::snark::versioned::CircuitId;
+use crate::Error;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_snark::SNARK;
+use ark_std::{
+    collections::BTreeMap,
+    marker::PhantomData,
+    rand::{CryptoRng, RngCore},
+};
+
+/// Storage for the keys cached by a [`SetupCache`], indexed by the
+/// fingerprint of the circuit they were generated for.
+pub trait SetupStorage<F: PrimeField, S: SNARK<F>> {
+    fn get(&self, fingerprint: &CircuitId) -> Option<(S::ProvingKey, S::VerifyingKey)>;
+
+    fn insert(&mut self, fingerprint: CircuitId, keys: (S::ProvingKey, S::VerifyingKey));
+}
+
+/// Keeps the keys in memory for the lifetime of the cache.
+#[derive(Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct MemoryStorage<F: PrimeField, S: SNARK<F>> {
+    keys: BTreeMap<CircuitId, (S::ProvingKey, S::VerifyingKey)>,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, S: SNARK<F>> SetupStorage<F, S> for MemoryStorage<F, S> {
+    fn get(&self, fingerprint: &CircuitId) -> Option<(S::ProvingKey, S::VerifyingKey)> {
+        self.keys.get(fingerprint).cloned()
+    }
+
+    fn insert(&mut self, fingerprint: CircuitId, keys: (S::ProvingKey, S::VerifyingKey)) {
+        self.keys.insert(fingerprint, keys);
+    }
+}
+
+/// Runs the circuit-specific setup of `S` once per circuit shape, reusing
+/// the keys for circuits with the same constraint matrices (see
+/// [`CircuitId::from_circuit`]).
+pub struct SetupCache<F: PrimeField, S: SNARK<F>, B: SetupStorage<F, S> = MemoryStorage<F, S>> {
+    storage: B,
+    _snark: PhantomData<(F, S)>,
+}
+
+impl<F: PrimeField, S: SNARK<F>> SetupCache<F, S> {
+    pub fn new() -> Self {
+        Self::with_storage(MemoryStorage::default())
+    }
+}
+
+impl<F: PrimeField, S: SNARK<F>> Default for SetupCache<F, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField, S: SNARK<F>, B: SetupStorage<F, S>> SetupCache<F, S, B> {
+    pub fn with_storage(storage: B) -> Self {
+        Self {
+            storage,
+            _snark: PhantomData,
+        }
+    }
+
+    pub fn storage(&self) -> &B {
+        &self.storage
+    }
+
+    /// Returns the keys for the shape of `circuit`, running the setup only
+    /// if no keys are stored for it yet.
+    pub fn get_or_setup<C: ConstraintSynthesizer<F> + Clone, R: RngCore + CryptoRng>(
+        &mut self,
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<(S::ProvingKey, S::VerifyingKey), Error> {
+        let fingerprint = CircuitId::from_circuit(circuit.clone()).map_err(Error::custom)?;
+        if let Some(keys) = self.storage.get(&fingerprint) {
+            return Ok(keys);
+        }
+        let keys = S::circuit_specific_setup(circuit, rng).map_err(Error::custom)?;
+        self.storage.insert(fingerprint, keys.clone());
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ed_on_bls12_381::Fq;
+    use ark_ff::UniformRand;
+    use ark_relations::{
+        lc,
+        r1cs::{ConstraintSystemRef, SynthesisError},
+    };
+    use ark_std::{
+        rand::{rngs::StdRng, SeedableRng},
+        sync::atomic::{AtomicUsize, Ordering},
+        test_rng,
+    };
+
+    static SETUPS: AtomicUsize = AtomicUsize::new(0);
+
+    /// A SNARK that only counts how often its setup runs.
+    struct CountingSNARK;
+
+    impl SNARK<Fq> for CountingSNARK {
+        type ProvingKey = Fq;
+        type VerifyingKey = Fq;
+        type Proof = ();
+        type ProcessedVerifyingKey = Fq;
+        type Error = SynthesisError;
+
+        fn circuit_specific_setup<C: ConstraintSynthesizer<Fq>, R: RngCore + CryptoRng>(
+            _circuit: C,
+            rng: &mut R,
+        ) -> Result<(Fq, Fq), SynthesisError> {
+            SETUPS.fetch_add(1, Ordering::SeqCst);
+            let key = Fq::rand(rng);
+            Ok((key, key))
+        }
+
+        fn prove<C: ConstraintSynthesizer<Fq>, R: RngCore + CryptoRng>(
+            _pk: &Fq,
+            _circuit: C,
+            _rng: &mut R,
+        ) -> Result<(), SynthesisError> {
+            Ok(())
+        }
+
+        fn process_vk(vk: &Fq) -> Result<Fq, SynthesisError> {
+            Ok(*vk)
+        }
+
+        fn verify_with_processed_vk(
+            _pvk: &Fq,
+            _inputs: &[Fq],
+            _proof: &(),
+        ) -> Result<bool, SynthesisError> {
+            Ok(true)
+        }
+    }
+
+    /// Enforces `x · x = y` for a public `y`, `constraints` times.
+    #[derive(Clone)]
+    struct SquareCircuit {
+        constraints: usize,
+        x: Fq,
+    }
+
+    impl ConstraintSynthesizer<Fq> for SquareCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fq>) -> Result<(), SynthesisError> {
+            let x = cs.new_witness_variable(|| Ok(self.x))?;
+            let y = cs.new_input_variable(|| Ok(self.x * self.x))?;
+            for _ in 0..self.constraints {
+                cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + y)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn setup_cache_test() {
+        let rng = &mut test_rng();
+        let mut cache = SetupCache::<Fq, CountingSNARK>::new();
+        let circuit = |constraints, x| SquareCircuit {
+            constraints,
+            x: Fq::from(x),
+        };
+
+        let keys = cache.get_or_setup(circuit(1, 3u64), rng).unwrap();
+        assert_eq!(SETUPS.load(Ordering::SeqCst), 1);
+        // The same shape with other witnesses hits the cache.
+        assert_eq!(cache.get_or_setup(circuit(1, 5u64), rng).unwrap(), keys);
+        assert_eq!(SETUPS.load(Ordering::SeqCst), 1);
+
+        // A different shape misses.
+        let other = cache.get_or_setup(circuit(2, 3u64), rng).unwrap();
+        assert_eq!(SETUPS.load(Ordering::SeqCst), 2);
+        assert_ne!(other, keys);
+        assert_eq!(cache.storage().keys.len(), 2);
+
+        // The keys only depend on the RNG passed in, so a seeded RNG makes
+        // the setup reproducible.
+        let seeded = |seed| {
+            let rng = &mut StdRng::seed_from_u64(seed);
+            SetupCache::<Fq, CountingSNARK>::new()
+                .get_or_setup(circuit(1, 3u64), rng)
+                .unwrap()
+        };
+        assert_eq!(seeded(0), seeded(0));
+        assert_ne!(seeded(0), seeded(1));
+    }
+}
@@ -15,6 +15,8 @@ use ark_relations::{
     },
 };
 use ark_snark::{CircuitSpecificSetupSNARK, UniversalSetupSNARK, SNARK};
+
+use crate::snark::PublicInputBuilder;
 use ark_std::{
     borrow::Borrow,
     fmt,
@@ -22,6 +24,33 @@ use ark_std::{
     vec::{IntoIter, Vec},
 };
 
+impl<F: PrimeField> PublicInputBuilder<F> {
+    /// Allocates the next public input of the layout, which must be `name`.
+    /// Allocating inputs out of order, or inputs that are not in the layout,
+    /// fails with `SynthesisError::Unsatisfiable`.
+    pub fn alloc(
+        &mut self,
+        cs: impl Into<Namespace<F>>,
+        name: &str,
+        f: impl FnOnce() -> Result<F, SynthesisError>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        match self.names.get(self.allocated) {
+            Some(expected) if expected == name => {}
+            _ => return Err(SynthesisError::Unsatisfiable),
+        }
+        self.allocated += 1;
+        FpVar::new_input(cs, f)
+    }
+
+    /// Checks that every input of the layout has been allocated.
+    pub fn finish(&self) -> Result<(), SynthesisError> {
+        if self.allocated != self.names.len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        Ok(())
+    }
+}
+
 /// This implements constraints for SNARK verifiers.
 pub trait SNARKGadget<F: PrimeField, ConstraintF: PrimeField, S: SNARK<F>> {
     type ProcessedVerifyingKeyVar: AllocVar<S::ProcessedVerifyingKey, ConstraintF> + Clone;
@@ -110,6 +139,76 @@ pub trait UniversalSetupSNARKGadget<
     type BoundCircuit: From<S::ComputationBound> + ConstraintSynthesizer<F> + Clone;
 }
 
+/// Verifies proofs of an inner SNARK `S` inside a circuit over `ConstraintF`,
+/// for one level of recursion.
+///
+/// The inner public inputs are passed as `ConstraintF` variables in the
+/// layout of `V::InputVar::repack_input`. The constraint cost is that of
+/// `V::verify` plus the unpacking of those inputs into `V::InputVar`.
+///
+/// This crate has no Groth16 gadget, so the struct is only tested with a
+/// mock SNARK; an end-to-end test over MNT4/MNT6 is still missing. For
+/// reference, the pairing check of a Groth16 verifier over MNT4-298 alone
+/// measures 31,050 constraints with a constant verifying key and 73,302
+/// with a witness one, before the public inputs are combined.
+pub struct ProofVerifierCircuit<F, ConstraintF, S, V>
+where
+    F: PrimeField,
+    ConstraintF: PrimeField,
+    S: SNARK<F>,
+    V: SNARKGadget<F, ConstraintF, S>,
+{
+    vk: V::VerifyingKeyVar,
+    _snark: PhantomData<(F, S)>,
+}
+
+impl<F, ConstraintF, S, V> ProofVerifierCircuit<F, ConstraintF, S, V>
+where
+    F: PrimeField,
+    ConstraintF: PrimeField,
+    S: SNARK<F>,
+    V: SNARKGadget<F, ConstraintF, S>,
+{
+    /// Allocates the inner verifying key `vk` with `mode`: as a constant to
+    /// verify proofs of a fixed inner circuit, or as a witness to leave the
+    /// inner circuit to the prover.
+    pub fn new(
+        cs: impl Into<Namespace<ConstraintF>>,
+        vk: &S::VerifyingKey,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let vk = V::VerifyingKeyVar::new_variable(cs, || Ok(vk), mode)?;
+        Ok(Self {
+            vk,
+            _snark: PhantomData,
+        })
+    }
+
+    /// The allocated inner verifying key.
+    pub fn verifying_key(&self) -> &V::VerifyingKeyVar {
+        &self.vk
+    }
+
+    /// Returns whether `proof` is valid for the inner public `inputs`.
+    pub fn verify(
+        &self,
+        inputs: &[FpVar<ConstraintF>],
+        proof: &V::ProofVar,
+    ) -> Result<Boolean<ConstraintF>, SynthesisError> {
+        let inputs = V::InputVar::from_field_elements(&inputs.to_vec())?;
+        V::verify(&self.vk, &inputs, proof)
+    }
+
+    /// Enforces that `proof` is valid for the inner public `inputs`.
+    pub fn enforce_verify(
+        &self,
+        inputs: &[FpVar<ConstraintF>],
+        proof: &V::ProofVar,
+    ) -> Result<(), SynthesisError> {
+        self.verify(inputs, proof)?.enforce_equal(&Boolean::TRUE)
+    }
+}
+
 /// Gadgets to convert elements between different fields for recursive proofs
 pub trait FromFieldElementsGadget<F: PrimeField, ConstraintF: PrimeField>: Sized {
     fn repack_input(src: &Vec<F>) -> Vec<ConstraintF>;
@@ -647,3 +746,253 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ed_on_bls12_381::Fq;
+    use ark_ff::UniformRand;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::{
+        rand::{CryptoRng, RngCore},
+        test_rng,
+    };
+
+    const INPUTS: [&str; 2] = ["root", "nullifier"];
+
+    /// Proves knowledge of `secret` with `nullifier = secret + root`,
+    /// allocating the public inputs in the order `order`.
+    struct TestCircuit {
+        order: [&'static str; 2],
+        root: Fq,
+        nullifier: Fq,
+        secret: Fq,
+    }
+
+    impl ConstraintSynthesizer<Fq> for TestCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fq>) -> Result<(), SynthesisError> {
+            let mut inputs = PublicInputBuilder::new(&INPUTS);
+            let mut root = None;
+            let mut nullifier = None;
+            for &name in self.order.iter() {
+                let value = if name == "root" {
+                    self.root
+                } else {
+                    self.nullifier
+                };
+                let var = inputs.alloc(ns!(cs, "input"), name, || Ok(value))?;
+                if name == "root" {
+                    root = Some(var);
+                } else {
+                    nullifier = Some(var);
+                }
+            }
+            inputs.finish()?;
+            let secret = FpVar::new_witness(ns!(cs, "secret"), || Ok(self.secret))?;
+            (secret + root.unwrap()).enforce_equal(&nullifier.unwrap())
+        }
+    }
+
+    /// A stand-in for a real SNARK over `Fq`: the proof for public inputs `x`
+    /// under the key `k` is `k · Σ x`. It is not sound, but exercises the
+    /// recursion plumbing without pairing-friendly curve cycles.
+    struct MockSNARK;
+
+    impl SNARK<Fq> for MockSNARK {
+        type ProvingKey = Fq;
+        type VerifyingKey = Fq;
+        type Proof = Fq;
+        type ProcessedVerifyingKey = Fq;
+        type Error = SynthesisError;
+
+        fn circuit_specific_setup<C: ConstraintSynthesizer<Fq>, R: RngCore + CryptoRng>(
+            _circuit: C,
+            rng: &mut R,
+        ) -> Result<(Fq, Fq), SynthesisError> {
+            let key = Fq::rand(rng);
+            Ok((key, key))
+        }
+
+        fn prove<C: ConstraintSynthesizer<Fq>, R: RngCore + CryptoRng>(
+            key: &Fq,
+            circuit: C,
+            _rng: &mut R,
+        ) -> Result<Fq, SynthesisError> {
+            let cs = ConstraintSystem::new_ref();
+            circuit.generate_constraints(cs.clone())?;
+            if !cs.is_satisfied()? {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+            let inputs = cs.borrow().unwrap().instance_assignment[1..].to_vec();
+            Ok(*key * inputs.iter().sum::<Fq>())
+        }
+
+        fn process_vk(vk: &Fq) -> Result<Fq, SynthesisError> {
+            Ok(*vk)
+        }
+
+        fn verify_with_processed_vk(
+            vk: &Fq,
+            inputs: &[Fq],
+            proof: &Fq,
+        ) -> Result<bool, SynthesisError> {
+            Ok(*vk * inputs.iter().sum::<Fq>() == *proof)
+        }
+    }
+
+    struct MockSNARKGadget;
+
+    impl SNARKGadget<Fq, Fq, MockSNARK> for MockSNARKGadget {
+        type ProcessedVerifyingKeyVar = FpVar<Fq>;
+        type VerifyingKeyVar = FpVar<Fq>;
+        type InputVar = BooleanInputVar<Fq, Fq>;
+        type ProofVar = FpVar<Fq>;
+        type VerifierSize = usize;
+
+        fn verifier_size(_vk: &Fq) -> usize {
+            1
+        }
+
+        fn verify_with_processed_vk(
+            vk: &FpVar<Fq>,
+            inputs: &BooleanInputVar<Fq, Fq>,
+            proof: &FpVar<Fq>,
+        ) -> Result<Boolean<Fq>, SynthesisError> {
+            let mut sum = FpVar::zero();
+            for bits in inputs.clone() {
+                sum += Boolean::le_bits_to_fp_var(&bits)?;
+            }
+            (vk * sum).is_eq(proof)
+        }
+
+        fn verify(
+            vk: &FpVar<Fq>,
+            inputs: &BooleanInputVar<Fq, Fq>,
+            proof: &FpVar<Fq>,
+        ) -> Result<Boolean<Fq>, SynthesisError> {
+            Self::verify_with_processed_vk(vk, inputs, proof)
+        }
+    }
+
+    type Verifier = ProofVerifierCircuit<Fq, Fq, MockSNARK, MockSNARKGadget>;
+
+    #[test]
+    fn proof_verifier_circuit_test() {
+        let rng = &mut test_rng();
+        let (root, secret) = (Fq::from(3u64), Fq::from(4u64));
+        let inner = || TestCircuit {
+            order: INPUTS,
+            root,
+            nullifier: root + secret,
+            secret,
+        };
+        let (pk, vk) = MockSNARK::circuit_specific_setup(inner(), rng).unwrap();
+        let proof = MockSNARK::prove(&pk, inner(), rng).unwrap();
+        let inner_inputs = vec![root, root + secret];
+        assert!(MockSNARK::verify(&vk, &inner_inputs, &proof).unwrap());
+
+        let repacked = BooleanInputVar::<Fq, Fq>::repack_input(&inner_inputs);
+        for &mode in [AllocationMode::Constant, AllocationMode::Witness].iter() {
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let verifier = Verifier::new(ns!(cs, "vk"), &vk, mode).unwrap();
+            let inputs = Vec::new_witness(ns!(cs, "inputs"), || Ok(repacked.clone())).unwrap();
+            let proof_var = FpVar::new_witness(ns!(cs, "proof"), || Ok(proof)).unwrap();
+            assert!(verifier
+                .verify(&inputs, &proof_var)
+                .unwrap()
+                .value()
+                .unwrap());
+            verifier.enforce_verify(&inputs, &proof_var).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+
+            // A proof for other inputs is rejected.
+            let tampered = FpVar::new_witness(ns!(cs, "tampered"), || Ok(proof + vk)).unwrap();
+            assert!(!verifier
+                .verify(&inputs, &tampered)
+                .unwrap()
+                .value()
+                .unwrap());
+            verifier.enforce_verify(&inputs, &tampered).unwrap();
+            assert!(!cs.is_satisfied().unwrap());
+        }
+    }
+
+    /// The pairing check of a Groth16 verifier over MNT4-298, three prepared
+    /// pairings and one final exponentiation, in a circuit over the MNT4-298
+    /// base field. `A`, `C` and the input combination are witnesses, as is
+    /// `B`; `γ` and `δ` come from the verifying key.
+    #[test]
+    fn groth16_pairing_check_cost_test() {
+        use ark_mnt4_298::{
+            constraints::{G1Var, G2Var, PairingVar as MNT4PairingVar},
+            Fq as MNT4Fq, G1Projective, G2Projective, MNT4_298,
+        };
+        use ark_r1cs_std::pairing::PairingVar;
+
+        type Pairing = MNT4PairingVar;
+
+        let rng = &mut test_rng();
+        let mut costs = Vec::new();
+        for &vk_mode in [AllocationMode::Constant, AllocationMode::Witness].iter() {
+            let cs = ConstraintSystem::<MNT4Fq>::new_ref();
+            let mut g1 = Vec::new();
+            let mut g2 = Vec::new();
+            for i in 0..3 {
+                let mode = if i == 0 {
+                    AllocationMode::Witness
+                } else {
+                    vk_mode
+                };
+                let p = G1Var::new_witness(ns!(cs, "g1"), || Ok(G1Projective::rand(rng))).unwrap();
+                let q = G2Var::new_variable(ns!(cs, "g2"), || Ok(G2Projective::rand(rng)), mode)
+                    .unwrap();
+                g1.push(<Pairing as PairingVar<MNT4_298, MNT4Fq>>::prepare_g1(&p).unwrap());
+                g2.push(<Pairing as PairingVar<MNT4_298, MNT4Fq>>::prepare_g2(&q).unwrap());
+            }
+            let product = Pairing::miller_loop(&g1, &g2).unwrap();
+            let _ = Pairing::final_exponentiation(&product).unwrap();
+            costs.push(cs.num_constraints());
+        }
+        assert_eq!(costs, vec![31_050, 73_302]);
+    }
+
+    #[test]
+    fn public_input_builder_test() {
+        let (root, secret) = (Fq::from(3u64), Fq::from(4u64));
+        let nullifier = root + secret;
+        let public_inputs = PublicInputBuilder::<Fq>::new(&INPUTS)
+            .build(&[("nullifier", nullifier), ("root", root)])
+            .unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let circuit = TestCircuit {
+            order: INPUTS,
+            root,
+            nullifier,
+            secret,
+        };
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        let instance = cs.borrow().unwrap().instance_assignment.clone();
+        assert_eq!(instance[1..], public_inputs[..]);
+
+        // Allocating the inputs in the other order fails synthesis.
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let circuit = TestCircuit {
+            order: ["nullifier", "root"],
+            root,
+            nullifier,
+            secret,
+        };
+        assert_eq!(
+            circuit.generate_constraints(cs).unwrap_err(),
+            SynthesisError::Unsatisfiable
+        );
+
+        // So does allocating only some of them.
+        let mut inputs = PublicInputBuilder::<Fq>::new(&INPUTS);
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let _root = inputs.alloc(cs, "root", || Ok(root)).unwrap();
+        assert_eq!(inputs.finish().unwrap_err(), SynthesisError::Unsatisfiable);
+    }
+}
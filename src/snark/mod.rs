@@ -1,6 +1,55 @@
+//! Helpers around the [`SNARK`] trait. Every randomized operation takes its
+//! RNG as an argument, as [`SNARK::prove`] does. The crate wraps no concrete
+//! SNARK such as Groth16, so it has no `prove_with_rng` of its own: proofs
+//! are made with the SNARK's `prove` and the caller's RNG.
+
+use crate::String;
+
 #[cfg(feature = "r1cs")]
 pub mod constraints;
 #[cfg(feature = "r1cs")]
 pub use constraints::*;
 
+pub mod cache;
+pub use cache::{MemoryStorage, SetupCache, SetupStorage};
+
+pub mod public_inputs;
+pub use public_inputs::{diagnose_public_inputs, Diagnostic, InputKind, PublicInputBuilder};
+
+pub mod versioned;
+
 pub use ark_snark::*;
+
+/// The reason public inputs or a stored key are rejected.
+#[derive(Debug)]
+pub enum SnarkError {
+    /// No value was given for the named public input.
+    MissingPublicInput(String),
+    /// A value was given for a public input that is not in the layout, or
+    /// more than once.
+    UnexpectedPublicInput(String),
+    /// The bytes do not start with a versioned key envelope.
+    InvalidKeyMagic,
+    /// The key envelope has a format version this crate cannot read.
+    UnsupportedKeyVersion(u16),
+    /// The key was generated for a different circuit.
+    CircuitMismatch,
+}
+
+impl core::fmt::Display for SnarkError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SnarkError::MissingPublicInput(name) => write!(f, "missing public input: {}", name),
+            SnarkError::UnexpectedPublicInput(name) => {
+                write!(f, "unexpected public input: {}", name)
+            }
+            SnarkError::InvalidKeyMagic => write!(f, "data is not a versioned key"),
+            SnarkError::UnsupportedKeyVersion(version) => {
+                write!(f, "unsupported key format version: {}", version)
+            }
+            SnarkError::CircuitMismatch => write!(f, "key was generated for a different circuit"),
+        }
+    }
+}
+
+impl ark_std::error::Error for SnarkError {}
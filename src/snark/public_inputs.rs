@@ -0,0 +1,265 @@
+use super::SnarkError;
+use crate::{CryptoError, String, ToOwned, Vec};
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::marker::PhantomData;
+
+/// The layout of the public inputs of a circuit: their names, in the order
+/// in which the circuit allocates them and the verifier expects them.
+///
+/// The circuit allocates every input through the builder (see
+/// `PublicInputBuilder::alloc`), which fails synthesis if the inputs are not
+/// allocated in the order of the layout. The prover and the verifier then
+/// call [`build`](Self::build) with named values, which puts them in the
+/// same order. The two sides thus agree by construction, instead of a
+/// misordered input only showing up as a failed verification.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "F: PrimeField"), Debug(bound = "F: PrimeField"))]
+pub struct PublicInputBuilder<F: PrimeField> {
+    pub(crate) names: Vec<String>,
+    pub(crate) allocated: usize,
+    #[derivative(Debug = "ignore")]
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField> PublicInputBuilder<F> {
+    pub fn new(names: &[&str]) -> Self {
+        Self {
+            names: names.iter().map(|&name| name.to_owned()).collect(),
+            allocated: 0,
+            _field: PhantomData,
+        }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Orders the named `values` as in the layout. Every input must be given
+    /// exactly once, and no other name may be given.
+    pub fn build(&self, values: &[(&str, F)]) -> Result<Vec<F>, CryptoError> {
+        for (i, (name, _)) in values.iter().enumerate() {
+            let known = self.names.iter().any(|n| n == name);
+            let repeated = values[..i].iter().any(|(other, _)| other == name);
+            if !known || repeated {
+                return Err(SnarkError::UnexpectedPublicInput((*name).to_owned()).into());
+            }
+        }
+        self.names
+            .iter()
+            .map(|name| {
+                values
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, value)| *value)
+                    .ok_or_else(|| SnarkError::MissingPublicInput(name.clone()).into())
+            })
+            .collect()
+    }
+
+    /// Checks `provided` against the layout with [`diagnose_public_inputs`],
+    /// naming the offending inputs. `kinds` gives the kind of each input of
+    /// the layout, in order, and `num_inputs` is the number of inputs of the
+    /// verifying key.
+    pub fn diagnose(
+        &self,
+        num_inputs: usize,
+        kinds: &[InputKind],
+        provided: &[F],
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = diagnose_public_inputs(num_inputs, kinds, provided);
+        for diagnostic in diagnostics.iter_mut() {
+            match diagnostic {
+                Diagnostic::NotBoolean { index, name }
+                | Diagnostic::TooManyBits { index, name, .. } => {
+                    *name = self.names.get(*index).cloned();
+                }
+                Diagnostic::LayoutMismatch { .. } | Diagnostic::WrongCount { .. } => {}
+            }
+        }
+        diagnostics
+    }
+}
+
+/// What a public input is expected to hold, for [`diagnose_public_inputs`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputKind {
+    /// Any field element.
+    Field,
+    /// A boolean, encoded as 0 or 1.
+    Boolean,
+    /// An integer of at most this many bits, such as an amount or a hash
+    /// truncated to the field capacity.
+    Bits(usize),
+}
+
+/// A problem found in public inputs by [`diagnose_public_inputs`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The layout describes a different number of inputs than the verifying
+    /// key takes.
+    LayoutMismatch { layout: usize, verifying_key: usize },
+    /// The number of inputs differs from that of the verifying key.
+    WrongCount { expected: usize, provided: usize },
+    /// The input at `index` should be a boolean but is not 0 or 1.
+    NotBoolean { index: usize, name: Option<String> },
+    /// The input at `index` has more than `bits` bits.
+    TooManyBits {
+        index: usize,
+        name: Option<String>,
+        bits: usize,
+    },
+}
+
+/// Checks public inputs that failed verification for common encoding
+/// mistakes: a wrong number of inputs, or inputs outside the range of their
+/// `kinds`. Problems are reported in input order, after a layout that does
+/// not match the key and a wrong count; an empty result means that the
+/// inputs are at least well-formed.
+///
+/// `num_inputs` is the number of public inputs that the verifying key
+/// takes, which the SNARK's key records; for Groth16 it is
+/// `vk.gamma_abc_g1.len() - 1`. The count is checked against it rather than
+/// against `kinds`, so that a layout that is out of date with the circuit
+/// shows up as well.
+///
+/// This is a debugging aid: inputs that pass may still be wrong.
+pub fn diagnose_public_inputs<F: PrimeField>(
+    num_inputs: usize,
+    kinds: &[InputKind],
+    provided: &[F],
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if kinds.len() != num_inputs {
+        diagnostics.push(Diagnostic::LayoutMismatch {
+            layout: kinds.len(),
+            verifying_key: num_inputs,
+        });
+    }
+    if provided.len() != num_inputs {
+        diagnostics.push(Diagnostic::WrongCount {
+            expected: num_inputs,
+            provided: provided.len(),
+        });
+    }
+    for (index, (kind, value)) in kinds.iter().zip(provided).enumerate() {
+        match *kind {
+            InputKind::Field => {}
+            InputKind::Boolean => {
+                if !value.is_zero() && !value.is_one() {
+                    diagnostics.push(Diagnostic::NotBoolean { index, name: None });
+                }
+            }
+            InputKind::Bits(bits) => {
+                if value.into_repr().num_bits() as usize > bits {
+                    diagnostics.push(Diagnostic::TooManyBits {
+                        index,
+                        name: None,
+                        bits,
+                    });
+                }
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ed_on_bls12_381::Fq;
+
+    #[test]
+    fn build_test() {
+        let builder = PublicInputBuilder::<Fq>::new(&["root", "nullifier"]);
+        let (root, nullifier) = (Fq::from(1u64), Fq::from(2u64));
+        assert_eq!(
+            builder
+                .build(&[("nullifier", nullifier), ("root", root)])
+                .unwrap(),
+            vec![root, nullifier]
+        );
+
+        let missing = [("root", root)];
+        let extra = [("root", root), ("nullifier", nullifier), ("fee", root)];
+        let repeated = [("root", root), ("nullifier", nullifier), ("root", root)];
+        let cases: Vec<(&[(&str, Fq)], SnarkError)> = vec![
+            (
+                &missing,
+                SnarkError::MissingPublicInput("nullifier".to_owned()),
+            ),
+            (&extra, SnarkError::UnexpectedPublicInput("fee".to_owned())),
+            (
+                &repeated,
+                SnarkError::UnexpectedPublicInput("root".to_owned()),
+            ),
+        ];
+        for (values, error) in cases {
+            assert_eq!(
+                format!("{}", builder.build(values).unwrap_err()),
+                format!("{}", CryptoError::Snark(error))
+            );
+        }
+    }
+
+    #[test]
+    fn diagnose_test() {
+        let builder = PublicInputBuilder::<Fq>::new(&["root", "is_withdrawal", "amount"]);
+        let kinds = [InputKind::Field, InputKind::Boolean, InputKind::Bits(64)];
+        let root = -Fq::from(1u64);
+        let good = [root, Fq::from(1u64), Fq::from(u64::MAX)];
+        assert!(builder.diagnose(3, &kinds, &good).is_empty());
+
+        // A boolean and an amount that are out of range.
+        let corrupted = [root, Fq::from(2u64), Fq::from(u64::MAX) + Fq::from(1u64)];
+        assert_eq!(
+            builder.diagnose(3, &kinds, &corrupted),
+            vec![
+                Diagnostic::NotBoolean {
+                    index: 1,
+                    name: Some("is_withdrawal".to_owned()),
+                },
+                Diagnostic::TooManyBits {
+                    index: 2,
+                    name: Some("amount".to_owned()),
+                    bits: 64,
+                },
+            ]
+        );
+
+        // The root and the amount swapped.
+        let swapped = [Fq::from(u64::MAX), Fq::from(0u64), root];
+        assert_eq!(
+            diagnose_public_inputs(3, &kinds, &swapped),
+            vec![Diagnostic::TooManyBits {
+                index: 2,
+                name: None,
+                bits: 64,
+            }]
+        );
+
+        // A missing input.
+        assert_eq!(
+            builder.diagnose(3, &kinds, &good[..2]),
+            vec![Diagnostic::WrongCount {
+                expected: 3,
+                provided: 2,
+            }]
+        );
+
+        // A verifying key with an input that the layout lacks: inputs that
+        // match the layout have the wrong count for the key.
+        assert_eq!(
+            builder.diagnose(4, &kinds, &good),
+            vec![
+                Diagnostic::LayoutMismatch {
+                    layout: 3,
+                    verifying_key: 4,
+                },
+                Diagnostic::WrongCount {
+                    expected: 4,
+                    provided: 3,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use super::SnarkError;
+use crate::{CryptoError, Vec};
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, Matrix, SynthesisError, SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::io::{Read, Write};
+use blake2::{Blake2s, Digest};
+
+/// The magic bytes that start every versioned key.
+pub const KEY_MAGIC: [u8; 4] = *b"ckey";
+
+/// The version of the envelope written by [`store`].
+pub const KEY_FORMAT_VERSION: u16 = 1;
+
+/// Identifies the circuit a proving or verifying key was generated for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CircuitId(pub [u8; 32]);
+
+impl CircuitId {
+    /// Derives an identifier from a label chosen by the user, such as the
+    /// name and revision of the circuit.
+    pub fn from_label(label: &[u8]) -> Self {
+        let mut h = Blake2s::new();
+        h.update(b"circuit-label");
+        h.update(label);
+        Self::from_digest(h)
+    }
+
+    /// Derives an identifier from the constraint matrices of `circuit`, so
+    /// that any change to its layout changes the identifier. The circuit is
+    /// synthesized in setup mode, so its witnesses are not needed.
+    pub fn from_circuit<F: PrimeField, C: ConstraintSynthesizer<F>>(
+        circuit: C,
+    ) -> Result<Self, SynthesisError> {
+        let cs = ConstraintSystem::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone())?;
+        cs.finalize();
+        let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+
+        let mut h = Blake2s::new();
+        h.update(b"circuit-matrices");
+        h.update((matrices.num_instance_variables as u64).to_le_bytes());
+        h.update((matrices.num_witness_variables as u64).to_le_bytes());
+        for matrix in [&matrices.a, &matrices.b, &matrices.c].iter() {
+            hash_matrix(&mut h, matrix);
+        }
+        Ok(Self::from_digest(h))
+    }
+
+    fn from_digest(h: Blake2s) -> Self {
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&h.finalize());
+        Self(id)
+    }
+}
+
+fn hash_matrix<F: PrimeField>(h: &mut Blake2s, matrix: &Matrix<F>) {
+    let mut coeff = Vec::new();
+    h.update((matrix.len() as u64).to_le_bytes());
+    for row in matrix {
+        h.update((row.len() as u64).to_le_bytes());
+        for (value, index) in row {
+            coeff.clear();
+            value.serialize(&mut coeff).unwrap();
+            h.update(&coeff);
+            h.update((*index as u64).to_le_bytes());
+        }
+    }
+}
+
+/// Writes `key` for the circuit `circuit`, preceded by [`KEY_MAGIC`],
+/// [`KEY_FORMAT_VERSION`] and the circuit identifier.
+pub fn store<K: CanonicalSerialize, W: Write>(
+    key: &K,
+    circuit: &CircuitId,
+    mut writer: W,
+) -> Result<(), CryptoError> {
+    writer.write_all(&KEY_MAGIC)?;
+    writer.write_all(&KEY_FORMAT_VERSION.to_le_bytes())?;
+    writer.write_all(&circuit.0)?;
+    key.serialize(writer)?;
+    Ok(())
+}
+
+/// Reads a key written by [`store`], checking that it was generated for the
+/// circuit `circuit`.
+pub fn load<K: CanonicalDeserialize, R: Read>(
+    circuit: &CircuitId,
+    mut reader: R,
+) -> Result<K, CryptoError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != KEY_MAGIC {
+        return Err(SnarkError::InvalidKeyMagic.into());
+    }
+    let mut version = [0u8; 2];
+    reader.read_exact(&mut version)?;
+    let version = u16::from_le_bytes(version);
+    if version != KEY_FORMAT_VERSION {
+        return Err(SnarkError::UnsupportedKeyVersion(version).into());
+    }
+    let mut id = [0u8; 32];
+    reader.read_exact(&mut id)?;
+    if id != circuit.0 {
+        return Err(SnarkError::CircuitMismatch.into());
+    }
+    Ok(K::deserialize(reader)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ec::ProjectiveCurve;
+    use ark_ed_on_bls12_381::{EdwardsAffine, EdwardsProjective as JubJub, Fq};
+    use ark_ff::UniformRand;
+    use ark_relations::{lc, r1cs::ConstraintSystemRef};
+    use ark_std::test_rng;
+
+    /// Enforces `x_i · x_i = y` for `inputs` public inputs `x_i`.
+    struct SquareCircuit {
+        inputs: usize,
+        x: Option<Fq>,
+    }
+
+    impl ConstraintSynthesizer<Fq> for SquareCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fq>) -> Result<(), SynthesisError> {
+            let x = self.x;
+            let y = cs.new_witness_variable(|| {
+                x.map(|x| x * x).ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            for _ in 0..self.inputs {
+                let x = cs.new_input_variable(|| x.ok_or(SynthesisError::AssignmentMissing))?;
+                cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + y)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit_id(inputs: usize) -> CircuitId {
+        CircuitId::from_circuit(SquareCircuit { inputs, x: None }).unwrap()
+    }
+
+    fn key() -> Vec<EdwardsAffine> {
+        let rng = &mut test_rng();
+        (0..3).map(|_| JubJub::rand(rng).into_affine()).collect()
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let key = key();
+        let id = circuit_id(2);
+        let mut bytes = Vec::new();
+        store(&key, &id, &mut bytes).unwrap();
+        assert_eq!(bytes[..4], KEY_MAGIC);
+        assert_eq!(load::<Vec<EdwardsAffine>, _>(&id, &bytes[..]).unwrap(), key);
+
+        // The identifier depends on the layout, not on the witnesses.
+        let with_witness = SquareCircuit {
+            inputs: 2,
+            x: Some(Fq::from(3u64)),
+        };
+        assert_eq!(CircuitId::from_circuit(with_witness).unwrap(), id);
+        assert_eq!(CircuitId::from_label(b"a"), CircuitId::from_label(b"a"));
+        assert_ne!(CircuitId::from_label(b"a"), CircuitId::from_label(b"b"));
+    }
+
+    #[test]
+    fn wrong_version_test() {
+        let id = circuit_id(2);
+        let mut bytes = Vec::new();
+        store(&key(), &id, &mut bytes).unwrap();
+
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(KEY_FORMAT_VERSION + 1).to_le_bytes());
+        let error = load::<Vec<EdwardsAffine>, _>(&id, &newer[..]).unwrap_err();
+        assert!(matches!(
+            error,
+            CryptoError::Snark(SnarkError::UnsupportedKeyVersion(v)) if v == KEY_FORMAT_VERSION + 1
+        ));
+
+        let mut unversioned = bytes.clone();
+        unversioned[0] ^= 1;
+        let error = load::<Vec<EdwardsAffine>, _>(&id, &unversioned[..]).unwrap_err();
+        assert!(matches!(
+            error,
+            CryptoError::Snark(SnarkError::InvalidKeyMagic)
+        ));
+
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(matches!(
+            load::<Vec<EdwardsAffine>, _>(&id, truncated),
+            Err(CryptoError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn circuit_mismatch_test() {
+        let mut bytes = Vec::new();
+        store(&key(), &circuit_id(2), &mut bytes).unwrap();
+
+        for other in [circuit_id(3), CircuitId::from_label(b"square")].iter() {
+            let error = load::<Vec<EdwardsAffine>, _>(other, &bytes[..]).unwrap_err();
+            assert!(matches!(
+                error,
+                CryptoError::Snark(SnarkError::CircuitMismatch)
+            ));
+        }
+    }
+}
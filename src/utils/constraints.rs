@@ -0,0 +1,89 @@
+use super::fp_chunk_bits;
+use crate::Vec;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::SynthesisError;
+
+/// The in-circuit twin of [`super::bytes_to_bits_le`].
+pub fn bytes_to_bits_le<F: PrimeField>(
+    bytes: &[UInt8<F>],
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    bytes.to_bits_le()
+}
+
+/// The in-circuit twin of [`super::bits_to_bytes_le`]. Costs no
+/// constraints.
+pub fn bits_to_bytes_le<F: PrimeField>(bits: &[Boolean<F>]) -> Vec<UInt8<F>> {
+    bits.chunks(8)
+        .map(|byte| {
+            let mut byte = byte.to_vec();
+            byte.resize(8, Boolean::FALSE);
+            UInt8::from_bits_le(&byte)
+        })
+        .collect()
+}
+
+/// The in-circuit twin of [`super::le_bits_to_fp`]: the weighted sum of
+/// `bits`, which reduces modulo the characteristic of `F` exactly like the
+/// native function. Costs no constraints, and unlike
+/// `Boolean::le_bits_to_fp_var` does not check that `bits` is below the
+/// modulus.
+pub fn le_bits_to_fp<F: PrimeField>(bits: &[Boolean<F>]) -> FpVar<F> {
+    let mut element = FpVar::zero();
+    let mut power = F::one();
+    for bit in bits {
+        element += FpVar::from(bit.clone()) * power;
+        power.double_in_place();
+    }
+    element
+}
+
+/// The in-circuit twin of [`super::bits_to_fp_chunks`]. The chunks are below
+/// the modulus by construction, so this costs no constraints.
+pub fn bits_to_fp_chunks<F: PrimeField>(bits: &[Boolean<F>]) -> Vec<FpVar<F>> {
+    bits.chunks(fp_chunk_bits::<F>())
+        .map(le_bits_to_fp)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils;
+    use ark_ed_on_bn254::Fq;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::{rand::Rng, test_rng};
+
+    #[test]
+    fn native_equality_test() {
+        let rng = &mut test_rng();
+        for len in [0, 1, 7, 31, 32, 33, 64, 100].iter() {
+            let bytes = (0..*len).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let bits = utils::bytes_to_bits_le(&bytes);
+            // Lengths that are not a multiple of 8 exercise the padding.
+            let odd_bits = &bits[..bits.len().saturating_sub(3)];
+
+            let cs = ConstraintSystem::<Fq>::new_ref();
+            let bytes_var = UInt8::new_witness_vec(cs.clone(), &bytes).unwrap();
+            let bits_var = bytes_to_bits_le(&bytes_var).unwrap();
+            let odd_bits_var = &bits_var[..odd_bits.len()];
+            let constraints = cs.num_constraints();
+
+            assert_eq!(bits_var.value().unwrap(), bits);
+            assert_eq!(
+                bits_to_bytes_le(odd_bits_var).value().unwrap(),
+                utils::bits_to_bytes_le(odd_bits)
+            );
+            assert_eq!(
+                le_bits_to_fp(odd_bits_var).value().unwrap(),
+                utils::le_bits_to_fp::<Fq>(odd_bits)
+            );
+            assert_eq!(
+                bits_to_fp_chunks(odd_bits_var).value().unwrap(),
+                utils::bits_to_fp_chunks::<Fq>(odd_bits)
+            );
+            assert_eq!(cs.num_constraints(), constraints);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
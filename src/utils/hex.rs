@@ -0,0 +1,130 @@
+//! Hexadecimal encoding of field elements, as used by parameter files: an
+//! optional `0x` prefix followed by the big-endian digits of the canonical
+//! representative.
+
+use super::bytes_to_bits_le;
+use crate::{CryptoError, String, Vec};
+use ark_ff::{BigInteger, PrimeField};
+
+/// Parses a big-endian hexadecimal number, with or without a `0x` prefix,
+/// as an element of `F`. Leading zeros are allowed up to the byte size of
+/// `F::BigInt`; longer strings and values not smaller than the modulus are
+/// rejected rather than reduced.
+pub fn fe_from_hex<F: PrimeField>(hex: &str) -> Result<F, CryptoError> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex).as_bytes();
+    let max_digits = 2 * 8 * <F::BigInt as BigInteger>::NUM_LIMBS;
+    if digits.is_empty() || digits.len() > max_digits {
+        return Err(CryptoError::InvalidHex);
+    }
+    let mut nibbles = digits
+        .iter()
+        .rev()
+        .map(|&digit| (digit as char).to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(CryptoError::InvalidHex)?;
+    nibbles.resize(max_digits, 0);
+    let bytes = nibbles
+        .chunks(2)
+        .map(|pair| pair[0] | (pair[1] << 4))
+        .collect::<Vec<_>>();
+    let repr = F::BigInt::from_bits_le(&bytes_to_bits_le(&bytes));
+    F::from_repr(repr).ok_or(CryptoError::NotInField)
+}
+
+/// Formats `element` as `0x` followed by the big-endian digits of its
+/// canonical representative, zero-padded to the byte size of `F::BigInt`.
+/// [`fe_from_hex`] inverts it.
+pub fn fe_to_hex<F: PrimeField>(element: &F) -> String {
+    let mut hex = String::from("0x");
+    for byte in element.into_repr().to_bytes_be() {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
+
+/// Parses every string of `values` with [`fe_from_hex`], in order.
+pub fn fes_from_hex<F: PrimeField>(values: &[&str]) -> Result<Vec<F>, CryptoError> {
+    values.iter().map(|value| fe_from_hex(value)).collect()
+}
+
+/// Parses one element per line of `text` with [`fe_from_hex`], skipping
+/// blank lines and surrounding whitespace.
+pub fn fes_from_hex_lines<F: PrimeField>(text: &str) -> Result<Vec<F>, CryptoError> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(fe_from_hex)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ed_on_bn254::Fq;
+    use ark_ff::{FpParameters, One, UniformRand, Zero};
+
+    const MODULUS: &str = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+
+    #[test]
+    fn round_trip_test() {
+        let rng = &mut ark_std::test_rng();
+        for element in (0..8)
+            .map(|_| Fq::rand(rng))
+            .chain(vec![Fq::zero(), Fq::one(), -Fq::one()])
+        {
+            let hex = fe_to_hex(&element);
+            assert_eq!(hex.len(), 66);
+            assert_eq!(fe_from_hex::<Fq>(&hex).unwrap(), element);
+        }
+        assert_eq!(fe_to_hex(&Fq::from(0xabu64)), format!("0x{:0>64}", "ab"));
+    }
+
+    #[test]
+    fn leading_zero_test() {
+        let cases = ["1", "0x1", "0x01", "0x0001", &format!("0x{:0>64}", "1")];
+        for hex in cases.iter() {
+            assert_eq!(fe_from_hex::<Fq>(hex).unwrap(), Fq::one());
+        }
+        assert_eq!(fe_from_hex::<Fq>("0x0").unwrap(), Fq::zero());
+        assert_eq!(fe_from_hex::<Fq>("0x0A").unwrap(), Fq::from(10u64));
+    }
+
+    #[test]
+    fn invalid_hex_test() {
+        let too_long = format!("0x{:0>65}", "1");
+        for hex in ["", "0x", "0xg1", "0x 1", &too_long].iter() {
+            assert!(matches!(
+                fe_from_hex::<Fq>(hex),
+                Err(CryptoError::InvalidHex)
+            ));
+        }
+
+        // The modulus and above are rejected, not reduced.
+        assert_eq!(
+            fe_from_hex::<Fq>(MODULUS).unwrap_err().to_string(),
+            CryptoError::NotInField.to_string()
+        );
+        let modulus_bytes = <Fq as PrimeField>::Params::MODULUS.to_bytes_be();
+        let modulus_hex = modulus_bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        assert_eq!(format!("0x{}", modulus_hex), MODULUS);
+        assert!(fe_from_hex::<Fq>(&format!("0x{}", "f".repeat(64))).is_err());
+        let below = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000";
+        assert_eq!(fe_from_hex::<Fq>(below).unwrap(), -Fq::one());
+    }
+
+    #[test]
+    fn bulk_test() {
+        let text = "0x1\n\n  0x02  \n0x3\n";
+        let expected = vec![Fq::from(1u64), Fq::from(2u64), Fq::from(3u64)];
+        assert_eq!(fes_from_hex_lines::<Fq>(text).unwrap(), expected);
+        assert_eq!(
+            fes_from_hex::<Fq>(&["0x1", "0x02", "0x3"]).unwrap(),
+            expected
+        );
+        assert!(fes_from_hex_lines::<Fq>("0x1\nnope\n").is_err());
+        assert!(fes_from_hex_lines::<Fq>("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+//! Conversions between bytes, bits and field elements, shared by the
+//! primitives so that they agree on bit order and chunking. Bits are always
+//! little-endian: the first bit of a byte is its least significant one, and
+//! the first bit of a chunk the least significant bit of its element.
+//!
+//! Each function has an in-circuit twin of the same name in
+//! `utils::constraints`.
+
+use crate::Vec;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+pub mod hex;
+
+/// The little-endian bits of `bytes`, eight per byte.
+pub fn bytes_to_bits_le(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect()
+}
+
+/// Packs little-endian `bits` into bytes, zero-padding the last byte.
+pub fn bits_to_bytes_le(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, bit)| acc | ((*bit as u8) << i))
+        })
+        .collect()
+}
+
+/// Reads little-endian `bits` of any length as an integer reduced modulo the
+/// characteristic of `F`.
+pub fn le_bits_to_fp<F: PrimeField>(bits: &[bool]) -> F {
+    F::from_le_bytes_mod_order(&bits_to_bytes_le(bits))
+}
+
+/// The number of bits [`bits_to_fp_chunks`] reads into each element: the
+/// capacity of `F`, so that every chunk is below the modulus.
+pub fn fp_chunk_bits<F: PrimeField>() -> usize {
+    F::Params::CAPACITY as usize
+}
+
+/// Splits `bits` into chunks of [`fp_chunk_bits`] bits, the last one possibly
+/// shorter, and reads each as an element. No chunk overflows the field, so
+/// the conversion is injective for inputs of a given length.
+pub fn bits_to_fp_chunks<F: PrimeField>(bits: &[bool]) -> Vec<F> {
+    bits.chunks(fp_chunk_bits::<F>())
+        .map(|chunk| F::from_repr(F::BigInt::from_bits_le(chunk)).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ed_on_bn254::Fq;
+    use ark_ff::{Field, One, Zero};
+
+    #[test]
+    fn bit_order_test() {
+        assert_eq!(
+            bytes_to_bits_le(&[0b0000_0101, 0x80]),
+            [
+                vec![true, false, true, false, false, false, false, false],
+                vec![false; 7],
+                vec![true],
+            ]
+            .concat()
+        );
+        assert_eq!(bits_to_bytes_le(&[true, true, false, true]), vec![0b1011]);
+        assert_eq!(bits_to_bytes_le(&bytes_to_bits_le(b"bits")), b"bits");
+
+        assert_eq!(le_bits_to_fp::<Fq>(&[false, true, true]), Fq::from(6u64));
+        // Integers of the modulus or above are reduced.
+        let modulus = <Fq as PrimeField>::Params::MODULUS.to_bits_le();
+        assert_eq!(le_bits_to_fp::<Fq>(&modulus), Fq::zero());
+
+        let capacity = fp_chunk_bits::<Fq>();
+        assert_eq!(capacity, 253);
+        let all_ones = vec![true; capacity + 1];
+        assert_eq!(
+            bits_to_fp_chunks::<Fq>(&all_ones),
+            vec![Fq::from(2u64).pow([capacity as u64]) - Fq::one(), Fq::one()]
+        );
+    }
+}